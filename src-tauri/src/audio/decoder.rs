@@ -1,14 +1,20 @@
-use super::types::{AudioError, AudioMetadata, AudioResult};
+use super::types::{AudioError, AudioMetadata, AudioResult, SampleFormatInfo};
 use std::fs::File;
 use std::path::Path;
+use std::time::Duration;
 use symphonia::core::audio::{AudioBufferRef, Signal};
 use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::errors::Error as SymphoniaError;
 use symphonia::core::formats::{FormatOptions, FormatReader};
-use symphonia::core::io::MediaSourceStream;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 
+// Tries for a decode that keeps hitting transient IO errors, and the wait before the first
+// retry (doubling after each failure)
+pub const TRANSIENT_IO_ATTEMPTS: u32 = 3;
+pub const TRANSIENT_IO_BACKOFF: Duration = Duration::from_millis(100);
+
 pub struct AudioDecoder {
   format: Box<dyn FormatReader>,
   decoder: Box<dyn Decoder>,
@@ -17,12 +23,17 @@ pub struct AudioDecoder {
 
 impl AudioDecoder {
   pub fn new(path: &str) -> AudioResult<Self> {
-    let src = File::open(path).map_err(|e| AudioError::FileError(e.to_string()))?;
+    let src = File::open(path).map_err(open_error)?;
+    let extension = Path::new(path).extension().and_then(|e| e.to_str());
+    Self::from_source(Box::new(src), extension)
+  }
 
-    let mss = MediaSourceStream::new(Box::new(src), Default::default());
+  /// Decode from any byte source; `extension` is a hint for the container format
+  pub fn from_source(source: Box<dyn MediaSource>, extension: Option<&str>) -> AudioResult<Self> {
+    let mss = MediaSourceStream::new(source, Default::default());
 
     let mut hint = Hint::new();
-    if let Some(ext) = Path::new(path).extension().and_then(|e| e.to_str()) {
+    if let Some(ext) = extension {
       hint.with_extension(ext);
     }
 
@@ -31,7 +42,10 @@ impl AudioDecoder {
 
     let probed = symphonia::default::get_probe()
       .format(&hint, mss, &fmt_opts, &meta_opts)
-      .map_err(|e| AudioError::DecodeError(format!("Failed to probe file: {}", e)))?;
+      .map_err(|e| match e {
+        SymphoniaError::IoError(e) if e.kind() != std::io::ErrorKind::UnexpectedEof => AudioError::IoError(e.to_string()),
+        e => AudioError::DecodeError(format!("Failed to probe file: {}", e)),
+      })?;
 
     let format = probed.format;
 
@@ -106,6 +120,9 @@ impl AudioDecoder {
         Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
           return Ok(None);
         }
+        Err(SymphoniaError::IoError(e)) => {
+          return Err(AudioError::IoError(format!("Failed to read packet: {}", e)));
+        }
         Err(e) => {
           return Err(AudioError::DecodeError(format!("Failed to read packet: {}", e)));
         }
@@ -131,15 +148,76 @@ impl AudioDecoder {
     }
   }
 
+  /// Decode the first packet to find the file's sample format, without committing to a full
+  /// decode. Returns None when the file has no audio. Consumes that packet
+  pub fn probe_sample_format(&mut self) -> AudioResult<Option<SampleFormatInfo>> {
+    loop {
+      let packet = match self.format.next_packet() {
+        Ok(packet) => packet,
+        Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+          return Ok(None);
+        }
+        Err(SymphoniaError::IoError(e)) => {
+          return Err(AudioError::IoError(format!("Failed to read packet: {}", e)));
+        }
+        Err(e) => {
+          return Err(AudioError::DecodeError(format!("Failed to read packet: {}", e)));
+        }
+      };
+
+      if packet.track_id() != self.track_id {
+        continue;
+      }
+
+      match self.decoder.decode(&packet) {
+        Ok(decoded) => {
+          let (sample_format, bits_per_sample) = buffer_sample_format(&decoded);
+          return Ok(Some(SampleFormatInfo {
+            sample_format: sample_format.to_string(),
+            bits_per_sample,
+            // Same check the full decode makes, so the two can't disagree
+            supported: convert_audio_buffer(decoded).is_ok(),
+          }));
+        }
+        Err(SymphoniaError::DecodeError(e)) => {
+          log::warn!("Decode error: {}, skipping packet", e);
+          continue;
+        }
+        Err(e) => {
+          return Err(AudioError::DecodeError(format!("Decoder error: {}", e)));
+        }
+      }
+    }
+  }
+
   /// Decode the entire audio file into memory
   /// Returns all samples as a single Vec<f32>
   pub fn decode_all(&mut self) -> AudioResult<Vec<f32>> {
+    self.decode_all_with_progress(|_, _| {})
+  }
+
+  /// Decode the entire audio file, calling `on_progress(decoded_frames, total_frames)`
+  /// after every packet. `total_frames` is None when the container doesn't report a length
+  pub fn decode_all_with_progress<F>(&mut self, mut on_progress: F) -> AudioResult<Vec<f32>>
+  where
+    F: FnMut(u64, Option<u64>),
+  {
+    let (channels, total_frames) = self
+      .format
+      .tracks()
+      .iter()
+      .find(|t| t.id == self.track_id)
+      .map(|t| (t.codec_params.channels.map(|c| c.count()).unwrap_or(1), t.codec_params.n_frames))
+      .unwrap_or((1, None));
+    let channels = channels.max(1);
+
     let mut all_samples = Vec::new();
 
     loop {
       match self.decode_next_packet()? {
         Some(decoded) => {
           all_samples.extend_from_slice(&decoded.samples);
+          on_progress((all_samples.len() / channels) as u64, total_frames);
         }
         None => break,
       }
@@ -149,6 +227,25 @@ impl AudioDecoder {
     Ok(all_samples)
   }
 
+  /// Decode only the first `seconds` of the file, stopping once enough packets are in. For
+  /// previews of long files. Returns interleaved samples, shorter if the file is
+  pub fn decode_first_seconds(&mut self, seconds: f64) -> AudioResult<Vec<f32>> {
+    let metadata = self.get_metadata()?;
+    let channels = metadata.channels.max(1) as usize;
+    let wanted = (seconds.max(0.0) * metadata.sample_rate as f64).ceil() as usize * channels;
+
+    let mut samples = Vec::with_capacity(wanted);
+    while samples.len() < wanted {
+      match self.decode_next_packet()? {
+        Some(decoded) => samples.extend_from_slice(&decoded.samples),
+        None => break,
+      }
+    }
+
+    samples.truncate(wanted);
+    Ok(samples)
+  }
+
   pub fn seek(&mut self, time_seconds: f64) -> AudioResult<()> {
     let track = self
       .format
@@ -185,8 +282,87 @@ pub struct DecodedAudio {
   pub samples: Vec<f32>,
 }
 
-fn convert_audio_buffer(buffer: AudioBufferRef) -> AudioResult<Vec<f32>> {
+/// Report the sample format and bit depth of an audio file and whether it can be decoded
+pub fn probe_format(path: &str) -> AudioResult<SampleFormatInfo> {
+  AudioDecoder::new(path)?
+    .probe_sample_format()?
+    .ok_or_else(|| AudioError::InvalidFormat("File contains no audio".to_string()))
+}
+
+// A file that isn't there or can't be read is permanent; other open failures (timeouts,
+// dropped connections) may clear on a retry
+fn open_error(e: std::io::Error) -> AudioError {
+  use std::io::ErrorKind;
+  match e.kind() {
+    ErrorKind::NotFound | ErrorKind::PermissionDenied | ErrorKind::InvalidInput => AudioError::FileError(e.to_string()),
+    _ => AudioError::IoError(e.to_string()),
+  }
+}
+
+/// Run `op` until it succeeds, fails with a non-transient error, or has been tried `attempts`
+/// times. Waits `backoff` before the first retry and doubles the wait after each one
+pub fn retry_transient<T, F>(attempts: u32, backoff: Duration, mut op: F) -> AudioResult<T>
+where
+  F: FnMut() -> AudioResult<T>,
+{
+  let mut wait = backoff;
+  let mut attempt = 1;
+  loop {
+    match op() {
+      Err(e) if e.is_transient() && attempt < attempts => {
+        log::warn!("Transient read error (attempt {}/{}), retrying in {:?}: {}", attempt, attempts, wait, e);
+        std::thread::sleep(wait);
+        wait *= 2;
+        attempt += 1;
+      }
+      result => return result,
+    }
+  }
+}
+
+// Name and bit depth of a decoded buffer's sample type
+fn buffer_sample_format(buffer: &AudioBufferRef) -> (&'static str, u32) {
+  match buffer {
+    AudioBufferRef::U8(_) => ("u8", 8),
+    AudioBufferRef::U16(_) => ("u16", 16),
+    AudioBufferRef::U24(_) => ("u24", 24),
+    AudioBufferRef::U32(_) => ("u32", 32),
+    AudioBufferRef::S8(_) => ("s8", 8),
+    AudioBufferRef::S16(_) => ("s16", 16),
+    AudioBufferRef::S24(_) => ("s24", 24),
+    AudioBufferRef::S32(_) => ("s32", 32),
+    AudioBufferRef::F32(_) => ("f32", 32),
+    AudioBufferRef::F64(_) => ("f64", 64),
+  }
+}
+
+pub(crate) fn convert_audio_buffer(buffer: AudioBufferRef) -> AudioResult<Vec<f32>> {
   match buffer {
+    AudioBufferRef::U8(buf) => {
+      let num_channels = buf.spec().channels.count();
+      let mut samples = Vec::with_capacity(buf.frames() * num_channels);
+      for frame_idx in 0..buf.frames() {
+        for channel_idx in 0..num_channels {
+          // Unsigned 8-bit is offset binary: 128 is silence
+          let sample_u8 = buf.chan(channel_idx)[frame_idx];
+          let sample_f32 = (sample_u8 as f32 - 128.0) / 128.0;
+          samples.push(sample_f32);
+        }
+      }
+      Ok(samples)
+    }
+    AudioBufferRef::S8(buf) => {
+      let num_channels = buf.spec().channels.count();
+      let mut samples = Vec::with_capacity(buf.frames() * num_channels);
+      for frame_idx in 0..buf.frames() {
+        for channel_idx in 0..num_channels {
+          let sample_i8 = buf.chan(channel_idx)[frame_idx];
+          let sample_f32 = sample_i8 as f32 / 128.0;
+          samples.push(sample_f32);
+        }
+      }
+      Ok(samples)
+    }
     AudioBufferRef::F32(buf) => {
       let num_channels = buf.spec().channels.count();
       let mut samples = Vec::with_capacity(buf.frames() * num_channels);
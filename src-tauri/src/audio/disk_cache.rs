@@ -0,0 +1,232 @@
+/// Decoded stems kept on disk so a warmed setlist loads without decoding, even after a restart
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Identifies a cache entry file and its layout version
+const MAGIC: &[u8; 8] = b"TRXSTEM2";
+// Magic and sample rate (u32)
+const HEADER_LEN: usize = 8 + 4;
+
+/// Directory of decoded stems, one file per source content hash and sample rate, so stems
+/// with identical files (e.g. a click track reused across songs) share one entry. An edited
+/// source has a new hash and so a new entry. Each entry keeps a list of the stems using it and
+/// is deleted with the last of them. Kept under a size limit by evicting the least recently
+/// used entries (an entry's mtime is its last use)
+pub struct CacheManager {
+  dir: PathBuf,
+  max_bytes: AtomicU64,
+  refs_lock: Mutex<()>, // Serializes updates of the reference lists
+}
+
+impl CacheManager {
+  pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+    CacheManager { dir: dir.into(), max_bytes: AtomicU64::new(max_bytes), refs_lock: Mutex::new(()) }
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  pub fn max_bytes(&self) -> u64 {
+    self.max_bytes.load(Ordering::Acquire)
+  }
+
+  /// Change the size limit, evicting entries right away if the cache is over it
+  pub fn set_max_bytes(&self, max_bytes: u64) {
+    self.max_bytes.store(max_bytes, Ordering::Release);
+    self.evict_to_limit(None);
+  }
+
+  /// Bytes of all entries on disk
+  pub fn size_bytes(&self) -> u64 {
+    self.entries().iter().map(|entry| entry.size).sum()
+  }
+
+  /// Drop a stem's references at every sample rate (e.g. when the stem is deleted). Entries no
+  /// other stem uses are deleted
+  pub fn remove_stem(&self, stem_id: &str) {
+    let _guard = self.refs_lock.lock().unwrap_or_else(|e| e.into_inner());
+    for entry in self.entries() {
+      let refs_path = entry.path.with_extension("refs");
+      let mut stem_ids = read_refs(&refs_path);
+      if !stem_ids.iter().any(|id| id == stem_id) {
+        continue;
+      }
+
+      stem_ids.retain(|id| id != stem_id);
+      if stem_ids.is_empty() {
+        let _ = fs::remove_file(&entry.path);
+        let _ = fs::remove_file(&refs_path);
+      } else if let Err(e) = write_refs(&refs_path, &stem_ids) {
+        log::warn!("Disk cache: Failed to update {}: {}", refs_path.display(), e);
+      }
+    }
+  }
+
+  /// Delete every entry. Returns the bytes freed
+  pub fn clear(&self) -> u64 {
+    self.entries()
+      .into_iter()
+      .filter(|entry| fs::remove_file(&entry.path).is_ok())
+      .map(|entry| {
+        let _ = fs::remove_file(entry.path.with_extension("refs"));
+        entry.size
+      })
+      .sum()
+  }
+
+  // "<source hash>@<rate>.f32"; the hash is hex so it's safe as a file name
+  fn entry_path(&self, source_hash: &str, sample_rate: u32) -> PathBuf {
+    self.dir.join(format!("{}@{}.f32", source_hash, sample_rate))
+  }
+
+  /// Interleaved samples of a source decoded at `sample_rate`, None if missing. A hit records
+  /// the stem as a user of the entry
+  pub fn get(&self, stem_id: &str, source_hash: &str, sample_rate: u32) -> Option<Vec<f32>> {
+    let mut file = self.open_valid(source_hash, sample_rate)?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    if bytes.len() % 4 != 0 {
+      log::warn!("Ignoring truncated disk cache entry for stem {}", stem_id);
+      return None;
+    }
+
+    // Reading counts as a use for LRU eviction
+    let path = self.entry_path(source_hash, sample_rate);
+    if let Ok(file) = fs::File::options().write(true).open(&path) {
+      let _ = file.set_modified(SystemTime::now());
+    }
+    self.add_ref(&path, stem_id);
+
+    Some(bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect())
+  }
+
+  /// Whether an entry exists, without reading the samples. A hit records the stem as a user
+  /// of the entry
+  pub fn contains(&self, stem_id: &str, source_hash: &str, sample_rate: u32) -> bool {
+    if self.open_valid(source_hash, sample_rate).is_none() {
+      return false;
+    }
+    self.add_ref(&self.entry_path(source_hash, sample_rate), stem_id);
+    true
+  }
+
+  /// Store a stem's decoded samples. Written to a temporary file first so a crash never
+  /// leaves a half-written entry behind
+  pub fn put(&self, stem_id: &str, source_hash: &str, sample_rate: u32, samples: &[f32]) -> io::Result<()> {
+    fs::create_dir_all(&self.dir)?;
+
+    let path = self.entry_path(source_hash, sample_rate);
+    // Per stem, so stems with the same source written at once don't share a partial file
+    let partial = path.with_extension(format!("f32.{}.partial", stem_id));
+    {
+      let mut writer = io::BufWriter::new(fs::File::create(&partial)?);
+      writer.write_all(MAGIC)?;
+      writer.write_all(&sample_rate.to_le_bytes())?;
+      for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+      }
+      writer.flush()?;
+    }
+    fs::rename(&partial, &path)?;
+    self.add_ref(&path, stem_id);
+
+    self.evict_to_limit(Some(&path));
+    Ok(())
+  }
+
+  // Record a stem as a user of the entry at `path`
+  fn add_ref(&self, path: &Path, stem_id: &str) {
+    let _guard = self.refs_lock.lock().unwrap_or_else(|e| e.into_inner());
+    let refs_path = path.with_extension("refs");
+    let mut stem_ids = read_refs(&refs_path);
+    if stem_ids.iter().any(|id| id == stem_id) {
+      return;
+    }
+
+    stem_ids.push(stem_id.to_string());
+    if let Err(e) = write_refs(&refs_path, &stem_ids) {
+      log::warn!("Disk cache: Failed to update {}: {}", refs_path.display(), e);
+    }
+  }
+
+  // Delete the least recently used entries until the cache fits its limit. `keep` (the entry
+  // just written) is never evicted, even if it alone is over the limit
+  fn evict_to_limit(&self, keep: Option<&Path>) {
+    let max_bytes = self.max_bytes();
+    let mut entries = self.entries();
+    let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+    if total <= max_bytes {
+      return;
+    }
+
+    entries.sort_by_key(|entry| entry.last_used);
+    for entry in entries {
+      if total <= max_bytes {
+        break;
+      }
+      if Some(entry.path.as_path()) == keep {
+        continue;
+      }
+      if fs::remove_file(&entry.path).is_ok() {
+        let _ = fs::remove_file(entry.path.with_extension("refs"));
+        log::info!("Disk cache: Evicted {}", entry.path.display());
+        total -= entry.size;
+      }
+    }
+  }
+
+  // Every finished entry file in the cache directory
+  fn entries(&self) -> Vec<CacheEntryFile> {
+    let Ok(dir) = fs::read_dir(&self.dir) else {
+      return Vec::new();
+    };
+
+    dir
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "f32"))
+      .filter_map(|entry| {
+        let metadata = entry.metadata().ok()?;
+        Some(CacheEntryFile {
+          path: entry.path(),
+          size: metadata.len(),
+          last_used: metadata.modified().unwrap_or(UNIX_EPOCH),
+        })
+      })
+      .collect()
+  }
+
+  // The entry positioned after its header, if it's in this layout and at the rate
+  fn open_valid(&self, source_hash: &str, sample_rate: u32) -> Option<fs::File> {
+    let mut file = fs::File::open(self.entry_path(source_hash, sample_rate)).ok()?;
+    let mut header = [0u8; HEADER_LEN];
+    file.read_exact(&mut header).ok()?;
+
+    let rate = u32::from_le_bytes(header[8..12].try_into().ok()?);
+    if &header[..8] != MAGIC || rate != sample_rate {
+      return None;
+    }
+    Some(file)
+  }
+}
+
+struct CacheEntryFile {
+  path: PathBuf,
+  size: u64,
+  last_used: SystemTime,
+}
+
+// Ids of the stems using an entry, one per line. Empty if the list is missing
+fn read_refs(refs_path: &Path) -> Vec<String> {
+  fs::read_to_string(refs_path)
+    .map(|refs| refs.lines().filter(|line| !line.is_empty()).map(str::to_string).collect())
+    .unwrap_or_default()
+}
+
+fn write_refs(refs_path: &Path, stem_ids: &[String]) -> io::Result<()> {
+  fs::write(refs_path, stem_ids.join("\n"))
+}
@@ -8,7 +8,7 @@ use std::thread;
 use super::buffer::AudioBuffer;
 use super::decoder::AudioDecoder;
 use super::resampler::LinearResampler;
-use super::types::{AudioCommand, AudioError, AudioMetadata, AudioResult, PlaybackState};
+use super::types::{AudioCommand, AudioError, AudioMetadata, AudioResult, FadeCurve, PlaybackState};
 
 const TARGET_SAMPLE_RATE: u32 = 48000;
 const BUFFER_SIZE: usize = 512;
@@ -35,6 +35,7 @@ struct EngineState {
   resampler: Option<LinearResampler>,
   crossfade_samples: usize,
   fade_position: usize,
+  fade_curve: FadeCurve,
   fading_in: bool,
   fading_out: bool,
 }
@@ -63,6 +64,7 @@ impl AudioEngine {
       resampler: None,
       crossfade_samples: ((CROSSFADE_MS / 1000.0) * TARGET_SAMPLE_RATE as f64) as usize,
       fade_position: 0,
+      fade_curve: FadeCurve::default(),
       fading_in: false,
       fading_out: false,
     }));
@@ -222,6 +224,10 @@ impl AudioEngine {
     Ok(())
   }
 
+  pub fn set_fade_curve(&mut self, curve: FadeCurve) {
+    self.state.lock().unwrap().fade_curve = curve;
+  }
+
   pub fn load_file(&mut self, path: &str) -> AudioResult<AudioMetadata> {
     let decoder = AudioDecoder::new(path)?;
     let metadata = decoder.get_metadata()?;
@@ -263,7 +269,7 @@ impl EngineState {
       output[i] *= self.volume;
 
       if self.fading_in && self.fade_position < self.crossfade_samples {
-        let fade_gain = self.fade_position as f32 / self.crossfade_samples as f32;
+        let fade_gain = self.fade_curve.gain(self.fade_position as f32 / self.crossfade_samples as f32);
         output[i] *= fade_gain;
         self.fade_position += 1;
       } else if self.fading_in {
@@ -272,7 +278,7 @@ impl EngineState {
       }
 
       if self.fading_out && self.fade_position < self.crossfade_samples {
-        let fade_gain = 1.0 - (self.fade_position as f32 / self.crossfade_samples as f32);
+        let fade_gain = self.fade_curve.gain(1.0 - (self.fade_position as f32 / self.crossfade_samples as f32));
         output[i] *= fade_gain;
         self.fade_position += 1;
       } else if self.fading_out && self.fade_position >= self.crossfade_samples {
@@ -0,0 +1,110 @@
+// Integrated loudness (ITU-R BS.1770) for matching songs to a loudness target
+
+// Blocks quieter than this never count towards the integrated loudness
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+// Blocks this far below the ungated loudness are dropped as pauses
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Largest correction applied either way, so a near-silent song isn't boosted into clipping
+pub const MAX_LOUDNESS_TRIM_DB: f64 = 12.0;
+
+// Two-stage K-weighting filter: a high shelf modelling the head, then a low-cut
+struct KWeighting {
+  shelf: Biquad,
+  high_pass: Biquad,
+}
+
+impl KWeighting {
+  // Coefficients derived for any sample rate from the analog prototypes BS.1770 specifies at 48 kHz
+  fn new(sample_rate: u32) -> Self {
+    let rate = sample_rate as f64;
+
+    let (gain_db, fc, q) = (3.999843853973347, 1681.974450955533, 0.7071752369554196);
+    let k = (std::f64::consts::PI * fc / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+      [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+      [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    let (fc, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * fc / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new(
+      [1.0, -2.0, 1.0],
+      [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    KWeighting { shelf, high_pass }
+  }
+
+  fn process(&mut self, sample: f64) -> f64 {
+    self.high_pass.process(self.shelf.process(sample))
+  }
+}
+
+struct Biquad {
+  b: [f64; 3],
+  a: [f64; 2],
+  z: [f64; 2], // Transposed direct form II state
+}
+
+impl Biquad {
+  fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+    Biquad { b, a, z: [0.0; 2] }
+  }
+
+  fn process(&mut self, x: f64) -> f64 {
+    let y = self.b[0] * x + self.z[0];
+    self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+    self.z[1] = self.b[2] * x - self.a[1] * y;
+    y
+  }
+}
+
+/// Gated integrated loudness in LUFS of interleaved samples, None if the audio is silent or
+/// shorter than one 400 ms block
+pub fn integrated_loudness(samples: &[f32], channels: usize, sample_rate: u32) -> Option<f64> {
+  if channels == 0 || sample_rate < 10 {
+    return None;
+  }
+
+  // Sum of the K-weighted squares of every channel, per 100 ms step
+  let step_frames = sample_rate as usize / 10;
+  let mut filters: Vec<KWeighting> = (0..channels).map(|_| KWeighting::new(sample_rate)).collect();
+  let mut steps = Vec::new();
+  let mut sum = 0.0;
+  for (index, frame) in samples.chunks_exact(channels).enumerate() {
+    for (&sample, filter) in frame.iter().zip(filters.iter_mut()) {
+      let weighted = filter.process(sample as f64);
+      sum += weighted * weighted;
+    }
+    if (index + 1) % step_frames == 0 {
+      steps.push(sum);
+      sum = 0.0;
+    }
+  }
+
+  // 400 ms blocks overlapping by 75%, as mean power
+  let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+  let blocks: Vec<f64> = steps
+    .windows(4)
+    .map(|window| window.iter().sum::<f64>() / (4 * step_frames) as f64)
+    .filter(|&power| power > 0.0 && loudness(power) > ABSOLUTE_GATE_LUFS)
+    .collect();
+  if blocks.is_empty() {
+    return None;
+  }
+
+  let mean = |powers: &[f64]| powers.iter().sum::<f64>() / powers.len() as f64;
+  let relative_gate = loudness(mean(&blocks)) + RELATIVE_GATE_LU;
+  let gated: Vec<f64> = blocks.into_iter().filter(|&power| loudness(power) > relative_gate).collect();
+
+  Some(loudness(mean(&gated)))
+}
+
+/// Master trim in dB that brings a song measured at `measured_lufs` to `target_lufs`
+pub fn loudness_trim_db(measured_lufs: f64, target_lufs: f64) -> f64 {
+  (target_lufs - measured_lufs).clamp(-MAX_LOUDNESS_TRIM_DB, MAX_LOUDNESS_TRIM_DB)
+}
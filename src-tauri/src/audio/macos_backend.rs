@@ -16,6 +16,8 @@ pub struct MacOSAudioStream {
     device_id: AudioDeviceID,
     device_name: String,
     sample_rate: f64,
+    channels: u32,
+    output_channels: usize,
 }
 
 impl MacOSAudioStream {
@@ -52,17 +54,17 @@ impl MacOSAudioStream {
         log::info!("Audio unit initialized with device default format");
 
         // Get the actual format we ended up with
-        let actual_sample_rate = if let Ok(format) = audio_unit.get_property::<StreamFormat>(
+        let (actual_sample_rate, channels) = if let Ok(format) = audio_unit.get_property::<StreamFormat>(
             kAudioUnitProperty_StreamFormat,
             Scope::Input,
             Element::Output,
         ) {
             log::info!("Using device format: sample_rate={}, channels={}",
                       format.sample_rate, format.channels);
-            format.sample_rate
+            (format.sample_rate, format.channels)
         } else {
-            log::warn!("Could not get device format, assuming 48kHz");
-            48000.0
+            log::warn!("Could not get device format, assuming 48kHz stereo");
+            (48000.0, 2)
         };
 
         Ok(Self {
@@ -72,11 +74,13 @@ impl MacOSAudioStream {
             device_id,
             device_name: device_name.to_string(),
             sample_rate: actual_sample_rate,
+            channels,
+            output_channels: 2,
         })
     }
 
     /// Find a device ID by name
-    fn find_device_id(device_name: &str) -> AudioResult<AudioDeviceID> {
+    pub(crate) fn find_device_id(device_name: &str) -> AudioResult<AudioDeviceID> {
         use coreaudio::sys::{
             kAudioHardwarePropertyDevices, kAudioObjectPropertyScopeGlobal,
             kAudioObjectSystemObject, AudioObjectGetPropertyData,
@@ -187,6 +191,7 @@ impl MacOSAudioStream {
     {
         let playback_state = self.playback_state.clone();
         let position = self.position.clone();
+        let output_channels = self.output_channels;
 
         let result = self.audio_unit.set_render_callback(move |mut args: coreaudio::audio_unit::render_callback::Args<coreaudio::audio_unit::render_callback::data::NonInterleaved<f32>>| {
             // Check playback state
@@ -206,20 +211,18 @@ impl MacOSAudioStream {
             let num_frames = args.num_frames;
 
             // Create a temporary interleaved buffer
-            let mut interleaved = vec![0.0f32; num_frames * 2];
+            let mut interleaved = vec![0.0f32; num_frames * output_channels];
             callback(&mut interleaved);
 
             // Copy to output buffers (non-interleaved)
-            let mut channels = args.data.channels_mut();
-            if let (Some(left), Some(right)) = (channels.next(), channels.next()) {
+            for (channel_index, channel) in args.data.channels_mut().enumerate().take(output_channels) {
                 for i in 0..num_frames {
-                    left[i] = interleaved[i * 2];
-                    right[i] = interleaved[i * 2 + 1];
+                    channel[i] = interleaved[i * output_channels + channel_index];
                 }
             }
 
-            // Position is already updated by audio_callback in multi_track.rs (line 439)
-            // which increments by output.len() (num_frames * 2 for stereo)
+            // Position is already updated by audio_callback in multi_track.rs
+            // which advances by one stereo frame per output frame
             // Do NOT increment again here or we'll skip samples causing crackling
 
             Ok(())
@@ -267,6 +270,77 @@ impl MacOSAudioStream {
     pub fn sample_rate(&self) -> f64 {
         self.sample_rate
     }
+
+    /// Get the device's output channel count
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// Set the device's I/O buffer size in frames, clamped to the range it supports.
+    /// Returns the size actually applied
+    pub fn set_buffer_frame_size(&mut self, frames: u32) -> AudioResult<u32> {
+        use coreaudio::sys::{
+            kAudioDevicePropertyBufferFrameSize, kAudioDevicePropertyBufferFrameSizeRange,
+            kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain,
+            AudioObjectGetPropertyData, AudioObjectSetPropertyData,
+            AudioObjectPropertyAddress, AudioValueRange,
+        };
+        use std::ptr;
+
+        unsafe {
+            let range_property = AudioObjectPropertyAddress {
+                mSelector: kAudioDevicePropertyBufferFrameSizeRange,
+                mScope: kAudioObjectPropertyScopeGlobal,
+                mElement: kAudioObjectPropertyElementMain as u32,
+            };
+
+            let mut range = AudioValueRange { mMinimum: 0.0, mMaximum: 0.0 };
+            let mut range_size = std::mem::size_of::<AudioValueRange>() as u32;
+            let status = AudioObjectGetPropertyData(
+                self.device_id,
+                &range_property,
+                0,
+                ptr::null(),
+                &mut range_size,
+                &mut range as *mut _ as *mut _,
+            );
+
+            let frames = if status == 0 && range.mMaximum > 0.0 {
+                frames.clamp(range.mMinimum as u32, range.mMaximum as u32)
+            } else {
+                log::warn!("Could not get buffer size range for device {}", self.device_name);
+                frames
+            };
+
+            let size_property = AudioObjectPropertyAddress {
+                mSelector: kAudioDevicePropertyBufferFrameSize,
+                mScope: kAudioObjectPropertyScopeGlobal,
+                mElement: kAudioObjectPropertyElementMain as u32,
+            };
+
+            let status = AudioObjectSetPropertyData(
+                self.device_id,
+                &size_property,
+                0,
+                ptr::null(),
+                std::mem::size_of::<u32>() as u32,
+                &frames as *const _ as *const _,
+            );
+
+            if status != 0 {
+                return Err(AudioError::DeviceInit(format!("Failed to set buffer size: {}", status)));
+            }
+
+            log::info!("Buffer size set to {} frames for device {}", frames, self.device_name);
+            Ok(frames)
+        }
+    }
+
+    /// Set how many interleaved channels the render callback produces
+    /// (must be called before set_render_callback)
+    pub fn set_output_channels(&mut self, channels: usize) {
+        self.output_channels = channels.min(self.channels as usize).max(1);
+    }
 }
 
 impl Drop for MacOSAudioStream {
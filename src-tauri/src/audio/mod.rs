@@ -6,13 +6,23 @@ mod multi_track;
 pub mod decoder;
 pub mod resampler;
 pub mod cache;
+pub mod disk_cache;
+pub mod loudness;
+pub mod waveform;
+pub mod tone;
+pub mod recorder;
 #[cfg(target_os = "macos")]
 pub mod macos_backend;
 
 pub use engine::AudioEngine;
-pub use multi_track::{MultiTrackEngine, StemCapacity};
-pub use types::{PlaybackState, AudioCommand, AudioMetadata};
+pub use multi_track::{available_audio_hosts, MeterHandles, MultiTrackEngine, StemCapacity};
+pub use multi_track::{MAX_HIGHPASS_CUTOFF_HZ, MIN_HIGHPASS_CUTOFF_HZ};
+#[cfg(not(target_os = "macos"))]
+pub use multi_track::audio_host;
+pub use types::{AudioError, PlaybackState, ActiveStreamConfig, AudioCommand, AudioMetadata, FadeCurve, MeterMode, SampleFormatInfo};
 pub use decoder::AudioDecoder;
+pub use disk_cache::CacheManager;
+pub use recorder::{RecordingStatus, RecordingSummary};
 
 #[cfg(test)]
 mod tests;
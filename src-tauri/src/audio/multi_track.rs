@@ -1,4 +1,4 @@
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 #[cfg(not(target_os = "macos"))]
@@ -10,12 +10,46 @@ use cpal::{Device, Stream, StreamConfig, SampleRate};
 use super::macos_backend::MacOSAudioStream;
 
 use super::decoder::AudioDecoder;
+use super::recorder::{RecordBlock, RecordTap, Recording, RecordingStatus, RecordingSummary};
 use super::resampler::LinearResampler;
-use super::types::{AudioError, AudioResult, PlaybackState};
+use super::types::{ActiveStreamConfig, AudioError, AudioResult, FadeCurve, MeterMode, PlaybackState};
 
+// Project rate until the saved setting is applied
 const TARGET_SAMPLE_RATE: u32 = 48000;
-const BUFFER_SIZE: usize = 512;
+const MIN_PROJECT_SAMPLE_RATE: u32 = 8000;
+const MAX_PROJECT_SAMPLE_RATE: u32 = 384000;
+const DEFAULT_BUFFER_SIZE: usize = 512;
+const MIN_BUFFER_SIZE: usize = 16;
+const MAX_BUFFER_SIZE: usize = 8192;
 const RING_BUFFER_SIZE: usize = 48000 * 2;
+const MAX_OUTPUT_BUSES: usize = 32;
+
+// cue_output value when no headphone pair is selected
+const NO_CUE_OUTPUT: usize = usize::MAX;
+
+const DEFAULT_LIMITER_THRESHOLD_DB: f32 = -0.3;
+// Fraction of the threshold below which the limiter leaves the signal untouched
+const LIMITER_KNEE_RATIO: f32 = 0.8;
+
+// Master high-pass cutoff used until one is chosen, and the range accepted, in Hz
+const DEFAULT_HIGHPASS_CUTOFF_HZ: f32 = 30.0;
+pub const MIN_HIGHPASS_CUTOFF_HZ: f32 = 10.0;
+pub const MAX_HIGHPASS_CUTOFF_HZ: f32 = 200.0;
+
+const MIN_PLAYBACK_RATE: f32 = 0.25;
+const MAX_PLAYBACK_RATE: f32 = 2.0;
+
+// Noise gate opening and closing times until a stem sets its own, and how fast its level
+// detector falls after a peak
+const DEFAULT_GATE_ATTACK_SECONDS: f32 = 0.001;
+const DEFAULT_GATE_RELEASE_SECONDS: f32 = 0.05;
+const GATE_DETECTOR_SECONDS: f32 = 0.01;
+
+const DEFAULT_METER_DECAY_DB_PER_SEC: f32 = 20.0;
+// Rise time of the VU meter mode
+const VU_INTEGRATION_SECONDS: f32 = 0.3;
+// Time constant of the fader smoothing that keeps fast fader moves from stepping audibly
+const FADER_SMOOTHING_SECONDS: f32 = 0.005;
 
 /// Preset configurations for maximum stem count
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,6 +65,9 @@ pub enum StemCapacity {
 }
 
 impl StemCapacity {
+  /// Largest stem count an engine can be created with
+  pub const MAX: usize = 256;
+
   pub fn as_usize(&self) -> usize {
     match self {
       StemCapacity::Standard => 16,
@@ -48,6 +85,18 @@ impl StemCapacity {
       n => StemCapacity::Custom(n),
     }
   }
+
+  /// Capacity for the saved `stem_capacity` setting, falling back to Extended when it's
+  /// outside 1-256 so a bad value can't stop the engine from starting
+  pub fn from_setting(value: i32) -> Self {
+    match usize::try_from(value) {
+      Ok(count) if (1..=Self::MAX).contains(&count) => Self::from_usize(count),
+      _ => {
+        log::warn!("Invalid stem capacity setting {}, using {}", value, StemCapacity::Extended.as_usize());
+        StemCapacity::Extended
+      }
+    }
+  }
 }
 
 pub struct MultiTrackEngine {
@@ -56,17 +105,203 @@ pub struct MultiTrackEngine {
   stem_volumes: Vec<Arc<std::sync::atomic::AtomicU32>>,
   stem_mutes: Vec<Arc<AtomicBool>>,
   stem_solos: Vec<Arc<AtomicBool>>,
+  stem_solo_safe: Vec<Arc<AtomicBool>>,
+  stem_phase_inverted: Vec<Arc<AtomicBool>>,
+  stem_swap_channels: Vec<Arc<AtomicBool>>, // Exchange left and right
+  stem_mono_sum: Vec<Arc<AtomicBool>>, // Average left and right into both sides
+  stem_trims: Vec<Arc<std::sync::atomic::AtomicU32>>, // Linear input gain applied with the fader
+  stem_gates: Vec<Arc<AtomicBool>>, // Noise gate on
+  stem_gate_thresholds: Vec<Arc<std::sync::atomic::AtomicU32>>, // Linear level the gate opens at
+  stem_gate_attacks: Vec<Arc<std::sync::atomic::AtomicU32>>, // Gate opening time in seconds
+  stem_gate_releases: Vec<Arc<std::sync::atomic::AtomicU32>>, // Gate closing time in seconds
   stem_levels: Vec<Arc<std::sync::atomic::AtomicU32>>,
+  stem_outputs: Vec<Arc<AtomicUsize>>,
+  stem_pans: Vec<Arc<std::sync::atomic::AtomicU32>>,
+  stem_offsets: Vec<Arc<AtomicU64>>, // Leading frames skipped when reading each stem
+  stem_delays: Vec<Arc<AtomicI64>>, // Manual latency compensation in frames; negative plays early
+  stem_cued: Vec<Arc<AtomicBool>>, // Copied at unity to the cue output for pre-listening
+  cue_output: Arc<AtomicUsize>, // Output bus used as the cue (headphone) pair, NO_CUE_OUTPUT = none
   master_volume: Arc<std::sync::atomic::AtomicU32>,
+  master_trim: Arc<std::sync::atomic::AtomicU32>, // Linear loudness-matching gain applied with the master volume
   master_level: Arc<std::sync::atomic::AtomicU32>,
+  stem_peak_holds: Vec<MeterHold>,
+  master_peak_hold: MeterHold,
+  meter_mode: Arc<AtomicU8>, // MeterMode as u8
+  meter_decay: Arc<std::sync::atomic::AtomicU32>, // dB per second meters fall after a peak
+  meter_hold: Arc<std::sync::atomic::AtomicU32>, // Seconds a peak is held, 0 = no hold
   playback_state: Arc<Mutex<PlaybackState>>,
   position: Arc<AtomicU64>,
+  current_duration: Arc<AtomicU64>, // f64 bits, seconds of the longest loaded stem
+  fade_gain: Arc<std::sync::atomic::AtomicU32>,
+  fade_step: Arc<std::sync::atomic::AtomicU32>,
+  fade_curve: Arc<AtomicU8>, // FadeCurve as u8
+  limiter_enabled: Arc<AtomicBool>,
+  limiter_threshold: Arc<std::sync::atomic::AtomicU32>, // Linear gain
+  highpass_enabled: Arc<AtomicBool>, // Master low-cut that keeps rumble out of the subs
+  highpass_cutoff: Arc<std::sync::atomic::AtomicU32>, // Hz
+  highpass: Arc<Mutex<MasterHighpass>>,
+  recorder: Arc<Mutex<Option<RecordTap>>>, // Where the callback sends blocks while recording
+  recording: Option<Recording>, // Writer thread of the running recording
+  interrupted_recording: Option<RecordingSummary>, // Finished by a device fallback, for stop_recording
+  callback_frames: Arc<AtomicUsize>, // Frames in the most recent output callback, 0 before the first
+  playback_rate: Arc<std::sync::atomic::AtomicU32>,
+  position_frac: Arc<std::sync::atomic::AtomicU32>, // Fraction of a frame past `position` (varispeed)
+  loop_start: Arc<AtomicU64>, // Sample position, like `position`
+  loop_end: Arc<AtomicU64>,   // 0 = no loop region
+  auto_stop_at_end: Arc<AtomicBool>,
+  song_ended: Arc<AtomicBool>, // Set when auto-stop ends the song, taken by the setlist auto-advance
+  reverse: Arc<AtomicBool>, // Play backwards from the position, stopping at the start
+  stream_lost: Arc<AtomicBool>, // Set by the stream error callback when the device disappears
+  device_fallback: bool, // Rebuild on the system default device when the output device is lost
+  fallback_failed: bool, // A fallback for the current loss failed; the watcher's retries stay quiet
+  output_channels: usize,
+  device_max_channels: usize,
   #[cfg(target_os = "macos")]
   stream: Option<MacOSAudioStream>,
   #[cfg(not(target_os = "macos"))]
   stream: Option<Stream>,
+  stream_config: Option<ActiveStreamConfig>, // What the running stream negotiated, None without a stream
   current_device_name: Option<String>,
+  #[cfg(not(target_os = "macos"))]
+  audio_host: Option<String>, // Host API devices are opened on (e.g. "ASIO"), None = cpal's default
   device_sample_rate: u32,
+  // Rate stems are decoded at and positions are counted in; the stream is opened at it
+  project_sample_rate: Arc<std::sync::atomic::AtomicU32>,
+  buffer_size: usize, // Frames per callback requested from the device
+}
+
+/// Shared handles the real-time callback mixes with (cloned into the stream closure)
+#[derive(Clone)]
+struct MixerState {
+  stems: Arc<Mutex<Vec<Option<Stem>>>>,
+  playback_state: Arc<Mutex<PlaybackState>>,
+  position: Arc<AtomicU64>,
+  stem_volumes: Vec<Arc<std::sync::atomic::AtomicU32>>,
+  stem_mutes: Vec<Arc<AtomicBool>>,
+  stem_solos: Vec<Arc<AtomicBool>>,
+  stem_solo_safe: Vec<Arc<AtomicBool>>,
+  stem_phase_inverted: Vec<Arc<AtomicBool>>,
+  stem_swap_channels: Vec<Arc<AtomicBool>>, // Exchange left and right
+  stem_mono_sum: Vec<Arc<AtomicBool>>, // Average left and right into both sides
+  stem_trims: Vec<Arc<std::sync::atomic::AtomicU32>>, // Linear input gain applied with the fader
+  stem_gates: Vec<Arc<AtomicBool>>, // Noise gate on
+  stem_gate_thresholds: Vec<Arc<std::sync::atomic::AtomicU32>>, // Linear level the gate opens at
+  stem_gate_attacks: Vec<Arc<std::sync::atomic::AtomicU32>>, // Gate opening time in seconds
+  stem_gate_releases: Vec<Arc<std::sync::atomic::AtomicU32>>, // Gate closing time in seconds
+  stem_levels: Vec<Arc<std::sync::atomic::AtomicU32>>,
+  stem_outputs: Vec<Arc<AtomicUsize>>,
+  stem_pans: Vec<Arc<std::sync::atomic::AtomicU32>>,
+  stem_offsets: Vec<Arc<AtomicU64>>, // Leading frames skipped when reading each stem
+  stem_delays: Vec<Arc<AtomicI64>>, // Manual latency compensation in frames; negative plays early
+  stem_cued: Vec<Arc<AtomicBool>>, // Copied at unity to the cue output for pre-listening
+  cue_output: Arc<AtomicUsize>, // Output bus used as the cue (headphone) pair, NO_CUE_OUTPUT = none
+  master_volume: Arc<std::sync::atomic::AtomicU32>,
+  master_trim: Arc<std::sync::atomic::AtomicU32>, // Linear loudness-matching gain applied with the master volume
+  master_level: Arc<std::sync::atomic::AtomicU32>,
+  stem_peak_holds: Vec<MeterHold>,
+  master_peak_hold: MeterHold,
+  meter_mode: Arc<AtomicU8>, // MeterMode as u8
+  meter_decay: Arc<std::sync::atomic::AtomicU32>, // dB per second meters fall after a peak
+  meter_hold: Arc<std::sync::atomic::AtomicU32>, // Seconds a peak is held, 0 = no hold
+  // Fade-out gain (1.0 = no fade) and per-frame decrement (0.0 = not fading)
+  fade_gain: Arc<std::sync::atomic::AtomicU32>,
+  fade_step: Arc<std::sync::atomic::AtomicU32>,
+  fade_curve: Arc<AtomicU8>,
+  limiter_enabled: Arc<AtomicBool>,
+  limiter_threshold: Arc<std::sync::atomic::AtomicU32>,
+  highpass_enabled: Arc<AtomicBool>,
+  highpass_cutoff: Arc<std::sync::atomic::AtomicU32>,
+  highpass: Arc<Mutex<MasterHighpass>>,
+  recorder: Arc<Mutex<Option<RecordTap>>>,
+  callback_frames: Arc<AtomicUsize>,
+  playback_rate: Arc<std::sync::atomic::AtomicU32>,
+  position_frac: Arc<std::sync::atomic::AtomicU32>,
+  loop_start: Arc<AtomicU64>,
+  loop_end: Arc<AtomicU64>,
+  // Stop once every stem has run out of samples
+  auto_stop_at_end: Arc<AtomicBool>,
+  song_ended: Arc<AtomicBool>,
+  reverse: Arc<AtomicBool>, // Play backwards from the position, stopping at the start
+  output_channels: usize,
+  sample_rate: u32,
+}
+
+/// Shared meter readings for the UI: ballistic levels and held peaks per stem and for the master
+#[derive(Clone)]
+pub struct MeterHandles {
+  pub stem_levels: Vec<Arc<std::sync::atomic::AtomicU32>>,
+  pub stem_peak_holds: Vec<Arc<std::sync::atomic::AtomicU32>>,
+  pub master_level: Arc<std::sync::atomic::AtomicU32>,
+  pub master_peak_hold: Arc<std::sync::atomic::AtomicU32>,
+}
+
+// A meter's held peak and the frames left before it starts to fall
+#[derive(Clone, Default)]
+struct MeterHold {
+  peak: Arc<std::sync::atomic::AtomicU32>,
+  frames_left: Arc<AtomicU64>,
+}
+
+impl MeterHold {
+  fn reset(&self) {
+    self.peak.store(f32::to_bits(0.0), Ordering::Release);
+    self.frames_left.store(0, Ordering::Release);
+  }
+}
+
+// Meter response for one callback block, read from the engine's settings
+struct MeterBallistics {
+  mode: MeterMode,
+  fall: f32, // Gain a reading falls by over one block
+  rise: f32, // Fraction of the way a VU reading rises towards the block level
+  hold_frames: u64,
+  block_frames: u64,
+}
+
+impl MeterBallistics {
+  fn new(mixer: &MixerState, frames: usize) -> Self {
+    let block_seconds = frames as f32 / mixer.sample_rate.max(1) as f32;
+    let decay = f32::from_bits(mixer.meter_decay.load(Ordering::Acquire));
+    let hold = f32::from_bits(mixer.meter_hold.load(Ordering::Acquire));
+    MeterBallistics {
+      mode: meter_mode_from_u8(mixer.meter_mode.load(Ordering::Acquire)),
+      fall: db_to_linear(-decay * block_seconds),
+      rise: 1.0 - (-block_seconds / VU_INTEGRATION_SECONDS).exp(),
+      hold_frames: (hold * mixer.sample_rate as f32) as u64,
+      block_frames: frames as u64,
+    }
+  }
+
+  // Block level in this mode from its peak and sum of squared samples
+  fn measure(&self, peak: f32, sum_squares: f32, samples: usize) -> f32 {
+    match self.mode {
+      MeterMode::Peak => peak,
+      MeterMode::Rms | MeterMode::VuSlow => (sum_squares / samples.max(1) as f32).sqrt(),
+    }
+  }
+
+  // Update a meter with this block's level: rise (slowly for VU), fall at the decay rate
+  // and latch the held peak
+  fn apply(&self, level: &std::sync::atomic::AtomicU32, hold: &MeterHold, block_level: f32) {
+    let previous = f32::from_bits(level.load(Ordering::Acquire));
+    let reading = if self.mode == MeterMode::VuSlow && block_level > previous {
+      previous + (block_level - previous) * self.rise
+    } else {
+      block_level.max(previous * self.fall)
+    };
+    level.store(f32::to_bits(reading), Ordering::Release);
+
+    let held = f32::from_bits(hold.peak.load(Ordering::Acquire));
+    let frames_left = hold.frames_left.load(Ordering::Acquire);
+    if reading >= held {
+      hold.peak.store(f32::to_bits(reading), Ordering::Release);
+      hold.frames_left.store(self.hold_frames, Ordering::Release);
+    } else if frames_left > 0 {
+      hold.frames_left.store(frames_left.saturating_sub(self.block_frames), Ordering::Release);
+    } else {
+      hold.peak.store(f32::to_bits(reading.max(held * self.fall)), Ordering::Release);
+    }
+  }
 }
 
 struct Stem {
@@ -76,6 +311,87 @@ struct Stem {
   sample_rate: u32,
   channels: u16,
   duration: f64,
+  // Volume envelope as (frame, gain) sorted by frame; replaces the fader when not empty
+  automation: Vec<(f64, f32)>,
+  gate: GateState,
+  // Fader gain applied on the last frame, eased towards the fader setting. None until first played
+  fader: Option<f32>,
+}
+
+// Noise gate state carried between callbacks: a peak envelope follower and the smoothed gain
+#[derive(Debug, Clone, Copy)]
+struct GateState {
+  envelope: f32,
+  gain: f32,
+}
+
+impl Default for GateState {
+  fn default() -> Self {
+    GateState { envelope: 0.0, gain: 1.0 }
+  }
+}
+
+impl GateState {
+  // Advance one frame with input peak `level`, returning the gain for that frame.
+  // `coeffs` are the one-pole (attack, release, detector) coefficients
+  fn process(&mut self, level: f32, threshold: f32, coeffs: (f32, f32, f32)) -> f32 {
+    let (attack, release, detector) = coeffs;
+    self.envelope = if level > self.envelope { level } else { self.envelope * detector };
+    let target = if self.envelope >= threshold { 1.0 } else { 0.0 };
+    let coeff = if target > self.gain { attack } else { release };
+    self.gain = target + (self.gain - target) * coeff;
+    self.gain
+  }
+}
+
+// Master high-pass filter: a 2nd-order Butterworth biquad with transposed direct form II state
+// for each output channel, carried between callbacks
+struct MasterHighpass {
+  cutoff_hz: f32,
+  sample_rate: u32,
+  b: [f64; 3],
+  a: [f64; 2],
+  z: Vec<[f64; 2]>,
+}
+
+impl Default for MasterHighpass {
+  fn default() -> Self {
+    MasterHighpass { cutoff_hz: 0.0, sample_rate: 0, b: [1.0, 0.0, 0.0], a: [0.0, 0.0], z: Vec::new() }
+  }
+}
+
+impl MasterHighpass {
+  // Recompute the coefficients when the cutoff or rate changed and size the state to the output
+  fn configure(&mut self, cutoff_hz: f32, sample_rate: u32, channels: usize) {
+    if self.z.len() != channels {
+      self.z = vec![[0.0; 2]; channels];
+    }
+    if cutoff_hz == self.cutoff_hz && sample_rate == self.sample_rate {
+      return;
+    }
+
+    let w0 = 2.0 * std::f64::consts::PI * cutoff_hz as f64 / sample_rate as f64;
+    let (sin, cos) = w0.sin_cos();
+    let alpha = sin / (2.0 * std::f64::consts::FRAC_1_SQRT_2);
+    let a0 = 1.0 + alpha;
+    self.b = [(1.0 + cos) / 2.0 / a0, -(1.0 + cos) / a0, (1.0 + cos) / 2.0 / a0];
+    self.a = [-2.0 * cos / a0, (1.0 - alpha) / a0];
+    self.cutoff_hz = cutoff_hz;
+    self.sample_rate = sample_rate;
+  }
+
+  fn reset(&mut self) {
+    self.z.iter_mut().for_each(|z| *z = [0.0; 2]);
+  }
+
+  fn process(&mut self, channel: usize, sample: f32) -> f32 {
+    let x = sample as f64;
+    let z = &mut self.z[channel];
+    let y = self.b[0] * x + z[0];
+    z[0] = self.b[1] * x - self.a[0] * y + z[1];
+    z[1] = self.b[2] * x - self.a[1] * y;
+    y as f32
+  }
 }
 
 impl MultiTrackEngine {
@@ -107,10 +423,10 @@ impl MultiTrackEngine {
         "Maximum stems must be at least 1".to_string()
       ));
     }
-    if max_stems > 256 {
+    if max_stems > StemCapacity::MAX {
       return Err(AudioError::DeviceInit(format!(
-        "Maximum 256 stems supported for stability, requested {}",
-        max_stems
+        "Maximum {} stems supported for stability, requested {}",
+        StemCapacity::MAX, max_stems
       )));
     }
 
@@ -120,14 +436,42 @@ impl MultiTrackEngine {
     let mut stem_volumes = Vec::with_capacity(max_stems);
     let mut stem_mutes = Vec::with_capacity(max_stems);
     let mut stem_solos = Vec::with_capacity(max_stems);
+    let mut stem_solo_safe = Vec::with_capacity(max_stems);
+    let mut stem_phase_inverted = Vec::with_capacity(max_stems);
+    let mut stem_swap_channels = Vec::with_capacity(max_stems);
+    let mut stem_mono_sum = Vec::with_capacity(max_stems);
+    let mut stem_trims = Vec::with_capacity(max_stems);
+    let mut stem_gates = Vec::with_capacity(max_stems);
+    let mut stem_gate_thresholds = Vec::with_capacity(max_stems);
+    let mut stem_gate_attacks = Vec::with_capacity(max_stems);
+    let mut stem_gate_releases = Vec::with_capacity(max_stems);
     let mut stem_levels = Vec::with_capacity(max_stems);
+    let mut stem_outputs = Vec::with_capacity(max_stems);
+    let mut stem_pans = Vec::with_capacity(max_stems);
+    let mut stem_offsets = Vec::with_capacity(max_stems);
+    let mut stem_delays = Vec::with_capacity(max_stems);
+    let mut stem_cued = Vec::with_capacity(max_stems);
 
     for _ in 0..max_stems {
       stems_vec.push(None);
       stem_volumes.push(Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(1.0))));
       stem_mutes.push(Arc::new(AtomicBool::new(false)));
       stem_solos.push(Arc::new(AtomicBool::new(false)));
+      stem_solo_safe.push(Arc::new(AtomicBool::new(false)));
+      stem_phase_inverted.push(Arc::new(AtomicBool::new(false)));
+      stem_swap_channels.push(Arc::new(AtomicBool::new(false)));
+      stem_mono_sum.push(Arc::new(AtomicBool::new(false)));
+      stem_trims.push(Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(1.0))));
+      stem_gates.push(Arc::new(AtomicBool::new(false)));
+      stem_gate_thresholds.push(Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(0.0))));
+      stem_gate_attacks.push(Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(DEFAULT_GATE_ATTACK_SECONDS))));
+      stem_gate_releases.push(Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(DEFAULT_GATE_RELEASE_SECONDS))));
       stem_levels.push(Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(0.0))));
+      stem_outputs.push(Arc::new(AtomicUsize::new(0)));
+      stem_pans.push(Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(0.0))));
+      stem_offsets.push(Arc::new(AtomicU64::new(0)));
+      stem_delays.push(Arc::new(AtomicI64::new(0)));
+      stem_cued.push(Arc::new(AtomicBool::new(false)));
     }
 
     let stems = Arc::new(Mutex::new(stems_vec));
@@ -135,6 +479,7 @@ impl MultiTrackEngine {
     let position = Arc::new(AtomicU64::new(0));
     let master_volume = Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(1.0))); // Default to 100%
     let master_level = Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(0.0)));
+    let stem_peak_holds = (0..max_stems).map(|_| MeterHold::default()).collect();
 
     let mut engine = Self {
       max_stems,
@@ -142,14 +487,67 @@ impl MultiTrackEngine {
       stem_volumes,
       stem_mutes,
       stem_solos,
+      stem_solo_safe,
+      stem_phase_inverted,
+      stem_swap_channels,
+      stem_mono_sum,
+      stem_trims,
+      stem_gates,
+      stem_gate_thresholds,
+      stem_gate_attacks,
+      stem_gate_releases,
       stem_levels,
+      stem_outputs,
+      stem_pans,
+      stem_offsets,
+      stem_delays,
+      stem_cued,
+      cue_output: Arc::new(AtomicUsize::new(NO_CUE_OUTPUT)),
       master_volume,
+      master_trim: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(1.0))),
       master_level,
+      stem_peak_holds,
+      master_peak_hold: MeterHold::default(),
+      meter_mode: Arc::new(AtomicU8::new(MeterMode::default() as u8)),
+      meter_decay: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(DEFAULT_METER_DECAY_DB_PER_SEC))),
+      meter_hold: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(0.0))),
       playback_state: playback_state.clone(),
       position: position.clone(),
+      current_duration: Arc::new(AtomicU64::new(f64::to_bits(0.0))),
+      fade_gain: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(1.0))),
+      fade_step: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(0.0))),
+      fade_curve: Arc::new(AtomicU8::new(FadeCurve::default() as u8)),
+      limiter_enabled: Arc::new(AtomicBool::new(true)),
+      limiter_threshold: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(
+        db_to_linear(DEFAULT_LIMITER_THRESHOLD_DB)
+      ))),
+      highpass_enabled: Arc::new(AtomicBool::new(false)),
+      highpass_cutoff: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(DEFAULT_HIGHPASS_CUTOFF_HZ))),
+      highpass: Arc::new(Mutex::new(MasterHighpass::default())),
+      recorder: Arc::new(Mutex::new(None)),
+      recording: None,
+      interrupted_recording: None,
+      callback_frames: Arc::new(AtomicUsize::new(0)),
+      playback_rate: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(1.0))),
+      position_frac: Arc::new(std::sync::atomic::AtomicU32::new(f32::to_bits(0.0))),
+      loop_start: Arc::new(AtomicU64::new(0)),
+      loop_end: Arc::new(AtomicU64::new(0)),
+      auto_stop_at_end: Arc::new(AtomicBool::new(true)),
+      song_ended: Arc::new(AtomicBool::new(false)),
+      reverse: Arc::new(AtomicBool::new(false)),
+      stream_lost: Arc::new(AtomicBool::new(false)),
+      device_fallback: true,
+      fallback_failed: false,
+      output_channels: 2,
+      device_max_channels: 2,
       stream: None,
+      stream_config: None,
       current_device_name: None,
+      #[cfg(not(target_os = "macos"))]
+      audio_host: None,
       device_sample_rate: TARGET_SAMPLE_RATE,
+      project_sample_rate: Arc::new(std::sync::atomic::AtomicU32::new(TARGET_SAMPLE_RATE)),
+      buffer_size: DEFAULT_BUFFER_SIZE,
     };
 
     // Initialize with default device
@@ -184,48 +582,81 @@ impl MultiTrackEngine {
       .map_err(|e| AudioError::DeviceInit(format!("Failed to get supported configs: {}", e)))?;
 
     log::info!("Device supported output configs:");
+    let mut device_max_channels = 0;
+    let mut supported_rates = Vec::new();
     for (i, config) in supported_configs.enumerate() {
       log::info!("  Config #{}: channels={}, sample_rate={:?}",
         i + 1,
         config.channels(),
         config.min_sample_rate()..=config.max_sample_rate()
       );
+      device_max_channels = device_max_channels.max(config.channels() as usize);
+      supported_rates.push(config.min_sample_rate().0..=config.max_sample_rate().0);
+    }
+
+    // A device that lists no configs gets the stereo stream it almost certainly supports
+    if device_max_channels == 0 {
+      device_max_channels = 2;
     }
+    self.output_channels = stream_channel_count(self.output_channels, device_max_channels);
 
-    // Get the device's default configuration to use its preferred sample rate
     let default_config = device
       .default_output_config()
       .map_err(|e| AudioError::DeviceInit(format!("Failed to get default config: {}", e)))?;
+    log::info!("Device default sample rate: {}Hz", default_config.sample_rate().0);
+
+    // Open the stream at the project rate, or the device's preferred rate if it can't run at it
+    let project_sample_rate = self.project_sample_rate();
+    let device_sample_rate = if supported_rates.iter().any(|rates| rates.contains(&project_sample_rate)) {
+      project_sample_rate
+    } else {
+      log::warn!("Device does not support the {}Hz project rate, using {}Hz",
+        project_sample_rate, default_config.sample_rate().0);
+      default_config.sample_rate().0
+    };
 
-    let device_sample_rate = default_config.sample_rate().0;
-    log::info!("Device default sample rate: {}Hz", device_sample_rate);
+    // Use the nearest buffer size the device can actually run at
+    let buffer_size = match *default_config.buffer_size() {
+      cpal::SupportedBufferSize::Range { min, max } => {
+        self.buffer_size.clamp(min as usize, max as usize)
+      }
+      cpal::SupportedBufferSize::Unknown => self.buffer_size,
+    };
+    if buffer_size != self.buffer_size {
+      log::warn!("Device does not support a {} frame buffer, using {}", self.buffer_size, buffer_size);
+    }
 
     let config = StreamConfig {
-      channels: 2,
+      channels: self.output_channels as u16,
       sample_rate: SampleRate(device_sample_rate),
-      buffer_size: cpal::BufferSize::Fixed(BUFFER_SIZE as u32),
+      buffer_size: cpal::BufferSize::Fixed(buffer_size as u32),
     };
 
     log::info!("Building stream with config: channels={}, sample_rate={}, buffer_size={}",
-      config.channels, config.sample_rate.0, BUFFER_SIZE);
-
-    let stems = self.stems.clone();
-    let playback_state = self.playback_state.clone();
-    let position = self.position.clone();
-    let stem_volumes: Vec<_> = self.stem_volumes.iter().cloned().collect();
-    let stem_mutes: Vec<_> = self.stem_mutes.iter().cloned().collect();
-    let stem_solos: Vec<_> = self.stem_solos.iter().cloned().collect();
-    let stem_levels: Vec<_> = self.stem_levels.iter().cloned().collect();
-    let master_volume = self.master_volume.clone();
-    let master_level = self.master_level.clone();
-
-    let err_fn = |err| log::error!("Audio stream error: {}", err);
+      config.channels, config.sample_rate.0, buffer_size);
+
+    let mixer = self.mixer_state();
+
+    // A vanished device (e.g. an unplugged USB interface) is flagged so the
+    // device watcher can rebuild the stream on the default device
+    let stream_lost = self.stream_lost.clone();
+    let err_fn = move |err: cpal::StreamError| {
+      // Drivers keep reporting a vanished device; it's logged once
+      if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+        if !stream_lost.swap(true, Ordering::AcqRel) {
+          log::error!("Audio stream error: {}", err);
+        }
+      } else {
+        log::error!("Audio stream error: {}", err);
+      }
+    };
 
+    let mut stereo_scratch = Vec::new();
     let stream = device
       .build_output_stream(
         &config,
         move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-          Self::audio_callback(data, &stems, &playback_state, &position, &stem_volumes, &stem_mutes, &stem_solos, &stem_levels, &master_volume, &master_level);
+          Self::render(data, &mixer, &mut stereo_scratch);
         },
         err_fn,
         None,
@@ -241,7 +672,20 @@ impl MultiTrackEngine {
     log::info!("Stream is now playing");
 
     self.stream = Some(stream);
+    self.stream_config = Some(ActiveStreamConfig {
+      sample_rate: config.sample_rate.0,
+      requested_buffer_size: match config.buffer_size {
+        cpal::BufferSize::Fixed(frames) => frames,
+        cpal::BufferSize::Default => buffer_size as u32,
+      },
+      buffer_size: None,
+      channels: config.channels,
+    });
+    self.stream_lost.store(false, Ordering::Release);
     self.device_sample_rate = device_sample_rate;
+    self.project_sample_rate.store(device_sample_rate, Ordering::Release);
+    self.device_max_channels = device_max_channels;
+    self.buffer_size = buffer_size;
 
     Ok(())
   }
@@ -266,19 +710,18 @@ impl MultiTrackEngine {
       self.position.clone()
     )?;
 
+    // The render callback hands us an interleaved buffer with one slot per device channel
+    self.device_max_channels = (stream.channels() as usize).max(1);
+    self.output_channels = stream_channel_count(self.output_channels, self.device_max_channels);
+    stream.set_output_channels(self.output_channels);
+    self.buffer_size = stream.set_buffer_frame_size(self.buffer_size as u32)? as usize;
+
     // Set up render callback with our audio processing
-    let stems = self.stems.clone();
-    let playback_state = self.playback_state.clone();
-    let position = self.position.clone();
-    let stem_volumes: Vec<_> = self.stem_volumes.iter().cloned().collect();
-    let stem_mutes: Vec<_> = self.stem_mutes.iter().cloned().collect();
-    let stem_solos: Vec<_> = self.stem_solos.iter().cloned().collect();
-    let stem_levels: Vec<_> = self.stem_levels.iter().cloned().collect();
-    let master_volume = self.master_volume.clone();
-    let master_level = self.master_level.clone();
+    let mixer = self.mixer_state();
 
+    let mut stereo_scratch = Vec::new();
     stream.set_render_callback(move |data: &mut [f32]| {
-      Self::audio_callback(data, &stems, &playback_state, &position, &stem_volumes, &stem_mutes, &stem_solos, &stem_levels, &master_volume, &master_level);
+      Self::render(data, &mixer, &mut stereo_scratch);
     })?;
 
     // Initialize and start the audio unit
@@ -289,9 +732,21 @@ impl MultiTrackEngine {
     let device_sample_rate = stream.sample_rate() as u32;
     log::info!("Device sample rate: {}Hz", device_sample_rate);
 
+    // CoreAudio runs at the device's nominal rate, so the project follows it
+    if device_sample_rate != self.project_sample_rate() {
+      log::warn!("Device runs at {}Hz, not the {}Hz project rate", device_sample_rate, self.project_sample_rate());
+    }
     self.current_device_name = Some(actual_device_name);
     self.device_sample_rate = device_sample_rate;
+    self.project_sample_rate.store(device_sample_rate, Ordering::Release);
     self.stream = Some(stream);
+    self.stream_config = Some(ActiveStreamConfig {
+      sample_rate: device_sample_rate,
+      requested_buffer_size: self.buffer_size as u32,
+      buffer_size: None,
+      channels: self.output_channels as u16,
+    });
+    self.stream_lost.store(false, Ordering::Release);
 
     log::info!("macOS audio stream initialized and started successfully");
     Ok(())
@@ -358,94 +813,409 @@ impl MultiTrackEngine {
     }
   }
 
-  fn audio_callback(
-    output: &mut [f32],
-    stems: &Arc<Mutex<Vec<Option<Stem>>>>,
-    playback_state: &Arc<Mutex<PlaybackState>>,
-    position: &Arc<AtomicU64>,
-    stem_volumes: &[Arc<std::sync::atomic::AtomicU32>],
-    stem_mutes: &[Arc<AtomicBool>],
-    stem_solos: &[Arc<AtomicBool>],
-    stem_levels: &[Arc<std::sync::atomic::AtomicU32>],
-    master_volume: &Arc<std::sync::atomic::AtomicU32>,
-    master_level: &Arc<std::sync::atomic::AtomicU32>,
-  ) {
-    let state = playback_state.lock().unwrap();
+  fn mixer_state(&self) -> MixerState {
+    MixerState {
+      stems: self.stems.clone(),
+      playback_state: self.playback_state.clone(),
+      position: self.position.clone(),
+      stem_volumes: self.stem_volumes.clone(),
+      stem_mutes: self.stem_mutes.clone(),
+      stem_solos: self.stem_solos.clone(),
+      stem_solo_safe: self.stem_solo_safe.clone(),
+      stem_phase_inverted: self.stem_phase_inverted.clone(),
+      stem_swap_channels: self.stem_swap_channels.clone(),
+      stem_mono_sum: self.stem_mono_sum.clone(),
+      stem_trims: self.stem_trims.clone(),
+      stem_gates: self.stem_gates.clone(),
+      stem_gate_thresholds: self.stem_gate_thresholds.clone(),
+      stem_gate_attacks: self.stem_gate_attacks.clone(),
+      stem_gate_releases: self.stem_gate_releases.clone(),
+      stem_offsets: self.stem_offsets.clone(),
+      stem_delays: self.stem_delays.clone(),
+      stem_cued: self.stem_cued.clone(),
+      cue_output: self.cue_output.clone(),
+      stem_levels: self.stem_levels.clone(),
+      stem_outputs: self.stem_outputs.clone(),
+      stem_pans: self.stem_pans.clone(),
+      master_volume: self.master_volume.clone(),
+      master_trim: self.master_trim.clone(),
+      master_level: self.master_level.clone(),
+      stem_peak_holds: self.stem_peak_holds.clone(),
+      master_peak_hold: self.master_peak_hold.clone(),
+      meter_mode: self.meter_mode.clone(),
+      meter_decay: self.meter_decay.clone(),
+      meter_hold: self.meter_hold.clone(),
+      fade_gain: self.fade_gain.clone(),
+      fade_step: self.fade_step.clone(),
+      fade_curve: self.fade_curve.clone(),
+      limiter_enabled: self.limiter_enabled.clone(),
+      limiter_threshold: self.limiter_threshold.clone(),
+      highpass_enabled: self.highpass_enabled.clone(),
+      highpass_cutoff: self.highpass_cutoff.clone(),
+      highpass: self.highpass.clone(),
+      recorder: self.recorder.clone(),
+      callback_frames: self.callback_frames.clone(),
+      playback_rate: self.playback_rate.clone(),
+      position_frac: self.position_frac.clone(),
+      loop_start: self.loop_start.clone(),
+      loop_end: self.loop_end.clone(),
+      auto_stop_at_end: self.auto_stop_at_end.clone(),
+      song_ended: self.song_ended.clone(),
+      reverse: self.reverse.clone(),
+      output_channels: self.output_channels,
+      sample_rate: self.device_sample_rate,
+    }
+  }
+
+  /// Run the mixer on a caller-provided interleaved buffer, as the stream callback would
+  #[cfg(test)]
+  pub(crate) fn process_block(&self, output: &mut [f32], output_channels: usize) {
+    let mut mixer = self.mixer_state();
+    mixer.output_channels = output_channels;
+    Self::render(output, &mixer, &mut Vec::new());
+  }
+
+  // Stream callback entry point. A mono device gets the stereo mix rendered into `stereo_scratch`
+  // (which grows once to the callback size) and folded down at -6 dB so both sides fit
+  fn render(output: &mut [f32], mixer: &MixerState, stereo_scratch: &mut Vec<f32>) {
+    if mixer.output_channels != 1 {
+      Self::audio_callback(output, mixer);
+      return;
+    }
+
+    stereo_scratch.resize(output.len() * 2, 0.0);
+    Self::audio_callback(stereo_scratch, mixer);
+    for (sample, pair) in output.iter_mut().zip(stereo_scratch.chunks_exact(2)) {
+      *sample = (pair[0] + pair[1]) * 0.5;
+    }
+  }
+
+  fn audio_callback(output: &mut [f32], mixer: &MixerState) {
+    mixer.callback_frames.store(output.len() / mixer.output_channels.max(2), Ordering::Relaxed);
+
+    let state = mixer.playback_state.lock().unwrap();
     if *state != PlaybackState::Playing {
       output.fill(0.0);
       // Reset all levels to 0 when not playing
-      for level in stem_levels {
-        level.store(f32::to_bits(0.0), Ordering::Release);
-      }
-      master_level.store(f32::to_bits(0.0), Ordering::Release);
+      reset_meters(&mixer.stem_levels, &mixer.stem_peak_holds, &mixer.master_level, &mixer.master_peak_hold);
       return;
     }
     drop(state);
 
     output.fill(0.0);
 
-    let stems_guard = stems.lock().unwrap();
+    let mut stems_guard = mixer.stems.lock().unwrap();
 
-    let any_soloed = stem_solos
+    let any_soloed = mixer.stem_solos
       .iter()
       .any(|s| s.load(Ordering::Acquire));
 
-    let current_position = position.load(Ordering::Acquire) as usize;
+    // Stems are stereo; each output frame consumes one stereo frame from every stem
+    let output_channels = mixer.output_channels.max(2);
+    let frames = output.len() / output_channels;
+    let bus_count = output_channels / 2;
+    // The cue pair only exists when the selected bus is on this output; it never carries the main mix
+    let cue_bus = mixer.cue_output.load(Ordering::Acquire);
+    let cue_offset = if cue_bus > 0 && cue_bus < bus_count { Some(cue_bus * 2) } else { None };
+    let meters = MeterBallistics::new(mixer, frames);
+
+    let current_position = mixer.position.load(Ordering::Acquire) as usize;
+
+    // Recording tap, if one is running. try_lock so starting or stopping never stalls the callback.
+    // The master and every stem block of this callback are recorded together or not at all
+    let recorder = mixer.recorder.try_lock().ok();
+    let record_tap = recorder.as_deref().and_then(Option::as_ref).filter(|tap| {
+      let stem_blocks = if tap.per_stem() { stems_guard.iter().flatten().count() } else { 0 };
+      tap.begin(1 + stem_blocks, frames * 2)
+    });
+    let stem_tap = record_tap.filter(|tap| tap.per_stem());
+
+    // Source frame for each output frame: varispeed by the playback rate, wrapping inside the loop region
+    let rate = f32::from_bits(mixer.playback_rate.load(Ordering::Acquire)) as f64;
+    let start_frame = (current_position / 2) as f64
+      + f32::from_bits(mixer.position_frac.load(Ordering::Acquire)) as f64;
+    let loop_start = (mixer.loop_start.load(Ordering::Acquire) / 2) as f64;
+    let loop_end = (mixer.loop_end.load(Ordering::Acquire) / 2) as f64;
+    let reverse = mixer.reverse.load(Ordering::Acquire);
+    // Reverse steps back towards the start (frames before it are silent); loop regions don't apply
+    let source_frame = |frame: usize| -> f64 {
+      if reverse {
+        return start_frame - frame as f64 * rate;
+      }
+      let pos = start_frame + frame as f64 * rate;
+      if loop_end > loop_start && pos >= loop_end {
+        loop_start + (pos - loop_start) % (loop_end - loop_start)
+      } else {
+        pos
+      }
+    };
+
+    // Last source frame any stem has audio for (muted stems still count towards the song's length)
+    let mut content_end = 0usize;
 
-    for (idx, stem_opt) in stems_guard.iter().enumerate() {
+    for (idx, stem_opt) in stems_guard.iter_mut().enumerate() {
       if let Some(stem) = stem_opt {
-        let is_muted = stem_mutes[idx].load(Ordering::Acquire);
-        let is_soloed = stem_solos[idx].load(Ordering::Acquire);
+        // Net read shift: alignment offset skips frames, a positive delay pushes the stem later
+        let shift = mixer.stem_offsets[idx].load(Ordering::Acquire) as i64
+          - mixer.stem_delays[idx].load(Ordering::Acquire);
+        content_end = content_end.max(((stem.samples.len() / 2) as i64 - shift).max(0) as usize);
+
+        let stem_frames = stem.samples.len() / 2;
+        let swap = mixer.stem_swap_channels[idx].load(Ordering::Acquire);
+        let mono = mixer.stem_mono_sum[idx].load(Ordering::Acquire);
+        // Interpolated stereo frame at a (shifted) source position, None outside the stem
+        let frame_at = |pos: f64| -> Option<(f32, f32)> {
+          // Frames before a delayed stem's start (or past its end) are silence
+          if pos < 0.0 {
+            return None;
+          }
+          let index = pos as usize;
+          if index >= stem_frames {
+            return None;
+          }
+          let next = (index + 1).min(stem_frames - 1);
+          let t = (pos - index as f64) as f32;
+          let mut left = stem.samples[index * 2] * (1.0 - t) + stem.samples[next * 2] * t;
+          let mut right = stem.samples[index * 2 + 1] * (1.0 - t) + stem.samples[next * 2 + 1] * t;
+          if swap {
+            std::mem::swap(&mut left, &mut right);
+          }
+          if mono {
+            let sum = (left + right) * 0.5;
+            left = sum;
+            right = sum;
+          }
+          Some((left, right))
+        };
+
+        // Pre-listen copy: unity gain, independent of the fader, mute and solo
+        if let Some(cue_offset) = cue_offset {
+          if mixer.stem_cued[idx].load(Ordering::Acquire) {
+            for frame in 0..frames {
+              let timeline = source_frame(frame);
+              if timeline < 0.0 {
+                continue;
+              }
+              if let Some((left, right)) = frame_at(timeline + shift as f64) {
+                let dst = frame * output_channels + cue_offset;
+                output[dst] += left;
+                output[dst + 1] += right;
+              }
+            }
+          }
+        }
 
+        let is_muted = mixer.stem_mutes[idx].load(Ordering::Acquire);
+        let is_soloed = mixer.stem_solos[idx].load(Ordering::Acquire);
+        let is_solo_safe = mixer.stem_solo_safe[idx].load(Ordering::Acquire);
+
+        // Solo-safe stems (e.g. click) keep playing through solos, but still obey their own mute
         let should_output = if any_soloed {
-          is_soloed
+          is_soloed || (is_solo_safe && !is_muted)
         } else {
           !is_muted
         };
+        // Post-fader copy for the stem's recording, silent while the stem is muted
+        let mut take = stem_tap.map(|tap| tap.buffer(frames * 2));
 
         if should_output {
-          let volume_bits = stem_volumes[idx].load(Ordering::Acquire);
-          let volume = f32::from_bits(volume_bits);
-
-          // Read directly from pre-decoded samples
-          let samples_to_copy = output.len().min(stem.samples.len().saturating_sub(current_position));
-
+          // Automation takes over from the fader and is applied per frame below
+          let automated = !stem.automation.is_empty();
+          let fader_target = if automated {
+            1.0
+          } else {
+            f32::from_bits(mixer.stem_volumes[idx].load(Ordering::Acquire))
+          };
+          // The applied fader eases towards the setting frame by frame; a stem starts at its setting
+          let mut fader = stem.fader.unwrap_or(fader_target);
+          let fader_coeff = (-1.0 / (FADER_SMOOTHING_SECONDS * stem.sample_rate as f32)).exp();
+          let trim = f32::from_bits(mixer.stem_trims[idx].load(Ordering::Acquire));
+          // Phase invert flips the polarity of everything the stem contributes
+          let volume = if mixer.stem_phase_inverted[idx].load(Ordering::Acquire) {
+            -trim
+          } else {
+            trim
+          };
+
+          // Balance-style pan: attenuate the opposite side, hard pan silences it
+          let pan = f32::from_bits(mixer.stem_pans[idx].load(Ordering::Acquire));
+          let left_gain = volume * (1.0 - pan).min(1.0);
+          let right_gain = volume * (1.0 + pan).min(1.0);
+
+          // Buses that don't exist on the current output (or are taken by the cue) fold back to the main pair
+          let bus = mixer.stem_outputs[idx].load(Ordering::Acquire);
+          let channel_offset = if bus < bus_count && Some(bus * 2) != cue_offset { bus * 2 } else { 0 };
+
+          // The gate listens to the stem before the trim and fader. The cue copy above stays ungated
+          let gated = mixer.stem_gates[idx].load(Ordering::Acquire);
+          let gate_threshold = f32::from_bits(mixer.stem_gate_thresholds[idx].load(Ordering::Acquire));
+          let gate_coeff = |seconds: f32| (-1.0 / (seconds * stem.sample_rate as f32)).exp();
+          let gate_attack = f32::from_bits(mixer.stem_gate_attacks[idx].load(Ordering::Acquire));
+          let gate_release = f32::from_bits(mixer.stem_gate_releases[idx].load(Ordering::Acquire));
+          let gate_coeffs = (gate_coeff(gate_attack), gate_coeff(gate_release), gate_coeff(GATE_DETECTOR_SECONDS));
+
+          // Read directly from pre-decoded samples, interpolating between frames
           let mut peak = 0.0f32;
-          for i in 0..samples_to_copy {
-            let sample = stem.samples[current_position + i] * volume;
-            output[i] += sample;
-            // Track peak level
-            peak = peak.max(sample.abs());
+          let mut sum_squares = 0.0f32;
+          for frame in 0..frames {
+            fader = fader_target + (fader - fader_target) * fader_coeff;
+            if (fader - fader_target).abs() < 1e-5 {
+              fader = fader_target;
+            }
+            let timeline = source_frame(frame);
+            if timeline < 0.0 {
+              continue;
+            }
+            let Some((left, right)) = frame_at(timeline + shift as f64) else {
+              continue;
+            };
+            let mut gain = fader * if automated { automation_gain(&stem.automation, timeline) } else { 1.0 };
+            if gated {
+              gain *= stem.gate.process(left.abs().max(right.abs()), gate_threshold, gate_coeffs);
+            }
+            let dst = frame * output_channels + channel_offset;
+            let left = left * left_gain * gain;
+            let right = right * right_gain * gain;
+            output[dst] += left;
+            output[dst + 1] += right;
+            if let Some(take) = take.as_mut() {
+              take[frame * 2] = left;
+              take[frame * 2 + 1] = right;
+            }
+            // Track peak and power for the meter
+            peak = peak.max(left.abs()).max(right.abs());
+            sum_squares += left * left + right * right;
           }
 
-          // Store peak level for this stem
-          stem_levels[idx].store(f32::to_bits(peak), Ordering::Release);
+          stem.fader = Some(fader);
+
+          let level = meters.measure(peak, sum_squares, frames * 2);
+          meters.apply(&mixer.stem_levels[idx], &mixer.stem_peak_holds[idx], level);
         } else {
-          // Stem is muted or not soloed, set level to 0
-          stem_levels[idx].store(f32::to_bits(0.0), Ordering::Release);
+          // Stem is muted or not soloed, its meter falls back. Unmuting starts at the fader setting
+          stem.fader = None;
+          meters.apply(&mixer.stem_levels[idx], &mixer.stem_peak_holds[idx], 0.0);
+        }
+
+        if let (Some(tap), Some(take)) = (stem_tap, take) {
+          tap.send(RecordBlock::Stem(idx, take));
         }
       } else {
         // No stem loaded, set level to 0
-        stem_levels[idx].store(f32::to_bits(0.0), Ordering::Release);
+        mixer.stem_levels[idx].store(f32::to_bits(0.0), Ordering::Release);
+        mixer.stem_peak_holds[idx].reset();
       }
     }
 
     drop(stems_guard);
 
-    // Apply master volume to the final mixed output
-    let master_vol_bits = master_volume.load(Ordering::Acquire);
-    let master_vol = f32::from_bits(master_vol_bits);
+    // Apply master volume and the loudness trim to the final mixed output
+    let master_vol_bits = mixer.master_volume.load(Ordering::Acquire);
+    let master_vol = f32::from_bits(master_vol_bits) * f32::from_bits(mixer.master_trim.load(Ordering::Acquire));
+
+    // Fade-out: ramp the gain down across this block, decrementing once per block
+    let fade_step = f32::from_bits(mixer.fade_step.load(Ordering::Acquire));
+    let fade_start = f32::from_bits(mixer.fade_gain.load(Ordering::Acquire));
+    let fade_end = (fade_start - fade_step * frames as f32).max(0.0);
+    let fade_curve = fade_curve_from_u8(mixer.fade_curve.load(Ordering::Acquire));
+
+    // Master limiter keeps the summed stems below the threshold
+    let limiter_enabled = mixer.limiter_enabled.load(Ordering::Acquire);
+    let limiter_threshold = f32::from_bits(mixer.limiter_threshold.load(Ordering::Acquire));
+    // Low-cut after the master gain, ahead of the limiter. The callback never waits on the lock:
+    // while the control thread resets the filter, this block goes out unfiltered
+    let mut highpass = mixer.highpass_enabled
+      .load(Ordering::Acquire)
+      .then(|| mixer.highpass.try_lock().ok())
+      .flatten()
+      .map(|mut highpass| {
+        let cutoff = f32::from_bits(mixer.highpass_cutoff.load(Ordering::Acquire));
+        highpass.configure(cutoff, mixer.sample_rate, output_channels);
+        highpass
+      });
 
     let mut master_peak = 0.0f32;
-    for sample in output.iter_mut() {
-      *sample *= master_vol;
-      master_peak = master_peak.max(sample.abs());
+    let mut master_sum_squares = 0.0f32;
+    let mut master_samples = 0usize;
+    for (frame, samples) in output.chunks_mut(output_channels).enumerate() {
+      // The ramp is linear in time; the curve shapes it into a gain
+      let gain = if fade_step > 0.0 {
+        fade_curve.gain(fade_start - fade_step * frame as f32)
+      } else {
+        1.0
+      };
+      for (channel, sample) in samples.iter_mut().enumerate() {
+        // The cue pair stays at unity: headphone pre-listen isn't part of the main mix
+        if cue_offset.is_some_and(|offset| channel == offset || channel == offset + 1) {
+          continue;
+        }
+        *sample *= master_vol * gain;
+        if let Some(highpass) = highpass.as_mut() {
+          *sample = highpass.process(channel, *sample);
+        }
+        if limiter_enabled {
+          *sample = soft_limit(*sample, limiter_threshold);
+        }
+        master_peak = master_peak.max(sample.abs());
+        master_sum_squares += *sample * *sample;
+        master_samples += 1;
+      }
+    }
+    let master = meters.measure(master_peak, master_sum_squares, master_samples);
+    meters.apply(&mixer.master_level, &mixer.master_peak_hold, master);
+
+    // The recording gets the main pair as the speakers hear it, after the limiter
+    if let Some(tap) = record_tap {
+      let mut block = tap.buffer(frames * 2);
+      for (pair, samples) in block.chunks_exact_mut(2).zip(output.chunks_exact(output_channels)) {
+        pair.copy_from_slice(&samples[..2]);
+      }
+      tap.send(RecordBlock::Master(block));
+    }
+    drop(recorder);
+
+    if fade_step > 0.0 && fade_end <= 0.0 {
+      // Fade finished: stop and rewind, like stop()
+      *mixer.playback_state.lock().unwrap() = PlaybackState::Stopped;
+      mixer.fade_step.store(f32::to_bits(0.0), Ordering::Release);
+      mixer.fade_gain.store(f32::to_bits(1.0), Ordering::Release);
+      mixer.position.store(0, Ordering::Release);
+      mixer.position_frac.store(f32::to_bits(0.0), Ordering::Release);
+      log::info!("Fade-out complete, playback stopped");
+      return;
+    }
+    if fade_step > 0.0 {
+      mixer.fade_gain.store(f32::to_bits(fade_end), Ordering::Release);
     }
-    master_level.store(f32::to_bits(master_peak), Ordering::Release);
 
-    // Advance position by the number of samples we output
-    let new_position = current_position + output.len();
-    position.store(new_position as u64, Ordering::Release);
+    // Advance position by the source frames consumed (stereo samples), keeping the fractional part
+    let end_frame = source_frame(frames);
+    if reverse && end_frame < 0.0 {
+      // Played back to the start; pressing play again goes forwards
+      *mixer.playback_state.lock().unwrap() = PlaybackState::Stopped;
+      mixer.reverse.store(false, Ordering::Release);
+      mixer.position.store(0, Ordering::Release);
+      mixer.position_frac.store(f32::to_bits(0.0), Ordering::Release);
+      log::info!("Reverse playback reached the start, playback stopped");
+      return;
+    }
+    let new_position = end_frame as usize * 2;
+    mixer.position.store(new_position as u64, Ordering::Release);
+    mixer.position_frac.store(f32::to_bits(end_frame.fract() as f32), Ordering::Release);
+
+    // Past the end of every stem: stop and rewind rather than play silence forever.
+    // A loop region keeps wrapping, so it never reaches the end
+    if mixer.auto_stop_at_end.load(Ordering::Acquire)
+      && content_end > 0
+      && loop_end <= loop_start
+      && end_frame >= content_end as f64
+    {
+      *mixer.playback_state.lock().unwrap() = PlaybackState::Stopped;
+      mixer.position.store(0, Ordering::Release);
+      mixer.position_frac.store(f32::to_bits(0.0), Ordering::Release);
+      mixer.song_ended.store(true, Ordering::Release);
+      log::info!("Reached the end of the song, playback stopped");
+    }
   }
 
   pub fn max_stems(&self) -> usize {
@@ -461,10 +1231,78 @@ impl MultiTrackEngine {
     self.active_stems()
   }
 
+  /// Whether each stem slot currently holds a stem, by engine index
+  pub fn loaded_slots(&self) -> Vec<bool> {
+    let stems = self.stems.lock().unwrap();
+    stems.iter().map(|s| s.is_some()).collect()
+  }
+
   pub fn device_sample_rate(&self) -> u32 {
     self.device_sample_rate
   }
 
+  /// Rate stems are decoded at and the transport position is counted in
+  pub fn project_sample_rate(&self) -> u32 {
+    self.project_sample_rate.load(Ordering::Acquire)
+  }
+
+  /// Get a clone of the project rate Arc so position readers can convert samples to seconds
+  pub fn project_sample_rate_arc(&self) -> Arc<std::sync::atomic::AtomicU32> {
+    self.project_sample_rate.clone()
+  }
+
+  /// Change the project rate: the stream is reopened at `rate` and the loaded stems, which were
+  /// decoded at the old rate, are dropped (the transport stops). Fails without changing anything
+  /// if the output device can't run at `rate`
+  pub fn set_project_sample_rate(&mut self, rate: u32) -> AudioResult<()> {
+    if !(MIN_PROJECT_SAMPLE_RATE..=MAX_PROJECT_SAMPLE_RATE).contains(&rate) {
+      return Err(AudioError::InvalidFormat(format!(
+        "Project sample rate must be between {} and {}Hz, got {}",
+        MIN_PROJECT_SAMPLE_RATE, MAX_PROJECT_SAMPLE_RATE, rate
+      )));
+    }
+    let previous = self.project_sample_rate();
+    if rate == previous {
+      return Ok(());
+    }
+    // The recording's files are written at the rate it started with
+    if self.recording.is_some() {
+      return Err(AudioError::PlaybackError(
+        "Stop recording before changing the project sample rate".to_string()
+      ));
+    }
+
+    log::info!("Changing project sample rate from {}Hz to {}Hz", previous, rate);
+    self.project_sample_rate.store(rate, Ordering::Release);
+
+    let device_name = self.current_device_name.clone().unwrap_or_else(|| "default".to_string());
+    let result = self.reopen_stream(&device_name);
+    if result.is_err() || self.device_sample_rate != rate {
+      self.project_sample_rate.store(previous, Ordering::Release);
+      if let Err(e) = self.reopen_stream(&device_name) {
+        log::error!("Failed to reopen the stream at {}Hz: {}", previous, e);
+      }
+      return Err(result.err().unwrap_or_else(|| {
+        AudioError::DeviceInit(format!("Output device does not support {}Hz", rate))
+      }));
+    }
+
+    self.stop()?;
+    self.clear_stems();
+    Ok(())
+  }
+
+  /// Sample rate, buffer size and channel count the running stream negotiated with the device.
+  /// The buffer size is what the callback actually receives, which drivers don't always match
+  /// to the requested one
+  pub fn active_stream_config(&self) -> Option<ActiveStreamConfig> {
+    let frames = self.callback_frames.load(Ordering::Relaxed);
+    self.stream_config.map(|config| ActiveStreamConfig {
+      buffer_size: (frames > 0).then_some(frames as u32),
+      ..config
+    })
+  }
+
   pub fn current_device_name(&self) -> Option<String> {
     self.current_device_name.clone()
   }
@@ -483,11 +1321,12 @@ impl MultiTrackEngine {
     let mut decoded_samples = decoder.decode_all()?;
 
     // Resample if necessary
-    if metadata.sample_rate != self.device_sample_rate {
-      log::info!("Resampling from {}Hz to {}Hz", metadata.sample_rate, self.device_sample_rate);
+    let project_sample_rate = self.project_sample_rate();
+    if metadata.sample_rate != project_sample_rate {
+      log::info!("Resampling from {}Hz to {}Hz", metadata.sample_rate, project_sample_rate);
       let mut resampler = LinearResampler::new(
         metadata.sample_rate,
-        self.device_sample_rate,
+        project_sample_rate,
         metadata.channels,
       );
       decoded_samples = resampler.process(&decoded_samples);
@@ -506,19 +1345,26 @@ impl MultiTrackEngine {
       .position(|s| s.is_none())
       .ok_or_else(|| AudioError::PlaybackError("No available stem slots".to_string()))?;
 
-    let duration = samples.len() as f64 / (self.device_sample_rate as f64 * 2.0);
+    let sample_rate = self.project_sample_rate();
+    let duration = samples.len() as f64 / (sample_rate as f64 * 2.0);
 
     let stem = Stem {
       id: stem_id,
       samples, // No copying - just share the Arc!
-      sample_rate: self.device_sample_rate,
+      sample_rate,
       channels: 2, // Assuming stereo
       duration,
+      automation: Vec::new(),
+      gate: GateState::default(),
+      fader: None,
     };
 
     stems[stem_id] = Some(stem);
+    let longest = stems.iter().flatten().map(|s| s.duration).fold(0.0, f64::max);
     drop(stems);
 
+    self.current_duration.store(f64::to_bits(longest), Ordering::Release);
+
     log::info!("Successfully loaded stem from samples at index {} (zero-copy)", stem_id);
 
     Ok(stem_id)
@@ -534,6 +1380,20 @@ impl MultiTrackEngine {
     drop(stems);
 
     self.position.store(0, Ordering::Release);
+    self.position_frac.store(f32::to_bits(0.0), Ordering::Release);
+    self.current_duration.store(f64::to_bits(0.0), Ordering::Release);
+    for offset in &self.stem_offsets {
+      offset.store(0, Ordering::Release);
+    }
+    for delay in &self.stem_delays {
+      delay.store(0, Ordering::Release);
+    }
+    for cued in &self.stem_cued {
+      cued.store(false, Ordering::Release);
+    }
+    // A loop region and reverse drill belong to the song that was loaded
+    self.clear_loop_region();
+    self.reverse.store(false, Ordering::Release);
   }
 
   pub fn set_stem_volume(&mut self, stem_id: usize, volume: f32) {
@@ -545,6 +1405,13 @@ impl MultiTrackEngine {
     self.stem_volumes[stem_id].store(f32::to_bits(clamped_volume), Ordering::Release);
   }
 
+  /// Apply several stem volumes at once (e.g. recalling a mixer scene)
+  pub fn set_stem_volumes(&mut self, updates: &[(usize, f32)]) {
+    for &(stem_id, volume) in updates {
+      self.set_stem_volume(stem_id, volume);
+    }
+  }
+
   pub fn stem_volume(&self, stem_id: usize) -> f32 {
     if stem_id >= self.max_stems {
       return 0.0;
@@ -564,6 +1431,23 @@ impl MultiTrackEngine {
     }
   }
 
+  /// Set stem pan from -1.0 (hard left) to 1.0 (hard right)
+  pub fn set_stem_pan(&mut self, stem_id: usize, pan: f32) {
+    if stem_id >= self.max_stems {
+      return;
+    }
+
+    self.stem_pans[stem_id].store(f32::to_bits(pan.clamp(-1.0, 1.0)), Ordering::Release);
+  }
+
+  pub fn stem_pan(&self, stem_id: usize) -> f32 {
+    if stem_id >= self.max_stems {
+      return 0.0;
+    }
+
+    f32::from_bits(self.stem_pans[stem_id].load(Ordering::Acquire))
+  }
+
   pub fn set_master_volume(&mut self, volume: f32) {
     let clamped_volume = volume.clamp(0.0, 1.0);
     self.master_volume.store(f32::to_bits(clamped_volume), Ordering::Release);
@@ -574,6 +1458,142 @@ impl MultiTrackEngine {
     f32::from_bits(bits)
   }
 
+  /// Master gain in dB that brings the loaded song to the loudness target (0 dB = unchanged)
+  pub fn set_master_trim_db(&mut self, trim_db: f32) {
+    self.master_trim.store(f32::to_bits(db_to_linear(trim_db)), Ordering::Release);
+  }
+
+  pub fn master_trim_db(&self) -> f32 {
+    20.0 * f32::from_bits(self.master_trim.load(Ordering::Acquire)).log10()
+  }
+
+  /// Stop automatically once playback passes the end of the longest stem (on by default)
+  pub fn set_auto_stop_at_end(&mut self, enabled: bool) {
+    self.auto_stop_at_end.store(enabled, Ordering::Release);
+  }
+
+  pub fn is_auto_stop_at_end(&self) -> bool {
+    self.auto_stop_at_end.load(Ordering::Acquire)
+  }
+
+  /// True once after auto-stop ends a song; the flag is cleared by this call
+  pub fn take_song_ended(&self) -> bool {
+    self.song_ended.swap(false, Ordering::AcqRel)
+  }
+
+  /// Play backwards from the current position, stopping at the start of the song. Reaching the
+  /// start turns reverse off again
+  pub fn set_reverse(&mut self, enabled: bool) {
+    self.reverse.store(enabled, Ordering::Release);
+  }
+
+  pub fn is_reverse(&self) -> bool {
+    self.reverse.load(Ordering::Acquire)
+  }
+
+  pub fn set_limiter_enabled(&mut self, enabled: bool) {
+    self.limiter_enabled.store(enabled, Ordering::Release);
+  }
+
+  pub fn is_limiter_enabled(&self) -> bool {
+    self.limiter_enabled.load(Ordering::Acquire)
+  }
+
+  /// Turn the master high-pass filter on or off and set its cutoff (clamped to 10..200 Hz)
+  pub fn set_master_highpass(&mut self, enabled: bool, cutoff_hz: f32) {
+    let cutoff_hz = cutoff_hz.clamp(MIN_HIGHPASS_CUTOFF_HZ, MAX_HIGHPASS_CUTOFF_HZ);
+    self.highpass_cutoff.store(f32::to_bits(cutoff_hz), Ordering::Release);
+    if enabled && !self.highpass_enabled.load(Ordering::Acquire) {
+      // Don't resume from whatever the filter held when it was last switched off
+      self.highpass.lock().unwrap().reset();
+    }
+    self.highpass_enabled.store(enabled, Ordering::Release);
+  }
+
+  /// Record what's played to WAV files in `output_dir` until stop_recording: the master mix as
+  /// master.wav and, with `per_stem`, each slot's post-fader signal as stem-01.wav, stem-02.wav, ...
+  /// Only blocks rendered while playing are recorded
+  pub fn start_recording(&mut self, output_dir: &std::path::Path, per_stem: bool) -> AudioResult<()> {
+    if self.recording.is_some() {
+      return Err(AudioError::PlaybackError("Already recording".to_string()));
+    }
+
+    // Spares fit the callbacks the device actually delivers, or the requested size before the first
+    let callback_frames = self.callback_frames.load(Ordering::Relaxed).max(self.buffer_size);
+    let (recording, tap) = Recording::start(
+      output_dir,
+      self.device_sample_rate,
+      callback_frames,
+      per_stem,
+      self.max_stems,
+    )?;
+    *self.recorder.lock().unwrap() = Some(tap);
+    self.recording = Some(recording);
+    self.interrupted_recording = None;
+
+    log::info!("Recording {} to {}", if per_stem { "master and stems" } else { "master" }, output_dir.display());
+    Ok(())
+  }
+
+  /// Stop recording and wait for the files to be finished. Blocks on the writer; commands
+  /// use take_recording() and finish it without holding the engine
+  pub fn stop_recording(&mut self) -> AudioResult<RecordingSummary> {
+    match self.take_recording()? {
+      Ok(recording) => recording.finish(),
+      Err(summary) => Ok(summary),
+    }
+  }
+
+  // Stop feeding the recording and hand back its writer to finish (Ok), or the summary of a
+  // recording a device fallback already finished (Err)
+  pub(crate) fn take_recording(&mut self) -> AudioResult<Result<Recording, RecordingSummary>> {
+    let Some(recording) = self.recording.take() else {
+      return self.interrupted_recording
+        .take()
+        .map(Err)
+        .ok_or_else(|| AudioError::PlaybackError("Not recording".to_string()));
+    };
+
+    // Dropping the tap closes the queue; the writer drains what's left and exits
+    self.recorder.lock().unwrap().take();
+    Ok(Ok(recording))
+  }
+
+  pub fn is_recording(&self) -> bool {
+    self.recording.is_some()
+  }
+
+  /// Whether a recording is running, and whether it has lost audio so far
+  pub fn recording_status(&self) -> RecordingStatus {
+    if let Some(recording) = &self.recording {
+      return recording.status();
+    }
+    RecordingStatus {
+      recording: false,
+      dropped_callbacks: self.interrupted_recording.as_ref().map_or(0, |summary| summary.dropped_callbacks),
+      writer_failed: false,
+      interrupted: self.interrupted_recording.is_some(),
+    }
+  }
+
+  pub fn master_highpass(&self) -> (bool, f32) {
+    (
+      self.highpass_enabled.load(Ordering::Acquire),
+      f32::from_bits(self.highpass_cutoff.load(Ordering::Acquire)),
+    )
+  }
+
+  /// Set the master limiter ceiling in dBFS (clamped to -20..0 dB)
+  pub fn set_limiter_threshold_db(&mut self, threshold_db: f32) {
+    let clamped_db = threshold_db.clamp(-20.0, 0.0);
+    self.limiter_threshold.store(f32::to_bits(db_to_linear(clamped_db)), Ordering::Release);
+  }
+
+  pub fn limiter_threshold_db(&self) -> f32 {
+    let linear = f32::from_bits(self.limiter_threshold.load(Ordering::Acquire));
+    20.0 * linear.log10()
+  }
+
   pub fn set_stem_mute(&mut self, stem_id: usize, muted: bool) {
     if stem_id >= self.max_stems {
       return;
@@ -606,57 +1626,549 @@ impl MultiTrackEngine {
     self.stem_solos[stem_id].load(Ordering::Acquire)
   }
 
-  pub fn play(&mut self) -> AudioResult<()> {
-    let mut state = self.playback_state.lock().unwrap();
-    *state = PlaybackState::Playing;
-    Ok(())
-  }
+  /// Mark a stem as solo-safe so it stays audible when other stems are soloed
+  pub fn set_stem_solo_safe(&mut self, stem_id: usize, solo_safe: bool) {
+    if stem_id >= self.max_stems {
+      return;
+    }
 
-  pub fn pause(&mut self) -> AudioResult<()> {
-    let mut state = self.playback_state.lock().unwrap();
-    *state = PlaybackState::Paused;
-    drop(state);
+    self.stem_solo_safe[stem_id].store(solo_safe, Ordering::Release);
+  }
 
-    // Reset all stem levels and master level to 0 immediately
-    for level in &self.stem_levels {
-      level.store(f32::to_bits(0.0), Ordering::Release);
+  /// Flip a stem's polarity before it's summed into the mix
+  pub fn set_stem_phase_invert(&mut self, stem_id: usize, inverted: bool) {
+    if stem_id >= self.max_stems {
+      return;
     }
-    self.master_level.store(f32::to_bits(0.0), Ordering::Release);
 
-    Ok(())
+    self.stem_phase_inverted[stem_id].store(inverted, Ordering::Release);
   }
 
-  pub fn stop(&mut self) -> AudioResult<()> {
-    let mut state = self.playback_state.lock().unwrap();
-    *state = PlaybackState::Stopped;
-    drop(state);
+  pub fn is_stem_phase_inverted(&self, stem_id: usize) -> bool {
+    if stem_id >= self.max_stems {
+      return false;
+    }
 
-    self.position.store(0, Ordering::Release);
+    self.stem_phase_inverted[stem_id].load(Ordering::Acquire)
+  }
 
-    // Reset all stem levels and master level to 0 immediately
-    for level in &self.stem_levels {
-      level.store(f32::to_bits(0.0), Ordering::Release);
+  /// Exchange a stem's left and right channels and/or sum them to mono (e.g. for a mono wedge).
+  /// Swap is applied first, so with both set the result is the same mono sum
+  pub fn set_stem_channel_mode(&mut self, stem_id: usize, swap: bool, mono: bool) {
+    if stem_id >= self.max_stems {
+      return;
     }
-    self.master_level.store(f32::to_bits(0.0), Ordering::Release);
 
-    Ok(())
+    self.stem_swap_channels[stem_id].store(swap, Ordering::Release);
+    self.stem_mono_sum[stem_id].store(mono, Ordering::Release);
   }
 
-  pub fn seek(&mut self, position_seconds: f64) -> AudioResult<()> {
-    // Convert seconds to sample position (stereo, so multiply by 2)
-    let sample_position = (position_seconds * TARGET_SAMPLE_RATE as f64 * 2.0) as u64;
-
-    // Update the position - no need to clear buffers since we read directly from pre-decoded samples
+  /// Input gain in dB applied before the fader (0 dB = unchanged)
+  pub fn set_stem_trim(&mut self, stem_id: usize, trim_db: f32) {
+    if stem_id >= self.max_stems {
+      return;
+    }
+
+    self.stem_trims[stem_id].store(f32::to_bits(db_to_linear(trim_db)), Ordering::Release);
+  }
+
+  pub fn stem_trim_db(&self, stem_id: usize) -> f32 {
+    if stem_id >= self.max_stems {
+      return 0.0;
+    }
+
+    20.0 * f32::from_bits(self.stem_trims[stem_id].load(Ordering::Acquire)).log10()
+  }
+
+  /// Noise gate that silences a stem while it stays below `threshold_db`
+  pub fn set_stem_gate(&mut self, stem_id: usize, threshold_db: f32, enabled: bool) {
+    if stem_id >= self.max_stems {
+      return;
+    }
+
+    self.stem_gate_thresholds[stem_id].store(f32::to_bits(db_to_linear(threshold_db)), Ordering::Release);
+    self.stem_gates[stem_id].store(enabled, Ordering::Release);
+  }
+
+  /// How fast a stem's noise gate opens and closes, in seconds
+  pub fn set_stem_gate_times(&mut self, stem_id: usize, attack_seconds: f32, release_seconds: f32) {
+    if stem_id >= self.max_stems {
+      return;
+    }
+
+    self.stem_gate_attacks[stem_id].store(f32::to_bits(attack_seconds), Ordering::Release);
+    self.stem_gate_releases[stem_id].store(f32::to_bits(release_seconds), Ordering::Release);
+  }
+
+  /// Whether a stem's noise gate is on
+  pub fn is_stem_gated(&self, stem_id: usize) -> bool {
+    if stem_id >= self.max_stems {
+      return false;
+    }
+
+    self.stem_gates[stem_id].load(Ordering::Acquire)
+  }
+
+  /// Linear trim gain for a stem
+  pub fn stem_trim_gain(&self, stem_id: usize) -> f32 {
+    if stem_id >= self.max_stems {
+      return 1.0;
+    }
+
+    f32::from_bits(self.stem_trims[stem_id].load(Ordering::Acquire))
+  }
+
+  /// (swap_channels, mono_sum) for a stem
+  pub fn stem_channel_mode(&self, stem_id: usize) -> (bool, bool) {
+    if stem_id >= self.max_stems {
+      return (false, false);
+    }
+
+    (
+      self.stem_swap_channels[stem_id].load(Ordering::Acquire),
+      self.stem_mono_sum[stem_id].load(Ordering::Acquire),
+    )
+  }
+
+  pub fn is_stem_solo_safe(&self, stem_id: usize) -> bool {
+    if stem_id >= self.max_stems {
+      return false;
+    }
+
+    self.stem_solo_safe[stem_id].load(Ordering::Acquire)
+  }
+
+  /// Skip the first `frames` frames of a stem so its aligned start plays at position 0
+  pub fn set_stem_offset(&mut self, stem_id: usize, frames: u64) {
+    if stem_id >= self.max_stems {
+      return;
+    }
+
+    self.stem_offsets[stem_id].store(frames, Ordering::Release);
+  }
+
+  pub fn stem_offset(&self, stem_id: usize) -> u64 {
+    if stem_id >= self.max_stems {
+      return 0;
+    }
+
+    self.stem_offsets[stem_id].load(Ordering::Acquire)
+  }
+
+  /// Delay a stem by `frames` to compensate for export latency; negative values play it early.
+  /// Reads that would start before the stem's first frame are silence
+  pub fn set_stem_delay(&mut self, stem_id: usize, frames: i64) {
+    if stem_id >= self.max_stems {
+      return;
+    }
+
+    self.stem_delays[stem_id].store(frames, Ordering::Release);
+  }
+
+  pub fn stem_delay(&self, stem_id: usize) -> i64 {
+    if stem_id >= self.max_stems {
+      return 0;
+    }
+
+    self.stem_delays[stem_id].load(Ordering::Acquire)
+  }
+
+
+  /// Route a stem to a stereo output bus (bus 0 = channels 1-2, bus 1 = channels 3-4, ...)
+  pub fn set_stem_output(&mut self, stem_id: usize, bus: usize) -> AudioResult<()> {
+    if stem_id >= self.max_stems {
+      return Err(AudioError::PlaybackError(format!("Invalid stem index {}", stem_id)));
+    }
+    // Buses beyond the current channel count are allowed (they play on the main pair
+    // until the output is widened), so only reject obviously invalid values
+    if bus >= MAX_OUTPUT_BUSES {
+      return Err(AudioError::PlaybackError(format!(
+        "Output bus {} out of range (max {})",
+        bus, MAX_OUTPUT_BUSES - 1
+      )));
+    }
+
+    self.stem_outputs[stem_id].store(bus, Ordering::Release);
+    Ok(())
+  }
+
+  pub fn stem_output(&self, stem_id: usize) -> usize {
+    if stem_id >= self.max_stems {
+      return 0;
+    }
+
+    self.stem_outputs[stem_id].load(Ordering::Acquire)
+  }
+
+  /// Drive a stem's volume from (position_seconds, volume) points instead of its fader,
+  /// interpolating linearly between them. An empty list returns the stem to manual control
+  pub fn set_stem_automation(&mut self, stem_id: usize, points: &[(f64, f32)]) {
+    let mut stems = self.stems.lock().unwrap();
+    let Some(stem) = stems.get_mut(stem_id).and_then(|s| s.as_mut()) else {
+      return;
+    };
+
+    let rate = stem.sample_rate as f64;
+    let mut envelope: Vec<(f64, f32)> = points
+      .iter()
+      .filter(|(seconds, volume)| seconds.is_finite() && volume.is_finite())
+      .map(|&(seconds, volume)| (seconds.max(0.0) * rate, volume.clamp(0.0, 1.0)))
+      .collect();
+    envelope.sort_by(|a, b| a.0.total_cmp(&b.0));
+    stem.automation = envelope;
+    // Control changes hands between the fader and the envelope; don't ramp the fader across it
+    stem.fader = None;
+  }
+
+  pub fn has_stem_automation(&self, stem_id: usize) -> bool {
+    let stems = self.stems.lock().unwrap();
+    stems.get(stem_id).and_then(|s| s.as_ref()).is_some_and(|stem| !stem.automation.is_empty())
+  }
+
+  /// Automated volume of a stem at a song position, None when the stem isn't automated
+  pub fn stem_automation_gain(&self, stem_id: usize, position_seconds: f64) -> Option<f32> {
+    let stems = self.stems.lock().unwrap();
+    let stem = stems.get(stem_id).and_then(|s| s.as_ref())?;
+    if stem.automation.is_empty() {
+      return None;
+    }
+
+    Some(automation_gain(&stem.automation, position_seconds * stem.sample_rate as f64))
+  }
+
+  /// Send a copy of a stem to the cue output at unity gain, leaving the main mix untouched
+  pub fn set_stem_cue(&mut self, stem_id: usize, enabled: bool) {
+    if stem_id >= self.max_stems {
+      return;
+    }
+
+    self.stem_cued[stem_id].store(enabled, Ordering::Release);
+  }
+
+  pub fn is_stem_cued(&self, stem_id: usize) -> bool {
+    if stem_id >= self.max_stems {
+      return false;
+    }
+
+    self.stem_cued[stem_id].load(Ordering::Acquire)
+  }
+
+  /// Choose the output bus used as the cue (headphone) pair, or None to turn cueing off.
+  /// While set, the pair carries only cued stems; stems routed to it play on the main pair
+  pub fn set_cue_output(&mut self, bus: Option<usize>) -> AudioResult<()> {
+    match bus {
+      Some(0) => Err(AudioError::PlaybackError(
+        "The cue output can't be the main pair".to_string()
+      )),
+      Some(bus) if bus >= MAX_OUTPUT_BUSES => Err(AudioError::PlaybackError(format!(
+        "Output bus {} out of range (max {})",
+        bus, MAX_OUTPUT_BUSES - 1
+      ))),
+      _ => {
+        self.cue_output.store(bus.unwrap_or(NO_CUE_OUTPUT), Ordering::Release);
+        Ok(())
+      }
+    }
+  }
+
+  pub fn cue_output(&self) -> Option<usize> {
+    let bus = self.cue_output.load(Ordering::Acquire);
+    (bus != NO_CUE_OUTPUT).then_some(bus)
+  }
+
+  /// Number of interleaved channels the output stream is built with
+  pub fn output_channels(&self) -> usize {
+    self.output_channels
+  }
+
+  /// Number of stereo buses available on the current output stream (0 on a mono device)
+  pub fn output_bus_count(&self) -> usize {
+    self.output_channels / 2
+  }
+
+  /// Maximum number of output channels the current device supports
+  pub fn device_channel_count(&self) -> usize {
+    self.device_max_channels
+  }
+
+  /// Rebuild the output stream with the given number of channels (must be an even count)
+  pub fn set_output_channels(&mut self, channels: usize) -> AudioResult<()> {
+    if channels < 2 || channels % 2 == 1 {
+      return Err(AudioError::InvalidFormat(format!(
+        "Output channel count must be an even number of at least 2, got {}",
+        channels
+      )));
+    }
+    if channels > self.device_max_channels {
+      return Err(AudioError::DeviceInit(format!(
+        "Device supports at most {} output channels, requested {}",
+        self.device_max_channels, channels
+      )));
+    }
+    if channels == self.output_channels {
+      return Ok(());
+    }
+
+    log::info!("Changing output channel count from {} to {}", self.output_channels, channels);
+    self.output_channels = channels;
+
+    let device_name = self.current_device_name.clone().unwrap_or_else(|| "default".to_string());
+    self.switch_audio_device(&device_name).map(|_| ())
+  }
+
+  /// Rebuild the output stream with a new buffer size (frames per callback), keeping
+  /// the transport where it is. Returns the size actually in use, which is the nearest
+  /// one the device supports
+  pub fn set_buffer_size(&mut self, frames: usize) -> AudioResult<usize> {
+    if !(MIN_BUFFER_SIZE..=MAX_BUFFER_SIZE).contains(&frames) {
+      return Err(AudioError::InvalidFormat(format!(
+        "Buffer size must be between {} and {} frames, got {}",
+        MIN_BUFFER_SIZE, MAX_BUFFER_SIZE, frames
+      )));
+    }
+    if frames == self.buffer_size {
+      return Ok(frames);
+    }
+
+    log::info!("Changing buffer size from {} to {} frames", self.buffer_size, frames);
+    let previous = self.buffer_size;
+    self.buffer_size = frames;
+
+    let device_name = self.current_device_name.clone().unwrap_or_else(|| "default".to_string());
+    if let Err(e) = self.switch_audio_device(&device_name) {
+      self.buffer_size = previous;
+      return Err(e);
+    }
+
+    Ok(self.buffer_size)
+  }
+
+  /// Get the buffer size (frames per callback) the output stream is running with
+  pub fn current_buffer_size(&self) -> usize {
+    self.buffer_size
+  }
+
+  pub fn play(&mut self) -> AudioResult<()> {
+    self.cancel_fade();
+    self.song_ended.store(false, Ordering::Release);
+    let mut state = self.playback_state.lock().unwrap();
+    *state = PlaybackState::Playing;
+    Ok(())
+  }
+
+  pub fn pause(&mut self) -> AudioResult<()> {
+    let mut state = self.playback_state.lock().unwrap();
+    *state = PlaybackState::Paused;
+    drop(state);
+
+    // Reset all stem levels and master level to 0 immediately
+    self.reset_meters();
+
+    Ok(())
+  }
+
+  pub fn stop(&mut self) -> AudioResult<()> {
+    self.cancel_fade();
+    let mut state = self.playback_state.lock().unwrap();
+    *state = PlaybackState::Stopped;
+    drop(state);
+
+    self.position.store(0, Ordering::Release);
+    self.position_frac.store(f32::to_bits(0.0), Ordering::Release);
+
+    // Reset all stem levels and master level to 0 immediately
+    self.reset_meters();
+
+    Ok(())
+  }
+
+  /// Emergency stop: silence the output at once, stop, rewind and drop any cues. Master volume
+  /// is held at zero while the transport stops, then restored so the next song isn't silent
+  pub fn panic_stop(&mut self) {
+    let master_volume = self.master_volume.swap(f32::to_bits(0.0), Ordering::AcqRel);
+
+    self.cancel_fade();
+    // Works even if a panicked thread poisoned the state lock
+    *self.playback_state.lock().unwrap_or_else(|e| e.into_inner()) = PlaybackState::Stopped;
+    self.position.store(0, Ordering::Release);
+    self.position_frac.store(f32::to_bits(0.0), Ordering::Release);
+
+    for cued in &self.stem_cued {
+      cued.store(false, Ordering::Release);
+    }
+    self.reset_meters();
+
+    self.master_volume.store(master_volume, Ordering::Release);
+  }
+
+  /// Ramp the master output to silence over `duration_seconds`, then stop and rewind
+  pub fn fade_out_and_stop(&mut self, duration_seconds: f64) -> AudioResult<()> {
+    if self.state() != PlaybackState::Playing || duration_seconds <= 0.0 {
+      return self.stop();
+    }
+
+    let total_frames = duration_seconds * self.project_sample_rate() as f64;
+    let step = (1.0 / total_frames) as f32;
+
+    log::info!("Fading out over {:.2}s", duration_seconds);
+    self.fade_gain.store(f32::to_bits(1.0), Ordering::Release);
+    self.fade_step.store(f32::to_bits(step), Ordering::Release);
+
+    Ok(())
+  }
+
+  /// Set the curve used by fade-outs
+  pub fn set_fade_curve(&mut self, curve: FadeCurve) {
+    self.fade_curve.store(curve as u8, Ordering::Release);
+  }
+
+  pub fn fade_curve(&self) -> FadeCurve {
+    fade_curve_from_u8(self.fade_curve.load(Ordering::Acquire))
+  }
+
+  /// Whether a fade-out is currently in progress
+  pub fn is_fading(&self) -> bool {
+    f32::from_bits(self.fade_step.load(Ordering::Acquire)) > 0.0
+  }
+
+  fn cancel_fade(&self) {
+    self.fade_step.store(f32::to_bits(0.0), Ordering::Release);
+    self.fade_gain.store(f32::to_bits(1.0), Ordering::Release);
+  }
+
+  /// Jump to `position_seconds` on the song timeline. While a loop region is active the target
+  /// is clamped into it (up to its last frame), so a seek never escapes the loop
+  pub fn seek(&mut self, position_seconds: f64) -> AudioResult<()> {
+    // Work in whole frames: an odd sample position would split a stereo frame
+    let rate = self.project_sample_rate() as f64;
+    let mut target_frame = (position_seconds * rate).max(0.0);
+    let loop_start = self.loop_start.load(Ordering::Acquire) / 2;
+    let loop_end = self.loop_end.load(Ordering::Acquire) / 2;
+    if loop_end > loop_start {
+      target_frame = target_frame.clamp(loop_start as f64, (loop_end - 1) as f64);
+    }
+
+    // Stems read at the timeline plus their own offset/delay, so moving the shared frame position
+    // (and its fraction) keeps every stem aligned - no need to clear buffers since we read
+    // directly from pre-decoded samples
+    let sample_position = target_frame as u64 * 2;
     self.position.store(sample_position, Ordering::Release);
+    self.position_frac.store(f32::to_bits(target_frame.fract() as f32), Ordering::Release);
+
+    // A gate held open (or closed) by the old position would otherwise carry over to the new one
+    for stem in self.stems.lock().unwrap().iter_mut().flatten() {
+      stem.gate = GateState::default();
+    }
+
+    log::info!(
+      "Seeked to position: {} seconds ({} samples, requested {} seconds)",
+      target_frame / rate,
+      sample_position,
+      position_seconds
+    );
+
+    Ok(())
+  }
+
+  /// Seek relative to the current position, clamped to [0, duration]; returns the new position
+  pub fn seek_relative(&mut self, offset_seconds: f64) -> AudioResult<f64> {
+    let target = (self.position() + offset_seconds).clamp(0.0, self.duration());
+    self.seek(target)?;
+    Ok(target)
+  }
+
+  /// Set the playback rate (1.0 = normal speed); pitch follows the rate
+  pub fn set_playback_rate(&mut self, rate: f32) -> AudioResult<()> {
+    if !(MIN_PLAYBACK_RATE..=MAX_PLAYBACK_RATE).contains(&rate) {
+      return Err(AudioError::PlaybackError(format!(
+        "Playback rate must be between {} and {}, got {}",
+        MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE, rate
+      )));
+    }
+    self.playback_rate.store(f32::to_bits(rate), Ordering::Release);
+    Ok(())
+  }
+
+  pub fn playback_rate(&self) -> f32 {
+    f32::from_bits(self.playback_rate.load(Ordering::Acquire))
+  }
+
+  /// Loop playback between `start_seconds` and `end_seconds`
+  pub fn set_loop_region(&mut self, start_seconds: f64, end_seconds: f64) -> AudioResult<()> {
+    self.validate_loop_region(start_seconds, end_seconds)?;
+
+    let rate = self.project_sample_rate() as f64;
+    let to_samples = |seconds: f64| (seconds * rate) as u64 * 2;
+    self.loop_start.store(to_samples(start_seconds), Ordering::Release);
+    self.loop_end.store(to_samples(end_seconds), Ordering::Release);
+
+    log::info!("Loop region set: {:.2}s - {:.2}s", start_seconds, end_seconds);
+    Ok(())
+  }
+
+  pub fn clear_loop_region(&mut self) {
+    self.loop_start.store(0, Ordering::Release);
+    self.loop_end.store(0, Ordering::Release);
+  }
 
-    log::info!("Seeked to position: {} seconds ({} samples)", position_seconds, sample_position);
+  /// Current loop region in seconds, if one is set
+  pub fn loop_region(&self) -> Option<(f64, f64)> {
+    let start = self.loop_start.load(Ordering::Acquire);
+    let end = self.loop_end.load(Ordering::Acquire);
+    let rate = self.project_sample_rate() as f64;
+    let to_seconds = |samples: u64| samples as f64 / (rate * 2.0);
+    (end > start).then(|| (to_seconds(start), to_seconds(end)))
+  }
 
+  fn validate_loop_region(&self, start_seconds: f64, end_seconds: f64) -> AudioResult<()> {
+    if !start_seconds.is_finite() || !end_seconds.is_finite() || start_seconds < 0.0 || end_seconds <= start_seconds {
+      return Err(AudioError::PlaybackError(format!(
+        "Invalid loop region: {}s - {}s", start_seconds, end_seconds
+      )));
+    }
+    let duration = self.duration();
+    if duration > 0.0 && end_seconds > duration {
+      return Err(AudioError::PlaybackError(format!(
+        "Loop end {}s is past the end of the song ({}s)", end_seconds, duration
+      )));
+    }
     Ok(())
   }
 
+  /// Drill a passage: loop `start..end` at `rate` and play from the loop start
+  /// Nothing changes if the region or rate is invalid
+  pub fn start_practice_loop(&mut self, start_seconds: f64, end_seconds: f64, rate: f32) -> AudioResult<()> {
+    self.validate_loop_region(start_seconds, end_seconds)?;
+    self.set_playback_rate(rate)?;
+    self.set_loop_region(start_seconds, end_seconds)?;
+    self.seek(start_seconds)?;
+    self.play()
+  }
+
+  /// Leave practice mode: clear the loop region and return to normal speed (transport is untouched)
+  pub fn stop_practice_loop(&mut self) {
+    self.clear_loop_region();
+    self.playback_rate.store(f32::to_bits(1.0), Ordering::Release);
+  }
+
+  /// Length of the loaded song in seconds (the longest stem), 0.0 if nothing is loaded
+  pub fn duration(&self) -> f64 {
+    self.current_duration()
+  }
+
+  /// Duration of the longest currently loaded stem in seconds (0.0 if no song is loaded)
+  pub fn current_duration(&self) -> f64 {
+    f64::from_bits(self.current_duration.load(Ordering::Acquire))
+  }
+
+  /// Get a clone of the current duration Arc (f64 bits) for cross-thread access
+  pub fn current_duration_arc(&self) -> Arc<AtomicU64> {
+    self.current_duration.clone()
+  }
+
   pub fn position(&self) -> f64 {
     let sample_position = self.position.load(Ordering::Acquire);
-    sample_position as f64 / (TARGET_SAMPLE_RATE as f64 * 2.0)
+    sample_position as f64 / (self.project_sample_rate() as f64 * 2.0)
   }
 
   pub fn state(&self) -> PlaybackState {
@@ -681,6 +2193,47 @@ impl MultiTrackEngine {
       .collect()
   }
 
+  /// Held peak per stem (equal to the level when peak hold is off)
+  pub fn get_stem_peak_holds(&self) -> Vec<f32> {
+    self.stem_peak_holds
+      .iter()
+      .map(|hold| f32::from_bits(hold.peak.load(Ordering::Acquire)))
+      .collect()
+  }
+
+  /// Shared meter readings for an emitter thread
+  pub fn meter_handles(&self) -> MeterHandles {
+    MeterHandles {
+      stem_levels: self.stem_levels.clone(),
+      stem_peak_holds: self.stem_peak_holds.iter().map(|hold| hold.peak.clone()).collect(),
+      master_level: self.master_level.clone(),
+      master_peak_hold: self.master_peak_hold.peak.clone(),
+    }
+  }
+
+  /// Measure meters as peak, RMS or slow-rising VU
+  pub fn set_meter_mode(&mut self, mode: MeterMode) {
+    self.meter_mode.store(mode as u8, Ordering::Release);
+  }
+
+  pub fn meter_mode(&self) -> MeterMode {
+    meter_mode_from_u8(self.meter_mode.load(Ordering::Acquire))
+  }
+
+  /// How fast meter readings fall after a peak, in dB per second
+  pub fn set_meter_decay(&mut self, db_per_second: f32) {
+    self.meter_decay.store(f32::to_bits(db_per_second.max(0.0)), Ordering::Release);
+  }
+
+  /// Hold each meter's peak for `seconds` before it falls (0 = no hold)
+  pub fn set_meter_peak_hold(&mut self, seconds: f32) {
+    self.meter_hold.store(f32::to_bits(seconds.max(0.0)), Ordering::Release);
+  }
+
+  fn reset_meters(&self) {
+    reset_meters(&self.stem_levels, &self.stem_peak_holds, &self.master_level, &self.master_peak_hold);
+  }
+
   /// Get a clone of the stem levels Arc for cross-thread access
   pub fn stem_levels_arc(&self) -> Vec<Arc<std::sync::atomic::AtomicU32>> {
     self.stem_levels.clone()
@@ -697,18 +2250,39 @@ impl MultiTrackEngine {
   }
 
 
-  /// Switch to a different audio output device by name
-  pub fn switch_audio_device(&mut self, device_name: &str) -> AudioResult<()> {
+  /// Switch to a different audio output device by name. Returns the new project rate when the
+  /// device can't run at the current one and the project moved to the device's rate instead.
+  /// The loaded stems were decoded at the old rate, so they're dropped and the transport stops
+  pub fn switch_audio_device(&mut self, device_name: &str) -> AudioResult<Option<u32>> {
+    let requested_rate = self.project_sample_rate();
+    self.reopen_stream(device_name)?;
+
+    let rate = self.project_sample_rate();
+    if rate != requested_rate {
+      log::warn!("Project sample rate moved from {}Hz to {}Hz to match {}", requested_rate, rate, device_name);
+      self.stop()?;
+      self.clear_stems();
+      return Ok(Some(rate));
+    }
+    Ok(None)
+  }
+
+  // Open the stream on `device_name`, keeping the position and transport state
+  fn reopen_stream(&mut self, device_name: &str) -> AudioResult<()> {
+    // The recording's files are written at the current device's rate and callback size
+    if self.recording.is_some() {
+      return Err(AudioError::PlaybackError(
+        "Stop recording before changing the output device".to_string()
+      ));
+    }
+
     log::info!("Switching audio device to: {}", device_name);
 
-    // Save current playback state and position
+    // Save current playback state
     let was_playing = {
       let state = self.playback_state.lock().unwrap();
       *state == PlaybackState::Playing
     };
-    let current_position = self.position.load(Ordering::Acquire);
-
-    log::info!("Current state: playing={}, position={}", was_playing, current_position);
 
     // Pause playback (don't use stop() as it resets position)
     {
@@ -719,11 +2293,40 @@ impl MultiTrackEngine {
     // Wait a moment for the audio callback to finish processing
     std::thread::sleep(std::time::Duration::from_millis(50));
 
+    // Capture the position only now the callback has stopped advancing it, including the
+    // fraction of a frame varispeed leaves, so resuming continues from the exact sample
+    let current_position = self.position.load(Ordering::Acquire);
+    let current_frac = self.position_frac.load(Ordering::Acquire);
+    log::info!("Current state: playing={}, position={}", was_playing, current_position);
+
+    let result = self.rebuild_stream(device_name);
+
+    // Restore position, even if the new stream failed to open
+    self.position.store(current_position, Ordering::Release);
+    self.position_frac.store(current_frac, Ordering::Release);
+    log::info!("Restored position to: {}", current_position);
+    result?;
+
+    // Restore playback state if it was playing
+    if was_playing {
+      let mut state = self.playback_state.lock().unwrap();
+      *state = PlaybackState::Playing;
+      log::info!("Resumed playback");
+    }
+
+    log::info!("Successfully switched to device: {}", device_name);
+    Ok(())
+  }
+
+  // Drop the current stream and open a new one on `device_name`
+  fn rebuild_stream(&mut self, device_name: &str) -> AudioResult<()> {
     // Drop the current stream completely
     if let Some(stream) = self.stream.take() {
       log::info!("Dropping old stream");
       drop(stream);
     }
+    self.stream_config = None;
+    self.callback_frames.store(0, Ordering::Relaxed);
 
     // Wait another moment to ensure stream is fully dropped
     std::thread::sleep(std::time::Duration::from_millis(50));
@@ -737,12 +2340,7 @@ impl MultiTrackEngine {
     #[cfg(not(target_os = "macos"))]
     {
       // Find the new device
-      let host = cpal::default_host();
-      let device = host
-        .output_devices()
-        .map_err(|e| AudioError::DeviceInit(format!("Failed to enumerate devices: {}", e)))?
-        .find(|d| d.name().ok().as_deref() == Some(device_name))
-        .ok_or_else(|| AudioError::DeviceInit(format!("Device '{}' not found", device_name)))?;
+      let device = find_output_device(self.audio_host.as_deref(), device_name)?;
 
       log::info!("Found device for stream: {:?}", device.name());
 
@@ -750,20 +2348,265 @@ impl MultiTrackEngine {
       self.initialize_stream(&device)?;
     }
 
-    // Restore position
-    self.position.store(current_position, Ordering::Release);
-    log::info!("Restored position to: {}", current_position);
+    Ok(())
+  }
 
-    // Restore playback state if it was playing
-    if was_playing {
-      let mut state = self.playback_state.lock().unwrap();
-      *state = PlaybackState::Playing;
-      log::info!("Resumed playback");
+  /// Name of the host API output devices are opened on
+  pub fn audio_host_name(&self) -> String {
+    #[cfg(target_os = "macos")]
+    {
+      "CoreAudio".to_string()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+      audio_host(self.audio_host.as_deref()).id().name().to_string()
+    }
+  }
+
+  /// Rebuild the stream on the default device of another host API (e.g. "ASIO" instead of
+  /// WASAPI on Windows), keeping the loaded stems and position. Returns the device now in use.
+  /// CoreAudio is the only host on macOS, so there this just checks the name
+  pub fn set_audio_host(&mut self, host_name: &str) -> AudioResult<Option<String>> {
+    #[cfg(target_os = "macos")]
+    {
+      if host_name != "CoreAudio" {
+        return Err(AudioError::DeviceInit(format!("Audio host '{}' is not available", host_name)));
+      }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+      let host_id = cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name() == host_name)
+        .ok_or_else(|| AudioError::DeviceInit(format!("Audio host '{}' is not available", host_name)))?;
+      let device_name = cpal::host_from_id(host_id)
+        .map_err(|e| AudioError::DeviceInit(format!("Failed to open audio host '{}': {}", host_name, e)))?
+        .default_output_device()
+        .and_then(|d| d.name().ok())
+        .ok_or_else(|| AudioError::DeviceInit(format!("No output device available on '{}'", host_name)))?;
+
+      log::info!("Switching audio host to {} (device: {})", host_name, device_name);
+
+      let previous_host = self.audio_host.replace(host_name.to_string());
+      if let Err(e) = self.switch_audio_device(&device_name) {
+        self.audio_host = previous_host;
+        return Err(e);
+      }
+    }
+
+    Ok(self.current_device_name.clone())
+  }
+
+  /// Rebuild the stream on the system default device when the current device is lost (on by default)
+  pub fn set_device_fallback_enabled(&mut self, enabled: bool) {
+    self.device_fallback = enabled;
+  }
+
+  pub fn is_device_fallback_enabled(&self) -> bool {
+    self.device_fallback
+  }
+
+  /// Whether the output device has gone away since the stream was built
+  pub fn is_device_disconnected(&self) -> bool {
+    if self.stream_lost.load(Ordering::Acquire) {
+      return true;
+    }
+
+    // CoreAudio has no stream error callback; check the device is still listed instead
+    #[cfg(target_os = "macos")]
+    {
+      if let Some(name) = &self.current_device_name {
+        return MacOSAudioStream::find_device_id(name).is_err();
+      }
+    }
+
+    false
+  }
+
+  /// If the output device was lost and fallback is enabled, move playback to the
+  /// system default device, keeping the loaded stems, position and transport state (unless the
+  /// default device can't run at the project rate, see `switch_audio_device`).
+  /// Returns the new device name when a fallback happened. A failed fallback is retried on the
+  /// next check, but only the first attempt for each loss is logged
+  pub fn check_device_fallback(&mut self) -> AudioResult<Option<String>> {
+    if !self.device_fallback || !self.is_device_disconnected() {
+      return Ok(None);
+    }
+
+    if !self.fallback_failed {
+      log::warn!("Output device {:?} disconnected, falling back to the default device", self.current_device_name);
+    }
+    let result = self.fall_back_to_default();
+    self.fallback_failed = result.is_err();
+    result?;
+
+    Ok(self.current_device_name.clone())
+  }
+
+  /// Whether the last fallback attempt failed, so the current device loss is already reported
+  pub fn device_fallback_failed(&self) -> bool {
+    self.fallback_failed
+  }
+
+  fn fall_back_to_default(&mut self) -> AudioResult<()> {
+    // Nothing more reaches the recording from the lost device. Finish its files there; the
+    // summary waits for stop_recording
+    if let Some(recording) = self.recording.take() {
+      self.recorder.lock().unwrap().take();
+      let summary = recording.finish()?;
+      log::warn!("Recording ended by the device loss after {} frames", summary.frames);
+      self.interrupted_recording = Some(summary);
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+      self.switch_audio_device("default")?;
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+      let default_name = audio_host(self.audio_host.as_deref())
+        .default_output_device()
+        .and_then(|d| d.name().ok())
+        .ok_or_else(|| AudioError::DeviceInit("No output device available".to_string()))?;
+      self.switch_audio_device(&default_name)?;
     }
 
-    log::info!("Successfully switched to device: {}", device_name);
     Ok(())
   }
+
+  /// Flag the stream as lost, as the error callback does when a device is unplugged
+  #[cfg(test)]
+  pub(crate) fn simulate_device_loss(&self) {
+    self.stream_lost.store(true, Ordering::Release);
+  }
+}
+
+/// Names of the audio host APIs available on this platform, the default first
+pub fn available_audio_hosts() -> Vec<String> {
+  #[cfg(target_os = "macos")]
+  {
+    vec!["CoreAudio".to_string()]
+  }
+
+  #[cfg(not(target_os = "macos"))]
+  {
+    let default_id = cpal::default_host().id();
+    let mut hosts: Vec<String> = vec![default_id.name().to_string()];
+    hosts.extend(
+      cpal::available_hosts()
+        .into_iter()
+        .filter(|id| *id != default_id)
+        .map(|id| id.name().to_string())
+    );
+    hosts
+  }
+}
+
+/// The named host API, or cpal's default when it's unset or unavailable
+#[cfg(not(target_os = "macos"))]
+pub fn audio_host(host_name: Option<&str>) -> cpal::Host {
+  host_name
+    .and_then(|name| cpal::available_hosts().into_iter().find(|id| id.name() == name))
+    .and_then(|id| cpal::host_from_id(id).ok())
+    .unwrap_or_else(cpal::default_host)
+}
+
+/// Look up an output device by name on the named host API (None = cpal's default host)
+#[cfg(not(target_os = "macos"))]
+pub fn find_output_device(host_name: Option<&str>, device_name: &str) -> AudioResult<Device> {
+  audio_host(host_name)
+    .output_devices()
+    .map_err(|e| AudioError::DeviceInit(format!("Failed to enumerate devices: {}", e)))?
+    .find(|d| d.name().ok().as_deref() == Some(device_name))
+    .ok_or_else(|| AudioError::DeviceInit(format!("Device '{}' not found", device_name)))
+}
+
+// Channels to open a stream with: mono on a mono-only device (the mix is folded down),
+// otherwise the requested layout, falling back to stereo if the device can't provide it
+pub(crate) fn stream_channel_count(requested: usize, device_max_channels: usize) -> usize {
+  if device_max_channels == 1 {
+    log::warn!("Device is mono only, folding the stereo mix down to one channel");
+    return 1;
+  }
+  if requested < 2 || requested > device_max_channels {
+    if requested > device_max_channels {
+      log::warn!("Device only supports {} channels, falling back to stereo output", device_max_channels);
+    }
+    return 2;
+  }
+  requested
+}
+
+// Gain of a (frame, gain) envelope at `frame`: linear between points, held flat before
+// the first point and after the last. `points` must be sorted and not empty
+fn automation_gain(points: &[(f64, f32)], frame: f64) -> f32 {
+  let next = points.partition_point(|&(point_frame, _)| point_frame <= frame);
+  if next == 0 {
+    return points[0].1;
+  }
+  if next == points.len() {
+    return points[next - 1].1;
+  }
+
+  let (start_frame, start_gain) = points[next - 1];
+  let (end_frame, end_gain) = points[next];
+  let t = ((frame - start_frame) / (end_frame - start_frame)) as f32;
+  start_gain + (end_gain - start_gain) * t
+}
+
+fn db_to_linear(db: f32) -> f32 {
+  10.0f32.powf(db / 20.0)
+}
+
+fn meter_mode_from_u8(value: u8) -> MeterMode {
+  if value == MeterMode::Rms as u8 {
+    MeterMode::Rms
+  } else if value == MeterMode::VuSlow as u8 {
+    MeterMode::VuSlow
+  } else {
+    MeterMode::Peak
+  }
+}
+
+fn reset_meters(
+  stem_levels: &[Arc<std::sync::atomic::AtomicU32>],
+  stem_peak_holds: &[MeterHold],
+  master_level: &std::sync::atomic::AtomicU32,
+  master_peak_hold: &MeterHold,
+) {
+  for level in stem_levels {
+    level.store(f32::to_bits(0.0), Ordering::Release);
+  }
+  for hold in stem_peak_holds {
+    hold.reset();
+  }
+  master_level.store(f32::to_bits(0.0), Ordering::Release);
+  master_peak_hold.reset();
+}
+
+fn fade_curve_from_u8(value: u8) -> FadeCurve {
+  if value == FadeCurve::EqualPower as u8 {
+    FadeCurve::EqualPower
+  } else {
+    FadeCurve::Linear
+  }
+}
+
+/// Instantaneous soft-knee limiter: samples below the knee pass unchanged, anything
+/// above is smoothly compressed so the output approaches but never exceeds `threshold`
+fn soft_limit(sample: f32, threshold: f32) -> f32 {
+  let knee = threshold * LIMITER_KNEE_RATIO;
+  let magnitude = sample.abs();
+  if magnitude <= knee {
+    return sample;
+  }
+
+  let headroom = threshold - knee;
+  let limited = knee + headroom * ((magnitude - knee) / headroom).tanh();
+  limited.copysign(sample)
 }
 
 impl Drop for MultiTrackEngine {
@@ -1,5 +1,6 @@
 use super::*;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[test]
 fn test_multi_track_engine_initialization() {
@@ -52,6 +53,19 @@ fn test_stem_capacity_enum() {
   assert_eq!(StemCapacity::from_usize(100), StemCapacity::Custom(100));
 }
 
+#[test]
+fn test_stem_capacity_setting_falls_back_when_out_of_range() {
+  assert_eq!(StemCapacity::from_setting(64), StemCapacity::Professional);
+  assert_eq!(StemCapacity::from_setting(256), StemCapacity::Custom(256));
+
+  for invalid in [0, -5, 257, i32::MAX] {
+    let capacity = StemCapacity::from_setting(invalid);
+    assert_eq!(capacity, StemCapacity::Extended, "{} should fall back to the default", invalid);
+    let engine = MultiTrackEngine::with_capacity(capacity).expect("Fallback capacity must create an engine");
+    assert_eq!(engine.max_stems(), 32);
+  }
+}
+
 #[test]
 fn test_maximum_stem_limit() {
   // Should succeed with 256 stems (maximum allowed)
@@ -315,3 +329,972 @@ fn test_stem_metadata() {
   // For now, just verify the API exists
   let _ = engine.stem_count();
 }
+
+#[test]
+fn test_stem_output_bus_routing() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+
+  // Constant stereo stem: left = 0.5, right = 0.25
+  let samples: Vec<f32> = (0..64).map(|i| if i % 2 == 0 { 0.5 } else { 0.25 }).collect();
+  let stem = engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.set_stem_output(stem, 1).expect("Bus 1 should be accepted");
+  assert_eq!(engine.stem_output(stem), 1);
+  engine.play().unwrap();
+
+  // 4-channel mock output, 8 frames
+  let mut output = vec![0.0f32; 8 * 4];
+  engine.process_block(&mut output, 4);
+
+  for frame in output.chunks(4) {
+    assert_eq!(frame[0], 0.0, "Channel 0 should be silent");
+    assert_eq!(frame[1], 0.0, "Channel 1 should be silent");
+    assert_eq!(frame[2], 0.5, "Channel 2 should carry the left signal");
+    assert_eq!(frame[3], 0.25, "Channel 3 should carry the right signal");
+  }
+
+  // 8 output frames consume 8 stereo frames from the stem
+  assert_eq!(engine.position_arc().load(std::sync::atomic::Ordering::Acquire), 16);
+}
+
+#[test]
+fn test_unavailable_output_bus_folds_to_main_pair() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+
+  let stem = engine.load_stem_from_samples(Arc::new(vec![0.5; 64])).unwrap();
+  engine.set_stem_output(stem, 1).unwrap();
+  engine.play().unwrap();
+
+  // Stereo output has no bus 1, so the stem plays on channels 0-1
+  let mut output = vec![0.0f32; 16];
+  engine.process_block(&mut output, 2);
+  assert!(output.iter().all(|&s| s == 0.5));
+
+  assert!(engine.set_stem_output(stem, 1000).is_err());
+  assert!(engine.set_output_channels(3).is_err(), "Odd channel counts should be rejected");
+}
+
+#[test]
+fn test_mono_device_gets_downmixed_output() {
+  use super::multi_track::stream_channel_count;
+
+  // A mono-only device opens a one-channel stream; others keep their layout or fall back to stereo
+  assert_eq!(stream_channel_count(2, 1), 1);
+  assert_eq!(stream_channel_count(4, 1), 1);
+  assert_eq!(stream_channel_count(1, 2), 2, "Back on a stereo device, stereo returns");
+  assert_eq!(stream_channel_count(8, 2), 2);
+  assert_eq!(stream_channel_count(4, 8), 4);
+
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+  let samples: Vec<f32> = (0..64).flat_map(|_| [0.4, 0.2]).collect();
+  engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 16];
+  engine.process_block(&mut output, 1);
+  assert!(output.iter().all(|&s| (s - 0.3).abs() < 1e-6), "Left and right are folded into one channel: {:?}", output);
+  assert!((engine.position() - 16.0 / engine.project_sample_rate() as f64).abs() < 1e-12, "One output sample is one frame");
+}
+
+#[test]
+fn test_cued_stem_goes_to_cue_pair_at_unity() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  engine.load_stem_from_samples(Arc::new(vec![0.25; 64])).unwrap();
+  let vocals = engine.load_stem_from_samples(Arc::new(vec![0.5; 64])).unwrap();
+  engine.set_stem_volume(vocals, 0.5);
+  engine.set_master_volume(0.8);
+  engine.set_cue_output(Some(1)).expect("Bus 1 should be accepted as the cue pair");
+  engine.set_stem_cue(vocals, true);
+  assert!(engine.is_stem_cued(vocals));
+  engine.play().unwrap();
+
+  // 4-channel mock output: main pair on 0-1, headphones on 2-3
+  let mut output = vec![0.0f32; 8 * 4];
+  engine.process_block(&mut output, 4);
+
+  let main = (0.25 + 0.5 * 0.5) * 0.8;
+  for frame in output.chunks(4) {
+    assert!((frame[0] - main).abs() < 1e-6, "Main left should carry the full mix, got {}", frame[0]);
+    assert!((frame[1] - main).abs() < 1e-6, "Main right should carry the full mix, got {}", frame[1]);
+    assert_eq!(frame[2], 0.5, "Cue left should carry the cued stem at unity");
+    assert_eq!(frame[3], 0.5, "Cue right should carry the cued stem at unity");
+  }
+
+  assert!(engine.set_cue_output(Some(0)).is_err(), "The main pair can't be the cue");
+  engine.set_cue_output(None).unwrap();
+  assert_eq!(engine.cue_output(), None);
+}
+
+#[test]
+fn test_fade_out_and_stop() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.load_stem_from_samples(Arc::new(vec![0.5; 48000 * 2])).unwrap();
+  engine.play().unwrap();
+
+  // 10 ms fade at the device rate
+  let fade_frames = (engine.device_sample_rate() as f64 * 0.01) as usize;
+  engine.fade_out_and_stop(0.01).unwrap();
+  assert!(engine.is_fading());
+
+  let mut output = vec![0.0f32; 64 * 2];
+  engine.process_block(&mut output, 2);
+  assert!(output[0] > output[output.len() - 1], "Gain should ramp down within the block");
+
+  // Run past the end of the fade
+  for _ in 0..(fade_frames / 64 + 1) {
+    engine.process_block(&mut output, 2);
+  }
+
+  assert_eq!(engine.state(), PlaybackState::Stopped);
+  assert!(!engine.is_fading());
+  assert_eq!(engine.position(), 0.0, "Position should be reset after the fade");
+
+  engine.process_block(&mut output, 2);
+  assert!(output.iter().all(|&s| s == 0.0), "Output should be silent after the fade");
+}
+
+#[test]
+fn test_skip_clamps_to_song_bounds() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  assert_eq!(engine.duration(), 0.0, "Duration should be zero with no stems loaded");
+
+  // Two seconds of stereo audio at the device rate
+  let rate = engine.device_sample_rate() as usize;
+  engine.load_stem_from_samples(Arc::new(vec![0.0; rate * 2 * 2])).unwrap();
+  let duration = engine.duration();
+  assert!((duration - 2.0).abs() < 1e-9);
+
+  let position = engine.seek_relative(-5.0).unwrap();
+  assert_eq!(position, 0.0, "Skipping back past zero should clamp to zero");
+  assert_eq!(engine.position(), 0.0);
+
+  let position = engine.seek_relative(10.0).unwrap();
+  assert_eq!(position, duration, "Skipping forward past the end should clamp to the duration");
+}
+
+#[test]
+fn test_current_duration_is_longest_stem() {
+  let mut engine = MultiTrackEngine::new(4).expect("Failed to create engine");
+  assert_eq!(engine.current_duration(), 0.0);
+
+  let frames_per_second = engine.device_sample_rate() as usize * 2;
+  engine.load_stem_from_samples(Arc::new(vec![0.0; frames_per_second])).unwrap();
+  engine.load_stem_from_samples(Arc::new(vec![0.0; frames_per_second * 3])).unwrap();
+  engine.load_stem_from_samples(Arc::new(vec![0.0; frames_per_second * 2])).unwrap();
+
+  assert!((engine.current_duration() - 3.0).abs() < 1e-9, "Duration should match the longest stem");
+
+  engine.clear_stems();
+  assert_eq!(engine.current_duration(), 0.0, "Duration should reset when stems are cleared");
+}
+
+#[test]
+fn test_master_limiter_caps_summed_output() {
+  let mut engine = MultiTrackEngine::new(8).expect("Failed to create engine");
+  assert!(engine.is_limiter_enabled(), "Limiter should be on by default");
+  assert!((engine.limiter_threshold_db() - -0.3).abs() < 1e-3);
+
+  // Six stems at 0.5 sum to 3.0, far over unity
+  for _ in 0..6 {
+    engine.load_stem_from_samples(Arc::new(vec![0.5; 256])).unwrap();
+  }
+  engine.play().unwrap();
+
+  let threshold = 10.0f32.powf(-0.3 / 20.0);
+  let mut output = vec![0.0f32; 128];
+  engine.process_block(&mut output, 2);
+  assert!(output.iter().all(|s| s.abs() <= threshold), "Output must not exceed the threshold");
+  assert!(output[0] > 0.9, "Limited signal should still be loud");
+
+  // Without the limiter the sum clips
+  engine.set_limiter_enabled(false);
+  engine.process_block(&mut output, 2);
+  assert!((output[0] - 3.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_master_limiter_passes_quiet_signals() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.load_stem_from_samples(Arc::new(vec![0.25; 256])).unwrap();
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 128];
+  engine.process_block(&mut output, 2);
+  assert!(output.iter().all(|&s| s == 0.25), "Signals below the knee should pass unchanged");
+}
+
+#[test]
+fn test_stem_hard_pan_silences_opposite_side() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let stem = engine.load_stem_from_samples(Arc::new(vec![0.5; 64])).unwrap();
+  engine.set_stem_pan(stem, 1.0);
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 16];
+  engine.process_block(&mut output, 2);
+  for frame in output.chunks(2) {
+    assert_eq!(frame[0], 0.0, "Left should be silent when hard right");
+    assert_eq!(frame[1], 0.5);
+  }
+
+  engine.set_stem_pan(stem, 5.0);
+  assert_eq!(engine.stem_pan(stem), 1.0, "Pan should be clamped");
+}
+
+#[test]
+fn test_practice_loop_sets_and_clears_loop_and_rate() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let rate = engine.device_sample_rate() as usize;
+  engine.load_stem_from_samples(Arc::new(vec![0.0; rate * 2 * 10])).unwrap();
+
+  engine.start_practice_loop(2.0, 4.0, 0.5).unwrap();
+  assert_eq!(engine.loop_region(), Some((2.0, 4.0)));
+  assert_eq!(engine.playback_rate(), 0.5);
+  assert_eq!(engine.state(), PlaybackState::Playing);
+  assert_eq!(engine.position(), 2.0, "Practice starts at the loop start");
+
+  engine.stop_practice_loop();
+  assert_eq!(engine.loop_region(), None);
+  assert_eq!(engine.playback_rate(), 1.0);
+}
+
+#[test]
+fn test_practice_loop_rejects_invalid_input_without_changes() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let rate = engine.device_sample_rate() as usize;
+  engine.load_stem_from_samples(Arc::new(vec![0.0; rate * 2 * 10])).unwrap();
+
+  assert!(engine.start_practice_loop(4.0, 2.0, 0.5).is_err(), "End before start");
+  assert!(engine.start_practice_loop(2.0, 30.0, 0.5).is_err(), "End past the song");
+  assert!(engine.start_practice_loop(2.0, 4.0, 0.0).is_err(), "Zero rate");
+  assert!(engine.start_practice_loop(2.0, 4.0, 5.0).is_err(), "Rate too fast");
+
+  assert_eq!(engine.loop_region(), None);
+  assert_eq!(engine.playback_rate(), 1.0);
+  assert_eq!(engine.state(), PlaybackState::Stopped);
+}
+
+#[test]
+fn test_loop_region_wraps_and_rate_scales_position() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let rate = engine.device_sample_rate() as usize;
+  // Each frame's value is its frame index so the output shows where playback read from
+  let samples: Vec<f32> = (0..rate * 2).flat_map(|i| [i as f32, i as f32]).collect();
+  engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.set_limiter_enabled(false);
+  engine.play().unwrap();
+
+  engine.set_playback_rate(0.5).unwrap();
+  let mut output = vec![0.0f32; 8];
+  engine.process_block(&mut output, 2);
+  assert_eq!(output, vec![0.0, 0.0, 0.5, 0.5, 1.0, 1.0, 1.5, 1.5], "Half speed interpolates between frames");
+  assert!((engine.position() - 2.0 / rate as f64).abs() < 1e-12, "Four output frames consume two source frames");
+
+  // Loop the first 1/8 second at normal speed and play past its end
+  engine.set_playback_rate(1.0).unwrap();
+  engine.seek(0.0).unwrap();
+  engine.set_loop_region(0.0, 0.125).unwrap();
+  let loop_frames = rate / 8;
+  let mut output = vec![0.0f32; (loop_frames + 2) * 2];
+  engine.process_block(&mut output, 2);
+  assert_eq!(output[(loop_frames - 1) * 2], (loop_frames - 1) as f32);
+  assert_eq!(output[loop_frames * 2], 0.0, "Playback wraps to the loop start");
+  assert_eq!(output[(loop_frames + 1) * 2], 1.0);
+}
+
+#[test]
+fn test_seek_inside_loop_region_is_honored() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let rate = engine.device_sample_rate() as usize;
+  engine.load_stem_from_samples(Arc::new(vec![0.0; rate * 2 * 10])).unwrap();
+  engine.set_loop_region(2.0, 4.0).unwrap();
+
+  engine.seek(3.0).unwrap();
+  assert_eq!(engine.position(), 3.0);
+  engine.seek(2.0).unwrap();
+  assert_eq!(engine.position(), 2.0, "The loop start itself is inside the region");
+
+  // Without a loop any position goes
+  engine.clear_loop_region();
+  engine.seek(7.5).unwrap();
+  assert_eq!(engine.position(), 7.5);
+}
+
+#[test]
+fn test_seek_outside_loop_region_clamps_into_it() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let rate = engine.device_sample_rate() as usize;
+  engine.load_stem_from_samples(Arc::new(vec![0.0; rate * 2 * 10])).unwrap();
+  engine.set_loop_region(2.0, 4.0).unwrap();
+
+  engine.seek(0.5).unwrap();
+  assert_eq!(engine.position(), 2.0, "Seeking before the loop lands on its start");
+
+  engine.seek(9.0).unwrap();
+  let last_frame = 4.0 - 1.0 / rate as f64;
+  assert!((engine.position() - last_frame).abs() < 1e-9, "Seeking past the loop lands on its last frame");
+  assert!(engine.position() < 4.0);
+
+  // Relative seeks go through the same clamp
+  engine.seek(3.0).unwrap();
+  engine.seek_relative(-2.0).unwrap();
+  assert_eq!(engine.position(), 2.0);
+}
+
+#[test]
+fn test_seek_keeps_offset_stems_aligned() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let rate = engine.device_sample_rate() as f64;
+  engine.set_limiter_enabled(false);
+
+  // Each frame's value is its index; the second stem carries 1000 frames of pre-roll
+  let ramp: Vec<f32> = (0..4000).flat_map(|i| [i as f32, i as f32]).collect();
+  let mut padded = vec![0.0f32; 1000 * 2];
+  padded.extend_from_slice(&ramp);
+  engine.load_stem_from_samples(Arc::new(ramp)).unwrap();
+  let late = engine.load_stem_from_samples(Arc::new(padded)).unwrap();
+  engine.set_stem_offset(late, 1000);
+  engine.play().unwrap();
+
+  // A target that doesn't fall on a whole frame still moves both stems by the same amount
+  let target_frame = 2500.5;
+  engine.seek(target_frame / rate).unwrap();
+  let mut output = vec![0.0f32; 16 * 2];
+  engine.process_block(&mut output, 2);
+  for frame in 0..16 {
+    let expected = (target_frame + frame as f64) as f32 * 2.0;
+    assert!((output[frame * 2] - expected).abs() < 1e-2, "Stems stay in sync at frame {}: {}", frame, output[frame * 2]);
+    assert_eq!(output[frame * 2], output[frame * 2 + 1], "A seek never splits a stereo frame");
+  }
+}
+
+#[test]
+fn test_solo_safe_stem_plays_through_solo() {
+  let mut engine = MultiTrackEngine::new(4).expect("Failed to create engine");
+  let click = engine.load_stem_from_samples(Arc::new(vec![0.1; 64])).unwrap();
+  let vocal = engine.load_stem_from_samples(Arc::new(vec![0.2; 64])).unwrap();
+  let _bass = engine.load_stem_from_samples(Arc::new(vec![0.4; 64])).unwrap();
+  engine.set_limiter_enabled(false);
+
+  engine.set_stem_solo_safe(click, true);
+  engine.set_stem_solo(vocal, true);
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 16];
+  engine.process_block(&mut output, 2);
+  assert!((output[0] - 0.3).abs() < 1e-6, "Click and vocal audible, bass silenced");
+
+  let levels = engine.get_stem_levels();
+  assert!(levels[click] > 0.0);
+  assert!(levels[vocal] > 0.0);
+  assert_eq!(levels[2], 0.0, "Non-safe bass should be silenced by the solo");
+
+  // A solo-safe stem still obeys its own mute
+  engine.set_stem_mute(click, true);
+  engine.process_block(&mut output, 2);
+  assert!((output[0] - 0.2).abs() < 1e-6);
+}
+
+#[test]
+fn test_set_buffer_size_rebuilds_stream_and_keeps_position() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.load_stem_from_samples(Arc::new(vec![0.5; 48000 * 2])).unwrap();
+  assert_eq!(engine.current_buffer_size(), 512);
+
+  engine.seek(0.25).unwrap();
+  let position = engine.position();
+
+  assert_eq!(engine.set_buffer_size(256).unwrap(), 256);
+  assert_eq!(engine.current_buffer_size(), 256);
+  assert_eq!(engine.position(), position, "Rebuilding the stream keeps the transport position");
+
+  assert!(engine.set_buffer_size(0).is_err());
+  assert!(engine.set_buffer_size(1_000_000).is_err());
+  assert_eq!(engine.current_buffer_size(), 256, "Rejected sizes leave the stream untouched");
+}
+
+#[test]
+fn test_project_sample_rate_changes_position_conversion() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  assert_eq!(engine.project_sample_rate(), 48000);
+  engine.load_stem_from_samples(Arc::new(vec![0.0; 48000 * 2 * 4])).unwrap();
+
+  engine.seek(1.0).unwrap();
+  assert_eq!(engine.position_arc().load(std::sync::atomic::Ordering::Acquire), 48000 * 2);
+
+  engine.set_project_sample_rate(96000).unwrap();
+  assert_eq!(engine.project_sample_rate(), 96000);
+  assert_eq!(engine.active_stream_config().unwrap().sample_rate, 96000, "The stream reopens at the project rate");
+  assert_eq!(engine.active_stems(), 0, "Stems decoded at the old rate are dropped");
+
+  // The same second is now twice as many samples, and those samples read back as one second
+  engine.seek(1.0).unwrap();
+  assert_eq!(engine.position_arc().load(std::sync::atomic::Ordering::Acquire), 96000 * 2);
+  assert_eq!(engine.position(), 1.0);
+  engine.position_arc().store(48000 * 2, std::sync::atomic::Ordering::Release);
+  assert_eq!(engine.position(), 0.5);
+
+  // A rate the device can't run at is refused and nothing changes
+  assert!(engine.set_project_sample_rate(22050).is_err());
+  assert_eq!(engine.project_sample_rate(), 96000);
+  assert_eq!(engine.active_stream_config().unwrap().sample_rate, 96000);
+  assert!(engine.set_project_sample_rate(1000).is_err());
+}
+
+#[test]
+fn test_active_stream_config_reports_negotiated_values() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+
+  let config = engine.active_stream_config().expect("Engine should have a running stream");
+  assert_eq!(config.sample_rate, engine.device_sample_rate());
+  assert_eq!(config.requested_buffer_size, 512, "The default buffer size was requested");
+  assert_eq!(config.channels, 2);
+
+  engine.set_buffer_size(256).unwrap();
+  let config = engine.active_stream_config().unwrap();
+  assert_eq!(config.requested_buffer_size, 256, "A rebuilt stream reports its new config");
+  assert_eq!(config.buffer_size, None, "No callback has run on the new stream yet");
+
+  // The driver decides what the callback really gets
+  engine.process_block(&mut vec![0.0f32; 300 * 2], 2);
+  assert_eq!(engine.active_stream_config().unwrap().buffer_size, Some(300));
+}
+
+#[test]
+fn test_stem_automation_interpolates_gain() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+  let rate = engine.device_sample_rate() as f64;
+
+  let stem = engine.load_stem_from_samples(Arc::new(vec![0.5; 48000 * 2 * 2])).unwrap();
+  // The fader is ignored while automation is present
+  engine.set_stem_volume(stem, 0.1);
+  engine.set_stem_automation(stem, &[(1.0, 0.8), (0.0, 0.2)]);
+  assert!(engine.has_stem_automation(stem));
+
+  // A quarter of the way from 0.2 to 0.8
+  let gain = engine.stem_automation_gain(stem, 0.25).unwrap();
+  assert!((gain - 0.35).abs() < 1e-5, "Expected 0.35, got {}", gain);
+  // Held flat past the last point
+  assert!((engine.stem_automation_gain(stem, 1.5).unwrap() - 0.8).abs() < 1e-6);
+
+  // Midway through playback the stem plays at the interpolated gain
+  engine.play().unwrap();
+  engine.seek(0.5).unwrap();
+  let mut output = vec![0.0f32; 2];
+  engine.process_block(&mut output, 2);
+  let expected = 0.5 * (0.2 + 0.6 * ((0.5 * rate).floor() / rate) as f32);
+  assert!((output[0] - expected).abs() < 1e-4, "Expected {}, got {}", expected, output[0]);
+
+  // Removing the automation hands control back to the fader
+  engine.set_stem_automation(stem, &[]);
+  assert!(!engine.has_stem_automation(stem));
+  assert_eq!(engine.stem_automation_gain(stem, 0.5), None);
+  let mut output = vec![0.0f32; 2];
+  engine.process_block(&mut output, 2);
+  assert!((output[0] - 0.05).abs() < 1e-6);
+}
+
+#[test]
+fn test_stem_offset_aligns_leading_silence() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  // Same ramp in both stems, but the second was exported with 1000 frames of pre-roll
+  let ramp: Vec<f32> = (0..256).flat_map(|i| {
+    let v = i as f32 / 1000.0;
+    [v, v]
+  }).collect();
+  let mut padded = vec![0.0f32; 1000 * 2];
+  padded.extend_from_slice(&ramp);
+
+  let _tight = engine.load_stem_from_samples(Arc::new(ramp.clone())).unwrap();
+  let late = engine.load_stem_from_samples(Arc::new(padded)).unwrap();
+  engine.set_stem_offset(late, 1000);
+  assert_eq!(engine.stem_offset(late), 1000);
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 64 * 2];
+  engine.process_block(&mut output, 2);
+  for frame in 0..64 {
+    assert!((output[frame * 2] - ramp[frame * 2] * 2.0).abs() < 1e-6, "Stems play in sync at frame {}", frame);
+  }
+
+  // Loading a new song drops the alignment
+  engine.clear_stems();
+  assert_eq!(engine.stem_offset(late), 0);
+}
+
+#[test]
+fn test_auto_stop_after_last_sample() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  // 100 frames of audio: the second 64-frame block runs past the end
+  engine.load_stem_from_samples(Arc::new(vec![0.25; 100 * 2])).unwrap();
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 64 * 2];
+  engine.process_block(&mut output, 2);
+  assert_eq!(engine.state(), PlaybackState::Playing);
+
+  engine.process_block(&mut output, 2);
+  assert_eq!(engine.state(), PlaybackState::Stopped, "Engine should stop once every sample is consumed");
+  assert_eq!(engine.position(), 0.0, "Position should rewind like stop()");
+
+  // With auto-stop disabled the transport keeps running through the silence
+  engine.set_auto_stop_at_end(false);
+  engine.play().unwrap();
+  for _ in 0..4 {
+    engine.process_block(&mut output, 2);
+  }
+  assert_eq!(engine.state(), PlaybackState::Playing);
+  assert!(output.iter().all(|&s| s == 0.0));
+}
+
+#[test]
+fn test_phase_inverted_stem_cancels_identical_stem() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  let signal: Vec<f32> = (0..256).map(|i| ((i as f32) * 0.1).sin() * 0.5).collect();
+  let _direct = engine.load_stem_from_samples(Arc::new(signal.clone())).unwrap();
+  let flipped = engine.load_stem_from_samples(Arc::new(signal)).unwrap();
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 64 * 2];
+  engine.process_block(&mut output, 2);
+  assert!(output.iter().any(|s| s.abs() > 0.1), "In phase, the stems reinforce each other");
+
+  engine.set_stem_phase_invert(flipped, true);
+  assert!(engine.is_stem_phase_inverted(flipped));
+  engine.process_block(&mut output, 2);
+  assert!(output.iter().all(|s| s.abs() < 1e-6), "Inverted copy should cancel the original");
+  assert!(engine.get_stem_levels()[flipped] > 0.0, "Meters still show the inverted stem's level");
+}
+
+#[test]
+fn test_device_fallback_keeps_stems_and_position() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let stem = engine.load_stem_from_samples(Arc::new(vec![0.25; 48000 * 2 * 4])).unwrap();
+  engine.set_stem_volume(stem, 0.5);
+  engine.seek(1.5).unwrap();
+  engine.play().unwrap();
+
+  // Nothing to do while the device is healthy
+  assert!(!engine.is_device_disconnected());
+  assert_eq!(engine.check_device_fallback().unwrap(), None);
+
+  engine.simulate_device_loss();
+  assert!(engine.is_device_disconnected());
+  let device = engine.check_device_fallback().unwrap();
+
+  assert_eq!(device, engine.current_device_name());
+  assert!(device.is_some(), "Playback should move to the default device");
+  assert!(!engine.is_device_disconnected(), "The rebuilt stream is healthy");
+  assert!(!engine.device_fallback_failed(), "A loss after this one is reported again");
+  assert_eq!(engine.active_stems(), 1);
+  assert_eq!(engine.stem_volume(stem), 0.5);
+  assert!((engine.position() - 1.5).abs() < 1e-6, "Position should survive the rebuild");
+  assert_eq!(engine.state(), PlaybackState::Playing);
+
+  // With fallback disabled a lost device is left alone
+  engine.set_device_fallback_enabled(false);
+  engine.simulate_device_loss();
+  assert_eq!(engine.check_device_fallback().unwrap(), None);
+}
+
+#[test]
+fn test_stem_delay_shifts_audible_start_later() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  let stem = engine.load_stem_from_samples(Arc::new(vec![0.5; 256 * 2])).unwrap();
+  engine.set_stem_delay(stem, 10);
+  assert_eq!(engine.stem_delay(stem), 10);
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 64 * 2];
+  engine.process_block(&mut output, 2);
+  for frame in 0..10 {
+    assert_eq!(output[frame * 2], 0.0, "Delayed stem should be silent at frame {}", frame);
+  }
+  for frame in 10..64 {
+    assert!((output[frame * 2] - 0.5).abs() < 1e-6, "Delayed stem should play from frame 10 (frame {})", frame);
+  }
+
+  // A negative delay plays the stem early instead of reading before its start
+  engine.set_stem_delay(stem, -10);
+  engine.seek(0.0).unwrap();
+  engine.process_block(&mut output, 2);
+  assert!(output.iter().all(|&s| (s - 0.5).abs() < 1e-6));
+
+  engine.clear_stems();
+  assert_eq!(engine.stem_delay(stem), 0);
+}
+
+#[test]
+fn test_swap_channels_exchanges_left_and_right() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  // Left 0.2, right 0.6 on every frame
+  let samples: Vec<f32> = (0..256).flat_map(|_| [0.2, 0.6]).collect();
+  let stem = engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.set_stem_channel_mode(stem, true, false);
+  assert_eq!(engine.stem_channel_mode(stem), (true, false));
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 64 * 2];
+  engine.process_block(&mut output, 2);
+  for frame in output.chunks(2) {
+    assert!((frame[0] - 0.6).abs() < 1e-6 && (frame[1] - 0.2).abs() < 1e-6, "Channels should be swapped: {:?}", frame);
+  }
+}
+
+#[test]
+fn test_mono_sum_averages_both_channels() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  let samples: Vec<f32> = (0..256).flat_map(|_| [0.2, 0.6]).collect();
+  let stem = engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.set_stem_channel_mode(stem, false, true);
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 64 * 2];
+  engine.process_block(&mut output, 2);
+  for frame in output.chunks(2) {
+    assert!((frame[0] - 0.4).abs() < 1e-6 && (frame[1] - 0.4).abs() < 1e-6, "Both sides should be the average: {:?}", frame);
+  }
+}
+
+#[test]
+fn test_device_switch_that_moves_the_rate_unloads_stems() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.load_stem_from_samples(Arc::new(vec![0.25; 48000 * 2])).unwrap();
+  engine.play().unwrap();
+
+  // The project runs at a rate the device can't, as after moving from another interface
+  let device_rate = engine.device_sample_rate();
+  engine.project_sample_rate_arc().store(32000, std::sync::atomic::Ordering::Release);
+  let device = engine.current_device_name().expect("Engine should have an output device");
+
+  assert_eq!(engine.switch_audio_device(&device).unwrap(), Some(device_rate));
+  assert_eq!(engine.project_sample_rate(), device_rate);
+  assert_eq!(engine.active_stems(), 0, "Stems decoded at the old rate are dropped");
+  assert_eq!(engine.state(), PlaybackState::Stopped);
+  assert_eq!(engine.position(), 0.0);
+}
+
+#[test]
+fn test_pause_position_survives_device_switch() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  // Each frame's value encodes its index so the resumed sample can be identified
+  let ramp: Vec<f32> = (0..4096).flat_map(|i| {
+    let value = i as f32 / 4096.0;
+    [value, value]
+  }).collect();
+  engine.load_stem_from_samples(Arc::new(ramp.clone())).unwrap();
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 64 * 2];
+  for _ in 0..3 {
+    engine.process_block(&mut output, 2);
+  }
+  engine.pause().unwrap();
+  let paused_at = engine.position();
+
+  let device = engine.current_device_name().expect("Engine should have an output device");
+  engine.switch_audio_device(&device).unwrap();
+  assert_eq!(engine.position(), paused_at, "Rebuilding the stream must not move the paused position");
+  assert_eq!(engine.state(), PlaybackState::Paused);
+
+  engine.play().unwrap();
+  engine.process_block(&mut output, 2);
+  assert!((output[0] - ramp[192 * 2]).abs() < 1e-6, "Playback should resume at frame 192, got {}", output[0] * 4096.0);
+}
+
+#[test]
+fn test_noise_gate_attenuates_quiet_section() {
+  let mut engine = MultiTrackEngine::new(1).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  // 0.1 s of a loud phrase followed by 0.5 s of low-level noise (-40 dBFS)
+  let loud_frames = 4800;
+  let quiet_frames = 24000;
+  let mut samples = vec![0.5f32; loud_frames * 2];
+  samples.extend(std::iter::repeat_n(0.01f32, quiet_frames * 2));
+  let stem = engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.set_stem_gate(stem, -30.0, true);
+  assert!(engine.is_stem_gated(stem));
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; (loud_frames + quiet_frames) * 2];
+  engine.process_block(&mut output, 2);
+
+  assert!((output[(loud_frames - 1) * 2] - 0.5).abs() < 1e-3, "The gate stays open during the phrase");
+  let tail = output[output.len() - 2];
+  assert!(tail.abs() < 0.001, "Noise after the phrase should be gated, got {}", tail);
+
+  // Without the gate the noise passes through
+  engine.set_stem_gate(stem, -30.0, false);
+  engine.seek(0.0).unwrap();
+  engine.play().unwrap();
+  let mut output = vec![0.0f32; (loud_frames + quiet_frames) * 2];
+  engine.process_block(&mut output, 2);
+  assert!((output[output.len() - 2] - 0.01).abs() < 1e-6);
+}
+
+#[test]
+fn test_noise_gate_release_time_and_seek_reset() {
+  let mut engine = MultiTrackEngine::new(1).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  // 0.1 s phrase, then 0.5 s of -40 dBFS noise
+  let loud_frames = 4800;
+  let quiet_frames = 24000;
+  let mut samples = vec![0.5f32; loud_frames * 2];
+  samples.extend(std::iter::repeat_n(0.01f32, quiet_frames * 2));
+  let stem = engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.set_stem_gate(stem, -30.0, true);
+
+  // 0.1 s after the phrase the default 50 ms release has mostly closed the gate; 500 ms hasn't
+  let probe = (loud_frames + 4800) * 2;
+  let mut tail_at = |release_seconds: f32| {
+    engine.set_stem_gate_times(stem, 0.001, release_seconds);
+    engine.seek(0.0).unwrap();
+    engine.play().unwrap();
+    let mut output = vec![0.0f32; probe + 2];
+    engine.process_block(&mut output, 2);
+    output[probe]
+  };
+  let fast = tail_at(0.05);
+  let slow = tail_at(0.5);
+  assert!(fast < 0.003, "Fast release should have closed the gate, got {}", fast);
+  assert!(slow > 0.005, "Slow release should still be open, got {}", slow);
+
+  // Once the noise has closed the gate, seeking back to the phrase starts it open again
+  // rather than fading in over the attack
+  engine.set_stem_gate_times(stem, 0.001, 0.05);
+  let mut output = vec![0.0f32; (loud_frames + quiet_frames / 2) * 2];
+  engine.process_block(&mut output, 2);
+  engine.seek(0.0).unwrap();
+  let mut output = vec![0.0f32; 2];
+  engine.process_block(&mut output, 2);
+  assert!((output[0] - 0.5).abs() < 1e-3, "Gate should be reset by the seek, got {}", output[0]);
+}
+
+#[test]
+fn test_peak_hold_latches_transient_for_hold_time() {
+  let mut engine = MultiTrackEngine::new(1).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+  engine.set_meter_decay(20.0);
+  engine.set_meter_peak_hold(0.1);
+
+  // 10 ms transient, then silence
+  let block = engine.device_sample_rate() as usize / 100;
+  let mut samples = vec![0.8f32; block * 2];
+  samples.extend(vec![0.0f32; block * 2 * 30]);
+  engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; block * 2];
+  engine.process_block(&mut output, 2);
+  assert!((engine.get_stem_peak_holds()[0] - 0.8).abs() < 1e-6);
+
+  // Held for 0.1 s (10 blocks) while the level itself falls
+  for _ in 0..10 {
+    engine.process_block(&mut output, 2);
+    assert!((engine.get_stem_peak_holds()[0] - 0.8).abs() < 1e-6, "Peak should be held");
+  }
+  let level = engine.get_stem_levels()[0];
+  assert!(level < 0.8 && level > 0.0, "The level decays rather than dropping, got {}", level);
+
+  engine.process_block(&mut output, 2);
+  let held = engine.get_stem_peak_holds()[0];
+  assert!(held < 0.8, "Peak should fall once the hold time has passed, got {}", held);
+  // 20 dB/s over one 10 ms block is 0.2 dB
+  assert!((held - 0.8 * 10f32.powf(-0.2 / 20.0)).abs() < 1e-4);
+}
+
+#[test]
+fn test_fader_change_ramps_across_blocks() {
+  let mut engine = MultiTrackEngine::new(1).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+  let stem = engine.load_stem_from_samples(Arc::new(vec![0.5; 48000 * 2])).unwrap();
+  engine.play().unwrap();
+
+  // 64-frame blocks, as a low-latency device would ask for
+  let mut output = vec![0.0f32; 64 * 2];
+  engine.process_block(&mut output, 2);
+  assert_eq!(output[output.len() - 2], 0.5, "A stem starts at its fader setting");
+
+  engine.set_stem_volume(stem, 0.0);
+  engine.process_block(&mut output, 2);
+  assert!(output[0] > 0.49, "The first frame after the move barely changes, got {}", output[0]);
+  let first_block_end = output[output.len() - 2];
+  assert!(first_block_end > 0.1, "Gain shouldn't reach the target within one block, got {}", first_block_end);
+
+  let mut previous = first_block_end;
+  for _ in 0..3 {
+    engine.process_block(&mut output, 2);
+    let sample = output[output.len() - 2];
+    assert!(sample < previous, "Gain keeps falling towards the target");
+    previous = sample;
+  }
+
+  for _ in 0..40 {
+    engine.process_block(&mut output, 2);
+  }
+  assert_eq!(output[output.len() - 2], 0.0, "The ramp settles on the new setting");
+}
+
+#[test]
+fn test_reverse_reads_stems_backwards_and_stops_at_start() {
+  let mut engine = MultiTrackEngine::new(1).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+
+  // Distinct left and right ramps so a swapped interleave would show
+  let source: Vec<f32> = (0..200).flat_map(|i| [i as f32 / 1000.0, -(i as f32) / 1000.0]).collect();
+  engine.load_stem_from_samples(Arc::new(source)).unwrap();
+
+  let rate = engine.device_sample_rate() as f64;
+  engine.seek(100.0 / rate).unwrap();
+  engine.set_reverse(true);
+  assert!(engine.is_reverse());
+  engine.play().unwrap();
+
+  let mut output = vec![0.0f32; 10 * 2];
+  engine.process_block(&mut output, 2);
+  for (i, frame) in output.chunks(2).enumerate() {
+    let source_frame = 100 - i;
+    assert!((frame[0] - source_frame as f32 / 1000.0).abs() < 1e-6, "Left of frame {} out of order", i);
+    assert!((frame[1] + source_frame as f32 / 1000.0).abs() < 1e-6, "Right of frame {} out of order", i);
+  }
+  assert!((engine.position() - 90.0 / rate).abs() < 1e-9, "Position should step backwards");
+
+  // Running past the start plays frame 0 last, then stops and rewinds
+  let mut output = vec![0.0f32; 100 * 2];
+  engine.process_block(&mut output, 2);
+  assert_eq!(output[90 * 2], 0.0);
+  assert_eq!(output[91 * 2], 0.0, "Nothing before the start of the song");
+  assert_eq!(engine.state(), PlaybackState::Stopped);
+  assert_eq!(engine.position(), 0.0);
+  assert!(!engine.is_reverse(), "Playing again from the start goes forwards");
+}
+
+#[test]
+fn test_master_highpass_cuts_rumble_and_passes_mids() {
+  // RMS of the last half second of one second of a 0.5 amplitude sine through the master
+  fn output_rms(frequency: f32, highpass: bool) -> f32 {
+    let mut engine = MultiTrackEngine::new(1).expect("Failed to create engine");
+    engine.set_limiter_enabled(false);
+    engine.set_master_highpass(highpass, 30.0);
+    let rate = engine.device_sample_rate() as usize;
+    let samples: Vec<f32> = (0..rate)
+      .flat_map(|i| {
+        let s = 0.5 * (2.0 * std::f32::consts::PI * frequency * i as f32 / rate as f32).sin();
+        [s, s]
+      })
+      .collect();
+    engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+    engine.play().unwrap();
+
+    let mut rendered = Vec::new();
+    let mut block = vec![0.0f32; 512 * 2];
+    while rendered.len() < rate * 2 {
+      engine.process_block(&mut block, 2);
+      rendered.extend_from_slice(&block);
+    }
+    let tail = &rendered[rate..rate * 2];
+    (tail.iter().map(|s| s * s).sum::<f32>() / tail.len() as f32).sqrt()
+  }
+
+  let rumble = output_rms(10.0, true) / output_rms(10.0, false);
+  assert!(rumble < 0.15, "10 Hz should lose well over 15 dB, kept {:.3}", rumble);
+
+  let mids = output_rms(1000.0, true) / output_rms(1000.0, false);
+  assert!((mids - 1.0).abs() < 0.02, "1 kHz should pass untouched, got {:.3}", mids);
+
+  let mut engine = MultiTrackEngine::new(1).expect("Failed to create engine");
+  engine.set_master_highpass(true, 1000.0);
+  assert_eq!(engine.master_highpass(), (true, 200.0), "The cutoff is clamped");
+}
+
+#[test]
+fn test_recording_writes_the_played_master_and_stems() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  engine.set_limiter_enabled(false);
+  let samples: Vec<f32> = (0..4096).map(|i| ((i % 200) as f32 / 200.0) - 0.5).collect();
+  engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.play().unwrap();
+
+  let dir = std::env::temp_dir().join(format!("trax_recording_{}", uuid::Uuid::new_v4()));
+  engine.start_recording(&dir, true).unwrap();
+  assert!(engine.is_recording());
+  assert!(engine.start_recording(&dir, false).is_err(), "Only one recording at a time");
+
+  let mut played = Vec::new();
+  let mut block = vec![0.0f32; 256 * 2];
+  for _ in 0..4 {
+    engine.process_block(&mut block, 2);
+    played.extend_from_slice(&block);
+  }
+  let summary = engine.stop_recording().unwrap();
+  assert!(!engine.is_recording());
+
+  assert_eq!(summary.frames, 1024);
+  assert_eq!(summary.dropped_callbacks, 0);
+  assert_eq!(summary.files.len(), 2, "Master and the one loaded stem");
+
+  let read = |name: &str| -> Vec<f32> {
+    let mut reader = hound::WavReader::open(dir.join(name)).unwrap();
+    assert_eq!(reader.spec().sample_rate, engine.device_sample_rate());
+    reader.samples::<f32>().map(|s| s.unwrap()).collect()
+  };
+  assert_eq!(read("master.wav"), played);
+  // A single stem at unity is the whole mix
+  assert_eq!(read("stem-01.wav"), played);
+  assert!(played.iter().any(|&s| s != 0.0));
+
+  std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_recording_drops_whole_callbacks_and_holds_the_device() {
+  let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+  let samples: Vec<f32> = (0..1 << 16).map(|i| ((i % 200) as f32 / 200.0) - 0.5).collect();
+  engine.load_stem_from_samples(Arc::new(samples)).unwrap();
+  engine.play().unwrap();
+
+  let dir = std::env::temp_dir().join(format!("trax_recording_{}", uuid::Uuid::new_v4()));
+  engine.start_recording(&dir, true).unwrap();
+  assert!(engine.switch_audio_device("default").is_err(), "The device can't change under a recording");
+  assert!(engine.set_buffer_size(4096).is_err());
+
+  // A callback bigger than the spare buffers is left out of every file, not just some
+  let spare_frames = engine.current_buffer_size().max(256);
+  let mut block = vec![0.0f32; 256 * 2];
+  let mut oversized = vec![0.0f32; (spare_frames + 1) * 2];
+  engine.process_block(&mut block, 2);
+  engine.process_block(&mut oversized, 2);
+  engine.process_block(&mut block, 2);
+
+  let status = engine.recording_status();
+  assert!(status.recording && !status.writer_failed);
+  assert_eq!(status.dropped_callbacks, 1);
+
+  let summary = engine.stop_recording().unwrap();
+  assert_eq!(summary.frames, 512);
+  assert_eq!(summary.dropped_callbacks, 1);
+  for name in ["master.wav", "stem-01.wav"] {
+    let reader = hound::WavReader::open(dir.join(name)).unwrap();
+    assert_eq!(reader.duration(), 512, "{} has only the recorded callbacks", name);
+  }
+  assert!(engine.stop_recording().is_err());
+
+  std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,239 @@
+use std::collections::hash_map::{Entry, HashMap};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use serde::Serialize;
+
+use super::types::{AudioError, AudioResult};
+
+// Audio queued between the audio thread and the writer, per file
+const RECORD_QUEUE_SECONDS: f64 = 2.0;
+const MASTER_FILE_NAME: &str = "master.wav";
+
+// One callback's worth of interleaved stereo audio, tagged with where it was tapped
+pub(crate) enum RecordBlock {
+  Master(Vec<f32>),
+  Stem(usize, Vec<f32>), // Engine slot, post-fader
+}
+
+// Audio-thread end of a recording. Never blocks or allocates: every buffer is one of the spares
+// made when the recording started, and a callback is recorded in full or dropped (and counted)
+// as a whole, so the stem files never slip against the master
+pub(crate) struct RecordTap {
+  blocks: Sender<RecordBlock>,
+  spares: Receiver<Vec<f32>>,
+  spare_samples: usize, // Capacity of every spare
+  per_stem: bool,
+  dropped: Arc<AtomicU64>,
+  failed: Arc<AtomicBool>,
+}
+
+impl RecordTap {
+  pub(crate) fn per_stem(&self) -> bool {
+    self.per_stem
+  }
+
+  // Whether this callback's `blocks` blocks of `len` samples can all be recorded: a spare for each
+  // and room in the queue. The audio thread is the only one taking spares and queueing blocks, so
+  // the answer holds for the rest of the callback. A callback that can't be recorded is counted
+  pub(crate) fn begin(&self, blocks: usize, len: usize) -> bool {
+    if self.failed.load(Ordering::Relaxed) {
+      return false;
+    }
+    let room = self.blocks.capacity().unwrap_or(usize::MAX) - self.blocks.len();
+    let fits = len <= self.spare_samples && self.spares.len() >= blocks && room >= blocks;
+    if !fits {
+      self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+    fits
+  }
+
+  // Zeroed buffer for `len` samples. Only called after begin() said the callback fits
+  pub(crate) fn buffer(&self, len: usize) -> Vec<f32> {
+    let mut buffer = self.spares.try_recv().unwrap_or_default();
+    buffer.clear();
+    buffer.resize(len, 0.0);
+    buffer
+  }
+
+  pub(crate) fn send(&self, block: RecordBlock) {
+    // The writer only hangs up when writing failed; the rest of the recording is lost
+    if let Err(TrySendError::Disconnected(_)) = self.blocks.try_send(block) {
+      self.failed.store(true, Ordering::Relaxed);
+    }
+  }
+}
+
+/// Files written by a finished recording
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordingSummary {
+  pub files: Vec<String>,
+  pub frames: u64, // Length of the master file
+  pub sample_rate: u32,
+  pub dropped_callbacks: u64, // Callbacks left out of every file because the writer fell behind
+}
+
+/// State of the running recording, for the UI to warn while it's still going
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RecordingStatus {
+  pub recording: bool,
+  pub dropped_callbacks: u64,
+  pub writer_failed: bool, // Writing failed; nothing after that point is being recorded
+  pub interrupted: bool, // A device loss ended it; stop_recording still returns its files
+}
+
+// Control end of a running recording, held by the engine
+pub(crate) struct Recording {
+  writer: JoinHandle<Result<(Vec<PathBuf>, u64), String>>,
+  dropped: Arc<AtomicU64>,
+  failed: Arc<AtomicBool>,
+  sample_rate: u32,
+}
+
+impl Recording {
+  // Start the writer thread on `output_dir` and return it with the tap the callback feeds.
+  // `callback_frames` is the largest callback to expect and `stem_slots` the most stem files,
+  // which size the spare buffers
+  pub(crate) fn start(
+    output_dir: &Path,
+    sample_rate: u32,
+    callback_frames: usize,
+    per_stem: bool,
+    stem_slots: usize,
+  ) -> AudioResult<(Recording, RecordTap)> {
+    std::fs::create_dir_all(output_dir)
+      .map_err(|e| AudioError::FileError(format!("Failed to create {}: {}", output_dir.display(), e)))?;
+    let master = create_wav(&output_dir.join(MASTER_FILE_NAME), sample_rate)?;
+
+    let callback_frames = callback_frames.max(1);
+    let spare_samples = callback_frames * 2;
+    let files = 1 + if per_stem { stem_slots } else { 0 };
+    let callbacks = (RECORD_QUEUE_SECONDS * sample_rate as f64 / callback_frames as f64).ceil() as usize;
+    let queue_blocks = callbacks.max(2) * files;
+
+    let (block_tx, block_rx) = crossbeam_channel::bounded(queue_blocks);
+    let (spare_tx, spare_rx) = crossbeam_channel::bounded(queue_blocks);
+    let dropped = Arc::new(AtomicU64::new(0));
+    let failed = Arc::new(AtomicBool::new(false));
+    for _ in 0..queue_blocks {
+      let _ = spare_tx.try_send(Vec::with_capacity(spare_samples));
+    }
+
+    let dir = output_dir.to_path_buf();
+    let writer = std::thread::Builder::new()
+      .name("trax-recorder".to_string())
+      .spawn(move || write_blocks(&dir, sample_rate, master, block_rx, spare_tx))
+      .map_err(|e| AudioError::IoError(format!("Failed to start recording thread: {}", e)))?;
+
+    let tap = RecordTap {
+      blocks: block_tx,
+      spares: spare_rx,
+      spare_samples,
+      per_stem,
+      dropped: dropped.clone(),
+      failed: failed.clone(),
+    };
+    Ok((Recording { writer, dropped, failed, sample_rate }, tap))
+  }
+
+  pub(crate) fn status(&self) -> RecordingStatus {
+    RecordingStatus {
+      recording: true,
+      dropped_callbacks: self.dropped.load(Ordering::Relaxed),
+      writer_failed: self.failed.load(Ordering::Relaxed) || self.writer.is_finished(),
+      interrupted: false,
+    }
+  }
+
+  // Wait for the writer to drain the queue and finalize the files. The tap must already be dropped
+  pub(crate) fn finish(self) -> AudioResult<RecordingSummary> {
+    let (files, frames) = self.writer
+      .join()
+      .map_err(|_| AudioError::IoError("Recording thread panicked".to_string()))?
+      .map_err(AudioError::IoError)?;
+
+    Ok(RecordingSummary {
+      files: files.iter().map(|path| path.to_string_lossy().to_string()).collect(),
+      frames,
+      sample_rate: self.sample_rate,
+      dropped_callbacks: self.dropped.load(Ordering::Relaxed),
+    })
+  }
+}
+
+fn create_wav(path: &Path, sample_rate: u32) -> AudioResult<WavWriter<BufWriter<File>>> {
+  let spec = WavSpec {
+    channels: 2,
+    sample_rate,
+    bits_per_sample: 32,
+    sample_format: SampleFormat::Float,
+  };
+  WavWriter::create(path, spec)
+    .map_err(|e| AudioError::FileError(format!("Failed to create {}: {}", path.display(), e)))
+}
+
+fn write_samples(writer: &mut WavWriter<BufWriter<File>>, samples: &[f32]) -> Result<(), String> {
+  samples
+    .iter()
+    .try_for_each(|&sample| writer.write_sample(sample))
+    .map_err(|e| format!("Failed to write recording: {}", e))
+}
+
+// Writer thread: runs until the tap is dropped. Stem files (stem-01.wav for slot 0, ...) open
+// on their first block; gaps while a slot was empty are filled with silence so every file
+// lines up with the master
+fn write_blocks(
+  dir: &Path,
+  sample_rate: u32,
+  mut master: WavWriter<BufWriter<File>>,
+  blocks: Receiver<RecordBlock>,
+  spares: Sender<Vec<f32>>,
+) -> Result<(Vec<PathBuf>, u64), String> {
+  let mut files = vec![dir.join(MASTER_FILE_NAME)];
+  // Each stem's writer and the frames written to it
+  let mut stems: HashMap<usize, (WavWriter<BufWriter<File>>, u64)> = HashMap::new();
+  let mut master_frames = 0u64;
+
+  for block in blocks {
+    let buffer = match block {
+      RecordBlock::Master(samples) => {
+        write_samples(&mut master, &samples)?;
+        master_frames += samples.len() as u64 / 2;
+        samples
+      }
+      RecordBlock::Stem(slot, samples) => {
+        let (writer, frames) = match stems.entry(slot) {
+          Entry::Occupied(entry) => entry.into_mut(),
+          Entry::Vacant(entry) => {
+            let path = dir.join(format!("stem-{:02}.wav", slot + 1));
+            let writer = create_wav(&path, sample_rate).map_err(|e| e.to_string())?;
+            files.push(path);
+            entry.insert((writer, 0))
+          }
+        };
+        // Stem blocks arrive ahead of their callback's master block; a stem that wasn't loaded
+        // for a while has a gap
+        let gap = master_frames.saturating_sub(*frames);
+        write_samples(writer, &vec![0.0; gap as usize * 2])?;
+        write_samples(writer, &samples)?;
+        *frames += gap + samples.len() as u64 / 2;
+        samples
+      }
+    };
+    // Hand the buffer back for reuse
+    let _ = spares.try_send(buffer);
+  }
+
+  master.finalize().map_err(|e| format!("Failed to finish recording: {}", e))?;
+  for (_, (writer, _)) in stems {
+    writer.finalize().map_err(|e| format!("Failed to finish recording: {}", e))?;
+  }
+
+  Ok((files, master_frames))
+}
@@ -116,3 +116,260 @@ fn test_seek_without_loaded_file() {
   let result = engine.seek(5.0);
   assert!(result.is_err(), "Seek should fail without a loaded file");
 }
+
+#[test]
+fn test_decode_progress_is_monotonic() {
+  let path = std::env::temp_dir().join(format!("trax_decode_progress_{}.wav", uuid::Uuid::new_v4()));
+  let spec = hound::WavSpec {
+    channels: 2,
+    sample_rate: 48000,
+    bits_per_sample: 16,
+    sample_format: hound::SampleFormat::Int,
+  };
+  let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+  for i in 0..48000 * 2 {
+    writer.write_sample((i % 1000) as i16).unwrap();
+  }
+  writer.finalize().unwrap();
+
+  let mut decoder = decoder::AudioDecoder::new(path.to_str().unwrap()).unwrap();
+  let mut updates = Vec::new();
+  let samples = decoder
+    .decode_all_with_progress(|decoded, total| updates.push((decoded, total)))
+    .unwrap();
+
+  assert!(updates.len() > 1, "A one-second file should decode in several packets");
+  assert!(updates.windows(2).all(|w| w[1].0 > w[0].0), "Progress should only increase");
+  assert_eq!(updates.last().unwrap().0, 48000);
+  assert_eq!(updates.last().unwrap().1, Some(48000));
+  assert_eq!(samples.len(), 48000 * 2);
+
+  let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_fade_curve_midpoint_gain() {
+  assert!((FadeCurve::Linear.gain(0.5) - 0.5).abs() < 1e-6);
+  assert!((FadeCurve::EqualPower.gain(0.5) - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+
+  // Equal-power fades in and out sum to constant power across a crossfade
+  for step in 0..=10 {
+    let t = step as f32 / 10.0;
+    let power = FadeCurve::EqualPower.gain(t).powi(2) + FadeCurve::EqualPower.gain(1.0 - t).powi(2);
+    assert!((power - 1.0).abs() < 1e-5);
+  }
+
+  assert_eq!(FadeCurve::Linear.gain(0.0), 0.0);
+  assert_eq!(FadeCurve::EqualPower.gain(1.0), 1.0);
+}
+
+#[test]
+fn test_tone_peaks_near_440_hz() {
+  use super::tone::{SineTone, TEST_TONE_AMPLITUDE, TEST_TONE_FREQUENCY, TEST_TONE_SECONDS};
+
+  let sample_rate = 48000;
+  let mut tone = SineTone::new(TEST_TONE_FREQUENCY, sample_rate, TEST_TONE_AMPLITUDE, TEST_TONE_SECONDS);
+  let mut output = vec![0.0f32; sample_rate as usize * 2];
+  tone.fill(&mut output, 2);
+  assert!(tone.is_finished());
+
+  assert!(output.chunks(2).all(|frame| frame[0] == frame[1]), "Every channel should carry the tone");
+  assert!(output.iter().all(|s| s.abs() <= TEST_TONE_AMPLITUDE), "The tone should stay at a safe level");
+
+  // Magnitude of a single DFT bin over a tenth of a second from the middle of the tone
+  let left: Vec<f32> = output.chunks(2).map(|frame| frame[0]).skip(24000).take(4800).collect();
+  let magnitude = |frequency: f32| -> f32 {
+    let (mut re, mut im) = (0.0f32, 0.0f32);
+    for (n, sample) in left.iter().enumerate() {
+      let angle = std::f32::consts::TAU * frequency * n as f32 / sample_rate as f32;
+      re += sample * angle.cos();
+      im -= sample * angle.sin();
+    }
+    (re * re + im * im).sqrt()
+  };
+
+  let peak = (10..=200)
+    .map(|step| step as f32 * 10.0)
+    .max_by(|a, b| magnitude(*a).total_cmp(&magnitude(*b)))
+    .unwrap();
+  assert!((peak - 440.0).abs() <= 10.0, "Spectrum should peak near 440 Hz, got {} Hz", peak);
+
+  // Silence once the tone has run its length
+  let mut tail = vec![1.0f32; 64];
+  tone.fill(&mut tail, 2);
+  assert!(tail.iter().all(|&s| s == 0.0));
+}
+
+#[test]
+fn test_convert_8_bit_buffers() {
+  use std::borrow::Cow;
+  use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec};
+
+  let spec = SignalSpec::new(48000, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+  // Unsigned: 128 is silence, 0 is full negative, 255 just under full positive
+  let mut unsigned = AudioBuffer::<u8>::new(3, spec);
+  unsigned.render_reserved(Some(3));
+  unsigned.chan_mut(0).copy_from_slice(&[128, 0, 255]);
+  unsigned.chan_mut(1).copy_from_slice(&[192, 64, 128]);
+  let samples = decoder::convert_audio_buffer(AudioBufferRef::U8(Cow::Borrowed(&unsigned))).unwrap();
+  assert_eq!(samples, vec![0.0, 0.5, -1.0, -0.5, 127.0 / 128.0, 0.0], "Channels should be interleaved");
+
+  let mut signed = AudioBuffer::<i8>::new(3, spec);
+  signed.render_reserved(Some(3));
+  signed.chan_mut(0).copy_from_slice(&[0, -128, 127]);
+  signed.chan_mut(1).copy_from_slice(&[64, -64, 0]);
+  let samples = decoder::convert_audio_buffer(AudioBufferRef::S8(Cow::Borrowed(&signed))).unwrap();
+  assert_eq!(samples, vec![0.0, 0.5, -1.0, -0.5, 127.0 / 128.0, 0.0]);
+}
+
+#[test]
+fn test_probe_format_reports_8_bit_wav() {
+  let path = std::env::temp_dir().join(format!("trax_probe_{}.wav", uuid::Uuid::new_v4()));
+  let spec = hound::WavSpec {
+    channels: 1,
+    sample_rate: 44100,
+    bits_per_sample: 8,
+    sample_format: hound::SampleFormat::Int,
+  };
+  let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+  for i in 0..4410 {
+    writer.write_sample(((i % 100) as i8) - 50).unwrap();
+  }
+  writer.finalize().unwrap();
+
+  let format = decoder::probe_format(path.to_str().unwrap());
+  std::fs::remove_file(&path).ok();
+
+  let format = format.expect("8-bit WAV should probe");
+  assert_eq!(format.bits_per_sample, 8);
+  assert!(format.supported, "8-bit files now decode");
+}
+
+#[test]
+fn test_transient_read_errors_are_retried_until_the_stem_loads() {
+  use std::time::Duration;
+  use types::{AudioError, AudioResult};
+
+  // A reader on a flaky share: the first two reads drop out, the third gets the audio
+  let mut reads = 0;
+  let samples = decoder::retry_transient(3, Duration::from_millis(1), || {
+    reads += 1;
+    if reads <= 2 {
+      return Err(AudioError::IoError("connection reset by peer".to_string()));
+    }
+    Ok(vec![0.25f32; 8])
+  })
+  .unwrap();
+  assert_eq!(reads, 3);
+  assert_eq!(samples.len(), 8);
+
+  // Format errors fail on the first try
+  let mut reads = 0;
+  let result: AudioResult<()> = decoder::retry_transient(3, Duration::from_millis(1), || {
+    reads += 1;
+    Err(AudioError::InvalidFormat("not audio".to_string()))
+  });
+  assert!(result.is_err());
+  assert_eq!(reads, 1);
+
+  // And the retries run out
+  let mut reads = 0;
+  let result: AudioResult<()> = decoder::retry_transient(3, Duration::from_millis(1), || {
+    reads += 1;
+    Err(AudioError::IoError("timed out".to_string()))
+  });
+  assert!(result.is_err());
+  assert_eq!(reads, 3);
+}
+
+#[test]
+fn test_disk_cache_evicts_least_recently_used_over_the_limit() {
+  let dir = std::env::temp_dir().join(format!("trax_disk_cache_test_{}", uuid::Uuid::new_v4()));
+  let samples = vec![0.25f32; 100];
+  let pause = || std::thread::sleep(std::time::Duration::from_millis(20));
+
+  // Each entry is a 12 byte header plus 400 bytes of samples: room for two
+  let cache = CacheManager::new(dir.join("cache"), 1000);
+  cache.put("first", "hash-1", 48000, &samples).unwrap();
+  pause();
+  cache.put("second", "hash-2", 48000, &samples).unwrap();
+  pause();
+  assert!(cache.get("first", "hash-1", 48000).is_some(), "Reading 'first' makes 'second' the oldest");
+  pause();
+  cache.put("third", "hash-3", 48000, &samples).unwrap();
+
+  assert!(cache.contains("first", "hash-1", 48000));
+  assert!(!cache.contains("second", "hash-2", 48000), "The least recently used entry is evicted");
+  assert!(cache.contains("third", "hash-3", 48000));
+  assert_eq!(cache.size_bytes(), 824);
+
+  cache.put("first", "hash-1", 44100, &samples).unwrap();
+  cache.set_max_bytes(2000);
+  cache.remove_stem("first");
+  assert!(!cache.contains("first", "hash-1", 48000));
+  assert!(!cache.contains("first", "hash-1", 44100), "Removing a stem drops every rate");
+  assert!(cache.contains("third", "hash-3", 48000));
+
+  assert_eq!(cache.clear(), 412);
+  assert_eq!(cache.size_bytes(), 0);
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_entry_is_shared_by_identical_stems() {
+  let dir = std::env::temp_dir().join(format!("trax_disk_cache_test_{}", uuid::Uuid::new_v4()));
+  let samples = vec![0.25f32; 100];
+  let cache = CacheManager::new(dir.join("cache"), 10_000);
+
+  // Two songs' click tracks with the same content: written once, found by the second stem
+  cache.put("click-1", "same-click", 48000, &samples).unwrap();
+  assert!(cache.contains("click-2", "same-click", 48000));
+  assert_eq!(cache.size_bytes(), 412, "Identical stems share one file");
+
+  // The first stem going away leaves the entry to the second
+  cache.remove_stem("click-1");
+  assert_eq!(cache.get("click-2", "same-click", 48000), Some(samples));
+
+  cache.remove_stem("click-2");
+  assert_eq!(cache.size_bytes(), 0, "The last user removes the entry");
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_sinc_resampler_keeps_high_frequencies_that_linear_loses() {
+  use resampler::{resample_sinc, LinearResampler};
+
+  // Error against the ideal tone away from the edges, where the kernel has full support
+  let max_error = |frequency: f64, resampled: &[f32]| -> f32 {
+    resampled
+      .iter()
+      .enumerate()
+      .skip(1000)
+      .take(resampled.len() - 2000)
+      .map(|(i, &s)| (s - (2.0 * std::f64::consts::PI * frequency * i as f64 / 48000.0).sin() as f32).abs())
+      .fold(0.0, f32::max)
+  };
+
+  for frequency in [1000.0, 15000.0] {
+    let tone: Vec<f32> = (0..44100)
+      .map(|i| (2.0 * std::f64::consts::PI * frequency * i as f64 / 44100.0).sin() as f32)
+      .collect();
+
+    let sinc = resample_sinc(&tone, 44100, 48000, 1);
+    assert_eq!(sinc.len(), 48000);
+    assert!(max_error(frequency, &sinc) < 0.01, "{} Hz off by {}", frequency, max_error(frequency, &sinc));
+
+    if frequency > 10000.0 {
+      let linear = LinearResampler::new(44100, 48000, 1).process(&tone);
+      assert!(max_error(frequency, &linear) > 0.1, "The linear resampler is the worse of the two up here");
+    }
+  }
+
+  // Stereo channels stay separate
+  let stereo: Vec<f32> = (0..4410).flat_map(|_| [0.5f32, -0.25]).collect();
+  let resampled = resample_sinc(&stereo, 44100, 48000, 2);
+  assert!((resampled[2400] - 0.5).abs() < 1e-3 && (resampled[2401] + 0.25).abs() < 1e-3);
+}
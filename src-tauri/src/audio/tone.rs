@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+#[cfg(not(target_os = "macos"))]
+use cpal::traits::{DeviceTrait, StreamTrait};
+
+use super::types::AudioResult;
+#[cfg(not(target_os = "macos"))]
+use super::types::AudioError;
+
+pub const TEST_TONE_FREQUENCY: f32 = 440.0;
+pub const TEST_TONE_AMPLITUDE: f32 = 0.1; // -20 dBFS, audible without being loud
+pub const TEST_TONE_SECONDS: f32 = 1.0;
+const TONE_RAMP_SECONDS: f32 = 0.01; // Fade in and out so the tone doesn't click
+
+/// Sine oscillator that plays for a fixed length and then outputs silence
+pub struct SineTone {
+  phase: f32,
+  step: f32,
+  amplitude: f32,
+  frame: usize,
+  total_frames: usize,
+  ramp_frames: usize,
+}
+
+impl SineTone {
+  pub fn new(frequency: f32, sample_rate: u32, amplitude: f32, seconds: f32) -> Self {
+    let sample_rate = sample_rate.max(1) as f32;
+    SineTone {
+      phase: 0.0,
+      step: std::f32::consts::TAU * frequency / sample_rate,
+      amplitude,
+      frame: 0,
+      total_frames: (seconds * sample_rate) as usize,
+      ramp_frames: ((TONE_RAMP_SECONDS * sample_rate) as usize).max(1),
+    }
+  }
+
+  /// Fill an interleaved buffer with the tone on every channel
+  pub fn fill(&mut self, output: &mut [f32], channels: usize) {
+    for frame in output.chunks_mut(channels.max(1)) {
+      let value = if self.frame < self.total_frames {
+        let remaining = self.total_frames - self.frame;
+        let ramp = (self.frame.min(remaining) as f32 / self.ramp_frames as f32).min(1.0);
+        self.phase.sin() * self.amplitude * ramp
+      } else {
+        0.0
+      };
+      frame.fill(value);
+
+      self.phase = (self.phase + self.step) % std::f32::consts::TAU;
+      self.frame += 1;
+    }
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.frame >= self.total_frames
+  }
+}
+
+/// Play the test tone on an output device, found the same way playback finds it,
+/// and close the stream once it has finished
+#[cfg(not(target_os = "macos"))]
+pub fn play_test_tone(host_name: Option<&str>, device_name: &str) -> AudioResult<()> {
+  let device = super::multi_track::find_output_device(host_name, device_name)?;
+  let config = device
+    .default_output_config()
+    .map_err(|e| AudioError::DeviceInit(format!("Failed to get default config: {}", e)))?;
+  let channels = config.channels() as usize;
+  let mut tone = SineTone::new(TEST_TONE_FREQUENCY, config.sample_rate().0, TEST_TONE_AMPLITUDE, TEST_TONE_SECONDS);
+
+  let stream = device
+    .build_output_stream(
+      &config.config(),
+      move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+        tone.fill(data, channels);
+      },
+      |err| log::error!("Test tone stream error: {}", err),
+      None,
+    )
+    .map_err(|e| AudioError::DeviceInit(format!("Failed to build stream: {}", e)))?;
+
+  stream
+    .play()
+    .map_err(|e| AudioError::PlaybackError(format!("Failed to start stream: {}", e)))?;
+
+  wait_for_tone();
+  Ok(())
+}
+
+/// Play the test tone on an output device, found the same way playback finds it,
+/// and close the stream once it has finished
+#[cfg(target_os = "macos")]
+pub fn play_test_tone(_host_name: Option<&str>, device_name: &str) -> AudioResult<()> {
+  use std::sync::atomic::AtomicU64;
+  use std::sync::{Arc, Mutex};
+  use super::macos_backend::MacOSAudioStream;
+  use super::types::PlaybackState;
+
+  let mut stream = MacOSAudioStream::new(
+    device_name,
+    Arc::new(Mutex::new(PlaybackState::Playing)),
+    Arc::new(AtomicU64::new(0)),
+  )?;
+  let mut tone = SineTone::new(TEST_TONE_FREQUENCY, stream.sample_rate() as u32, TEST_TONE_AMPLITUDE, TEST_TONE_SECONDS);
+
+  // The stream renders a stereo pair unless told otherwise
+  stream.set_render_callback(move |data: &mut [f32]| {
+    tone.fill(data, 2);
+  })?;
+  stream.start()?;
+
+  wait_for_tone();
+  stream.stop()
+}
+
+// Hold the stream open for the tone plus a little so the fade-out isn't cut off
+fn wait_for_tone() {
+  std::thread::sleep(Duration::from_secs_f32(TEST_TONE_SECONDS + 0.1));
+}
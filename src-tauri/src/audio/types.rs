@@ -7,6 +7,65 @@ pub enum PlaybackState {
   Paused,
 }
 
+/// Shape of a fade's gain over time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FadeCurve {
+  #[default]
+  Linear,
+  /// Sine/cosine law: the summed power of a crossfade stays constant, so there's no dip in the middle
+  EqualPower,
+}
+
+impl FadeCurve {
+  /// Gain at `progress` through a fade-in (0.0 = silent, 1.0 = full level).
+  /// Fade-outs use `gain(1.0 - progress)`
+  pub fn gain(self, progress: f32) -> f32 {
+    let progress = progress.clamp(0.0, 1.0);
+    match self {
+      FadeCurve::Linear => progress,
+      FadeCurve::EqualPower => (progress * std::f32::consts::FRAC_PI_2).sin(),
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "linear" => Some(FadeCurve::Linear),
+      "equal_power" => Some(FadeCurve::EqualPower),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      FadeCurve::Linear => "linear",
+      FadeCurve::EqualPower => "equal_power",
+    }
+  }
+}
+
+/// What a level meter measures each block
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeterMode {
+  #[default]
+  Peak,
+  Rms,
+  /// RMS that also rises slowly (~300 ms), like a VU meter
+  VuSlow,
+}
+
+impl MeterMode {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "peak" => Some(MeterMode::Peak),
+      "rms" => Some(MeterMode::Rms),
+      "vu_slow" => Some(MeterMode::VuSlow),
+      _ => None,
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub enum AudioCommand {
   Play(String),
@@ -16,6 +75,24 @@ pub enum AudioCommand {
   SetVolume(f32),
 }
 
+/// Sample format a file decodes to, and whether the decoder can convert it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleFormatInfo {
+  pub sample_format: String, // e.g. "s16", "s24", "f32", "u8"
+  pub bits_per_sample: u32,
+  pub supported: bool,
+}
+
+/// Configuration the output stream is actually running with, which may differ from what was
+/// requested (e.g. a buffer size the device can't do)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveStreamConfig {
+  pub sample_rate: u32,
+  pub requested_buffer_size: u32, // Frames per callback asked of the device
+  pub buffer_size: Option<u32>, // Frames in the latest callback, None until the device calls back
+  pub channels: u16,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioMetadata {
   pub duration: f64,
@@ -45,4 +122,15 @@ pub enum AudioError {
 
   #[error("Audio stream error: {0}")]
   StreamError(String),
+
+  #[error("Failed to read audio file: {0}")]
+  IoError(String),
+}
+
+impl AudioError {
+  // Read failures that can clear on a retry, like a network share dropping out mid-decode.
+  // Missing files and format/decode errors are permanent
+  pub fn is_transient(&self) -> bool {
+    matches!(self, AudioError::IoError(_))
+  }
 }
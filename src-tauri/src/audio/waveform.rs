@@ -0,0 +1,35 @@
+// Min/max peak overviews for drawing waveforms
+
+use serde::{Deserialize, Serialize};
+
+/// Most buckets a waveform can be drawn with
+pub const MAX_WAVEFORM_BUCKETS: usize = 65536;
+
+/// Lowest and highest sample in each bucket, across all channels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaveformPeaks {
+  pub min: Vec<f32>,
+  pub max: Vec<f32>,
+}
+
+/// Split interleaved `samples` into `buckets` equal runs of frames and take each run's peaks.
+/// Buckets past the end of a very short clip are silent (0.0)
+pub fn compute_peaks(samples: &[f32], channels: usize, buckets: usize) -> WaveformPeaks {
+  let channels = channels.max(1);
+  let frames = samples.len() / channels;
+  let mut min = vec![0.0f32; buckets];
+  let mut max = vec![0.0f32; buckets];
+
+  for bucket in 0..buckets {
+    let start = bucket * frames / buckets;
+    let end = ((bucket + 1) * frames / buckets).max(start);
+    let run = &samples[start * channels..end * channels];
+    if run.is_empty() {
+      continue;
+    }
+    min[bucket] = run.iter().copied().fold(f32::INFINITY, f32::min);
+    max[bucket] = run.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+  }
+
+  WaveformPeaks { min, max }
+}
@@ -1,37 +1,248 @@
-use super::AppState;
-use tauri::State;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::{lock_or_recover, run_blocking_limited, source_modified_time, stem_content_hash, AppState, CacheEfficiency, CommandError, ErrorCode, MIN_CACHE_SIZE_BYTES};
+use super::playback::decode_at_rate;
+use tauri::{Emitter, State};
 
 /// Get cache statistics (num_songs, current_bytes, max_bytes)
 #[tauri::command]
-pub async fn get_cache_stats(state: State<'_, AppState>) -> Result<(usize, usize, usize), String> {
-  let cache = state.song_cache.lock()
-    .map_err(|_| "Failed to lock cache".to_string())?;
+pub async fn get_cache_stats(state: State<'_, AppState>) -> Result<(usize, usize, usize), CommandError> {
+  let cache = lock_or_recover(&state.song_cache, "song cache");
 
   Ok(cache.stats())
 }
 
-/// Set cache size limit in bytes
+/// Get the cache hit rate overall and over the last 10 minutes, to judge whether the cache size
+/// is big enough for the songs in use
 #[tauri::command]
-pub async fn set_cache_size(size_bytes: usize, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn get_cache_efficiency(state: State<'_, AppState>) -> Result<CacheEfficiency, CommandError> {
+  Ok(lock_or_recover(&state.song_cache, "song cache").efficiency())
+}
+
+/// Set cache size limit in bytes (at least 256 MB); persisted for the next launch
+#[tauri::command]
+pub async fn set_cache_size(size_bytes: usize, state: State<'_, AppState>) -> Result<(), CommandError> {
   log::info!("Setting cache size to {} bytes ({:.1} GB)", size_bytes, size_bytes as f64 / 1_073_741_824.0);
 
-  let mut cache = state.song_cache.lock()
-    .map_err(|_| "Failed to lock cache".to_string())?;
+  if size_bytes < MIN_CACHE_SIZE_BYTES {
+    return Err(CommandError::new(
+      ErrorCode::Validation,
+      format!("Cache size must be at least {} MB", MIN_CACHE_SIZE_BYTES / 1_048_576),
+    ));
+  }
+
+  {
+    let mut cache = lock_or_recover(&state.song_cache, "song cache");
+    cache.set_max_size(size_bytes);
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| CommandError::from(e).context("Failed to get settings"))?;
+
+  settings.in_memory_cache_gb = size_bytes as f64 / 1_073_741_824.0;
 
-  cache.set_max_size(size_bytes);
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| CommandError::from(e).context("Failed to update cache size"))?;
 
   Ok(())
 }
 
-/// Clear all cached songs
+/// Decoded length of a cached stem, for checking stems loaded at the expected length
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CachedStemInfo {
+  pub stem_id: String,
+  pub sample_count: usize,
+  pub frame_count: usize,
+  pub sample_rate: u32,
+  pub duration: f64,
+}
+
+/// Get the decoded sample count, sample rate and duration of each stem of a cached song
 #[tauri::command]
-pub async fn clear_cache(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn get_cached_song_info(
+  song_id: String,
+  state: State<'_, AppState>
+) -> Result<Vec<CachedStemInfo>, CommandError> {
+  cached_song_info(&state, &song_id)
+}
+
+pub(crate) fn cached_song_info(state: &AppState, song_id: &str) -> Result<Vec<CachedStemInfo>, CommandError> {
+  let cache = lock_or_recover(&state.song_cache, "song cache");
+
+  let song = cache.peek(song_id)
+    .ok_or_else(|| CommandError::new(ErrorCode::Cache, format!("Song {} is not cached", song_id)))?;
+
+  Ok(song.stems.iter().map(|stem| CachedStemInfo {
+    stem_id: stem.stem_id.clone(),
+    sample_count: stem.samples.len(),
+    frame_count: stem.frame_count(),
+    sample_rate: stem.sample_rate,
+    duration: stem.duration(),
+  }).collect())
+}
+
+/// Clear all cached songs, in memory and on disk
+#[tauri::command]
+pub async fn clear_cache(state: State<'_, AppState>) -> Result<(), CommandError> {
   log::info!("Clearing cache");
 
-  let mut cache = state.song_cache.lock()
-    .map_err(|_| "Failed to lock cache".to_string())?;
+  let mut cache = lock_or_recover(&state.song_cache, "song cache");
 
   cache.clear();
+  drop(cache);
+
+  state.disk_cache.clear();
+
+  Ok(())
+}
+
+/// Get the disk cache's size on disk and its limit, in bytes
+#[tauri::command]
+pub async fn get_disk_cache_stats(state: State<'_, AppState>) -> Result<(u64, u64), CommandError> {
+  Ok((state.disk_cache.size_bytes(), state.disk_cache.max_bytes()))
+}
+
+/// Set the disk cache size limit in bytes (at least 256 MB), evicting the least recently used
+/// stems if it's over; persisted for the next launch
+#[tauri::command]
+pub async fn set_disk_cache_size(size_bytes: u64, state: State<'_, AppState>) -> Result<(), CommandError> {
+  log::info!("Setting disk cache size to {:.1} GB", size_bytes as f64 / 1_073_741_824.0);
+
+  if size_bytes < MIN_CACHE_SIZE_BYTES as u64 {
+    return Err(CommandError::new(
+      ErrorCode::Validation,
+      format!("Disk cache size must be at least {} MB", MIN_CACHE_SIZE_BYTES / 1_048_576),
+    ));
+  }
+
+  state.disk_cache.set_max_bytes(size_bytes);
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| CommandError::from(e).context("Failed to get settings"))?;
+
+  settings.disk_cache_gb = size_bytes as f64 / 1_073_741_824.0;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| CommandError::from(e).context("Failed to update disk cache size"))?;
 
   Ok(())
 }
+
+/// Delete every decoded stem from the disk cache. Returns the bytes freed
+#[tauri::command]
+pub async fn clear_disk_cache(state: State<'_, AppState>) -> Result<u64, CommandError> {
+  let freed = state.disk_cache.clear();
+  log::info!("Cleared disk cache ({:.1} MB freed)", freed as f64 / 1_048_576.0);
+  Ok(freed)
+}
+
+/// Outcome of warming a setlist's stems into the disk cache
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct DiskCacheWarmup {
+  pub stems: usize,          // Stems across the setlist's songs
+  pub already_cached: usize, // Fresh on disk before warming
+  pub written: usize,        // Decoded and written by this warm-up
+  pub failed: usize,         // Couldn't be decoded or written; they still load from source
+}
+
+/// Decode every stem of every song in a setlist into the disk cache at the engine's sample rate,
+/// so the set loads fast even after a restart. Unlike preloading, nothing is held in memory.
+/// Emits "cache:warm-progress" per stem and "cache:warm-complete" with the totals
+#[tauri::command]
+pub async fn warm_disk_cache(
+  setlist_id: String,
+  state: State<'_, AppState>,
+  app_handle: tauri::AppHandle
+) -> Result<DiskCacheWarmup, CommandError> {
+  warm_setlist_disk_cache(&state, &setlist_id, move |event, payload| {
+    let _ = app_handle.emit(event, payload);
+  }).await
+}
+
+pub(crate) async fn warm_setlist_disk_cache<E>(state: &AppState, setlist_id: &str, emit: E) -> Result<DiskCacheWarmup, CommandError>
+where
+  E: Fn(&str, serde_json::Value) + Clone + Send + 'static,
+{
+  let songs = state.database
+    .get_setlist_songs(setlist_id)
+    .map_err(|e| CommandError::from(e).context("Failed to get setlist songs"))?;
+  let sample_rate = lock_or_recover(&state.audio_engine, "audio engine").project_sample_rate();
+
+  // A song can appear twice in a set; warm it once
+  let mut seen = HashSet::new();
+  let mut stems = Vec::new();
+  for song in songs.into_iter().filter(|song| seen.insert(song.id.clone())) {
+    let song_stems = state.database
+      .get_stems_for_song(&song.id)
+      .map_err(|e| CommandError::from(e).context("Failed to get stems for song"))?;
+    stems.extend(song_stems.into_iter().map(|stem| (song.name.clone(), stem)));
+  }
+
+  let total = stems.len();
+  log::info!("Warming {} stems of setlist {} into the disk cache at {}Hz", total, setlist_id, sample_rate);
+
+  let finished = Arc::new(AtomicUsize::new(0));
+  let jobs: Vec<_> = stems.into_iter().map(|(song_name, stem)| {
+    let disk_cache = state.disk_cache.clone();
+    let database = state.database.clone();
+    let finished = finished.clone();
+    let emit = emit.clone();
+    let setlist_id = setlist_id.to_string();
+
+    // Ok(true) when written, Ok(false) when already fresh on disk
+    move || {
+      // Entries are keyed by content, so stems sharing a file are written once
+      let source_hash = stem_content_hash(&database, &stem, source_modified_time(&stem.file_path));
+      let result = match source_hash {
+        None => Err(format!("Failed to hash '{}'", stem.name)),
+        Some(hash) if disk_cache.contains(&stem.id, &hash, sample_rate) => Ok(false),
+        Some(hash) => decode_at_rate(&stem.file_path, &stem.name, sample_rate, |_, _| {})
+          .and_then(|samples| {
+            disk_cache
+              .put(&stem.id, &hash, sample_rate, &samples)
+              .map_err(|e| format!("Failed to write '{}' to the disk cache: {}", stem.name, e))
+          })
+          .map(|_| true),
+      };
+      if let Err(e) = &result {
+        log::warn!("Failed to warm stem '{}' of '{}': {}", stem.name, song_name, e);
+      }
+
+      emit("cache:warm-progress", serde_json::json!({
+        "setlist_id": setlist_id,
+        "song_name": song_name,
+        "stem_name": stem.name,
+        "current": finished.fetch_add(1, Ordering::AcqRel) + 1,
+        "total": total,
+      }));
+      result
+    }
+  }).collect();
+
+  let mut warmup = DiskCacheWarmup { stems: total, ..Default::default() };
+  for result in run_blocking_limited(&state.decode_semaphore(), jobs).await {
+    match result {
+      Ok(Ok(true)) => warmup.written += 1,
+      Ok(Ok(false)) => warmup.already_cached += 1,
+      Ok(Err(_)) | Err(_) => warmup.failed += 1,
+    }
+  }
+
+  log::info!(
+    "Disk cache warm-up of setlist {}: {} written, {} already cached, {} failed",
+    setlist_id, warmup.written, warmup.already_cached, warmup.failed
+  );
+  emit("cache:warm-complete", serde_json::json!({
+    "setlist_id": setlist_id,
+    "stems": warmup.stems,
+    "already_cached": warmup.already_cached,
+    "written": warmup.written,
+    "failed": warmup.failed,
+  }));
+  Ok(warmup)
+}
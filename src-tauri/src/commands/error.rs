@@ -0,0 +1,100 @@
+use crate::audio::AudioError;
+use crate::import::ImportError;
+use serde::Serialize;
+use std::fmt;
+
+/// Category of a failed command, so the UI can tell "file missing" from "device busy"
+/// without parsing messages. Serialized as e.g. "FILE_NOT_FOUND"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+  FileNotFound,
+  InvalidFormat,
+  Duplicate,
+  Validation,
+  NotFound, // A song, stem or setlist id that isn't in the library
+  Database,
+  Decode,
+  Device,
+  Playback,
+  Io,
+  Archive,
+  Cache, // A song that isn't in the memory cache
+  Internal, // Errors not yet given a category
+}
+
+/// Error returned to the frontend by commands: `{ code, message }`. Only the library
+/// (import, export, file preview) and cache commands return it so far; the others still reject
+/// with a plain message string, which the frontend reads with `errorMessage`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandError {
+  pub code: ErrorCode,
+  pub message: String,
+}
+
+impl CommandError {
+  pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+    CommandError { code, message: message.into() }
+  }
+
+  // Prefix the message with what was being done ("Import failed: ..."), keeping the code
+  pub fn context(mut self, what: &str) -> Self {
+    self.message = format!("{}: {}", what, self.message);
+    self
+  }
+}
+
+impl fmt::Display for CommandError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl std::error::Error for CommandError {}
+
+// Helpers that still report plain strings end up uncategorized
+impl From<String> for CommandError {
+  fn from(message: String) -> Self {
+    CommandError::new(ErrorCode::Internal, message)
+  }
+}
+
+impl From<ImportError> for CommandError {
+  fn from(e: ImportError) -> Self {
+    let code = match &e {
+      ImportError::FileNotFound(_) => ErrorCode::FileNotFound,
+      ImportError::InvalidFormat(_) | ImportError::MetadataExtraction(_) => ErrorCode::InvalidFormat,
+      ImportError::Database(_) => ErrorCode::Database,
+      ImportError::Validation(_) => ErrorCode::Validation,
+      ImportError::Duplicate(_) => ErrorCode::Duplicate,
+      ImportError::Archive(_) => ErrorCode::Archive,
+      ImportError::Io(io) if io.kind() == std::io::ErrorKind::NotFound => ErrorCode::FileNotFound,
+      ImportError::Io(_) => ErrorCode::Io,
+    };
+    CommandError::new(code, e.to_string())
+  }
+}
+
+impl From<AudioError> for CommandError {
+  fn from(e: AudioError) -> Self {
+    let code = match &e {
+      AudioError::DeviceInit(_) | AudioError::StreamError(_) => ErrorCode::Device,
+      AudioError::DecodeError(_) => ErrorCode::Decode,
+      AudioError::FileError(_) => ErrorCode::FileNotFound,
+      AudioError::PlaybackError(_) => ErrorCode::Playback,
+      AudioError::InvalidFormat(_) => ErrorCode::InvalidFormat,
+      AudioError::IoError(_) => ErrorCode::Io,
+    };
+    CommandError::new(code, e.to_string())
+  }
+}
+
+impl From<rusqlite::Error> for CommandError {
+  fn from(e: rusqlite::Error) -> Self {
+    let code = match e {
+      rusqlite::Error::QueryReturnedNoRows => ErrorCode::NotFound,
+      _ => ErrorCode::Database,
+    };
+    CommandError::new(code, e.to_string())
+  }
+}
@@ -1,26 +1,42 @@
-use super::{AppState, CachedSong, CachedStem};
-use crate::database::{Song, SongFilter, SortBy};
-use crate::import::{import_song, ImportRequest};
+use super::{lock_or_recover, stem_content_hash, AppState, CachedSong, CachedStem, CommandError, ErrorCode, source_modified_time};
+use crate::audio::decoder::AudioDecoder;
+use crate::audio::waveform::{compute_peaks, WaveformPeaks, MAX_WAVEFORM_BUCKETS};
+use crate::database::{LibraryFacets, MaintenanceReport, Song, SongFilter, SortBy, SortDirection, Stem, StemKeyword};
+use crate::import::{self, import_song, ImportRequest};
+use rayon::prelude::*;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
 
+// How much of a file preview_audio_waveform decodes unless asked, and at most, in seconds
+const DEFAULT_PREVIEW_WAVEFORM_SECONDS: f64 = 1.0;
+const MAX_PREVIEW_WAVEFORM_SECONDS: f64 = 30.0;
+
 /// Import audio files as a new song with stems
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn import_files(
   file_paths: Vec<String>,
   title: String,
   artist: Option<String>,
   key: Option<String>,
   time_signature: Option<String>,
+  align_stems: Option<bool>,
   state: State<'_, AppState>,
   app_handle: tauri::AppHandle,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
   log::info!("Importing {} files for song '{}'", file_paths.len(), title);
 
   // Convert string paths to PathBuf
   let paths: Vec<PathBuf> = file_paths.iter().map(PathBuf::from).collect();
 
+  // Convert stems to the project sample rate if that's turned on in settings.
+  // Practice mode writes nothing to disk: no converted copies and no mixdown
+  let settings = state.database
+    .get_settings()
+    .map_err(|e| CommandError::from(e).context("Failed to get settings"))?;
+  let import_sample_rate = if settings.practice_mode { 0 } else { settings.import_sample_rate };
+
   // Create import request
   let request = ImportRequest {
     file_paths: paths,
@@ -28,14 +44,111 @@ pub async fn import_files(
     artist,
     key,
     time_signature,
+    align_leading_silence: align_stems.unwrap_or(false),
+    target_sample_rate: (import_sample_rate > 0).then_some(import_sample_rate as u32),
+    generate_mixdown: !settings.practice_mode,
   };
 
   // Perform the import
   let import_result = import_song(&*state.database, request)
-    .map_err(|e| format!("Import failed: {}", e))?;
+    .map_err(|e| CommandError::from(e).context("Import failed"))?;
 
   log::info!("Successfully imported song with ID: {}", import_result.song_id);
 
+  cache_imported_song(&state, &import_result)?;
+
+  // TODO: Emit import:progress events using app_handle.emit()
+  // This will be implemented in the event emitter task
+
+  Ok(import_result.song_id)
+}
+
+/// Import one pre-rendered stereo track as a song named `title` with a single "Full Mix" stem,
+/// so songs without stems can still go in setlists
+#[tauri::command]
+pub async fn import_single_track(
+  file_path: String,
+  title: String,
+  artist: Option<String>,
+  state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+  log::info!("Importing single track '{}' as song '{}'", file_path, title);
+
+  let settings = state.database
+    .get_settings()
+    .map_err(|e| CommandError::from(e).context("Failed to get settings"))?;
+  let import_sample_rate = if settings.practice_mode { 0 } else { settings.import_sample_rate };
+
+  let request = ImportRequest {
+    file_paths: vec![PathBuf::from(file_path)],
+    title,
+    artist,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: (import_sample_rate > 0).then_some(import_sample_rate as u32),
+    generate_mixdown: !settings.practice_mode,
+  };
+
+  let import_result = import::import_single_track(&state.database, request)
+    .map_err(|e| CommandError::from(e).context("Import failed"))?;
+
+  log::info!("Successfully imported song with ID: {}", import_result.song_id);
+
+  cache_imported_song(&state, &import_result)?;
+
+  Ok(import_result.song_id)
+}
+
+/// Import audio files as a new song, naming each stem explicitly instead of detecting names
+/// from the file names. `entries` pairs each file path with its stem name; repeated names
+/// are numbered apart ("Vox 1", "Vox 2")
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_files_with_names(
+  entries: Vec<(String, String)>,
+  title: String,
+  artist: Option<String>,
+  key: Option<String>,
+  time_signature: Option<String>,
+  align_stems: Option<bool>,
+  state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+  log::info!("Importing {} named files for song '{}'", entries.len(), title);
+
+  let settings = state.database
+    .get_settings()
+    .map_err(|e| CommandError::from(e).context("Failed to get settings"))?;
+  let import_sample_rate = if settings.practice_mode { 0 } else { settings.import_sample_rate };
+
+  let (paths, names): (Vec<PathBuf>, Vec<String>) = entries
+    .into_iter()
+    .map(|(path, name)| (PathBuf::from(path), name))
+    .unzip();
+
+  let request = ImportRequest {
+    file_paths: paths,
+    title,
+    artist,
+    key,
+    time_signature,
+    align_leading_silence: align_stems.unwrap_or(false),
+    target_sample_rate: (import_sample_rate > 0).then_some(import_sample_rate as u32),
+    generate_mixdown: !settings.practice_mode,
+  };
+
+  let import_result = import::import_song_with_names(&state.database, request, &names)
+    .map_err(|e| CommandError::from(e).context("Import failed"))?;
+
+  log::info!("Successfully imported song with ID: {}", import_result.song_id);
+
+  cache_imported_song(&state, &import_result)?;
+
+  Ok(import_result.song_id)
+}
+
+// Put the stems decoded during an import into the song cache so the song plays instantly
+fn cache_imported_song(state: &AppState, import_result: &import::ImportResult) -> Result<(), String> {
   // Get the stems from database to match with decoded data
   let db_stems = state.database
     .get_stems_for_song(&import_result.song_id)
@@ -55,6 +168,9 @@ pub async fn import_files(
           sample_rate: decoded_stem.sample_rate,
           volume: db_stem.volume as f32,
           is_muted: db_stem.is_muted,
+          source_path: db_stem.file_path.clone(),
+          source_modified: source_modified_time(&db_stem.file_path),
+          source_hash: db_stem.file_hash.clone(), // Hashed by the import
         }
       })
       .collect();
@@ -65,17 +181,110 @@ pub async fn import_files(
     };
 
     // Insert into cache
-    let mut cache = state.song_cache.lock()
-      .map_err(|_| "Failed to lock cache".to_string())?;
+    let mut cache = lock_or_recover(&state.song_cache, "song cache");
     cache.insert(import_result.song_id.clone(), cached_song);
 
     log::info!("✅ Song cached in memory - ready for instant playback!");
   }
 
-  // TODO: Emit import:progress events using app_handle.emit()
-  // This will be implemented in the event emitter task
+  Ok(())
+}
 
-  Ok(import_result.song_id)
+/// Preview an import: detected stem names, durations and tags, without importing anything
+#[tauri::command]
+pub async fn preview_import(
+  file_paths: Vec<String>,
+  state: State<'_, AppState>
+) -> Result<import::ImportPreview, CommandError> {
+  let paths: Vec<PathBuf> = file_paths.into_iter().map(PathBuf::from).collect();
+
+  import::preview_import(&state.database, &paths)
+    .map_err(|e| CommandError::from(e).context("Failed to preview import"))
+}
+
+/// Min/max peaks of the first `seconds` (1 by default, up to 30) of an audio file, for previewing
+/// it in the add-file dialog before importing. Only that much of the file is decoded
+#[tauri::command]
+pub async fn preview_audio_waveform(
+  path: String,
+  buckets: usize,
+  seconds: Option<f64>,
+) -> Result<WaveformPeaks, CommandError> {
+  let seconds = seconds.unwrap_or(DEFAULT_PREVIEW_WAVEFORM_SECONDS);
+  tokio::task::spawn_blocking(move || preview_waveform(&path, buckets, seconds))
+    .await
+    .map_err(|e| format!("Waveform preview task failed: {}", e))?
+}
+
+pub(crate) fn preview_waveform(path: &str, buckets: usize, seconds: f64) -> Result<WaveformPeaks, CommandError> {
+  if !(1..=MAX_WAVEFORM_BUCKETS).contains(&buckets) {
+    return Err(CommandError::new(
+      ErrorCode::Validation,
+      format!("Invalid bucket count: {}, expected 1-{}", buckets, MAX_WAVEFORM_BUCKETS),
+    ));
+  }
+  if !seconds.is_finite() || seconds <= 0.0 || seconds > MAX_PREVIEW_WAVEFORM_SECONDS {
+    return Err(CommandError::new(
+      ErrorCode::Validation,
+      format!("Invalid preview length: {} s, expected up to {}", seconds, MAX_PREVIEW_WAVEFORM_SECONDS),
+    ));
+  }
+
+  let mut decoder = AudioDecoder::new(path)
+    .map_err(|e| CommandError::from(e).context(&format!("Failed to open {}", path)))?;
+  let channels = decoder.get_metadata()
+    .map_err(|e| CommandError::from(e).context(&format!("Failed to read {}", path)))?
+    .channels as usize;
+  let samples = decoder.decode_first_seconds(seconds)
+    .map_err(|e| CommandError::from(e).context(&format!("Failed to decode {}", path)))?;
+
+  Ok(compute_peaks(&samples, channels, buckets))
+}
+
+/// Export a song's stems and metadata to a ZIP archive at `dest_path`
+#[tauri::command]
+pub async fn export_song_archive(
+  song_id: String,
+  dest_path: String,
+  state: State<'_, AppState>
+) -> Result<(), CommandError> {
+  log::info!("Exporting song {} to {}", song_id, dest_path);
+
+  import::export_song_archive(&state.database, &song_id, &PathBuf::from(dest_path))
+    .map_err(|e| CommandError::from(e).context("Export failed"))
+}
+
+/// Import a song from a ZIP archive created by export_song_archive, returning the new song's ID
+#[tauri::command]
+pub async fn import_song_archive(
+  path: String,
+  state: State<'_, AppState>
+) -> Result<String, CommandError> {
+  log::info!("Importing song archive: {}", path);
+
+  let songs_dir = import::get_songs_directory()
+    .map_err(|e| CommandError::from(e).context("Import failed"))?;
+
+  import::import_song_archive(&state.database, &PathBuf::from(path), &songs_dir)
+    .map_err(|e| CommandError::from(e).context("Import failed"))
+}
+
+/// Move a song's stem files and mixdown into `new_directory` (e.g. out of a downloads folder)
+#[tauri::command]
+pub async fn relocate_song(
+  song_id: String,
+  new_directory: String,
+  state: State<'_, AppState>
+) -> Result<Song, String> {
+  log::info!("Relocating song {} to {}", song_id, new_directory);
+
+  let song = import::relocate_song(&state.database, &song_id, &PathBuf::from(new_directory))
+    .map_err(|e| format!("Failed to relocate song: {}", e))?;
+
+  // Cached samples point at the old files
+  lock_or_recover(&state.song_cache, "song cache").remove(&song_id);
+
+  Ok(song)
 }
 
 /// Get all songs from the library
@@ -104,6 +313,8 @@ pub async fn search_songs(
     tempo_max: None,
     key: None,
     sort_by: None,
+    sort_direction: None,
+    secondary_sort: None,
   };
 
   let songs = state.database
@@ -115,23 +326,24 @@ pub async fn search_songs(
 
 /// Filter songs with multiple criteria
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn filter_songs(
   search_query: Option<String>,
   tempo_min: Option<f64>,
   tempo_max: Option<f64>,
   key: Option<String>,
   sort_by: Option<String>,
+  sort_direction: Option<String>,
+  secondary_sort_by: Option<String>,
   state: State<'_, AppState>
 ) -> Result<Vec<Song>, String> {
   log::debug!("Filtering songs with criteria");
 
-  // Convert sort_by string to enum
-  let sort_option = match sort_by.as_deref() {
-    Some("name") => Some(SortBy::Name),
-    Some("artist") => Some(SortBy::Artist),
-    Some("tempo") => Some(SortBy::Tempo),
-    Some("duration") => Some(SortBy::Duration),
-    Some("date_added") => Some(SortBy::DateAdded),
+  // Convert sort strings to enums
+  let sort_option = sort_by.as_deref().and_then(parse_sort_by);
+  let direction = match sort_direction.as_deref() {
+    Some("asc") => Some(SortDirection::Ascending),
+    Some("desc") => Some(SortDirection::Descending),
     _ => None,
   };
 
@@ -141,6 +353,8 @@ pub async fn filter_songs(
     tempo_max,
     key,
     sort_by: sort_option,
+    sort_direction: direction,
+    secondary_sort: secondary_sort_by.as_deref().and_then(parse_sort_by),
   };
 
   let songs = state.database
@@ -150,6 +364,19 @@ pub async fn filter_songs(
   Ok(songs)
 }
 
+fn parse_sort_by(sort_by: &str) -> Option<SortBy> {
+  match sort_by {
+    "name" => Some(SortBy::Name),
+    "artist" => Some(SortBy::Artist),
+    "tempo" => Some(SortBy::Tempo),
+    "duration" => Some(SortBy::Duration),
+    "date_added" => Some(SortBy::DateAdded),
+    "most_played" => Some(SortBy::MostPlayed),
+    "recently_played" => Some(SortBy::RecentlyPlayed),
+    _ => None,
+  }
+}
+
 /// Get a specific song by ID
 #[tauri::command]
 pub async fn get_song(
@@ -165,6 +392,68 @@ pub async fn get_song(
   Ok(song)
 }
 
+/// Register a tap for tap tempo and return the current BPM estimate (None after the first tap)
+#[tauri::command]
+pub async fn tap_tempo(state: State<'_, AppState>) -> Result<Option<f64>, String> {
+  let mut tap_tempo = state.tap_tempo
+    .lock()
+    .map_err(|_| "Failed to lock tap tempo")?;
+
+  Ok(tap_tempo.tap_at(std::time::Instant::now()))
+}
+
+/// Save the tapped tempo as the song's BPM and start a new tap sequence
+#[tauri::command]
+pub async fn commit_tap_tempo(
+  song_id: String,
+  state: State<'_, AppState>
+) -> Result<f64, String> {
+  commit_tapped_tempo(&state, &song_id)
+}
+
+pub(crate) fn commit_tapped_tempo(state: &AppState, song_id: &str) -> Result<f64, String> {
+  let mut tap_tempo = state.tap_tempo
+    .lock()
+    .map_err(|_| "Failed to lock tap tempo")?;
+
+  let bpm = tap_tempo.bpm()
+    .ok_or_else(|| "Tap at least twice to set a tempo".to_string())?;
+  // One decimal place is as precise as tapping gets
+  let bpm = (bpm * 10.0).round() / 10.0;
+
+  let mut song = state.database
+    .get_song(song_id)
+    .map_err(|e| format!("Failed to get song: {}", e))?;
+  song.tempo = Some(bpm);
+  state.database
+    .update_song(&song)
+    .map_err(|e| format!("Failed to update song: {}", e))?;
+
+  log::info!("Set tempo of song {} to {} BPM from tap tempo", song_id, bpm);
+  tap_tempo.reset();
+
+  Ok(bpm)
+}
+
+/// Get the most recently played songs, newest first
+#[tauri::command]
+pub async fn get_recently_played(
+  limit: Option<usize>,
+  state: State<'_, AppState>
+) -> Result<Vec<Song>, String> {
+  state.database
+    .get_recently_played(limit.unwrap_or(20))
+    .map_err(|e| format!("Failed to get recently played songs: {}", e))
+}
+
+/// Get the keys and tempo range present in the library, for the filter dropdowns
+#[tauri::command]
+pub async fn get_library_facets(state: State<'_, AppState>) -> Result<LibraryFacets, String> {
+  state.database
+    .get_library_facets()
+    .map_err(|e| format!("Failed to get library facets: {}", e))
+}
+
 /// Delete a song and all its stems
 #[tauri::command]
 pub async fn delete_song(
@@ -182,6 +471,7 @@ pub async fn delete_song(
     state.database
       .delete_stem(&stem.id)
       .map_err(|e| format!("Failed to delete stem: {}", e))?;
+    state.disk_cache.remove_stem(&stem.id);
   }
 
   // Delete the song
@@ -197,12 +487,372 @@ pub async fn delete_song(
 pub async fn get_song_stems(
   song_id: String,
   state: State<'_, AppState>
-) -> Result<Vec<crate::database::Stem>, String> {
+) -> Result<Vec<StemInfo>, String> {
   log::debug!("Getting stems for song: {}", song_id);
 
   let stems = state.database
     .get_stems_for_song(&song_id)
     .map_err(|e| format!("Failed to get stems: {}", e))?;
+  let project_sample_rate = lock_or_recover(&state.audio_engine, "audio engine").project_sample_rate();
+
+  Ok(stems.into_iter().map(|stem| StemInfo::new(stem, project_sample_rate)).collect())
+}
 
-  Ok(stems)
+/// A stem with how it will be converted at load, derived from its stored metadata
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StemInfo {
+  #[serde(flatten)]
+  pub stem: Stem,
+  pub project_sample_rate: u32, // Rate every stem is decoded and played at
+  pub will_resample: bool,
+  pub is_mono: bool,
 }
+
+impl StemInfo {
+  pub fn new(stem: Stem, project_sample_rate: u32) -> Self {
+    // Unknown rates (0) are left alone rather than reported as a conversion
+    let will_resample = stem.sample_rate > 0 && stem.sample_rate as u32 != project_sample_rate;
+    let is_mono = stem.channels == 1;
+    StemInfo { stem, project_sample_rate, will_resample, is_mono }
+  }
+}
+
+/// Get the path of a song's generated mixdown, if it has one on disk
+#[tauri::command]
+pub async fn get_song_mixdown(
+  song_id: String,
+  state: State<'_, AppState>
+) -> Result<Option<String>, String> {
+  log::debug!("Getting mixdown for song: {}", song_id);
+
+  let song = state.database
+    .get_song(&song_id)
+    .map_err(|e| format!("Failed to get song: {}", e))?;
+
+  Ok(song.mixdown_path.filter(|path| {
+    let exists = std::path::Path::new(path).exists();
+    if !exists {
+      log::warn!("Mixdown for song {} is missing on disk: {}", song_id, path);
+    }
+    exists
+  }))
+}
+
+/// Add a custom stem detection keyword (e.g. "bgv" -> "BGV")
+#[tauri::command]
+pub async fn add_stem_keyword(
+  keyword: String,
+  display_name: String,
+  state: State<'_, AppState>
+) -> Result<StemKeyword, String> {
+  let keyword = keyword.trim().to_lowercase();
+  let display_name = display_name.trim().to_string();
+
+  if keyword.is_empty() || display_name.is_empty() {
+    return Err("Keyword and display name are required".to_string());
+  }
+
+  log::info!("Adding stem keyword '{}' -> '{}'", keyword, display_name);
+
+  let stem_keyword = StemKeyword {
+    keyword,
+    display_name,
+    created_at: chrono::Utc::now().timestamp(),
+  };
+
+  state.database
+    .add_stem_keyword(&stem_keyword)
+    .map_err(|e| format!("Failed to add stem keyword: {}", e))?;
+
+  Ok(stem_keyword)
+}
+
+/// List all custom stem detection keywords
+#[tauri::command]
+pub async fn list_stem_keywords(
+  state: State<'_, AppState>
+) -> Result<Vec<StemKeyword>, String> {
+  state.database
+    .list_stem_keywords()
+    .map_err(|e| format!("Failed to list stem keywords: {}", e))
+}
+
+/// Delete a custom stem detection keyword
+#[tauri::command]
+pub async fn delete_stem_keyword(
+  keyword: String,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::info!("Deleting stem keyword '{}'", keyword);
+
+  state.database
+    .delete_stem_keyword(&keyword.trim().to_lowercase())
+    .map_err(|e| format!("Failed to delete stem keyword: {}", e))
+}
+
+/// Why a stem failed the library health check
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StemProblemKind {
+  Missing,
+  Undecodable,
+  Empty,
+  UnsupportedFormat,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StemProblem {
+  pub song_id: String,
+  pub song_name: String,
+  pub stem_id: String,
+  pub stem_name: String,
+  pub file_path: String,
+  pub kind: StemProblemKind,
+  pub message: String,
+}
+
+/// Result of checking that every stem in a set of songs will play
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LibraryReport {
+  pub songs_checked: usize,
+  pub stems_checked: usize,
+  pub problems: Vec<StemProblem>,
+}
+
+/// Check that every stem in the library exists on disk and decodes
+#[tauri::command]
+pub async fn validate_library(state: State<'_, AppState>) -> Result<LibraryReport, String> {
+  log::info!("Validating library");
+
+  let songs = state.database
+    .list_songs(None)
+    .map_err(|e| format!("Failed to get songs: {}", e))?;
+
+  validate_songs(&state, &songs)
+}
+
+/// Check that every stem in a setlist exists on disk and decodes
+#[tauri::command]
+pub async fn validate_setlist(
+  setlist_id: String,
+  state: State<'_, AppState>
+) -> Result<LibraryReport, String> {
+  log::info!("Validating setlist: {}", setlist_id);
+
+  let songs = state.database
+    .get_setlist_songs(&setlist_id)
+    .map_err(|e| format!("Failed to get setlist songs: {}", e))?;
+
+  validate_songs(&state, &songs)
+}
+
+pub(crate) fn validate_songs(state: &AppState, songs: &[Song]) -> Result<LibraryReport, String> {
+  let mut stems: Vec<(&Song, Stem)> = Vec::new();
+  for song in songs {
+    let song_stems = state.database
+      .get_stems_for_song(&song.id)
+      .map_err(|e| format!("Failed to get stems for song '{}': {}", song.name, e))?;
+    stems.extend(song_stems.into_iter().map(|stem| (song, stem)));
+  }
+
+  // Opening decoders is I/O and CPU heavy, so check stems in parallel like the importer
+  let problems: Vec<StemProblem> = stems
+    .par_iter()
+    .filter_map(|(song, stem)| {
+      check_stem(stem).err().map(|(kind, message)| StemProblem {
+        song_id: song.id.clone(),
+        song_name: song.name.clone(),
+        stem_id: stem.id.clone(),
+        stem_name: stem.name.clone(),
+        file_path: stem.file_path.clone(),
+        kind,
+        message,
+      })
+    })
+    .collect();
+
+  log::info!("Validated {} stems in {} songs: {} problems", stems.len(), songs.len(), problems.len());
+
+  Ok(LibraryReport {
+    songs_checked: songs.len(),
+    stems_checked: stems.len(),
+    problems,
+  })
+}
+
+// Confirm a stem's source file exists, opens with the playback decoder and has audio in it
+fn check_stem(stem: &Stem) -> Result<(), (StemProblemKind, String)> {
+  let file_size = std::fs::metadata(&stem.file_path)
+    .map(|m| m.len())
+    .map_err(|e| (StemProblemKind::Missing, format!("File not found: {}", e)))?;
+  if file_size == 0 {
+    return Err((StemProblemKind::Empty, "File is empty".to_string()));
+  }
+
+  let mut decoder = AudioDecoder::new(&stem.file_path)
+    .map_err(|e| (StemProblemKind::Undecodable, e.to_string()))?;
+
+  match decoder.probe_sample_format() {
+    Ok(Some(format)) if format.supported => Ok(()),
+    Ok(Some(format)) => Err((
+      StemProblemKind::UnsupportedFormat,
+      format!("Unsupported sample format {} ({}-bit)", format.sample_format, format.bits_per_sample),
+    )),
+    Ok(None) => Err((StemProblemKind::Empty, "File contains no audio".to_string())),
+    Err(e) => Err((StemProblemKind::Undecodable, e.to_string())),
+  }
+}
+
+// Allowed difference from the median stem length before a stem is flagged
+const DEFAULT_LENGTH_TOLERANCE_SECONDS: f64 = 1.0;
+
+/// A stem whose length is far from the rest of its song, e.g. the wrong file was imported
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StemLengthMismatch {
+  pub stem_id: String,
+  pub stem_name: String,
+  pub duration: f64,
+  pub median_duration: f64,
+  pub difference: f64, // duration - median_duration, negative when the stem is short
+}
+
+/// Flag stems whose length differs from the song's median stem length by more than
+/// `tolerance_seconds` (1 second by default). Uses decoded lengths when the song is cached
+#[tauri::command]
+pub async fn check_stem_alignment(
+  song_id: String,
+  tolerance_seconds: Option<f64>,
+  state: State<'_, AppState>
+) -> Result<Vec<StemLengthMismatch>, String> {
+  stem_length_mismatches(&state, &song_id, tolerance_seconds.unwrap_or(DEFAULT_LENGTH_TOLERANCE_SECONDS))
+}
+
+pub(crate) fn stem_length_mismatches(
+  state: &AppState,
+  song_id: &str,
+  tolerance_seconds: f64,
+) -> Result<Vec<StemLengthMismatch>, String> {
+  if !tolerance_seconds.is_finite() || tolerance_seconds < 0.0 {
+    return Err(format!("Invalid tolerance: {}", tolerance_seconds));
+  }
+
+  let stems = state.database
+    .get_stems_for_song(song_id)
+    .map_err(|e| format!("Failed to get stems: {}", e))?;
+
+  // Decoded lengths are exact; the stored durations come from file metadata at import
+  let durations: Vec<f64> = {
+    let cache = lock_or_recover(&state.song_cache, "song cache");
+    let cached = cache.peek(song_id);
+    stems
+      .iter()
+      .map(|stem| {
+        cached
+          .and_then(|song| song.stems.iter().find(|s| s.stem_id == stem.id))
+          .map(|s| s.duration())
+          .unwrap_or(stem.duration)
+      })
+      .collect()
+  };
+
+  let Some(median_duration) = median(&durations) else {
+    return Ok(Vec::new());
+  };
+
+  Ok(stems
+    .iter()
+    .zip(&durations)
+    .filter(|(_, &duration)| (duration - median_duration).abs() > tolerance_seconds)
+    .map(|(stem, &duration)| StemLengthMismatch {
+      stem_id: stem.id.clone(),
+      stem_name: stem.name.clone(),
+      duration,
+      median_duration,
+      difference: duration - median_duration,
+    })
+    .collect())
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+  if values.is_empty() {
+    return None;
+  }
+
+  let mut sorted = values.to_vec();
+  sorted.sort_by(|a, b| a.total_cmp(b));
+  let mid = sorted.len() / 2;
+  Some(if sorted.len().is_multiple_of(2) { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] })
+}
+
+/// Checkpoint the database's write-ahead log and run an integrity check
+#[tauri::command]
+pub async fn maintain_database(state: State<'_, AppState>) -> Result<MaintenanceReport, String> {
+  log::info!("Running database maintenance");
+
+  let report = state.database
+    .maintain()
+    .map_err(|e| format!("Database maintenance failed: {}", e))?;
+
+  if report.integrity != "ok" {
+    log::error!("Database integrity check failed: {}", report.integrity);
+  }
+  log::info!("Database maintenance reclaimed {} bytes of WAL", report.wal_bytes_reclaimed);
+
+  Ok(report)
+}
+
+/// Songs whose stems are the same set of files, most likely the same song imported twice
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DuplicateSongCluster {
+  pub stem_count: usize,
+  pub songs: Vec<Song>, // Oldest import first
+}
+
+/// Group songs that have identical stem files (by content hash, regardless of stem names or
+/// order) so duplicates can be cleaned up. Stems imported before hashes were stored are hashed
+/// once here and the hash saved
+#[tauri::command]
+pub async fn find_duplicate_songs(state: State<'_, AppState>) -> Result<Vec<DuplicateSongCluster>, String> {
+  log::info!("Looking for duplicate songs");
+  duplicate_song_clusters(&state)
+}
+
+pub(crate) fn duplicate_song_clusters(state: &AppState) -> Result<Vec<DuplicateSongCluster>, String> {
+  let songs = state.database
+    .list_songs(None)
+    .map_err(|e| format!("Failed to get songs: {}", e))?;
+
+  let mut groups: std::collections::HashMap<Vec<String>, Vec<Song>> = std::collections::HashMap::new();
+  for song in songs {
+    let stems = state.database
+      .get_stems_for_song(&song.id)
+      .map_err(|e| format!("Failed to get stems for song: {}", e))?;
+    if stems.is_empty() {
+      continue;
+    }
+
+    // A song with a stem that can't be hashed can't be confirmed as a duplicate
+    let hashes: Option<Vec<String>> = stems.iter()
+      .map(|stem| stem_content_hash(&state.database, stem, source_modified_time(&stem.file_path)))
+      .collect();
+    let Some(mut hashes) = hashes else {
+      continue;
+    };
+    hashes.sort();
+    groups.entry(hashes).or_default().push(song);
+  }
+
+  let mut clusters: Vec<DuplicateSongCluster> = groups
+    .into_iter()
+    .filter(|(_, songs)| songs.len() > 1)
+    .map(|(hashes, mut songs)| {
+      songs.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.name.cmp(&b.name)));
+      DuplicateSongCluster { stem_count: hashes.len(), songs }
+    })
+    .collect();
+  clusters.sort_by(|a, b| a.songs[0].name.to_lowercase().cmp(&b.songs[0].name.to_lowercase()));
+
+  log::info!("Found {} clusters of duplicate songs", clusters.len());
+  Ok(clusters)
+}
+
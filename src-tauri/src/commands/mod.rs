@@ -4,6 +4,8 @@ mod library;
 mod setlists;
 mod cache;
 mod settings;
+mod preload;
+mod error;
 
 #[cfg(test)]
 mod tests;
@@ -14,12 +16,27 @@ pub use library::*;
 pub use setlists::*;
 pub use cache::*;
 pub use settings::*;
+pub use preload::*;
+pub use error::*;
 
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
-use crate::audio::MultiTrackEngine;
-use crate::database::Database;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use crate::audio::{CacheManager, MultiTrackEngine};
+use crate::database::{Database, Stem};
+
+// Lock a mutex, recovering the guard if a panic elsewhere poisoned it. The data may have been
+// left mid-update, but for the engine, cache and stem map that beats failing every later command
+pub(crate) fn lock_or_recover<'a, T>(mutex: &'a Mutex<T>, name: &str) -> MutexGuard<'a, T> {
+  mutex.lock().unwrap_or_else(|poisoned| {
+    log::error!("Recovering poisoned {} lock", name);
+    mutex.clear_poison();
+    poisoned.into_inner()
+  })
+}
 
 // Cached song data - all stems pre-decoded and ready to play (in-memory only)
 #[derive(Clone)]
@@ -35,6 +52,58 @@ pub struct CachedStem {
   pub sample_rate: u32, // Sample rate these samples were encoded at
   pub volume: f32,
   pub is_muted: bool,
+  pub source_path: String, // Original file the samples were decoded from
+  pub source_modified: Option<SystemTime>, // Source mtime at decode time, used to detect edits
+  pub source_hash: Option<String>, // Whole-file hash of the source; identical files share one decoded copy
+}
+
+impl CachedStem {
+  // Cached samples are interleaved stereo, the layout the engine plays
+  pub fn frame_count(&self) -> usize {
+    self.samples.len() / 2
+  }
+
+  pub fn duration(&self) -> f64 {
+    if self.sample_rate == 0 {
+      return 0.0;
+    }
+    self.frame_count() as f64 / self.sample_rate as f64
+  }
+
+  // Cheap staleness check: only the source file's mtime is compared, so unchanged
+  // files never need to be re-read or hashed.
+  pub fn is_stale(&self) -> bool {
+    source_modified_time(&self.source_path) != self.source_modified
+  }
+}
+
+// Modification time of a stem's source file (None if the file is missing or unreadable)
+pub fn source_modified_time(path: &str) -> Option<SystemTime> {
+  std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Full content hash of the file a stem plays. The stored hash is used while the file's mtime
+// (`modified`, read by the caller before anything else touches the file) matches the one it
+// was computed at; otherwise the file is hashed and the result saved. None if it can't be read
+pub(crate) fn stem_content_hash(database: &Database, stem: &Stem, modified: Option<SystemTime>) -> Option<String> {
+  let modified = modified.and_then(crate::import::modified_nanos);
+  if let Some(hash) = &stem.file_hash {
+    if modified.is_some() && stem.file_hash_modified == modified {
+      return Some(hash.clone());
+    }
+  }
+
+  let hash = match crate::import::calculate_content_hash(std::path::Path::new(&stem.file_path)) {
+    Ok(hash) => hash,
+    Err(e) => {
+      log::warn!("Failed to hash stem '{}': {}", stem.name, e);
+      return None;
+    }
+  };
+  if let Err(e) = database.set_stem_file_hash(&stem.id, &hash, modified) {
+    log::warn!("Failed to save hash of stem '{}': {}", stem.name, e);
+  }
+  Some(hash)
 }
 
 // LRU Cache Entry with access tracking
@@ -42,26 +111,117 @@ pub struct CachedStem {
 pub struct CacheEntry {
   pub song: CachedSong,
   pub last_accessed: u64, // Unix timestamp in seconds
-  pub size_bytes: usize,  // Approximate size in bytes
+  pub size_bytes: usize,  // Approximate size in bytes, excluding samples shared by content hash
+}
+
+// Decoded samples held once for every cached stem whose source has the same content and rate,
+// e.g. a click track reused across songs. Freed when the last stem referencing it is removed
+struct SharedSamples {
+  samples: Arc<Vec<f32>>,
+  refs: usize,
 }
 
 // LRU Song Cache with size limit
 pub struct SongCache {
   entries: HashMap<String, CacheEntry>,
+  shared: HashMap<String, SharedSamples>, // Keyed by shared_key(source_hash, sample_rate)
+  pinned: Option<String>, // Song never chosen for LRU eviction (the one on stage)
   max_size_bytes: usize,
   current_size_bytes: usize,
+  hits: u64, // Loads served from the cache
+  misses: u64, // Loads that had to decode
+  recent: VecDeque<LookupBucket>, // Hits and misses per minute over the recent window
+}
+
+// Hits and misses counted during one bucket of time
+#[derive(Debug, Clone, Copy)]
+struct LookupBucket {
+  start: u64, // Unix seconds, a multiple of EFFICIENCY_BUCKET_SECS
+  hits: u64,
+  misses: u64,
+}
+
+const EFFICIENCY_BUCKET_SECS: u64 = 60;
+// Buckets in the recent window (10 minutes)
+const EFFICIENCY_WINDOW_BUCKETS: u64 = 10;
+
+/// Share of song loads served from the cache, overall and over the last few minutes.
+/// Rates are None until there's been a load
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CacheEfficiency {
+  pub hits: u64,
+  pub misses: u64,
+  pub hit_rate: Option<f64>,
+  pub recent_hits: u64,
+  pub recent_misses: u64,
+  pub recent_hit_rate: Option<f64>,
+  pub recent_window_secs: u64,
+}
+
+fn hit_rate(hits: u64, misses: u64) -> Option<f64> {
+  let total = hits + misses;
+  (total > 0).then(|| hits as f64 / total as f64)
+}
+
+fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_secs()
+}
+
+// Smallest cache size accepted from settings; below this even one song rarely fits
+pub const MIN_CACHE_SIZE_BYTES: usize = 256 * 1024 * 1024; // 256 MB
+
+// Default cache size: 3GB (allows ~5 songs with 20 stems each)
+pub const DEFAULT_CACHE_SIZE_GB: f64 = 3.0;
+
+// Default disk cache size: 10GB (about 90 five-minute stems)
+pub const DEFAULT_DISK_CACHE_SIZE_GB: f64 = 10.0;
+
+const BYTES_PER_GB: f64 = 1_073_741_824.0;
+
+// Cache capacity in bytes for a size in GB from settings, raised to the floor
+pub fn cache_size_bytes_from_gb(gb: f64) -> usize {
+  let gb = if gb.is_finite() { gb } else { DEFAULT_CACHE_SIZE_GB };
+  ((gb.max(0.0) * BYTES_PER_GB) as usize).max(MIN_CACHE_SIZE_BYTES)
+}
+
+fn shared_key(source_hash: &str, sample_rate: u32) -> String {
+  format!("{}@{}", source_hash, sample_rate)
 }
 
 impl SongCache {
   pub fn new(max_size_bytes: usize) -> Self {
     SongCache {
       entries: HashMap::new(),
+      shared: HashMap::new(),
+      pinned: None,
       max_size_bytes,
       current_size_bytes: 0,
+      hits: 0,
+      misses: 0,
+      recent: VecDeque::new(),
     }
   }
 
+  // Cache sized from the `in_memory_cache_gb` setting
+  pub fn from_gb(gb: f64) -> Self {
+    SongCache::new(cache_size_bytes_from_gb(gb))
+  }
+
   pub fn get(&mut self, song_id: &str) -> Option<CachedSong> {
+    // Invalidate the entry if any source file changed since it was decoded
+    let is_stale = self.entries
+      .get(song_id)
+      .map(|entry| entry.song.stems.iter().any(|stem| stem.is_stale()))
+      .unwrap_or(false);
+    if is_stale {
+      log::info!("Cache: Source files changed for song {}, invalidating", song_id);
+      self.remove(song_id);
+      return None;
+    }
+
     if let Some(entry) = self.entries.get_mut(song_id) {
       // Update access time
       entry.last_accessed = SystemTime::now()
@@ -74,16 +234,16 @@ impl SongCache {
     }
   }
 
-  pub fn insert(&mut self, song_id: String, song: CachedSong) {
-    // Calculate approximate size (samples * 4 bytes per f32)
-    let size_bytes: usize = song.stems.iter()
-      .map(|stem| stem.samples.len() * 4)
-      .sum();
+  pub fn insert(&mut self, song_id: String, mut song: CachedSong) {
+    // Remove old entry if exists (to update size and release its shared samples)
+    self.release(&song_id);
+
+    // Stems with a known source hash point at one shared copy; only the rest count against this entry
+    let size_bytes = self.share_samples(&mut song);
+    self.current_size_bytes += size_bytes;
 
     // Evict entries if needed to make space
-    while self.current_size_bytes + size_bytes > self.max_size_bytes && !self.entries.is_empty() {
-      self.evict_lru();
-    }
+    while self.current_size_bytes > self.max_size_bytes && self.evict_lru() {}
 
     let entry = CacheEntry {
       song: song.clone(),
@@ -94,12 +254,6 @@ impl SongCache {
       size_bytes,
     };
 
-    // Remove old entry if exists (to update size)
-    if let Some(old_entry) = self.entries.remove(&song_id) {
-      self.current_size_bytes -= old_entry.size_bytes;
-    }
-
-    self.current_size_bytes += size_bytes;
     self.entries.insert(song_id, entry);
 
     log::info!(
@@ -110,32 +264,168 @@ impl SongCache {
     );
   }
 
+  // Read a cached song without touching its LRU access time or checking staleness
+  pub fn peek(&self, song_id: &str) -> Option<&CachedSong> {
+    self.entries.get(song_id).map(|entry| &entry.song)
+  }
+
   pub fn contains(&self, song_id: &str) -> bool {
     self.entries.contains_key(song_id)
   }
 
+  // Drop a song decoded at a rate other than `sample_rate`, so it decodes again at that rate
+  pub fn remove_at_other_rate(&mut self, song_id: &str, sample_rate: u32) {
+    let other_rate = self.entries
+      .get(song_id)
+      .is_some_and(|entry| entry.song.stems.iter().any(|stem| stem.sample_rate != sample_rate));
+    if other_rate {
+      log::info!("Cache: Song {} was decoded at another rate than {}Hz, invalidating", song_id, sample_rate);
+      self.remove(song_id);
+    }
+  }
+
   pub fn remove(&mut self, song_id: &str) {
-    if let Some(entry) = self.entries.remove(song_id) {
-      self.current_size_bytes -= entry.size_bytes;
-      log::info!("Cache: Removed song {} ({:.1} MB)", song_id, entry.size_bytes as f64 / 1_048_576.0);
+    if let Some(freed) = self.release(song_id) {
+      log::info!("Cache: Removed song {} ({:.1} MB freed)", song_id, freed as f64 / 1_048_576.0);
     }
   }
 
   pub fn clear(&mut self) {
     self.entries.clear();
+    self.shared.clear();
     self.current_size_bytes = 0;
     log::info!("Cache: Cleared all songs");
   }
 
-  fn evict_lru(&mut self) {
-    // Find the least recently used entry
-    if let Some(lru_id) = self.entries
+  // Decoded samples already cached for a source with this content hash at `sample_rate`
+  pub fn shared_samples(&self, source_hash: &str, sample_rate: u32) -> Option<Arc<Vec<f32>>> {
+    self.shared
+      .get(&shared_key(source_hash, sample_rate))
+      .map(|shared| shared.samples.clone())
+  }
+
+  // Point each hashed stem at the shared copy for its content (adding one if it's the first),
+  // returning the bytes of the song's unshared stems. New shared copies are counted here directly
+  fn share_samples(&mut self, song: &mut CachedSong) -> usize {
+    let mut unshared_bytes = 0;
+    for stem in &mut song.stems {
+      let Some(hash) = &stem.source_hash else {
+        unshared_bytes += stem.samples.len() * 4;
+        continue;
+      };
+
+      let key = shared_key(hash, stem.sample_rate);
+      match self.shared.get_mut(&key) {
+        Some(shared) => {
+          shared.refs += 1;
+          stem.samples = shared.samples.clone();
+        }
+        None => {
+          self.current_size_bytes += stem.samples.len() * 4;
+          self.shared.insert(key, SharedSamples { samples: stem.samples.clone(), refs: 1 });
+        }
+      }
+    }
+    unshared_bytes
+  }
+
+  // Drop a song's entry and its references to shared samples, returning the bytes freed
+  fn release(&mut self, song_id: &str) -> Option<usize> {
+    let entry = self.entries.remove(song_id)?;
+    let mut freed = entry.size_bytes;
+
+    for stem in &entry.song.stems {
+      let Some(hash) = &stem.source_hash else { continue };
+      let key = shared_key(hash, stem.sample_rate);
+      if let Some(shared) = self.shared.get_mut(&key) {
+        shared.refs -= 1;
+        if shared.refs == 0 {
+          freed += shared.samples.len() * 4;
+          self.shared.remove(&key);
+        }
+      }
+    }
+
+    self.current_size_bytes -= freed;
+    Some(freed)
+  }
+
+  // Evict the least recently used song other than the pinned one. False when nothing can go
+  fn evict_lru(&mut self) -> bool {
+    let lru_id = self.entries
       .iter()
+      .filter(|(id, _)| self.pinned.as_deref() != Some(id.as_str()))
       .min_by_key(|(_, entry)| entry.last_accessed)
-      .map(|(id, _)| id.clone())
-    {
-      log::info!("Cache: Evicting LRU song {}", lru_id);
-      self.remove(&lru_id);
+      .map(|(id, _)| id.clone());
+
+    match lru_id {
+      Some(lru_id) => {
+        log::info!("Cache: Evicting LRU song {}", lru_id);
+        self.remove(&lru_id);
+        true
+      }
+      None => false,
+    }
+  }
+
+  // Keep a song (e.g. the one playing) out of LRU eviction; None unpins
+  pub fn set_pinned(&mut self, song_id: Option<String>) {
+    self.pinned = song_id;
+  }
+
+  pub fn pinned(&self) -> Option<&str> {
+    self.pinned.as_deref()
+  }
+
+  // Count a song load as served from the cache (hit) or decoded (miss)
+  pub fn record_lookup(&mut self, hit: bool) {
+    self.record_lookup_at(hit, unix_now());
+  }
+
+  fn record_lookup_at(&mut self, hit: bool, now: u64) {
+    if hit {
+      self.hits += 1;
+    } else {
+      self.misses += 1;
+    }
+
+    let start = now - now % EFFICIENCY_BUCKET_SECS;
+    if self.recent.back().is_none_or(|bucket| bucket.start != start) {
+      self.recent.push_back(LookupBucket { start, hits: 0, misses: 0 });
+    }
+    let bucket = self.recent.back_mut().unwrap();
+    if hit {
+      bucket.hits += 1;
+    } else {
+      bucket.misses += 1;
+    }
+
+    let window_start = start.saturating_sub((EFFICIENCY_WINDOW_BUCKETS - 1) * EFFICIENCY_BUCKET_SECS);
+    while self.recent.front().is_some_and(|bucket| bucket.start < window_start) {
+      self.recent.pop_front();
+    }
+  }
+
+  pub fn efficiency(&self) -> CacheEfficiency {
+    self.efficiency_at(unix_now())
+  }
+
+  fn efficiency_at(&self, now: u64) -> CacheEfficiency {
+    let window_start = (now - now % EFFICIENCY_BUCKET_SECS)
+      .saturating_sub((EFFICIENCY_WINDOW_BUCKETS - 1) * EFFICIENCY_BUCKET_SECS);
+    let (recent_hits, recent_misses) = self.recent
+      .iter()
+      .filter(|bucket| bucket.start >= window_start)
+      .fold((0, 0), |(hits, misses), bucket| (hits + bucket.hits, misses + bucket.misses));
+
+    CacheEfficiency {
+      hits: self.hits,
+      misses: self.misses,
+      hit_rate: hit_rate(self.hits, self.misses),
+      recent_hits,
+      recent_misses,
+      recent_hit_rate: hit_rate(recent_hits, recent_misses),
+      recent_window_secs: EFFICIENCY_WINDOW_BUCKETS * EFFICIENCY_BUCKET_SECS,
     }
   }
 
@@ -149,18 +439,192 @@ impl SongCache {
     log::info!("Cache: Max size updated to {:.1} GB", new_max_bytes as f64 / 1_073_741_824.0);
 
     // Evict entries if current usage exceeds new limit
-    while self.current_size_bytes > self.max_size_bytes && !self.entries.is_empty() {
-      self.evict_lru();
+    while self.current_size_bytes > self.max_size_bytes && self.evict_lru() {}
+  }
+}
+
+// Marks a song as being decoded; the id is released when the guard drops (success or error)
+pub struct LoadingGuard {
+  loading_songs: Arc<Mutex<HashSet<String>>>,
+  song_id: String,
+}
+
+impl LoadingGuard {
+  // Returns None if another task is already loading this song
+  pub fn acquire(loading_songs: &Arc<Mutex<HashSet<String>>>, song_id: &str) -> Option<Self> {
+    let mut loading = loading_songs.lock().unwrap_or_else(|e| e.into_inner());
+    if !loading.insert(song_id.to_string()) {
+      return None;
+    }
+
+    Some(LoadingGuard {
+      loading_songs: loading_songs.clone(),
+      song_id: song_id.to_string(),
+    })
+  }
+}
+
+impl Drop for LoadingGuard {
+  fn drop(&mut self) {
+    let mut loading = self.loading_songs.lock().unwrap_or_else(|e| e.into_inner());
+    loading.remove(&self.song_id);
+  }
+}
+
+// Load a song into the cache exactly once: returns immediately if it's cached, waits if
+// another task is already decoding it, otherwise runs `load` and caches the result
+pub async fn load_once<F, Fut>(
+  loading_songs: &Arc<Mutex<HashSet<String>>>,
+  song_cache: &Arc<Mutex<SongCache>>,
+  song_id: &str,
+  load: F,
+) -> Result<(), String>
+where
+  F: FnOnce() -> Fut,
+  Fut: Future<Output = Result<CachedSong, String>>,
+{
+  let is_cached = || -> Result<bool, String> {
+    // Stale entries are invalidated by get
+    let mut cache = lock_or_recover(song_cache, "song cache");
+    Ok(cache.get(song_id).is_some())
+  };
+
+  let _guard = loop {
+    if is_cached()? {
+      log::info!("Song {} already in memory, skipping load", song_id);
+      lock_or_recover(song_cache, "song cache").record_lookup(true);
+      return Ok(());
+    }
+
+    if let Some(guard) = LoadingGuard::acquire(loading_songs, song_id) {
+      break guard;
+    }
+
+    // Another load is in flight; wait for it and re-check (retry if it failed)
+    log::info!("Song {} is already loading, waiting...", song_id);
+    while loading_songs.lock().map_err(|_| "Failed to lock loading set")?.contains(song_id) {
+      tokio::time::sleep(std::time::Duration::from_millis(25)).await;
+    }
+  };
+
+  lock_or_recover(song_cache, "song cache").record_lookup(false);
+  let song = load().await?;
+
+  let mut cache = lock_or_recover(song_cache, "song cache");
+  cache.insert(song_id.to_string(), song);
+
+  Ok(())
+}
+
+// Run blocking jobs on tokio's blocking pool, at most as many at once as the semaphore has
+// permits. Jobs queue for a permit instead of all starting at once; results keep job order
+// and a job that panics yields an Err
+pub async fn run_blocking_limited<T, F>(semaphore: &Arc<Semaphore>, jobs: Vec<F>) -> Vec<Result<T, String>>
+where
+  F: FnOnce() -> T + Send + 'static,
+  T: Send + 'static,
+{
+  let tasks = jobs.into_iter().map(|job| {
+    let semaphore = semaphore.clone();
+    async move {
+      let permit = semaphore
+        .acquire_owned()
+        .await
+        .map_err(|e| format!("Decode queue closed: {}", e))?;
+
+      tokio::task::spawn_blocking(move || {
+        let _permit = permit; // Released when the job finishes
+        job()
+      })
+      .await
+      .map_err(|e| format!("Task panic: {}", e))
+    }
+  });
+
+  futures::future::join_all(tasks).await
+}
+
+// The app's stem cache directory, or one under the temp directory if the app data
+// directory can't be found
+pub fn default_stem_cache_dir() -> std::path::PathBuf {
+  crate::import::get_stem_cache_directory().unwrap_or_else(|e| {
+    log::warn!("Using a temporary stem cache: {}", e);
+    std::env::temp_dir().join("trax_stem_cache")
+  })
+}
+
+// Number of stems decoded at once by default: one per CPU core
+pub fn default_decode_threads() -> usize {
+  std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+// Taps averaged for the tempo estimate
+const TAP_TEMPO_MAX_TAPS: usize = 8;
+// A pause longer than this starts a new tap sequence
+const TAP_TEMPO_RESET: Duration = Duration::from_secs(2);
+
+// Running BPM estimate from the user tapping along to a song
+#[derive(Default)]
+pub struct TapTempo {
+  taps: VecDeque<Instant>,
+}
+
+impl TapTempo {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // Record a tap and return the current estimate (None until there are two taps)
+  pub fn tap_at(&mut self, at: Instant) -> Option<f64> {
+    if let Some(&last) = self.taps.back() {
+      if at.saturating_duration_since(last) > TAP_TEMPO_RESET {
+        self.taps.clear();
+      }
     }
+
+    self.taps.push_back(at);
+    while self.taps.len() > TAP_TEMPO_MAX_TAPS {
+      self.taps.pop_front();
+    }
+
+    self.bpm()
+  }
+
+  // Average BPM over the buffered taps
+  pub fn bpm(&self) -> Option<f64> {
+    let (first, last) = (self.taps.front()?, self.taps.back()?);
+    let intervals = self.taps.len() - 1;
+    let elapsed = last.saturating_duration_since(*first).as_secs_f64();
+    if intervals == 0 || elapsed <= 0.0 {
+      return None;
+    }
+
+    Some(60.0 * intervals as f64 / elapsed)
+  }
+
+  pub fn reset(&mut self) {
+    self.taps.clear();
   }
 }
 
-// Shared application state for all Tauri commands
+// Shared application state for all Tauri commands. Every field is shared, so clones
+// (e.g. moved into background tasks) see the same state
+#[derive(Clone)]
 pub struct AppState {
   pub audio_engine: Arc<Mutex<MultiTrackEngine>>,
   pub database: Arc<Database>,
   pub stem_id_map: Arc<Mutex<HashMap<String, usize>>>,
   pub song_cache: Arc<Mutex<SongCache>>,
+  pub loading_songs: Arc<Mutex<HashSet<String>>>, // Songs currently being decoded
+  pub current_song_id: Arc<Mutex<Option<String>>>, // Song whose stems are loaded in the engine
+  pub tap_tempo: Arc<Mutex<TapTempo>>,
+  pub setlist_cursor: Arc<Mutex<SetlistCursor>>, // Where the performer is in the running setlist
+  pub disk_cache: Arc<CacheManager>, // Decoded stems warmed to disk, survives restarts
+  // The engine's transport position and project rate, readable without locking the engine
+  pub playback_position: Arc<AtomicU64>,
+  pub project_sample_rate: Arc<AtomicU32>,
+  // Limits concurrent stem decodes across all loads; replaced when the limit changes
+  pub decode_semaphore: Arc<Mutex<Arc<Semaphore>>>,
 }
 
 // SAFETY: AppState uses Arc<Mutex<>> for interior mutability which provides thread safety.
@@ -173,18 +637,64 @@ unsafe impl Send for AppState {}
 unsafe impl Sync for AppState {}
 
 impl AppState {
+  // `stem_cache_dir` holds the disk cache of decoded stems (default_stem_cache_dir() in the app)
   pub fn new(
     database: Database,
     audio_engine: MultiTrackEngine,
+    stem_cache_dir: std::path::PathBuf,
   ) -> Self {
-    // Default cache size: 3GB (allows ~5 songs with 20 stems each)
-    const DEFAULT_CACHE_SIZE_BYTES: usize = 3 * 1024 * 1024 * 1024; // 3 GB
+    let settings = database.get_settings().ok();
+    let cache_gb = settings
+      .as_ref()
+      .map(|settings| settings.in_memory_cache_gb)
+      .unwrap_or(DEFAULT_CACHE_SIZE_GB);
+    let disk_cache_gb = settings
+      .as_ref()
+      .map(|settings| settings.disk_cache_gb)
+      .unwrap_or(DEFAULT_DISK_CACHE_SIZE_GB);
+    let playback_position = audio_engine.position_arc();
+    let project_sample_rate = audio_engine.project_sample_rate_arc();
 
     AppState {
       audio_engine: Arc::new(Mutex::new(audio_engine)),
       database: Arc::new(database),
       stem_id_map: Arc::new(Mutex::new(HashMap::new())),
-      song_cache: Arc::new(Mutex::new(SongCache::new(DEFAULT_CACHE_SIZE_BYTES))),
+      song_cache: Arc::new(Mutex::new(SongCache::from_gb(cache_gb))),
+      loading_songs: Arc::new(Mutex::new(HashSet::new())),
+      current_song_id: Arc::new(Mutex::new(None)),
+      tap_tempo: Arc::new(Mutex::new(TapTempo::new())),
+      setlist_cursor: Arc::new(Mutex::new(SetlistCursor::default())),
+      disk_cache: Arc::new(CacheManager::new(stem_cache_dir, cache_size_bytes_from_gb(disk_cache_gb) as u64)),
+      playback_position,
+      project_sample_rate,
+      decode_semaphore: Arc::new(Mutex::new(Arc::new(Semaphore::new(default_decode_threads())))),
     }
   }
+
+  // Transport position in seconds, straight from the engine's atomics (no engine lock)
+  pub fn playback_position_seconds(&self) -> f64 {
+    let (frames, sample_rate) = self.playback_position_frames();
+    frames as f64 / sample_rate as f64
+  }
+
+  // Transport position as a whole frame count at the project rate, returned with that rate.
+  // Exact where seconds drift over long songs. The atomic counts interleaved stereo samples
+  pub fn playback_position_frames(&self) -> (u64, u32) {
+    let sample_rate = self.project_sample_rate.load(Ordering::Acquire);
+    (self.playback_position.load(Ordering::Acquire) / 2, sample_rate)
+  }
+
+  // Semaphore new decodes queue on
+  pub fn decode_semaphore(&self) -> Arc<Semaphore> {
+    self.decode_semaphore.lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+
+  // Change how many stems may decode at once (0 = one per CPU core). Decodes already
+  // queued finish under the old limit. Returns the limit in effect
+  pub fn set_max_decode_threads(&self, threads: usize) -> usize {
+    let threads = if threads == 0 { default_decode_threads() } else { threads };
+    *self.decode_semaphore.lock().unwrap_or_else(|e| e.into_inner()) = Arc::new(Semaphore::new(threads));
+    log::info!("Decoding at most {} stems at once", threads);
+    threads
+  }
 }
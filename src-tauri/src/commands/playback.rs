@@ -1,21 +1,113 @@
-use super::AppState;
+use super::{lock_or_recover, AppState};
+use crate::audio::{AudioDecoder, AudioError, PlaybackState, RecordingStatus, RecordingSummary};
+use crate::audio::loudness::{integrated_loudness, loudness_trim_db};
+use crate::database::{AppSettings, Song};
 use tauri::{State, Emitter};
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// settings.stem_overflow_policy values: refuse songs with more stems than the engine holds,
+/// or pre-mix the extra stems into one slot
+pub const STEM_OVERFLOW_ERROR: &str = "error";
+pub const STEM_OVERFLOW_MERGE: &str = "merge";
+// Id of the cached stem holding a song's merged overflow stems; it has no database record
+const MERGED_STEM_ID_PREFIX: &str = "merged:";
 
 /// Preload a song's stems into cache (decode and store in memory)
 #[tauri::command]
 pub async fn load_song(song_id: String, state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<(), String> {
   log::info!("Loading song stems: {}", song_id);
 
-  // Check if already in memory cache
-  {
-    let cache = state.song_cache.lock().map_err(|_| "Failed to lock cache")?;
-    if cache.contains(&song_id) {
-      log::info!("Song {} already in memory, skipping load", song_id);
-      return Ok(());
+  // Concurrent loads of the same song share a single decode
+  super::load_once(&state.loading_songs, &state.song_cache, &song_id, || {
+    decode_song(song_id.clone(), &state, move |event, payload| {
+      let _ = app_handle.emit(event, payload);
+    })
+  }).await
+}
+
+/// How ready a song is to play after prepare_song
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SongReadiness {
+  pub loaded_stems: usize, // Stems decoded and waiting in the cache
+  pub total_stems: usize,
+  pub warnings: Vec<String>, // One per stem that won't play, with the reason
+}
+
+/// Pre-show check: decode a song into the cache and report whether every stem made it.
+/// Never touches the engine, so nothing can be heard while doors are open
+#[tauri::command]
+pub async fn prepare_song(song_id: String, state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<SongReadiness, String> {
+  prepare_song_with(song_id, &state, move |event, payload| {
+    let _ = app_handle.emit(event, payload);
+  }).await
+}
+
+pub(crate) async fn prepare_song_with<E>(song_id: String, state: &AppState, emit: E) -> Result<SongReadiness, String>
+where
+  E: Fn(&str, serde_json::Value) + Clone + Send + 'static,
+{
+  log::info!("Preparing song {} (silent load)", song_id);
+
+  // Keep the reason each failed stem gave, keyed by stem id, and the stems merged to fit
+  let reasons = Arc::new(std::sync::Mutex::new(std::collections::HashMap::<String, String>::new()));
+  let merged = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+  let recorder = {
+    let reasons = reasons.clone();
+    let merged = merged.clone();
+    move |event: &str, payload: serde_json::Value| {
+      if event == "stem:warning" {
+        if let (Some(stem_id), Some(message)) = (payload["stem_id"].as_str(), payload["message"].as_str()) {
+          lock_or_recover(&reasons, "stem warnings").insert(stem_id.to_string(), message.to_string());
+        }
+      }
+      if event == "stem:merged" {
+        let ids = payload["stem_ids"].as_array().into_iter().flatten().filter_map(|id| id.as_str());
+        lock_or_recover(&merged, "merged stems").extend(ids.map(str::to_string));
+      }
+      emit(event, payload);
     }
-  }
+  };
+
+  super::load_once(&state.loading_songs, &state.song_cache, &song_id, || {
+    decode_song(song_id.clone(), state, recorder)
+  }).await?;
+
+  let stems = state.database
+    .get_stems_for_song(&song_id)
+    .map_err(|e| format!("Failed to get stems for song: {}", e))?;
+  let cached_ids: Vec<String> = lock_or_recover(&state.song_cache, "song cache")
+    .peek(&song_id)
+    .map(|song| song.stems.iter().map(|s| s.stem_id.clone()).collect())
+    .unwrap_or_default();
+
+  let reasons = lock_or_recover(&reasons, "stem warnings");
+  let merged = lock_or_recover(&merged, "merged stems");
+  let warnings: Vec<String> = stems.iter()
+    .filter(|stem| !cached_ids.contains(&stem.id) && !merged.contains(&stem.id))
+    .map(|stem| match reasons.get(&stem.id) {
+      Some(reason) => format!("{}: {}", stem.name, reason),
+      None => format!("{}: not loaded", stem.name),
+    })
+    .collect();
+
+  let readiness = SongReadiness {
+    loaded_stems: stems.len() - warnings.len(),
+    total_stems: stems.len(),
+    warnings,
+  };
+  log::info!("Song {} prepared: {}/{} stems ready", song_id, readiness.loaded_stems, readiness.total_stems);
+  Ok(readiness)
+}
 
+// Decode all stems of a song in parallel, ready to be inserted into the cache.
+// A stem that fails to decode is skipped with a "stem:warning" event so one bad file
+// doesn't cost the whole song; the load only fails if no stem decodes
+pub(crate) async fn decode_song<E>(song_id: String, state: &AppState, emit: E) -> Result<super::CachedSong, String>
+where
+  E: Fn(&str, serde_json::Value) + Clone + Send + 'static,
+{
   // Get song from database
   let song = state.database
     .get_song(&song_id)
@@ -33,15 +125,30 @@ pub async fn load_song(song_id: String, state: State<'_, AppState>, app_handle:
   let total_stems = stems.len();
   log::info!("Loading {} stems in PARALLEL...", total_stems);
 
-  // Get device sample rate once before spawning tasks
-  let device_sample_rate = {
-    let engine = state.audio_engine.lock().map_err(|_| "Failed to lock engine")?;
-    engine.device_sample_rate()
+  // Get the project sample rate and stem capacity once before spawning tasks
+  let (project_sample_rate, capacity) = {
+    let engine = lock_or_recover(&state.audio_engine, "audio engine");
+    (engine.project_sample_rate(), engine.max_stems())
   };
-  log::info!("Using device sample rate: {}Hz for all stems", device_sample_rate);
 
-  // Spawn parallel decoding tasks for all stems
-  let mut decode_tasks = Vec::new();
+  // A song the engine can't hold fails here rather than after every stem has been decoded
+  let merge_overflow = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?
+    .stem_overflow_policy == STEM_OVERFLOW_MERGE;
+  if total_stems > capacity && !merge_overflow {
+    return Err(format!(
+      "'{}' has {} stems but the engine holds {}. Raise the stem capacity or let overflow stems be merged",
+      song.name, total_stems, capacity
+    ));
+  }
+  log::info!("Using project sample rate: {}Hz for all stems", project_sample_rate);
+
+  // Per-stem decode progress in tenths of a percent, summed for the overall percentage
+  let stem_progress: Arc<Vec<AtomicU32>> = Arc::new((0..total_stems).map(|_| AtomicU32::new(0)).collect());
+
+  // Decode jobs for all stems, run in parallel up to the configured decode limit
+  let mut decode_jobs = Vec::new();
 
   for (index, stem) in stems.iter().enumerate() {
     let current_stem = index + 1;
@@ -52,124 +159,418 @@ pub async fn load_song(song_id: String, state: State<'_, AppState>, app_handle:
     let stem_file_path = stem.file_path.clone();
     let stem_volume = stem.volume;
     let stem_is_muted = stem.is_muted;
-    let app_handle_clone = app_handle.clone();
-
-    // Spawn blocking task for CPU-intensive decoding
-    let task = tokio::task::spawn_blocking(move || {
+    let db_stem = stem.clone();
+    let database = state.database.clone();
+    let emit = emit.clone();
+    let stem_progress = stem_progress.clone();
+    let song_cache = state.song_cache.clone();
+    let disk_cache = state.disk_cache.clone();
+
+    // Blocking job for CPU-intensive decoding
+    let job = move || {
       log::info!("⚙️  PARALLEL: Starting decode for stem {}/{}: {}", current_stem, total_stems, stem_name);
 
       // Emit progress event to frontend
-      let _ = app_handle_clone.emit("stem:loading", serde_json::json!({
+      emit("stem:loading", serde_json::json!({
         "song_name": song_name,
         "stem_name": stem_name.clone(),
         "current": current_stem,
         "total": total_stems,
       }));
 
-      let source_path = Path::new(&stem_file_path);
-
-      // Decode directly from original file
-      let mut decoder = super::super::audio::decoder::AudioDecoder::new(source_path.to_str().unwrap())
-        .map_err(|e| format!("Failed to create decoder for '{}': {}", stem_name, e))?;
-
-      let metadata = decoder.get_metadata()
-        .map_err(|e| format!("Failed to get metadata for '{}': {}", stem_name, e))?;
-
-      let mut samples = decoder.decode_all()
-        .map_err(|e| format!("Failed to decode '{}': {}", stem_name, e))?;
+      // Capture mtime before decoding so an edit made mid-decode still invalidates
+      let source_modified = super::source_modified_time(&stem_file_path);
+      // Only hashed when the file changed since its hash was stored
+      let source_hash = super::stem_content_hash(&database, &db_stem, source_modified);
+
+      // Another cached stem decoded from identical content can be reused without decoding
+      let shared = source_hash.as_deref().and_then(|hash| {
+        lock_or_recover(&song_cache, "song cache").shared_samples(hash, project_sample_rate)
+      });
+      if let Some(samples) = shared {
+        log::info!("♻️  PARALLEL: Reusing cached samples for stem {}/{}: {}", current_stem, total_stems, stem_name);
+        stem_progress[index].store(1000, Ordering::Release);
+        return Ok(super::CachedStem {
+          stem_id,
+          samples,
+          sample_rate: project_sample_rate,
+          volume: stem_volume as f32,
+          is_muted: stem_is_muted,
+          source_path: stem_file_path,
+          source_modified,
+          source_hash,
+        });
+      }
 
-      // Resample if necessary (using device_sample_rate from outer scope)
-      let final_sample_rate = if metadata.sample_rate != device_sample_rate {
-        log::info!("Resampling {} from {}Hz to {}Hz", stem_name, metadata.sample_rate, device_sample_rate);
-        let mut resampler = super::super::audio::resampler::LinearResampler::new(
-          metadata.sample_rate,
-          device_sample_rate,
-          metadata.channels,
-        );
-        samples = resampler.process(&samples);
-        device_sample_rate
-      } else {
-        metadata.sample_rate
-      };
+      // Stems warmed to disk by warm_disk_cache load without decoding
+      let warmed = source_hash.as_deref().and_then(|hash| disk_cache.get(&stem_id, hash, project_sample_rate));
+      if let Some(samples) = warmed {
+        log::info!("💾 PARALLEL: Loaded stem {}/{} from the disk cache: {}", current_stem, total_stems, stem_name);
+        stem_progress[index].store(1000, Ordering::Release);
+        return Ok(super::CachedStem {
+          stem_id,
+          samples: Arc::new(samples),
+          sample_rate: project_sample_rate,
+          volume: stem_volume as f32,
+          is_muted: stem_is_muted,
+          source_path: stem_file_path,
+          source_modified,
+          source_hash,
+        });
+      }
 
-      log::info!("✅ PARALLEL: Completed decode for stem {}/{}: {} at {}Hz", current_stem, total_stems, stem_name, final_sample_rate);
+      // Decode directly from original file, reporting progress at most once per whole percent
+      let mut last_percent = None;
+      let samples = decode_at_rate(&stem_file_path, &stem_name, project_sample_rate, |decoded, total| {
+        let Some(total) = total.filter(|&t| t > 0) else { return };
+        let fraction = (decoded as f64 / total as f64).min(1.0);
+        let percent = (fraction * 100.0) as u32;
+        if last_percent == Some(percent) {
+          return;
+        }
+        last_percent = Some(percent);
+
+        stem_progress[index].store((fraction * 1000.0) as u32, Ordering::Release);
+        let overall = stem_progress.iter()
+          .map(|p| p.load(Ordering::Acquire) as f64)
+          .sum::<f64>() / (total_stems as f64 * 10.0);
+
+        emit("stem:progress", serde_json::json!({
+          "song_id": song_id,
+          "stem_id": stem_id,
+          "stem_name": stem_name,
+          "current_frames": decoded,
+          "total_frames": total,
+          "stem_percent": fraction * 100.0,
+          "percent": overall,
+        }));
+      })?;
+      stem_progress[index].store(1000, Ordering::Release);
+
+      log::info!("✅ PARALLEL: Completed decode for stem {}/{}: {} at {}Hz", current_stem, total_stems, stem_name, project_sample_rate);
 
       Ok::<_, String>(super::CachedStem {
         stem_id,
         samples: std::sync::Arc::new(samples), // Wrap in Arc for zero-copy
-        sample_rate: final_sample_rate, // Store the sample rate
+        sample_rate: project_sample_rate, // Store the sample rate
         volume: stem_volume as f32,
         is_muted: stem_is_muted,
+        source_path: stem_file_path,
+        source_modified,
+        source_hash,
       })
-    });
+    };
 
-    decode_tasks.push(task);
+    decode_jobs.push(job);
   }
 
   // Wait for all parallel decoding tasks to complete
-  log::info!("⏳ Waiting for {} parallel decode tasks to complete...", decode_tasks.len());
-  let results = futures::future::join_all(decode_tasks).await;
+  log::info!("⏳ Waiting for {} parallel decode tasks to complete...", decode_jobs.len());
+  let results = super::run_blocking_limited(&state.decode_semaphore(), decode_jobs).await;
 
-  // Collect results and check for errors
+  // Collect results, skipping stems that failed to decode
   let mut cached_stems = Vec::new();
-  for (index, result) in results.into_iter().enumerate() {
-    match result {
+  for (stem, result) in stems.iter().zip(results) {
+    let error = match result {
       Ok(Ok(cached_stem)) => {
         cached_stems.push(cached_stem);
+        continue;
       }
-      Ok(Err(e)) => {
-        return Err(format!("Failed to decode stem {}: {}", index + 1, e));
-      }
-      Err(e) => {
-        return Err(format!("Task panic for stem {}: {}", index + 1, e));
+      Ok(Err(e)) => e,
+      Err(e) => format!("{} for '{}'", e, stem.name),
+    };
+
+    log::warn!("Skipping stem '{}' of song '{}': {}", stem.name, song.name, error);
+    emit("stem:warning", serde_json::json!({
+      "song_id": song_id,
+      "song_name": song.name,
+      "stem_id": stem.id,
+      "stem_name": stem.name,
+      "message": error,
+    }));
+  }
+
+  if cached_stems.is_empty() {
+    return Err(format!("None of the {} stems of '{}' could be decoded", total_stems, song.name));
+  }
+
+  log::info!("✅ {}/{} stems decoded successfully in parallel!", cached_stems.len(), total_stems);
+
+  // Measured once per song; the loudness target trims the master by it on every load
+  if song.loudness_lufs.is_none() {
+    let mix: Vec<Arc<Vec<f32>>> = cached_stems.iter()
+      .filter(|cached| !stems.iter().any(|stem| stem.id == cached.stem_id && stem.is_cue))
+      .map(|cached| cached.samples.clone())
+      .collect();
+    let measured = tokio::task::spawn_blocking(move || song_loudness(&mix, project_sample_rate))
+      .await
+      .unwrap_or(None);
+    if let Some(lufs) = measured {
+      log::info!("Measured '{}' at {:.1} LUFS", song.name, lufs);
+      if let Err(e) = state.database.set_song_loudness(&song_id, Some(lufs)) {
+        log::warn!("Failed to store loudness of '{}': {}", song.name, e);
       }
     }
   }
 
-  log::info!("✅ All {} stems decoded successfully in parallel!", cached_stems.len());
+  // Past capacity, the extra stems are pre-mixed into one slot
+  let cached_stems = if cached_stems.len() > capacity {
+    let (cached_stems, merged) = merge_overflow_stems(cached_stems, &stems, capacity, &song_id)?;
+    let merged_names: Vec<&str> = stems.iter()
+      .filter(|stem| merged.contains(&stem.id))
+      .map(|stem| stem.name.as_str())
+      .collect();
+    log::warn!("'{}' has more stems than the engine holds; merged {} into one slot", song.name, merged_names.join(", "));
+    emit("stem:merged", serde_json::json!({
+      "song_id": song_id,
+      "song_name": song.name,
+      "stem_ids": merged,
+      "stem_names": merged_names,
+    }));
+    cached_stems
+  } else {
+    cached_stems
+  };
+
+  log::info!("Successfully loaded song '{}' into memory", song.name);
 
-  // Store in memory cache (LRU will auto-evict if needed)
-  let mut cache = state.song_cache.lock().map_err(|_| "Failed to lock cache")?;
-  cache.insert(song_id.clone(), super::CachedSong {
-    song_id: song_id.clone(),
+  // Emit completion event
+  emit("stem:complete", serde_json::json!({}));
+
+  // Stored in the memory cache by load_once (LRU will auto-evict if needed)
+  Ok(super::CachedSong {
+    song_id,
     stems: cached_stems,
+  })
+}
+
+// Fold the stems that don't fit into `capacity` slots into one pre-mixed stem, returning the
+// stems to load and the ids that were merged. The last mergeable stems are merged first. Merged
+// stems keep their saved volume and mute, but the merged slot has no database record, so only
+// stems that play exactly as decoded can go into it (see is_mergeable)
+pub(crate) fn merge_overflow_stems(
+  cached_stems: Vec<super::CachedStem>,
+  db_stems: &[crate::database::Stem],
+  capacity: usize,
+  song_id: &str,
+) -> Result<(Vec<super::CachedStem>, Vec<String>), String> {
+  let overflow = cached_stems.len() + 1 - capacity.max(1);
+  let mergeable = |cached: &super::CachedStem| {
+    db_stems.iter().any(|stem| stem.id == cached.stem_id && is_mergeable(stem))
+  };
+  let merge_indices: Vec<usize> = (0..cached_stems.len())
+    .rev()
+    .filter(|&i| mergeable(&cached_stems[i]))
+    .take(overflow)
+    .collect();
+  if capacity == 0 || merge_indices.len() < overflow {
+    return Err(format!(
+      "Can't fit {} stems into {} slots: too many are cue stems or have their own pan, trim, \
+       polarity, alignment, delay, gate or channel mode. Reset some of them or raise the stem capacity",
+      cached_stems.len(), capacity
+    ));
+  }
+
+  let mut kept = Vec::new();
+  let mut merged = Vec::new();
+  for (index, cached) in cached_stems.into_iter().enumerate() {
+    if merge_indices.contains(&index) {
+      merged.push(cached);
+    } else {
+      kept.push(cached);
+    }
+  }
+
+  let mut mix = vec![0.0f32; merged.iter().map(|cached| cached.samples.len()).max().unwrap_or(0)];
+  for cached in merged.iter().filter(|cached| !cached.is_muted) {
+    for (out, sample) in mix.iter_mut().zip(cached.samples.iter()) {
+      *out += sample * cached.volume;
+    }
+  }
+
+  kept.push(super::CachedStem {
+    stem_id: format!("{}{}", MERGED_STEM_ID_PREFIX, song_id),
+    samples: Arc::new(mix),
+    sample_rate: merged[0].sample_rate,
+    volume: 1.0,
+    is_muted: false,
+    source_path: String::new(),
+    source_modified: None,
+    source_hash: None,
   });
+  Ok((kept, merged.into_iter().map(|cached| cached.stem_id).collect()))
+}
 
-  log::info!("Successfully loaded song '{}' into memory", song.name);
+// Cue stems keep their own slot for routing. Pan, trim, polarity, alignment, delay, gate and
+// channel mode are applied per slot on playback, so a stem using any of them can't be pre-mixed
+fn is_mergeable(stem: &crate::database::Stem) -> bool {
+  !stem.is_cue
+    && stem.pan.unwrap_or(0.0) == 0.0
+    && stem.trim_db == 0.0
+    && !stem.phase_inverted
+    && stem.offset_samples == 0
+    && stem.delay_samples == 0
+    && !stem.gate_enabled
+    && !stem.swap_channels
+    && !stem.mono_sum
+}
 
-  // Emit completion event
-  let _ = app_handle.emit("stem:complete", serde_json::json!({}));
+// Integrated loudness of stems summed at unity, the import-time mix the target is matched on
+fn song_loudness(stems: &[Arc<Vec<f32>>], sample_rate: u32) -> Option<f64> {
+  let mut mix = vec![0.0f32; stems.iter().map(|samples| samples.len()).max()?];
+  for samples in stems {
+    for (out, sample) in mix.iter_mut().zip(samples.iter()) {
+      *out += sample;
+    }
+  }
+  integrated_loudness(&mix, 2, sample_rate)
+}
 
-  Ok(())
+// Master trim for a song under the loudness target; 0 dB when the target is off or the song
+// hasn't been measured yet
+pub(crate) fn loudness_target_trim_db(settings: &AppSettings, loudness_lufs: Option<f64>) -> f64 {
+  match loudness_lufs {
+    Some(measured) if settings.loudness_target_enabled => loudness_trim_db(measured, settings.loudness_target_lufs),
+    _ => 0.0,
+  }
+}
+
+// Decode a stem file to interleaved samples at `sample_rate`, resampling if the file's rate differs.
+// `on_progress` receives (decoded frames, total frames) as decoding goes
+pub(crate) fn decode_at_rate<P>(file_path: &str, stem_name: &str, sample_rate: u32, on_progress: P) -> Result<Vec<f32>, String>
+where
+  P: FnMut(u64, Option<u64>),
+{
+  decode_with_at_rate(|| AudioDecoder::new(file_path), stem_name, sample_rate, on_progress)
 }
 
-/// Play a song from cache (load into audio engine and start playback)
+// decode_at_rate on a decoder from `open`, which is called again for every retry
+pub(crate) fn decode_with_at_rate<O, P>(mut open: O, stem_name: &str, sample_rate: u32, on_progress: P) -> Result<Vec<f32>, String>
+where
+  O: FnMut() -> Result<AudioDecoder, AudioError>,
+  P: FnMut(u64, Option<u64>),
+{
+  use crate::audio::decoder::{retry_transient, TRANSIENT_IO_ATTEMPTS, TRANSIENT_IO_BACKOFF};
+
+  // Networked libraries drop reads now and then, so those decodes start over; a bad file fails at once
+  let mut on_progress = on_progress;
+  let (metadata, samples) = retry_transient(TRANSIENT_IO_ATTEMPTS, TRANSIENT_IO_BACKOFF, || {
+    let mut decoder = open()?;
+    let metadata = decoder.get_metadata()?;
+    let samples = decoder.decode_all_with_progress(&mut on_progress)?;
+    Ok((metadata, samples))
+  })
+  .map_err(|e| format!("Failed to decode '{}': {}", stem_name, e))?;
+
+  if metadata.sample_rate == sample_rate {
+    return Ok(samples);
+  }
+
+  log::info!("Resampling {} from {}Hz to {}Hz", stem_name, metadata.sample_rate, sample_rate);
+  let mut resampler = super::super::audio::resampler::LinearResampler::new(
+    metadata.sample_rate,
+    sample_rate,
+    metadata.channels,
+  );
+  Ok(resampler.process(&samples))
+}
+
+/// Play a song from cache (load into audio engine and start playback).
+/// Emits "playback:song-changed" with the song's id, name, artist and duration once it starts
 #[tauri::command]
 pub async fn play_song(song_id: String, state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<(), String> {
+  play_song_with(song_id, &state, move |event, payload| {
+    let _ = app_handle.emit(event, payload);
+  }).await
+}
+
+// Decode the song if needed, start it and announce it to the UI
+pub(crate) async fn play_song_with<E>(song_id: String, state: &AppState, emit: E) -> Result<(), String>
+where
+  E: Fn(&str, serde_json::Value) + Clone + Send + 'static,
+{
   log::info!("Playing song: {}", song_id);
 
+  // A song decoded before the project rate changed would play at the wrong speed
+  let project_rate = lock_or_recover(&state.audio_engine, "audio engine").project_sample_rate();
+  lock_or_recover(&state.song_cache, "song cache").remove_at_other_rate(&song_id, project_rate);
+
   // Ensure song is cached (decode if needed)
-  load_song(song_id.clone(), state.clone(), app_handle).await?;
+  let decode_emit = emit.clone();
+  super::load_once(&state.loading_songs, &state.song_cache, &song_id, || {
+    decode_song(song_id.clone(), state, decode_emit)
+  }).await?;
+
+  start_cached_song(state, &song_id)?;
+
+  log::info!("Successfully started playback from cache");
 
+  emit("playback:song-changed", song_changed_payload(state, &song_id)?);
+
+  Ok(())
+}
+
+// Payload of the "playback:song-changed" event
+fn song_changed_payload(state: &AppState, song_id: &str) -> Result<serde_json::Value, String> {
+  let song = state.database
+    .get_song(song_id)
+    .map_err(|e| format!("Failed to get song: {}", e))?;
+
+  Ok(serde_json::json!({
+    "song_id": song.id,
+    "name": song.name,
+    "artist": song.artist,
+    "duration": song.duration,
+  }))
+}
+
+// Load a cached song's stems into the engine and start playback
+pub(crate) fn start_cached_song(state: &AppState, song_id: &str) -> Result<(), String> {
   // Get cached song data (this updates LRU access time)
   let cached_song = {
-    let mut cache = state.song_cache.lock().map_err(|_| "Failed to lock cache")?;
-    cache.get(&song_id)
+    let mut cache = lock_or_recover(&state.song_cache, "song cache");
+    cache.get(song_id)
       .ok_or_else(|| "Song not in cache".to_string())?
   };
 
+  // Pan, solo-safe and alignment aren't cached with the samples: read the current stem records so
+  // user overrides and the configured cue side are always honored
+  let settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+  let db_stems: std::collections::HashMap<String, crate::database::Stem> = state.database
+    .get_stems_for_song(song_id)
+    .map_err(|e| format!("Failed to get stems for song: {}", e))?
+    .into_iter()
+    .map(|stem| (stem.id.clone(), stem))
+    .collect();
+  // Automation is optional; a broken envelope shouldn't stop the song from playing
+  let automation = state.database
+    .get_song_automation(song_id)
+    .unwrap_or_else(|e| {
+      log::warn!("Failed to get automation for song {}: {}", song_id, e);
+      Default::default()
+    });
+
   // Lock the audio engine
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  // Stems decoded at another rate play at the wrong speed and pitch; the device may have moved
+  // the project rate since the song was cached
+  let project_rate = engine.project_sample_rate();
+  if let Some(stem) = cached_song.stems.iter().find(|stem| stem.sample_rate != project_rate) {
+    lock_or_recover(&state.song_cache, "song cache").remove(song_id);
+    return Err(format!(
+      "Song was decoded at {}Hz but the project runs at {}Hz; play it again to reload it",
+      stem.sample_rate, project_rate
+    ));
+  }
 
   // Clear any previously loaded stems
   engine.clear_stems();
 
   // Clear the stem ID map
-  let mut stem_map = state.stem_id_map
-    .lock()
-    .map_err(|_| "Failed to lock stem ID map")?;
+  let mut stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
   stem_map.clear();
 
   // Load cached stems into the engine (zero-copy via Arc)
@@ -184,14 +585,60 @@ pub async fn play_song(song_id: String, state: State<'_, AppState>, app_handle:
     // Set volume and mute state
     engine.set_stem_volume(stem_index, cached_stem.volume);
     engine.set_stem_mute(stem_index, cached_stem.is_muted);
+    let db_stem = db_stems.get(&cached_stem.stem_id);
+    engine.set_stem_pan(stem_index, db_stem.map(|s| s.effective_pan(&settings)).unwrap_or(0.0) as f32);
+    engine.set_stem_solo_safe(stem_index, db_stem.map(|s| s.solo_safe).unwrap_or(false));
+    engine.set_stem_phase_invert(stem_index, db_stem.map(|s| s.phase_inverted).unwrap_or(false));
+    engine.set_stem_trim(stem_index, db_stem.map(|s| s.trim_db).unwrap_or(0.0) as f32);
+    match db_stem {
+      Some(stem) => {
+        engine.set_stem_gate(stem_index, stem.gate_threshold_db as f32, stem.gate_enabled);
+        engine.set_stem_gate_times(stem_index, (stem.gate_attack_ms / 1000.0) as f32, (stem.gate_release_ms / 1000.0) as f32);
+      }
+      None => engine.set_stem_gate(stem_index, 0.0, false),
+    }
+    engine.set_stem_channel_mode(
+      stem_index,
+      db_stem.map(|s| s.swap_channels).unwrap_or(false),
+      db_stem.map(|s| s.mono_sum).unwrap_or(false),
+    );
+    if let Some(stem) = db_stem.filter(|s| s.offset_samples > 0 && s.sample_rate > 0) {
+      // Offsets are in source-file frames; convert to the cached samples' rate
+      let offset = stem.offset_samples as f64 * cached_stem.sample_rate as f64 / stem.sample_rate as f64;
+      engine.set_stem_offset(stem_index, offset.round() as u64);
+    }
+    if let Some(stem) = db_stem.filter(|s| s.delay_samples != 0 && s.sample_rate > 0) {
+      // Delays are in source-file frames too
+      let delay = stem.delay_samples as f64 * cached_stem.sample_rate as f64 / stem.sample_rate as f64;
+      engine.set_stem_delay(stem_index, delay.round() as i64);
+    }
+    if let Some(points) = automation.get(&cached_stem.stem_id) {
+      engine.set_stem_automation(stem_index, &super::stems::automation_pairs(points));
+    }
   }
 
+  let loudness_lufs = state.database.get_song(song_id).ok().and_then(|song| song.loudness_lufs);
+  engine.set_master_trim_db(loudness_target_trim_db(&settings, loudness_lufs) as f32);
+
   // Start playback
   engine
     .play()
     .map_err(|e| format!("Failed to start playback: {}", e))?;
 
-  log::info!("Successfully started playback from cache");
+  // Remember which song is loaded for transport queries
+  let mut current_song_id = state.current_song_id
+    .lock()
+    .map_err(|_| "Failed to lock current song")?;
+  *current_song_id = Some(song_id.to_string());
+  drop(current_song_id);
+  drop(stem_map);
+  drop(engine);
+
+  // Only actual playback counts as a play; preloading goes through load_song and never gets here.
+  // Stats are best-effort and must never stop the song from playing
+  if let Err(e) = state.database.record_song_play(song_id) {
+    log::warn!("Failed to record play for song {}: {}", song_id, e);
+  }
 
   Ok(())
 }
@@ -201,9 +648,7 @@ pub async fn play_song(song_id: String, state: State<'_, AppState>, app_handle:
 pub async fn resume_playback(state: State<'_, AppState>) -> Result<(), String> {
   log::info!("Resuming playback");
 
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
 
   engine
     .play()
@@ -212,62 +657,296 @@ pub async fn resume_playback(state: State<'_, AppState>) -> Result<(), String> {
   Ok(())
 }
 
-/// Pause current playback
+/// Pause current playback. Returns the position (in seconds) playback will resume from
 #[tauri::command]
-pub async fn pause_playback(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn pause_playback(state: State<'_, AppState>) -> Result<f64, String> {
   log::info!("Pausing playback");
 
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
 
   engine
     .pause()
     .map_err(|e| format!("Failed to pause playback: {}", e))?;
 
-  Ok(())
+  Ok(engine.position())
 }
 
-/// Stop current playback and reset position to start
+/// Stop current playback and empty the engine. The song stays cached, so playing it again is instant
 #[tauri::command]
 pub async fn stop_playback(state: State<'_, AppState>) -> Result<(), String> {
   log::info!("Stopping playback");
+  stop_current_song(&state)
+}
 
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
-
+// Stop and clear the engine, stem map and current song together, so nothing can resume
+// stems that are no longer tracked as the current song
+pub(crate) fn stop_current_song(state: &AppState) -> Result<(), String> {
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
   engine
     .stop()
     .map_err(|e| format!("Failed to stop playback: {}", e))?;
+  engine.clear_stems();
+  drop(engine);
+
+  lock_or_recover(&state.stem_id_map, "stem ID map").clear();
+  *lock_or_recover(&state.current_song_id, "current song") = None;
+  Ok(())
+}
+
+/// Free a song's memory between rehearsal and service: drops its decoded stems from the cache
+/// and, when it's the song in the engine, stops playback and empties the engine as well
+#[tauri::command]
+pub async fn unload_song(song_id: String, state: State<'_, AppState>) -> Result<(), String> {
+  unload_song_from(&state, &song_id)
+}
+
+pub(crate) fn unload_song_from(state: &AppState, song_id: &str) -> Result<(), String> {
+  log::info!("Unloading song: {}", song_id);
+
+  let is_current = lock_or_recover(&state.current_song_id, "current song").as_deref() == Some(song_id);
+  if is_current {
+    stop_current_song(state)?;
+  }
+
+  lock_or_recover(&state.song_cache, "song cache").remove(song_id);
+
+  Ok(())
+}
+
+/// Get the song whose stems are loaded in the engine, if any
+#[tauri::command]
+pub async fn get_current_song(state: State<'_, AppState>) -> Result<Option<Song>, String> {
+  current_song(&state)
+}
+
+pub(crate) fn current_song(state: &AppState) -> Result<Option<Song>, String> {
+  let Some(song_id) = lock_or_recover(&state.current_song_id, "current song").clone() else {
+    return Ok(None);
+  };
+
+  state.database
+    .get_song(&song_id)
+    .map(Some)
+    .map_err(|e| format!("Failed to get song: {}", e))
+}
+
+/// Emergency stop: silence the engine and any cue immediately and rewind. Safe to call in any
+/// state, and never waits on songs being decoded. The drone pad plays in the webview and stops
+/// on the `playback:panic` event
+#[tauri::command]
+pub fn panic_stop(state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<(), String> {
+  stop_all_audio(&state, move |event, payload| {
+    let _ = app_handle.emit(event, payload);
+  });
+  Ok(())
+}
+
+// Only takes the engine lock, which is never held while decoding
+pub(crate) fn stop_all_audio<E>(state: &AppState, emit: E)
+where
+  E: Fn(&str, serde_json::Value),
+{
+  log::warn!("Panic stop: silencing all audio");
+
+  lock_or_recover(&state.audio_engine, "audio engine").panic_stop();
+  emit("playback:panic", serde_json::json!({}));
+}
+
+/// Fade the current song out over the given duration, then stop
+#[tauri::command]
+pub async fn fade_out(duration_seconds: f64, state: State<'_, AppState>) -> Result<(), String> {
+  log::info!("Fading out over {} seconds", duration_seconds);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine
+    .fade_out_and_stop(duration_seconds)
+    .map_err(|e| format!("Failed to fade out: {}", e))?;
+
+  Ok(())
+}
+
+/// Start recording the performance into a new timestamped folder under `output_dir`:
+/// the master mix, plus each stem's post-fader signal when `per_stem` is set.
+/// Returns the folder the files are written to
+#[tauri::command]
+pub async fn start_recording(
+  output_dir: String,
+  per_stem: Option<bool>,
+  state: State<'_, AppState>
+) -> Result<String, String> {
+  let dir = recording_dir(Path::new(&output_dir), chrono::Local::now());
+
+  lock_or_recover(&state.audio_engine, "audio engine")
+    .start_recording(&dir, per_stem.unwrap_or(false))
+    .map_err(|e| format!("Failed to start recording: {}", e))?;
+
+  Ok(dir.to_string_lossy().to_string())
+}
+
+/// Stop the recording and finish its files
+#[tauri::command]
+pub async fn stop_recording(state: State<'_, AppState>) -> Result<RecordingSummary, String> {
+  let recording = lock_or_recover(&state.audio_engine, "audio engine")
+    .take_recording()
+    .map_err(|e| format!("Failed to stop recording: {}", e))?;
+
+  // Draining the queue and finalizing the files can take a while; the engine stays free meanwhile
+  match recording {
+    Ok(recording) => tokio::task::spawn_blocking(move || recording.finish())
+      .await
+      .map_err(|e| format!("Recording task failed: {}", e))?
+      .map_err(|e| format!("Failed to stop recording: {}", e)),
+    Err(summary) => Ok(summary),
+  }
+}
+
+/// Whether a recording is running and whether it has dropped audio or failed to write, so the
+/// UI can warn during the performance rather than after it
+#[tauri::command]
+pub async fn get_recording_status(state: State<'_, AppState>) -> Result<RecordingStatus, String> {
+  Ok(lock_or_recover(&state.audio_engine, "audio engine").recording_status())
+}
+
+// One folder per recording so a later session never overwrites an earlier one
+fn recording_dir(output_dir: &Path, started: chrono::DateTime<chrono::Local>) -> std::path::PathBuf {
+  output_dir.join(format!("recording-{}", started.format("%Y%m%d-%H%M%S")))
+}
+
+/// Choose whether a lost output device (e.g. an unplugged interface) hands playback to the system default device
+#[tauri::command]
+pub async fn set_device_fallback_enabled(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+  log::debug!("Setting device fallback: {}", enabled);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine.set_device_fallback_enabled(enabled);
+  Ok(())
+}
+
+/// Choose whether playback stops by itself once the song's audio runs out
+#[tauri::command]
+pub async fn set_auto_stop_at_end(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+  log::debug!("Setting auto-stop at end: {}", enabled);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine.set_auto_stop_at_end(enabled);
 
   Ok(())
 }
 
 /// Seek to a specific position in the current song (in seconds)
 #[tauri::command]
-pub async fn seek_to_position(position: f64, state: State<'_, AppState>) -> Result<(), String> {
-  log::info!("Seeking to position: {}", position);
+pub async fn seek_to_position(
+  position: f64,
+  autoplay: Option<bool>,
+  state: State<'_, AppState>,
+) -> Result<(), String> {
+  seek_to_position_with(&state, position, autoplay)
+}
 
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+// Seek, then start playback (Some(true)), hold it paused for scrubbing (Some(false)) or keep
+// whatever state the transport was in (None)
+pub(crate) fn seek_to_position_with(state: &AppState, position: f64, autoplay: Option<bool>) -> Result<(), String> {
+  log::info!("Seeking to position: {} (autoplay: {:?})", position, autoplay);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
 
   engine
     .seek(position)
     .map_err(|e| format!("Failed to seek: {}", e))?;
 
+  match autoplay {
+    Some(true) => engine
+      .play()
+      .map_err(|e| format!("Failed to start playback after seeking: {}", e))?,
+    // Stopped stays stopped, only a running transport is held
+    Some(false) if engine.state() == PlaybackState::Playing => engine
+      .pause()
+      .map_err(|e| format!("Failed to pause after seeking: {}", e))?,
+    _ => {}
+  }
+
   Ok(())
 }
 
-/// Get current playback position in seconds
+/// Get the playback position in frames at the project sample rate. Unlike seconds it is exact,
+/// so the UI can do frame-accurate math on long songs
+#[tauri::command]
+pub async fn get_playback_position_frames(state: State<'_, AppState>) -> Result<u64, String> {
+  Ok(state.playback_position_frames().0)
+}
+
+/// Skip forward by the given number of seconds (clamped to the song end)
+#[tauri::command]
+pub async fn skip_forward(seconds: f64, state: State<'_, AppState>) -> Result<f64, String> {
+  log::info!("Skipping forward {} seconds", seconds);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine
+    .seek_relative(seconds.abs())
+    .map_err(|e| format!("Failed to skip forward: {}", e))
+}
+
+/// Skip backward by the given number of seconds (clamped to the song start)
+#[tauri::command]
+pub async fn skip_backward(seconds: f64, state: State<'_, AppState>) -> Result<f64, String> {
+  log::info!("Skipping backward {} seconds", seconds);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine
+    .seek_relative(-seconds.abs())
+    .map_err(|e| format!("Failed to skip backward: {}", e))
+}
+
+/// Get current playback position in seconds. Reads the position atomic directly, so it never
+/// waits on a command holding the engine
 #[tauri::command]
 pub async fn get_playback_position(state: State<'_, AppState>) -> Result<f64, String> {
-  let engine = state.audio_engine
+  Ok(state.playback_position_seconds())
+}
+
+/// Snapshot of the transport for the frontend
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TransportState {
+  pub state: String, // "playing" | "paused" | "stopped"
+  pub position: f64,
+  pub duration: f64,
+  pub song_id: Option<String>,
+}
+
+/// Get playback state, position, duration and loaded song in one call
+#[tauri::command]
+pub async fn get_transport_state(state: State<'_, AppState>) -> Result<TransportState, String> {
+  transport_state(&state)
+}
+
+pub(crate) fn transport_state(state: &AppState) -> Result<TransportState, String> {
+  let (playback_state, position, duration) = {
+    let engine = lock_or_recover(&state.audio_engine, "audio engine");
+    (engine.state(), engine.position(), engine.duration())
+  };
+
+  let song_id = state.current_song_id
     .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+    .map_err(|_| "Failed to lock current song")?
+    .clone();
 
-  Ok(engine.position())
+  let state_name = match playback_state {
+    PlaybackState::Playing => "playing",
+    PlaybackState::Paused => "paused",
+    PlaybackState::Stopped => "stopped",
+  };
+
+  Ok(TransportState {
+    state: state_name.to_string(),
+    position,
+    duration,
+    song_id,
+  })
 }
 
 /// Preload songs with priority based on current playback position
@@ -408,3 +1087,46 @@ pub async fn preload_setlist(setlist_id: String, state: State<'_, AppState>, app
   log::info!("Finished preloading setlist '{}'", setlist.name);
   Ok(())
 }
+
+/// Loop a passage at a reduced (or increased) rate and start playing it
+#[tauri::command]
+pub async fn practice_loop(start: f64, end: f64, rate: f32, state: State<'_, AppState>) -> Result<(), String> {
+  log::info!("Starting practice loop {}s - {}s at {}x", start, end, rate);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine
+    .start_practice_loop(start, end, rate)
+    .map_err(|e| format!("Failed to start practice loop: {}", e))
+}
+
+/// Play backwards from the current position (an ear-training drill) until the start of the song.
+/// `enabled: false` goes back to normal forward playback without changing the transport
+#[tauri::command]
+pub async fn play_reverse(enabled: Option<bool>, state: State<'_, AppState>) -> Result<(), String> {
+  let enabled = enabled.unwrap_or(true);
+  log::info!("Reverse playback {}", if enabled { "on" } else { "off" });
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine.set_reverse(enabled);
+  if enabled {
+    engine
+      .play()
+      .map_err(|e| format!("Failed to start reverse playback: {}", e))?;
+  }
+
+  Ok(())
+}
+
+/// Clear the practice loop region and return to normal speed
+#[tauri::command]
+pub async fn stop_practice_loop(state: State<'_, AppState>) -> Result<(), String> {
+  log::info!("Stopping practice loop");
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine.stop_practice_loop();
+
+  Ok(())
+}
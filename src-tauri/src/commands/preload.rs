@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::{Emitter, State};
+
+use super::playback::{decode_song, play_song_with};
+use super::{lock_or_recover, AppState};
+use crate::database::Song;
+
+// Songs decoded ahead of the current one
+const PRELOAD_AHEAD: usize = 2;
+// Songs kept in the cache behind the current one; anything further back is evicted
+const PRELOAD_KEEP_BEHIND: usize = 1;
+// Quiet time after a move before preloading starts, so skipping through several songs
+// only decodes around where the performer lands
+const PRELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Position in the setlist being performed
+#[derive(Debug, Clone, Default)]
+pub struct SetlistCursor {
+  pub setlist_id: Option<String>,
+  pub index: usize,
+  pub auto_advance: bool, // Start the next song when the current one plays to its end
+  generation: u64, // Bumped on every move so preloads for an old position stop
+}
+
+/// How the cache is arranged around a setlist position
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreloadPlan {
+  pub pinned: String,     // Current song, kept out of LRU eviction
+  pub queue: Vec<String>, // Decoded in order: the current song, then the next few
+  pub evict: Vec<String>, // Songs far enough behind to drop from the cache
+}
+
+// Plan for position `index` of a setlist's songs, None past the end
+pub fn preload_plan(song_ids: &[String], index: usize) -> Option<PreloadPlan> {
+  let pinned = song_ids.get(index)?.clone();
+  let end = (index + PRELOAD_AHEAD + 1).min(song_ids.len());
+  let keep_from = index.saturating_sub(PRELOAD_KEEP_BEHIND);
+
+  let queue = song_ids[index..end].to_vec();
+  // A song can appear twice in a set; never evict one that's also coming up
+  let kept: HashSet<&String> = song_ids[keep_from..end].iter().collect();
+  let mut evict: Vec<String> = song_ids[..keep_from]
+    .iter()
+    .filter(|id| !kept.contains(id))
+    .cloned()
+    .collect();
+  evict.sort();
+  evict.dedup();
+
+  Some(PreloadPlan { pinned, queue, evict })
+}
+
+// Point the cursor at `index` of a setlist, returning the song there and the new cursor
+// generation. None (cursor unchanged) when the setlist has no song at that position
+pub(crate) fn move_setlist_cursor(state: &AppState, setlist_id: &str, index: usize) -> Result<Option<(Song, u64)>, String> {
+  let songs = state.database
+    .get_setlist_songs(setlist_id)
+    .map_err(|e| format!("Failed to get setlist songs: {}", e))?;
+  let Some(song) = songs.into_iter().nth(index) else {
+    return Ok(None);
+  };
+
+  let mut cursor = lock_or_recover(&state.setlist_cursor, "setlist cursor");
+  cursor.setlist_id = Some(setlist_id.to_string());
+  cursor.index = index;
+  cursor.generation += 1;
+
+  log::info!("Setlist {} at position {}: '{}'", setlist_id, index + 1, song.name);
+  Ok(Some((song, cursor.generation)))
+}
+
+// Setlist and index the cursor points at, if it hasn't moved since `generation`
+fn cursor_position(state: &AppState, generation: u64) -> Option<(String, usize)> {
+  let cursor = lock_or_recover(&state.setlist_cursor, "setlist cursor");
+  if cursor.generation != generation {
+    return None;
+  }
+  cursor.setlist_id.clone().map(|id| (id, cursor.index))
+}
+
+// After the debounce, pin the current song, evict songs far behind and decode the current and
+// next songs in order. Cached songs are skipped; stops as soon as the cursor moves again
+pub(crate) async fn preload_around_cursor<E>(state: AppState, generation: u64, emit: E)
+where
+  E: Fn(&str, serde_json::Value) + Clone + Send + 'static,
+{
+  tokio::time::sleep(PRELOAD_DEBOUNCE).await;
+
+  let Some((setlist_id, index)) = cursor_position(&state, generation) else {
+    return;
+  };
+  let song_ids: Vec<String> = match state.database.get_setlist_songs(&setlist_id) {
+    Ok(songs) => songs.into_iter().map(|song| song.id).collect(),
+    Err(e) => {
+      log::warn!("Failed to get setlist songs for preload: {}", e);
+      return;
+    }
+  };
+  let Some(plan) = preload_plan(&song_ids, index) else {
+    return;
+  };
+
+  {
+    let mut cache = lock_or_recover(&state.song_cache, "song cache");
+    cache.set_pinned(Some(plan.pinned.clone()));
+    for song_id in &plan.evict {
+      cache.remove(song_id);
+    }
+  }
+
+  for song_id in &plan.queue {
+    if cursor_position(&state, generation).is_none() {
+      log::info!("Setlist position changed, re-prioritizing preload");
+      return;
+    }
+
+    let emit = emit.clone();
+    let result = super::load_once(&state.loading_songs, &state.song_cache, song_id, || {
+      decode_song(song_id.clone(), &state, emit)
+    }).await;
+    if let Err(e) = result {
+      log::warn!("Failed to preload song {}: {}", song_id, e);
+    }
+  }
+}
+
+pub(crate) fn spawn_preload(state: &AppState, generation: u64, app_handle: tauri::AppHandle) {
+  let state = state.clone();
+  tauri::async_runtime::spawn(preload_around_cursor(state, generation, move |event, payload| {
+    let _ = app_handle.emit(event, payload);
+  }));
+}
+
+// Once auto-stop has ended the current song of an auto-advancing setlist, wait the configured
+// gap and play the next song. Returns that song and the new cursor generation, None when
+// nothing advanced (no song ended, auto-advance off, the performer moved or the set is over)
+pub(crate) async fn advance_after_song_end<E>(state: &AppState, emit: E) -> Result<Option<(Song, u64)>, String>
+where
+  E: Fn(&str, serde_json::Value) + Clone + Send + 'static,
+{
+  if !lock_or_recover(&state.audio_engine, "audio engine").take_song_ended() {
+    return Ok(None);
+  }
+
+  let (setlist_id, index, generation) = {
+    let cursor = lock_or_recover(&state.setlist_cursor, "setlist cursor");
+    match &cursor.setlist_id {
+      Some(setlist_id) if cursor.auto_advance => (setlist_id.clone(), cursor.index, cursor.generation),
+      _ => return Ok(None),
+    }
+  };
+
+  let gap = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?
+    .auto_advance_gap_seconds;
+  if gap > 0.0 {
+    tokio::time::sleep(Duration::from_secs_f64(gap)).await;
+    if cursor_position(state, generation).is_none() {
+      log::info!("Setlist position changed during the gap, not auto-advancing");
+      return Ok(None);
+    }
+  }
+
+  let Some((song, generation)) = move_setlist_cursor(state, &setlist_id, index + 1)? else {
+    log::info!("End of setlist {}, nothing to auto-advance to", setlist_id);
+    return Ok(None);
+  };
+
+  play_song_with(song.id.clone(), state, emit).await?;
+  Ok(Some((song, generation)))
+}
+
+/// Start performing a setlist at `index` (the first song by default) and return that song.
+/// The current and next songs are preloaded in the background. With `auto_advance` the next
+/// song starts by itself when the current one ends, after the configured gap
+#[tauri::command]
+pub async fn start_setlist(
+  setlist_id: String,
+  index: Option<usize>,
+  auto_advance: Option<bool>,
+  state: State<'_, AppState>,
+  app_handle: tauri::AppHandle
+) -> Result<Song, String> {
+  let index = index.unwrap_or(0);
+  let (song, generation) = move_setlist_cursor(&state, &setlist_id, index)?
+    .ok_or_else(|| format!("Setlist has no song at position {}", index + 1))?;
+  lock_or_recover(&state.setlist_cursor, "setlist cursor").auto_advance = auto_advance.unwrap_or(false);
+
+  spawn_preload(&state, generation, app_handle);
+  Ok(song)
+}
+
+/// Advance the running setlist and return the next song, or None at the end of the set.
+/// Preloading is re-prioritized around the new position
+#[tauri::command]
+pub async fn next_song(
+  state: State<'_, AppState>,
+  app_handle: tauri::AppHandle
+) -> Result<Option<Song>, String> {
+  let (setlist_id, index) = {
+    let cursor = lock_or_recover(&state.setlist_cursor, "setlist cursor");
+    let setlist_id = cursor.setlist_id.clone().ok_or("No setlist is running")?;
+    (setlist_id, cursor.index)
+  };
+
+  let Some((song, generation)) = move_setlist_cursor(&state, &setlist_id, index + 1)? else {
+    return Ok(None);
+  };
+
+  spawn_preload(&state, generation, app_handle);
+  Ok(Some(song))
+}
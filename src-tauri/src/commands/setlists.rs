@@ -1,5 +1,6 @@
+use std::collections::HashMap;
 use super::AppState;
-use crate::database::Setlist;
+use crate::database::{Setlist, SetlistTemplate};
 use tauri::State;
 
 /// Create a new empty setlist
@@ -19,6 +20,9 @@ pub async fn create_setlist(
     created_at: now,
     updated_at: now,
     song_ids: Vec::new(),
+    position: 0,
+    color: None,
+    songs: Vec::new(),
   };
 
   state.database
@@ -51,6 +55,7 @@ pub async fn update_setlist(
   setlist_id: String,
   name: Option<String>,
   song_ids: Option<Vec<String>>,
+  color: Option<String>,
   state: State<'_, AppState>
 ) -> Result<(), String> {
   log::info!("Updating setlist: {}", setlist_id);
@@ -69,6 +74,12 @@ pub async fn update_setlist(
     setlist.song_ids = new_song_ids;
   }
 
+  // A blank color clears it
+  if let Some(new_color) = color {
+    let new_color = new_color.trim();
+    setlist.color = (!new_color.is_empty()).then(|| new_color.to_string());
+  }
+
   // Update timestamp
   setlist.updated_at = chrono::Utc::now().timestamp();
 
@@ -136,6 +147,42 @@ pub async fn add_song_to_setlist(
   Ok(())
 }
 
+/// Insert a song into a setlist at `position` (clamped to the end of the list).
+/// A song already in the setlist is moved there rather than added twice
+#[tauri::command]
+pub async fn insert_song_into_setlist(
+  setlist_id: String,
+  song_id: String,
+  position: usize,
+  state: State<'_, AppState>
+) -> Result<Vec<String>, String> {
+  log::info!("Inserting song {} into setlist {} at {}", song_id, setlist_id, position);
+  insert_setlist_song(&state, &setlist_id, &song_id, position)
+}
+
+// Insert or move `song_id` to `position` and save, returning the new song order
+pub(crate) fn insert_setlist_song(
+  state: &AppState,
+  setlist_id: &str,
+  song_id: &str,
+  position: usize,
+) -> Result<Vec<String>, String> {
+  let mut setlist = state.database
+    .get_setlist(setlist_id)
+    .map_err(|e| format!("Failed to get setlist: {}", e))?;
+
+  setlist.song_ids.retain(|id| id != song_id);
+  let index = position.min(setlist.song_ids.len());
+  setlist.song_ids.insert(index, song_id.to_string());
+  setlist.updated_at = chrono::Utc::now().timestamp();
+
+  state.database
+    .update_setlist(&setlist)
+    .map_err(|e| format!("Failed to update setlist: {}", e))?;
+
+  Ok(setlist.song_ids)
+}
+
 /// Remove a song from a setlist
 #[tauri::command]
 pub async fn remove_song_from_setlist(
@@ -185,3 +232,268 @@ pub async fn reorder_setlist_songs(
 
   Ok(())
 }
+
+/// Reorder the setlist list; setlists left out of `ids` keep their order after the listed ones
+#[tauri::command]
+pub async fn reorder_setlists(
+  ids: Vec<String>,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::info!("Reordering {} setlists", ids.len());
+
+  for (index, id) in ids.iter().enumerate() {
+    if ids[..index].contains(id) {
+      return Err(format!("Setlist {} is listed more than once", id));
+    }
+  }
+
+  state.database
+    .reorder_setlists(&ids)
+    .map_err(|e| match e {
+      rusqlite::Error::QueryReturnedNoRows => "Setlist not found; it may have been deleted".to_string(),
+      e => format!("Failed to reorder setlists: {}", e),
+    })
+}
+
+/// Set the per-setlist key/tempo/transition notes for a song
+/// These only apply to this setlist; the song itself is unchanged
+#[tauri::command]
+pub async fn set_setlist_song_override(
+  setlist_id: String,
+  song_id: String,
+  key_override: Option<String>,
+  tempo_note: Option<String>,
+  transition_note: Option<String>,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::info!("Setting overrides for song {} in setlist {}", song_id, setlist_id);
+
+  // Treat blank values as "no override"
+  let clean = |value: Option<String>| value
+    .map(|v| v.trim().to_string())
+    .filter(|v| !v.is_empty());
+
+  state.database
+    .set_setlist_song_override(
+      &setlist_id,
+      &song_id,
+      clean(key_override).as_deref(),
+      clean(tempo_note).as_deref(),
+      clean(transition_note).as_deref(),
+    )
+    .map_err(|e| match e {
+      rusqlite::Error::QueryReturnedNoRows => format!("Song {} is not in setlist {}", song_id, setlist_id),
+      e => format!("Failed to set setlist song override: {}", e),
+    })
+}
+
+/// One song's place in the running order of a setlist
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SetlistSongTiming {
+  pub song_id: String,
+  pub name: String,
+  pub duration: f64,   // 0 when the song has no usable duration
+  pub start_time: f64, // Seconds from the start of the set, including earlier gaps
+  pub has_duration: bool,
+}
+
+/// Total runtime of a setlist with a per-song breakdown
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SetlistDuration {
+  pub total_seconds: f64,
+  pub gap_seconds: f64,
+  pub songs: Vec<SetlistSongTiming>,
+}
+
+/// Get a setlist's total runtime: the songs' durations plus `gap_seconds` (default 0)
+/// between consecutive songs for talking and transitions
+#[tauri::command]
+pub async fn get_setlist_duration(
+  setlist_id: String,
+  gap_seconds: Option<f64>,
+  state: State<'_, AppState>
+) -> Result<SetlistDuration, String> {
+  log::debug!("Getting duration of setlist {}", setlist_id);
+  setlist_duration(&state, &setlist_id, gap_seconds.unwrap_or(0.0))
+}
+
+pub(crate) fn setlist_duration(state: &AppState, setlist_id: &str, gap_seconds: f64) -> Result<SetlistDuration, String> {
+  if !gap_seconds.is_finite() || gap_seconds < 0.0 {
+    return Err(format!("Invalid transition gap: {}", gap_seconds));
+  }
+
+  let songs = state.database
+    .get_setlist_songs(setlist_id)
+    .map_err(|e| format!("Failed to get setlist songs: {}", e))?;
+
+  let mut timings = Vec::with_capacity(songs.len());
+  let mut elapsed = 0.0;
+  for (index, song) in songs.into_iter().enumerate() {
+    if index > 0 {
+      elapsed += gap_seconds;
+    }
+
+    // Songs imported without a readable length still hold their slot, they just add no time
+    let has_duration = song.duration.is_finite() && song.duration > 0.0;
+    let duration = if has_duration { song.duration } else { 0.0 };
+
+    timings.push(SetlistSongTiming {
+      song_id: song.id,
+      name: song.name,
+      duration,
+      start_time: elapsed,
+      has_duration,
+    });
+    elapsed += duration;
+  }
+
+  Ok(SetlistDuration {
+    total_seconds: elapsed,
+    gap_seconds,
+    songs: timings,
+  })
+}
+
+/// Save a reusable setlist structure of labeled slots (e.g. Opener, Worship, Worship, Offering)
+#[tauri::command]
+pub async fn create_setlist_template(
+  name: String,
+  slots: Vec<String>,
+  state: State<'_, AppState>
+) -> Result<SetlistTemplate, String> {
+  log::info!("Creating setlist template '{}' with {} slots", name, slots.len());
+  new_setlist_template(&state, &name, slots)
+}
+
+/// Get all setlist templates, by name
+#[tauri::command]
+pub async fn get_setlist_templates(
+  state: State<'_, AppState>
+) -> Result<Vec<SetlistTemplate>, String> {
+  state.database
+    .list_setlist_templates()
+    .map_err(|e| format!("Failed to get setlist templates: {}", e))
+}
+
+/// Delete a setlist template; setlists already made from it are kept
+#[tauri::command]
+pub async fn delete_setlist_template(
+  template_id: String,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::info!("Deleting setlist template: {}", template_id);
+
+  state.database
+    .delete_setlist_template(&template_id)
+    .map_err(|e| format!("Failed to delete setlist template: {}", e))
+}
+
+/// Save a setlist's structure as a template with one slot per song. Slots are labeled
+/// `labels` in order, or "Song 1", "Song 2", ... when not given
+#[tauri::command]
+pub async fn save_setlist_as_template(
+  setlist_id: String,
+  name: Option<String>,
+  labels: Option<Vec<String>>,
+  state: State<'_, AppState>
+) -> Result<SetlistTemplate, String> {
+  log::info!("Saving setlist {} as a template", setlist_id);
+
+  let setlist = state.database
+    .get_setlist(&setlist_id)
+    .map_err(|e| format!("Failed to get setlist: {}", e))?;
+
+  let slots = match labels {
+    Some(labels) if labels.len() != setlist.song_ids.len() => {
+      return Err(format!("Expected {} slot labels, got {}", setlist.song_ids.len(), labels.len()));
+    }
+    Some(labels) => labels,
+    None => (1..=setlist.song_ids.len()).map(|n| format!("Song {}", n)).collect(),
+  };
+
+  new_setlist_template(&state, &name.unwrap_or(setlist.name), slots)
+}
+
+/// Build a setlist from a template, filling slots by index from `slot_song_map` (slot index to
+/// song id). Empty slots are left out. Returns the new setlist's ID
+#[tauri::command]
+pub async fn create_setlist_from_template(
+  template_id: String,
+  slot_song_map: HashMap<usize, String>,
+  name: Option<String>,
+  state: State<'_, AppState>
+) -> Result<String, String> {
+  log::info!("Creating setlist from template {}", template_id);
+  setlist_from_template(&state, &template_id, &slot_song_map, name)
+}
+
+pub(crate) fn new_setlist_template(state: &AppState, name: &str, slots: Vec<String>) -> Result<SetlistTemplate, String> {
+  if name.trim().is_empty() {
+    return Err("Template name cannot be empty".to_string());
+  }
+  if slots.is_empty() {
+    return Err("A template needs at least one slot".to_string());
+  }
+
+  let template = SetlistTemplate {
+    id: uuid::Uuid::new_v4().to_string(),
+    name: name.trim().to_string(),
+    slots,
+    created_at: chrono::Utc::now().timestamp(),
+  };
+
+  state.database
+    .create_setlist_template(&template)
+    .map_err(|e| format!("Failed to create setlist template: {}", e))?;
+
+  Ok(template)
+}
+
+pub(crate) fn setlist_from_template(
+  state: &AppState,
+  template_id: &str,
+  slot_song_map: &HashMap<usize, String>,
+  name: Option<String>,
+) -> Result<String, String> {
+  let template = state.database
+    .get_setlist_template(template_id)
+    .map_err(|e| format!("Failed to get setlist template: {}", e))?;
+
+  if let Some(slot) = slot_song_map.keys().find(|&&slot| slot >= template.slots.len()) {
+    return Err(format!("Template '{}' has no slot {}", template.name, slot));
+  }
+
+  let mut song_ids: Vec<String> = Vec::new();
+  for (slot, label) in template.slots.iter().enumerate() {
+    let Some(song_id) = slot_song_map.get(&slot) else {
+      continue;
+    };
+    // A setlist holds each song once
+    if song_ids.contains(song_id) {
+      return Err(format!("Song {} fills more than one slot", song_id));
+    }
+    state.database
+      .get_song(song_id)
+      .map_err(|e| format!("Failed to get song for slot '{}': {}", label, e))?;
+    song_ids.push(song_id.clone());
+  }
+
+  let now = chrono::Utc::now().timestamp();
+  let setlist = Setlist {
+    id: uuid::Uuid::new_v4().to_string(),
+    name: name.unwrap_or_else(|| template.name.clone()),
+    created_at: now,
+    updated_at: now,
+    song_ids,
+    position: 0,
+    color: None,
+    songs: Vec::new(),
+  };
+
+  state.database
+    .create_setlist(&setlist)
+    .map_err(|e| format!("Failed to create setlist: {}", e))?;
+
+  log::info!("Created setlist {} from template '{}'", setlist.id, template.name);
+  Ok(setlist.id)
+}
@@ -4,8 +4,13 @@ use serde::{Serialize, Deserialize};
 #[cfg(not(target_os = "macos"))]
 use cpal::traits::{HostTrait, DeviceTrait};
 
-use super::AppState;
-use crate::database::AppSettings;
+use super::{cache_size_bytes_from_gb, lock_or_recover, AppState, STEM_OVERFLOW_ERROR, STEM_OVERFLOW_MERGE};
+use crate::audio::{ActiveStreamConfig, FadeCurve, PlaybackState, StemCapacity};
+use crate::audio::{MAX_HIGHPASS_CUTOFF_HZ, MIN_HIGHPASS_CUTOFF_HZ};
+use crate::database::{AppSettings, Database};
+
+// Loudness targets accepted, in LUFS
+const LOUDNESS_TARGET_RANGE: std::ops::RangeInclusive<f64> = -40.0..=0.0;
 
 #[derive(Serialize, Deserialize)]
 pub struct AudioDevice {
@@ -152,8 +157,13 @@ pub fn get_audio_devices() -> Result<Vec<AudioDevice>, String> {
 
 #[cfg(not(target_os = "macos"))]
 #[tauri::command]
-pub fn get_audio_devices() -> Result<Vec<AudioDevice>, String> {
-  let host = cpal::default_host();
+pub fn get_audio_devices(state: State<'_, AppState>) -> Result<Vec<AudioDevice>, String> {
+  // Devices come from the host API the engine is using
+  let host_name = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?
+    .audio_host;
+  let host = crate::audio::audio_host(host_name.as_deref());
 
   log::info!("Enumerating audio output devices...");
 
@@ -199,6 +209,136 @@ pub fn get_audio_devices() -> Result<Vec<AudioDevice>, String> {
   Ok(audio_devices)
 }
 
+// Common rates offered for devices that report a continuous range
+const STANDARD_SAMPLE_RATES: [u32; 9] = [22050, 32000, 44100, 48000, 88200, 96000, 176400, 192000, 384000];
+
+// Distinct, ascending sample rates covered by a device's (min, max) ranges: the range
+// bounds themselves plus every standard rate inside a range
+pub(crate) fn sample_rates_in_ranges(ranges: &[(u32, u32)]) -> Vec<u32> {
+  let mut rates: Vec<u32> = ranges
+    .iter()
+    .flat_map(|&(min, max)| {
+      let standard = STANDARD_SAMPLE_RATES.into_iter().filter(move |rate| (min..=max).contains(rate));
+      [min, max].into_iter().chain(standard)
+    })
+    .filter(|&rate| rate > 0)
+    .collect();
+
+  rates.sort_unstable();
+  rates.dedup();
+  rates
+}
+
+/// List the sample rates an output device supports (the default device if no name is given)
+#[cfg(target_os = "macos")]
+#[tauri::command]
+pub fn get_supported_sample_rates(device_name: Option<String>) -> Result<Vec<u32>, String> {
+  use coreaudio::sys::{
+    kAudioDevicePropertyAvailableNominalSampleRates, kAudioHardwarePropertyDefaultOutputDevice,
+    kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyElementMain, kAudioObjectSystemObject,
+    AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize, AudioObjectPropertyAddress,
+    AudioValueRange, AudioDeviceID,
+  };
+  use crate::audio::macos_backend::MacOSAudioStream;
+  use std::ptr;
+
+  let device_id: AudioDeviceID = match device_name {
+    Some(name) => MacOSAudioStream::find_device_id(&name).map_err(|e| e.to_string())?,
+    None => unsafe {
+      let mut default_device_id: AudioDeviceID = 0;
+      let default_property = AudioObjectPropertyAddress {
+        mSelector: kAudioHardwarePropertyDefaultOutputDevice,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMain as u32,
+      };
+
+      let mut size = std::mem::size_of::<AudioDeviceID>() as u32;
+      let status = AudioObjectGetPropertyData(
+        kAudioObjectSystemObject,
+        &default_property,
+        0,
+        ptr::null(),
+        &mut size,
+        &mut default_device_id as *mut _ as *mut _,
+      );
+      if status != 0 {
+        return Err(format!("Failed to get default output device: {}", status));
+      }
+      default_device_id
+    },
+  };
+
+  let ranges = unsafe {
+    let rates_property = AudioObjectPropertyAddress {
+      mSelector: kAudioDevicePropertyAvailableNominalSampleRates,
+      mScope: kAudioObjectPropertyScopeGlobal,
+      mElement: kAudioObjectPropertyElementMain as u32,
+    };
+
+    let mut data_size: u32 = 0;
+    let status = AudioObjectGetPropertyDataSize(
+      device_id,
+      &rates_property,
+      0,
+      ptr::null(),
+      &mut data_size,
+    );
+    if status != 0 {
+      return Err(format!("Failed to get sample rate list size: {}", status));
+    }
+
+    let count = data_size as usize / std::mem::size_of::<AudioValueRange>();
+    let mut ranges = vec![AudioValueRange { mMinimum: 0.0, mMaximum: 0.0 }; count];
+    let status = AudioObjectGetPropertyData(
+      device_id,
+      &rates_property,
+      0,
+      ptr::null(),
+      &mut data_size,
+      ranges.as_mut_ptr() as *mut _,
+    );
+    if status != 0 {
+      return Err(format!("Failed to get sample rates: {}", status));
+    }
+    ranges
+  };
+
+  let ranges: Vec<(u32, u32)> = ranges
+    .iter()
+    .map(|range| (range.mMinimum as u32, range.mMaximum as u32))
+    .collect();
+  Ok(sample_rates_in_ranges(&ranges))
+}
+
+/// List the sample rates an output device supports (the default device if no name is given)
+#[cfg(not(target_os = "macos"))]
+#[tauri::command]
+pub fn get_supported_sample_rates(device_name: Option<String>) -> Result<Vec<u32>, String> {
+  let host = cpal::default_host();
+
+  let device = match device_name {
+    // The device may belong to a non-default host API (e.g. ASIO), so search them all
+    Some(name) => cpal::available_hosts()
+      .into_iter()
+      .filter_map(|id| cpal::host_from_id(id).ok())
+      .filter_map(|host| host.output_devices().ok())
+      .flatten()
+      .find(|d| d.name().ok().as_deref() == Some(name.as_str()))
+      .ok_or_else(|| format!("Device '{}' not found", name))?,
+    None => host
+      .default_output_device()
+      .ok_or_else(|| "No output device available".to_string())?,
+  };
+
+  let ranges: Vec<(u32, u32)> = device
+    .supported_output_configs()
+    .map_err(|e| format!("Failed to get supported configs: {}", e))?
+    .map(|config| (config.min_sample_rate().0, config.max_sample_rate().0))
+    .collect();
+
+  Ok(sample_rates_in_ranges(&ranges))
+}
+
 #[tauri::command]
 pub fn get_audio_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
   state.database
@@ -225,74 +365,765 @@ pub fn set_audio_device(
   Ok(())
 }
 
+/// Rebuild the output stream with a new buffer size and persist it.
+/// Returns the size in use, which may be the nearest one the device supports
 #[tauri::command]
 pub fn set_buffer_size(
   state: State<'_, AppState>,
   buffer_size: i32,
-) -> Result<(), String> {
+) -> Result<i32, String> {
+  if buffer_size <= 0 {
+    return Err(format!("Invalid buffer size: {}", buffer_size));
+  }
+
+  let applied = {
+    let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+    engine.set_buffer_size(buffer_size as usize)
+      .map_err(|e| format!("Failed to set buffer size: {}", e))? as i32
+  };
+
   let mut settings = state.database
     .get_settings()
     .map_err(|e| format!("Failed to get settings: {}", e))?;
 
-  settings.audio_buffer_size = buffer_size;
+  settings.audio_buffer_size = applied;
 
   state.database
     .update_settings(&settings)
     .map_err(|e| format!("Failed to update buffer size: {}", e))?;
 
-  log::info!("Audio buffer size set to: {}", buffer_size);
-  Ok(())
+  log::info!("Audio buffer size set to: {} (requested {})", applied, buffer_size);
+  Ok(applied)
 }
 
+/// Same as set_project_sample_rate, kept for callers that still use the old name
 #[tauri::command]
 pub fn set_sample_rate(
   state: State<'_, AppState>,
   sample_rate: i32,
 ) -> Result<(), String> {
+  if sample_rate <= 0 {
+    return Err(format!("Invalid sample rate: {}", sample_rate));
+  }
+  apply_project_sample_rate(&state, sample_rate as u32)
+}
+
+/// Reopen the output stream at a new project sample rate and persist it. Stems are decoded at
+/// this rate, so the loaded song and the in-memory song cache are dropped and reload on demand
+#[tauri::command]
+pub fn set_project_sample_rate(
+  state: State<'_, AppState>,
+  sample_rate: u32,
+) -> Result<(), String> {
+  apply_project_sample_rate(&state, sample_rate)
+}
+
+pub(crate) fn apply_project_sample_rate(state: &AppState, sample_rate: u32) -> Result<(), String> {
+  let changed = {
+    let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+    let previous = engine.project_sample_rate();
+    engine
+      .set_project_sample_rate(sample_rate)
+      .map_err(|e| format!("Failed to set project sample rate: {}", e))?;
+    previous != sample_rate
+  };
+
+  // Everything decoded so far is at the old rate
+  if changed {
+    forget_decoded_songs(state);
+  }
+
   let mut settings = state.database
     .get_settings()
     .map_err(|e| format!("Failed to get settings: {}", e))?;
 
-  settings.sample_rate = sample_rate;
+  settings.sample_rate = sample_rate as i32;
 
   state.database
     .update_settings(&settings)
     .map_err(|e| format!("Failed to update sample rate: {}", e))?;
 
-  log::info!("Sample rate set to: {}", sample_rate);
+  log::info!("Project sample rate set to {}Hz", sample_rate);
   Ok(())
 }
 
+// Drop the song cache, the engine slot map and the current song once the engine has dropped
+// its stems for a new project rate
+fn forget_decoded_songs(state: &AppState) {
+  lock_or_recover(&state.song_cache, "song cache").clear();
+  lock_or_recover(&state.stem_id_map, "stem ID map").clear();
+  *lock_or_recover(&state.current_song_id, "current song") = None;
+}
+
+/// The output device moved the project to `sample_rate` (it can't run at the old one). Songs
+/// decoded at the old rate are dropped and the new rate is saved as the project rate
+pub(crate) fn follow_device_sample_rate(state: &AppState, sample_rate: u32) -> Result<(), String> {
+  forget_decoded_songs(state);
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.sample_rate = sample_rate as i32;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update sample rate: {}", e))?;
+
+  log::info!("Project sample rate follows the output device to {}Hz", sample_rate);
+  Ok(())
+}
+
+/// Set which side click/guide (cue) stems are panned to by default ("left" or "right")
 #[tauri::command]
-pub fn switch_audio_device(
+pub fn set_cue_pan_side(
+  state: State<'_, AppState>,
+  side: String,
+) -> Result<(), String> {
+  let side = side.to_lowercase();
+  if side != "left" && side != "right" {
+    return Err(format!("Invalid cue pan side '{}', expected 'left' or 'right'", side));
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.cue_pan_side = side.clone();
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update cue pan side: {}", e))?;
+
+  log::info!("Cue pan side set to: {}", side);
+  Ok(())
+}
+
+/// Choose how solo behaves: "additive" (solos stack) or "exclusive" (a new solo releases the others)
+#[tauri::command]
+pub fn set_solo_mode(
+  state: State<'_, AppState>,
+  mode: String,
+) -> Result<(), String> {
+  let mode = mode.to_lowercase();
+  if mode != "additive" && mode != "exclusive" {
+    return Err(format!("Invalid solo mode '{}', expected 'additive' or 'exclusive'", mode));
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.solo_mode = mode.clone();
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update solo mode: {}", e))?;
+
+  log::info!("Solo mode set to: {}", mode);
+  Ok(())
+}
+
+/// Choose the fade curve: "linear" or "equal_power" (no loudness dip mid-fade)
+#[tauri::command]
+pub fn set_fade_curve(
+  state: State<'_, AppState>,
+  curve: String,
+) -> Result<(), String> {
+  let curve = curve.to_lowercase();
+  let fade_curve = FadeCurve::from_name(&curve)
+    .ok_or_else(|| format!("Invalid fade curve '{}', expected 'linear' or 'equal_power'", curve))?;
+
+  {
+    let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+    engine.set_fade_curve(fade_curve);
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.fade_curve = curve.clone();
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update fade curve: {}", e))?;
+
+  log::info!("Fade curve set to: {}", curve);
+  Ok(())
+}
+
+/// Limit how many stems are decoded at once while loading songs (0 = one per CPU core).
+/// Returns the limit in effect
+#[tauri::command]
+pub fn set_max_decode_threads(
+  state: State<'_, AppState>,
+  threads: i32,
+) -> Result<i32, String> {
+  if threads < 0 {
+    return Err(format!("Invalid decode thread count: {}", threads));
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.max_decode_threads = threads;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update decode thread count: {}", e))?;
+
+  Ok(state.set_max_decode_threads(threads as usize) as i32)
+}
+
+/// Convert stems imported from now on to `rate` Hz, keeping the originals (0 = keep each file's rate)
+#[tauri::command]
+pub fn set_import_sample_rate(
+  state: State<'_, AppState>,
+  rate: i32,
+) -> Result<(), String> {
+  if rate != 0 && !(8000..=384000).contains(&rate) {
+    return Err(format!("Invalid import sample rate: {}", rate));
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.import_sample_rate = rate;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update import sample rate: {}", e))?;
+
+  log::info!("Import sample rate set to: {}", rate);
+  Ok(())
+}
+
+/// Turn practice mode on or off. While on, imports skip the mixdown and sample rate
+/// conversion so quick experiments leave no files behind
+#[tauri::command]
+pub fn set_practice_mode(
+  state: State<'_, AppState>,
+  enabled: bool,
+) -> Result<(), String> {
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.practice_mode = enabled;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update practice mode: {}", e))?;
+
+  log::info!("Practice mode {}", if enabled { "enabled" } else { "disabled" });
+  Ok(())
+}
+
+/// Import individual drum kit pieces ("01 Kick.wav", "Song.Snare.wav") as "Drums" stems
+/// instead of naming each after its piece. Applies to later imports
+#[tauri::command]
+pub fn set_group_drum_kit_stems(
+  state: State<'_, AppState>,
+  enabled: bool,
+) -> Result<(), String> {
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.group_drum_kit_stems = enabled;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update drum kit grouping: {}", e))?;
+
+  log::info!("Drum kit grouping {}", if enabled { "enabled" } else { "disabled" });
+  Ok(())
+}
+
+/// Turn the master high-pass filter on or off and optionally move its cutoff (10 to 200 Hz,
+/// 30 Hz by default). Keeps DI and room rumble out of the subs; saved for the next launch
+#[tauri::command]
+pub fn set_master_highpass(
+  state: State<'_, AppState>,
+  enabled: bool,
+  cutoff_hz: Option<f64>,
+) -> Result<(), String> {
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  if let Some(cutoff) = cutoff_hz {
+    if !highpass_cutoff_in_range(cutoff) {
+      return Err(format!(
+        "Invalid high-pass cutoff: {} Hz, expected {} to {}", cutoff, MIN_HIGHPASS_CUTOFF_HZ, MAX_HIGHPASS_CUTOFF_HZ
+      ));
+    }
+    settings.master_highpass_hz = cutoff;
+  }
+  settings.master_highpass_enabled = enabled;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update master high-pass: {}", e))?;
+
+  lock_or_recover(&state.audio_engine, "audio engine")
+    .set_master_highpass(enabled, settings.master_highpass_hz as f32);
+
+  log::info!(
+    "Master high-pass {} at {:.0} Hz",
+    if enabled { "on" } else { "off" }, settings.master_highpass_hz
+  );
+  Ok(())
+}
+
+fn highpass_cutoff_in_range(cutoff_hz: f64) -> bool {
+  cutoff_hz.is_finite() && (MIN_HIGHPASS_CUTOFF_HZ as f64..=MAX_HIGHPASS_CUTOFF_HZ as f64).contains(&cutoff_hz)
+}
+
+/// Choose what loading a song with more stems than the engine holds does: "error" refuses it
+/// before decoding, "merge" pre-mixes the extra stems into one slot. Applies to later loads.
+/// The merged slot has no stem record, so the UI can't control it: its stems play at their saved
+/// volume and mute, and stems with their own pan, trim, polarity, alignment, delay, gate or
+/// channel mode (and cue stems) are never merged. A song that can't be fitted that way is refused
+#[tauri::command]
+pub fn set_stem_overflow_policy(
+  state: State<'_, AppState>,
+  policy: String,
+) -> Result<(), String> {
+  let policy = parse_stem_overflow_policy(&policy)?;
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.stem_overflow_policy = policy;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update stem overflow policy: {}", e))?;
+
+  log::info!("Stem overflow policy set to {}", settings.stem_overflow_policy);
+  Ok(())
+}
+
+fn parse_stem_overflow_policy(policy: &str) -> Result<String, String> {
+  let policy = policy.to_lowercase();
+  if policy != STEM_OVERFLOW_ERROR && policy != STEM_OVERFLOW_MERGE {
+    return Err(format!("Invalid stem overflow policy '{}', expected 'error' or 'merge'", policy));
+  }
+  Ok(policy)
+}
+
+/// Change how much is logged ("error", "warn", "info", "debug" or "trace"), effective immediately
+#[tauri::command]
+pub fn set_log_level(
+  state: State<'_, AppState>,
+  level: String,
+) -> Result<(), String> {
+  let filter = crate::logging::parse_level(&level)
+    .ok_or_else(|| format!("Invalid log level '{}'", level))?;
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.log_level = filter.to_string().to_lowercase();
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update log level: {}", e))?;
+
+  crate::logging::set_level(filter);
+  log::info!("Log level set to: {}", settings.log_level);
+  Ok(())
+}
+
+/// Settings file written by export_settings. Unknown fields are ignored so files from
+/// newer versions still import
+#[derive(Serialize, Deserialize)]
+pub struct SettingsExport {
+  pub settings: AppSettings,
+}
+
+// Check imported values against the same rules the individual setters use,
+// normalising the case of the named options
+fn validate_settings(mut settings: AppSettings) -> Result<AppSettings, String> {
+  if settings.audio_buffer_size <= 0 {
+    return Err(format!("Invalid buffer size: {}", settings.audio_buffer_size));
+  }
+  if !(8000..=384000).contains(&settings.sample_rate) {
+    return Err(format!("Invalid sample rate: {}", settings.sample_rate));
+  }
+  settings.cue_pan_side = settings.cue_pan_side.to_lowercase();
+  if settings.cue_pan_side != "left" && settings.cue_pan_side != "right" {
+    return Err(format!("Invalid cue pan side '{}', expected 'left' or 'right'", settings.cue_pan_side));
+  }
+  settings.solo_mode = settings.solo_mode.to_lowercase();
+  if settings.solo_mode != "additive" && settings.solo_mode != "exclusive" {
+    return Err(format!("Invalid solo mode '{}', expected 'additive' or 'exclusive'", settings.solo_mode));
+  }
+  settings.fade_curve = settings.fade_curve.to_lowercase();
+  if FadeCurve::from_name(&settings.fade_curve).is_none() {
+    return Err(format!("Invalid fade curve '{}', expected 'linear' or 'equal_power'", settings.fade_curve));
+  }
+  if settings.max_decode_threads < 0 {
+    return Err(format!("Invalid decode thread count: {}", settings.max_decode_threads));
+  }
+  if settings.import_sample_rate != 0 && !(8000..=384000).contains(&settings.import_sample_rate) {
+    return Err(format!("Invalid import sample rate: {}", settings.import_sample_rate));
+  }
+  if !settings.in_memory_cache_gb.is_finite() || settings.in_memory_cache_gb <= 0.0 {
+    return Err(format!("Invalid cache size: {} GB", settings.in_memory_cache_gb));
+  }
+  settings.log_level = crate::logging::parse_level(&settings.log_level)
+    .ok_or_else(|| format!("Invalid log level '{}'", settings.log_level))?
+    .to_string()
+    .to_lowercase();
+  if !(1..=StemCapacity::MAX as i32).contains(&settings.stem_capacity) {
+    return Err(format!("Invalid stem capacity: {}", settings.stem_capacity));
+  }
+  if !settings.auto_advance_gap_seconds.is_finite() || settings.auto_advance_gap_seconds < 0.0 {
+    return Err(format!("Invalid auto-advance gap: {}", settings.auto_advance_gap_seconds));
+  }
+  if !LOUDNESS_TARGET_RANGE.contains(&settings.loudness_target_lufs) {
+    return Err(format!("Invalid loudness target: {} LUFS", settings.loudness_target_lufs));
+  }
+  if !highpass_cutoff_in_range(settings.master_highpass_hz) {
+    return Err(format!("Invalid high-pass cutoff: {} Hz", settings.master_highpass_hz));
+  }
+  settings.stem_overflow_policy = parse_stem_overflow_policy(&settings.stem_overflow_policy)?;
+  if !settings.disk_cache_gb.is_finite() || settings.disk_cache_gb <= 0.0 {
+    return Err(format!("Invalid disk cache size: {} GB", settings.disk_cache_gb));
+  }
+
+  Ok(settings)
+}
+
+// Write the stored settings to `path` as pretty-printed JSON
+pub(crate) fn write_settings_file(db: &Database, path: &str) -> Result<(), String> {
+  let settings = db.get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  let json = serde_json::to_string_pretty(&SettingsExport { settings })
+    .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+  std::fs::write(path, json)
+    .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+// Read, validate and store a settings file. Nothing is saved if any value is invalid
+pub(crate) fn read_settings_file(db: &Database, path: &str) -> Result<AppSettings, String> {
+  let json = std::fs::read_to_string(path)
+    .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+  let export: SettingsExport = serde_json::from_str(&json)
+    .map_err(|e| format!("Invalid settings file: {}", e))?;
+  let settings = validate_settings(export.settings)?;
+
+  db.update_settings(&settings)
+    .map_err(|e| format!("Failed to update settings: {}", e))?;
+
+  Ok(settings)
+}
+
+/// Save all app settings to a JSON file, e.g. to carry them to another machine
+#[tauri::command]
+pub fn export_settings(
+  state: State<'_, AppState>,
+  path: String,
+) -> Result<(), String> {
+  write_settings_file(&state.database, &path)?;
+
+  log::info!("Exported settings to {}", path);
+  Ok(())
+}
+
+/// Load settings saved by export_settings and return them. The fade curve, master high-pass,
+/// loudness target, log level, decode threads and cache size apply immediately; device, buffer
+/// size and host on next start
+#[tauri::command]
+pub fn import_settings(
+  state: State<'_, AppState>,
+  path: String,
+) -> Result<AppSettings, String> {
+  let settings = read_settings_file(&state.database, &path)?;
+  apply_imported_settings(&state, &settings);
+
+  log::info!("Imported settings from {}", path);
+  Ok(settings)
+}
+
+// Put the imported settings that don't need a restart into effect
+pub(crate) fn apply_imported_settings(state: &AppState, settings: &AppSettings) {
+  let trim_db = current_song_trim_db(state, settings);
+  {
+    let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+    if let Some(curve) = FadeCurve::from_name(&settings.fade_curve) {
+      engine.set_fade_curve(curve);
+    }
+    engine.set_master_highpass(settings.master_highpass_enabled, settings.master_highpass_hz as f32);
+    engine.set_master_trim_db(trim_db as f32);
+  }
+  if let Some(level) = crate::logging::parse_level(&settings.log_level) {
+    crate::logging::set_level(level);
+  }
+  state.set_max_decode_threads(settings.max_decode_threads as usize);
+  lock_or_recover(&state.song_cache, "song cache")
+    .set_max_size(cache_size_bytes_from_gb(settings.in_memory_cache_gb));
+  state.disk_cache.set_max_bytes(cache_size_bytes_from_gb(settings.disk_cache_gb) as u64);
+}
+
+/// Play a one second 440 Hz tone on a device to confirm it outputs sound.
+/// Refused during playback so it can't cut into a song
+#[tauri::command]
+pub async fn test_audio_device(
   state: State<'_, AppState>,
   device_name: String,
 ) -> Result<(), String> {
+  let host_name = {
+    let engine = lock_or_recover(&state.audio_engine, "audio engine");
+    if engine.state() == PlaybackState::Playing {
+      return Err("Stop playback before testing a device".to_string());
+    }
+    engine.audio_host_name()
+  };
+
+  log::info!("Playing test tone on {} ({})", device_name, host_name);
+  // The tone blocks for its whole second; keep it off the async runtime's workers
+  let tone_device = device_name.clone();
+  tokio::task::spawn_blocking(move || crate::audio::tone::play_test_tone(Some(&host_name), &tone_device))
+    .await
+    .map_err(|e| format!("Test tone failed on '{}': {}", device_name, e))?
+    .map_err(|e| format!("Test tone failed on '{}': {}", device_name, e))
+}
+
+/// Sample rate, buffer size and channel count the output stream is actually running with,
+/// for diagnosing latency and dropouts
+#[tauri::command]
+pub fn get_active_stream_config(state: State<'_, AppState>) -> Result<ActiveStreamConfig, String> {
+  lock_or_recover(&state.audio_engine, "audio engine")
+    .active_stream_config()
+    .ok_or_else(|| "No audio stream is running".to_string())
+}
+
+/// Save how many stems the engine can hold (1-256). Takes effect the next time the app starts
+#[tauri::command]
+pub fn set_stem_capacity(
+  state: State<'_, AppState>,
+  capacity: i32,
+) -> Result<(), String> {
+  if !(1..=StemCapacity::MAX as i32).contains(&capacity) {
+    return Err(format!("Invalid stem capacity: {}, expected 1-{}", capacity, StemCapacity::MAX));
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.stem_capacity = capacity;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update stem capacity: {}", e))?;
+
+  log::info!("Stem capacity set to {} (applies after restart)", capacity);
+  Ok(())
+}
+
+/// Turn loudness matching on or off and optionally change its target (-40 to 0 LUFS). Every song
+/// then plays with a master trim that brings its measured loudness to the target. The loaded
+/// song is re-trimmed straight away
+#[tauri::command]
+pub fn set_loudness_target(
+  state: State<'_, AppState>,
+  enabled: bool,
+  target_lufs: Option<f64>,
+) -> Result<(), String> {
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  if let Some(target) = target_lufs {
+    if !LOUDNESS_TARGET_RANGE.contains(&target) {
+      return Err(format!("Invalid loudness target: {} LUFS, expected -40 to 0", target));
+    }
+    settings.loudness_target_lufs = target;
+  }
+  settings.loudness_target_enabled = enabled;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update loudness target: {}", e))?;
+
+  let trim_db = current_song_trim_db(&state, &settings);
+  lock_or_recover(&state.audio_engine, "audio engine").set_master_trim_db(trim_db as f32);
+
+  log::info!(
+    "Loudness target {} at {:.1} LUFS (current song trimmed {:+.1} dB)",
+    if enabled { "on" } else { "off" }, settings.loudness_target_lufs, trim_db
+  );
+  Ok(())
+}
+
+// Master trim the loudness target gives the loaded song
+fn current_song_trim_db(state: &AppState, settings: &AppSettings) -> f64 {
+  let current_song_id = lock_or_recover(&state.current_song_id, "current song").clone();
+  let loudness_lufs = current_song_id
+    .and_then(|song_id| state.database.get_song(&song_id).ok())
+    .and_then(|song| song.loudness_lufs);
+  super::loudness_target_trim_db(settings, loudness_lufs)
+}
+
+/// Save the silence left between songs when a setlist auto-advances, in seconds
+#[tauri::command]
+pub fn set_auto_advance_gap(
+  state: State<'_, AppState>,
+  seconds: f64,
+) -> Result<(), String> {
+  if !seconds.is_finite() || seconds < 0.0 {
+    return Err(format!("Invalid auto-advance gap: {}", seconds));
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.auto_advance_gap_seconds = seconds;
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update auto-advance gap: {}", e))?;
+
+  log::info!("Auto-advance gap set to {:.1}s", seconds);
+  Ok(())
+}
+
+/// How many stems the running engine can hold
+#[tauri::command]
+pub fn get_max_stems(state: State<'_, AppState>) -> Result<usize, String> {
+  Ok(lock_or_recover(&state.audio_engine, "audio engine").max_stems())
+}
+
+/// The most recent `lines` log lines, oldest first, for copying into bug reports
+#[tauri::command]
+pub fn get_recent_logs(lines: usize) -> Result<Vec<String>, String> {
+  Ok(crate::logging::recent_logs(lines))
+}
+
+/// Move output to another device and remember the choice. Returns the new project sample rate
+/// when the device can't run at the current one; songs decoded at the old rate decode again
+/// the next time they play
+#[tauri::command]
+pub fn switch_audio_device(
+  state: State<'_, AppState>,
+  device_name: String,
+) -> Result<Option<u32>, String> {
+  apply_audio_device(&state, &device_name)
+}
+
+pub(crate) fn apply_audio_device(state: &AppState, device_name: &str) -> Result<Option<u32>, String> {
   // First save the setting to database
   let mut settings = state.database
     .get_settings()
     .map_err(|e| format!("Failed to get settings: {}", e))?;
 
-  settings.audio_output_device = Some(device_name.clone());
+  settings.audio_output_device = Some(device_name.to_string());
 
   state.database
     .update_settings(&settings)
     .map_err(|e| format!("Failed to update audio device: {}", e))?;
 
   // Then switch the audio engine to the new device
-  let mut engine = state.audio_engine.lock().unwrap();
-  engine.switch_audio_device(&device_name)
+  let rate_changed = lock_or_recover(&state.audio_engine, "audio engine")
+    .switch_audio_device(device_name)
     .map_err(|e| format!("Failed to switch audio device: {}", e))?;
+  if let Some(sample_rate) = rate_changed {
+    follow_device_sample_rate(state, sample_rate)?;
+  }
 
   log::info!("Audio output device switched to: {}", device_name);
-  Ok(())
+  Ok(rate_changed)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudioHost {
+  pub id: String,
+  pub is_default: bool,
+}
+
+/// List the audio host APIs available on this platform (e.g. WASAPI and ASIO on Windows)
+#[tauri::command]
+pub fn list_audio_hosts() -> Result<Vec<AudioHost>, String> {
+  Ok(crate::audio::available_audio_hosts()
+    .into_iter()
+    .enumerate()
+    .map(|(index, id)| AudioHost { id, is_default: index == 0 })
+    .collect())
+}
+
+/// Move output to the default device of another host API and remember the choice.
+/// Returns the device now in use
+#[tauri::command]
+pub fn set_audio_host(
+  state: State<'_, AppState>,
+  host_id: String,
+) -> Result<Option<String>, String> {
+  let (device_name, previous_rate, sample_rate) = {
+    let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+    let previous_rate = engine.project_sample_rate();
+    let device_name = engine.set_audio_host(&host_id)
+      .map_err(|e| format!("Failed to switch audio host: {}", e))?;
+    (device_name, previous_rate, engine.project_sample_rate())
+  };
+  if sample_rate != previous_rate {
+    follow_device_sample_rate(&state, sample_rate)?;
+  }
+
+  let mut settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  settings.audio_host = Some(host_id.clone());
+  settings.audio_output_device = device_name.clone();
+
+  state.database
+    .update_settings(&settings)
+    .map_err(|e| format!("Failed to update audio host: {}", e))?;
+
+  log::info!("Audio host set to: {}", host_id);
+  Ok(device_name)
 }
 
 /// Get the current audio output device name
 #[tauri::command]
 pub fn get_current_audio_device(state: State<'_, AppState>) -> Result<Option<String>, String> {
-  let engine = state.audio_engine.lock()
-    .map_err(|_| "Failed to lock audio engine".to_string())?;
+  let engine = lock_or_recover(&state.audio_engine, "audio engine");
 
   Ok(engine.current_device_name())
 }
+
+/// Get the number of output channels supported by the current audio device
+#[tauri::command]
+pub fn get_output_channel_count(state: State<'_, AppState>) -> Result<usize, String> {
+  let engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  Ok(engine.device_channel_count())
+}
+
+/// Rebuild the output stream with the given number of channels for multi-out routing
+#[tauri::command]
+pub fn set_output_channels(
+  state: State<'_, AppState>,
+  channels: usize,
+) -> Result<(), String> {
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine.set_output_channels(channels)
+    .map_err(|e| format!("Failed to set output channels: {}", e))?;
+
+  log::info!("Output channel count set to: {}", channels);
+  Ok(())
+}
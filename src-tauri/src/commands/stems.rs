@@ -1,6 +1,19 @@
-use super::AppState;
+use super::playback::decode_at_rate;
+use super::{lock_or_recover, AppState};
+use crate::audio::waveform::{compute_peaks, MAX_WAVEFORM_BUCKETS};
+use crate::audio::MeterMode;
+use crate::database::{AutomationPoint, MixerSnapshot, Stem, StemMix, StemWaveform};
+use std::collections::HashMap;
+use std::sync::Arc;
 use tauri::State;
 
+// Input trims accepted, in dB
+const STEM_TRIM_RANGE: std::ops::RangeInclusive<f64> = -48.0..=24.0;
+// Longest manual delay either way, in seconds
+const MAX_STEM_DELAY_SECONDS: f64 = 10.0;
+// Where normalize_stem_peak puts a stem's peak unless told otherwise, in dBFS
+const DEFAULT_PEAK_TARGET_DBFS: f64 = -1.0;
+
 /// Set the volume for a specific stem (0.0 to 1.0)
 #[tauri::command]
 pub async fn set_stem_volume(
@@ -14,18 +27,14 @@ pub async fn set_stem_volume(
   let clamped_volume = volume.clamp(0.0, 1.0);
 
   // Get the engine stem index from the database stem ID
-  let stem_map = state.stem_id_map
-    .lock()
-    .map_err(|_| "Failed to lock stem ID map")?;
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
 
   let stem_index = stem_map
     .get(&stem_id)
     .ok_or_else(|| format!("Stem not found in audio engine: {}", stem_id))?;
 
   // Update the audio engine
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
 
   engine.set_stem_volume(*stem_index, clamped_volume as f32);
 
@@ -43,6 +52,71 @@ pub async fn set_stem_volume(
   Ok(())
 }
 
+/// Set the volume for several stems at once (0.0 to 1.0), e.g. when recalling a mixer scene
+#[tauri::command]
+pub async fn set_stem_volumes(
+  updates: Vec<(String, f64)>,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting volume for {} stems", updates.len());
+  apply_stem_volumes(&state, &updates)
+}
+
+/// Save the song's current stem volumes, mutes and pans as a named snapshot
+#[tauri::command]
+pub async fn save_mixer_snapshot(
+  song_id: String,
+  name: String,
+  state: State<'_, AppState>
+) -> Result<MixerSnapshot, String> {
+  log::info!("Saving mixer snapshot '{}' for song {}", name, song_id);
+  capture_mixer_snapshot(&state, &song_id, &name)
+}
+
+/// List the saved mixer snapshots for a song
+#[tauri::command]
+pub async fn list_mixer_snapshots(
+  song_id: String,
+  state: State<'_, AppState>
+) -> Result<Vec<MixerSnapshot>, String> {
+  state.database
+    .list_mixer_snapshots(&song_id)
+    .map_err(|e| format!("Failed to list mixer snapshots: {}", e))
+}
+
+/// Restore a saved mixer snapshot to the engine and database
+#[tauri::command]
+pub async fn recall_mixer_snapshot(
+  snapshot_id: String,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::info!("Recalling mixer snapshot {}", snapshot_id);
+  apply_mixer_snapshot(&state, &snapshot_id)
+}
+
+/// Restore every stem of a song to the volume and mute it had when imported
+#[tauri::command]
+pub async fn reset_mix_to_default(
+  song_id: String,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::info!("Resetting mix to default for song {}", song_id);
+  apply_default_mix(&state, &song_id)
+}
+
+/// Delete a saved mixer snapshot
+#[tauri::command]
+pub async fn delete_mixer_snapshot(
+  snapshot_id: String,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::info!("Deleting mixer snapshot {}", snapshot_id);
+
+  state.database
+    .delete_mixer_snapshot(&snapshot_id)
+    .map_err(|e| format!("Failed to delete mixer snapshot: {}", e))
+}
+
 /// Toggle mute state for a specific stem
 #[tauri::command]
 pub async fn toggle_stem_mute(
@@ -60,18 +134,14 @@ pub async fn toggle_stem_mute(
   stem.is_muted = !stem.is_muted;
 
   // Get the engine stem index
-  let stem_map = state.stem_id_map
-    .lock()
-    .map_err(|_| "Failed to lock stem ID map")?;
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
 
   let stem_index = stem_map
     .get(&stem_id)
     .ok_or_else(|| format!("Stem not found in audio engine: {}", stem_id))?;
 
   // Update the audio engine
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
 
   engine.set_stem_mute(*stem_index, stem.is_muted);
 
@@ -90,24 +160,755 @@ pub async fn toggle_stem_solo(
   state: State<'_, AppState>
 ) -> Result<bool, String> {
   log::debug!("Toggling solo for stem {}", stem_id);
+  apply_stem_solo(&state, &stem_id)
+}
+
+/// Mark a stem as solo-safe so it keeps playing when other stems are soloed
+#[tauri::command]
+pub async fn set_stem_solo_safe(
+  stem_id: String,
+  solo_safe: bool,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting stem {} solo-safe to {}", stem_id, solo_safe);
+
+  // Update the audio engine if the stem is currently loaded
+  {
+    let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+    if let Some(stem_index) = stem_map.get(&stem_id) {
+      let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+      engine.set_stem_solo_safe(*stem_index, solo_safe);
+    }
+  }
+
+  let mut stem = state.database
+    .get_stem(&stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  stem.solo_safe = solo_safe;
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(())
+}
+
+/// Invert a stem's polarity, e.g. when it was recorded out of phase with another stem
+#[tauri::command]
+pub async fn set_stem_phase_invert(
+  stem_id: String,
+  inverted: bool,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting stem {} phase invert to {}", stem_id, inverted);
+
+  // Update the audio engine if the stem is currently loaded
+  {
+    let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+    if let Some(stem_index) = stem_map.get(&stem_id) {
+      let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+      engine.set_stem_phase_invert(*stem_index, inverted);
+    }
+  }
+
+  let mut stem = state.database
+    .get_stem(&stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  stem.phase_inverted = inverted;
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(())
+}
+
+/// Set a stem's input trim in dB (applied before the fader)
+#[tauri::command]
+pub async fn set_stem_trim(
+  stem_id: String,
+  trim_db: f64,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  apply_stem_trim(&state, &stem_id, trim_db)
+}
+
+pub(crate) fn apply_stem_trim(state: &AppState, stem_id: &str, trim_db: f64) -> Result<(), String> {
+  if !trim_db.is_finite() || !STEM_TRIM_RANGE.contains(&trim_db) {
+    return Err(format!("Invalid trim: {} dB", trim_db));
+  }
+
+  log::debug!("Setting stem {} trim to {} dB", stem_id, trim_db);
+
+  // Update the audio engine if the stem is currently loaded
+  {
+    let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+    if let Some(stem_index) = stem_map.get(stem_id) {
+      let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+      engine.set_stem_trim(*stem_index, trim_db as f32);
+    }
+  }
+
+  let mut stem = state.database
+    .get_stem(stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  stem.trim_db = trim_db;
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(())
+}
+
+/// Turn a stem's noise gate on or off. While on, the stem is silenced whenever its level
+/// stays below `threshold_db` (dBFS). `attack_ms` and `release_ms` set how fast it opens and
+/// closes; the stem's current times are kept when they're left out
+#[tauri::command]
+pub async fn set_stem_gate(
+  stem_id: String,
+  threshold_db: f64,
+  enabled: bool,
+  attack_ms: Option<f64>,
+  release_ms: Option<f64>,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  if !threshold_db.is_finite() || !(-96.0..=0.0).contains(&threshold_db) {
+    return Err(format!("Invalid gate threshold: {} dB", threshold_db));
+  }
+  if let Some(attack_ms) = attack_ms.filter(|ms| !ms.is_finite() || !(0.1..=100.0).contains(ms)) {
+    return Err(format!("Invalid gate attack: {} ms, expected 0.1 to 100", attack_ms));
+  }
+  if let Some(release_ms) = release_ms.filter(|ms| !ms.is_finite() || !(5.0..=2000.0).contains(ms)) {
+    return Err(format!("Invalid gate release: {} ms, expected 5 to 2000", release_ms));
+  }
+
+  let mut stem = state.database
+    .get_stem(&stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  stem.gate_enabled = enabled;
+  stem.gate_threshold_db = threshold_db;
+  stem.gate_attack_ms = attack_ms.unwrap_or(stem.gate_attack_ms);
+  stem.gate_release_ms = release_ms.unwrap_or(stem.gate_release_ms);
+
+  log::debug!("Setting stem {} gate {} at {} dB", stem_id, if enabled { "on" } else { "off" }, threshold_db);
+
+  // Update the audio engine if the stem is currently loaded
+  {
+    let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+    if let Some(stem_index) = stem_map.get(&stem_id) {
+      let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+      engine.set_stem_gate(*stem_index, threshold_db as f32, enabled);
+      engine.set_stem_gate_times(*stem_index, (stem.gate_attack_ms / 1000.0) as f32, (stem.gate_release_ms / 1000.0) as f32);
+    }
+  }
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(())
+}
+
+/// Render a stem with its trim, polarity, channel mode and pan baked into a new file to save
+/// real-time processing. The previous file is kept as the stem's original
+#[tauri::command]
+pub async fn freeze_stem(
+  stem_id: String,
+  state: State<'_, AppState>
+) -> Result<Stem, String> {
+  log::info!("Freezing stem {}", stem_id);
+
+  let stem = crate::import::freeze_stem(&state.database, &stem_id)
+    .map_err(|e| format!("Failed to freeze stem: {}", e))?;
+
+  // The cached samples are the unprocessed file. A song already loaded in the engine keeps
+  // playing the old samples with their old processing, which sounds the same until reloaded
+  lock_or_recover(&state.song_cache, "song cache").remove(&stem.song_id);
+
+  Ok(stem)
+}
+
+/// Swap a stem's left/right channels and/or sum it to mono, e.g. for stems exported with the
+/// channels reversed or a mono wedge mix
+#[tauri::command]
+pub async fn set_stem_channel_mode(
+  stem_id: String,
+  swap: bool,
+  mono: bool,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting stem {} channel mode: swap={}, mono={}", stem_id, swap, mono);
+
+  // Update the audio engine if the stem is currently loaded
+  {
+    let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+    if let Some(stem_index) = stem_map.get(&stem_id) {
+      let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+      engine.set_stem_channel_mode(*stem_index, swap, mono);
+    }
+  }
+
+  let mut stem = state.database
+    .get_stem(&stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  stem.swap_channels = swap;
+  stem.mono_sum = mono;
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(())
+}
+
+/// Shift a stem later (positive) or earlier (negative) by `delay_samples` frames of its source
+/// file, e.g. to undo a fixed export latency. Finer-grained than the import alignment offset.
+/// Delays are held to 10 seconds either way; returns the delay that was saved
+#[tauri::command]
+pub async fn set_stem_delay(
+  stem_id: String,
+  delay_samples: i64,
+  state: State<'_, AppState>
+) -> Result<i64, String> {
+  apply_stem_delay(&state, &stem_id, delay_samples)
+}
+
+pub(crate) fn apply_stem_delay(state: &AppState, stem_id: &str, delay_samples: i64) -> Result<i64, String> {
+  let mut stem = state.database
+    .get_stem(stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  // Loaded samples were resampled to the project rate when decoded; the limit is 10 seconds of them
+  let project_rate = engine.project_sample_rate() as f64;
+  let source_rate = if stem.sample_rate > 0 { stem.sample_rate as f64 } else { project_rate };
+  let max_frames = (MAX_STEM_DELAY_SECONDS * project_rate) as i64;
+  let frames = ((delay_samples as f64 * project_rate / source_rate).round() as i64).clamp(-max_frames, max_frames);
+  let delay_samples = if frames.abs() < max_frames {
+    delay_samples
+  } else {
+    (frames as f64 * source_rate / project_rate).round() as i64
+  };
+  log::debug!("Setting stem {} delay to {} samples", stem_id, delay_samples);
+
+  // Update the audio engine if the stem is currently loaded
+  if let Some(stem_index) = stem_map.get(stem_id) {
+    engine.set_stem_delay(*stem_index, frames);
+  }
+  drop(engine);
+  drop(stem_map);
+
+  stem.delay_samples = delay_samples;
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(delay_samples)
+}
+
+/// Rename a stem. A name already used by another stem in the song gets a number appended;
+/// returns the name that was saved
+#[tauri::command]
+pub async fn rename_stem(
+  stem_id: String,
+  new_name: String,
+  state: State<'_, AppState>
+) -> Result<String, String> {
+  log::debug!("Renaming stem {} to '{}'", stem_id, new_name);
+  apply_stem_rename(&state, &stem_id, &new_name)
+}
+
+pub(crate) fn apply_stem_rename(state: &AppState, stem_id: &str, new_name: &str) -> Result<String, String> {
+  let new_name = new_name.trim();
+  if new_name.is_empty() {
+    return Err("Stem name cannot be empty".to_string());
+  }
+
+  let mut stem = state.database
+    .get_stem(stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  let taken: Vec<String> = state.database
+    .get_stems_for_song(&stem.song_id)
+    .map_err(|e| format!("Failed to get stems for song: {}", e))?
+    .into_iter()
+    .filter(|other| other.id != stem.id)
+    .map(|other| other.name)
+    .collect();
+
+  stem.name = crate::import::unique_stem_name(new_name, &taken);
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(stem.name)
+}
+
+/// Set the pan for a specific stem (-1.0 left to 1.0 right); overrides any default pan
+#[tauri::command]
+pub async fn set_stem_pan(
+  stem_id: String,
+  pan: f64,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting stem {} pan to {}", stem_id, pan);
+
+  let clamped_pan = pan.clamp(-1.0, 1.0);
+
+  // Update the audio engine if the stem is currently loaded
+  {
+    let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+    if let Some(stem_index) = stem_map.get(&stem_id) {
+      let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+      engine.set_stem_pan(*stem_index, clamped_pan as f32);
+    }
+  }
+
+  // Persist as a user override
+  let mut stem = state.database
+    .get_stem(&stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  stem.pan = Some(clamped_pan);
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(())
+}
+
+/// Route a stem to a stereo output bus (0 = outputs 1-2, 1 = outputs 3-4, ...)
+#[tauri::command]
+pub async fn set_stem_output(
+  stem_id: String,
+  bus: usize,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Routing stem {} to output bus {}", stem_id, bus);
 
   // Get the engine stem index
-  let stem_map = state.stem_id_map
-    .lock()
-    .map_err(|_| "Failed to lock stem ID map")?;
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+  let stem_index = stem_map
+    .get(&stem_id)
+    .ok_or_else(|| format!("Stem not found in audio engine: {}", stem_id))?;
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine
+    .set_stem_output(*stem_index, bus)
+    .map_err(|e| format!("Failed to set stem output: {}", e))
+}
+
+/// Automate a stem's volume along the song timeline. While a stem has automation its fader
+/// is ignored; an empty list removes the automation and returns it to manual control
+#[tauri::command]
+pub async fn set_stem_automation(
+  stem_id: String,
+  points: Vec<AutomationPoint>,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting {} automation points for stem {}", points.len(), stem_id);
+
+  if let Some(point) = points.iter().find(|p| {
+    !p.position_seconds.is_finite() || p.position_seconds < 0.0 || !(0.0..=1.0).contains(&p.volume)
+  }) {
+    return Err(format!(
+      "Invalid automation point at {}s (volume {}): positions must be >= 0 and volumes 0.0 to 1.0",
+      point.position_seconds, point.volume
+    ));
+  }
+
+  let mut points = points;
+  points.sort_by(|a, b| a.position_seconds.total_cmp(&b.position_seconds));
+
+  // Make sure the stem exists before storing anything for it
+  state.database
+    .get_stem(&stem_id)
+    .map_err(|e| format!("Failed to get stem: {}", e))?;
+
+  state.database
+    .set_stem_automation(&stem_id, &points)
+    .map_err(|e| format!("Failed to save automation: {}", e))?;
+
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+  if let Some(&stem_index) = stem_map.get(&stem_id) {
+    let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+    engine.set_stem_automation(stem_index, &automation_pairs(&points));
+  }
+
+  Ok(())
+}
+
+/// Get a stem's volume automation points (empty when the stem is under manual control)
+#[tauri::command]
+pub async fn get_stem_automation(
+  stem_id: String,
+  state: State<'_, AppState>
+) -> Result<Vec<AutomationPoint>, String> {
+  state.database
+    .get_stem_automation(&stem_id)
+    .map_err(|e| format!("Failed to get automation: {}", e))
+}
+
+// Automation points as the engine's (position_seconds, volume) pairs
+pub(crate) fn automation_pairs(points: &[AutomationPoint]) -> Vec<(f64, f32)> {
+  points.iter().map(|p| (p.position_seconds, p.volume as f32)).collect()
+}
+
+/// Pre-listen a stem on the cue output at unity, without changing the main mix
+#[tauri::command]
+pub async fn cue_stem(
+  stem_id: String,
+  enabled: bool,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting cue for stem {} to {}", stem_id, enabled);
+
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
 
   let stem_index = stem_map
     .get(&stem_id)
     .ok_or_else(|| format!("Stem not found in audio engine: {}", stem_id))?;
 
-  // Get current solo state and toggle it
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  if enabled && engine.cue_output().is_none() {
+    return Err("No cue output selected".to_string());
+  }
+
+  engine.set_stem_cue(*stem_index, enabled);
+  Ok(())
+}
+
+/// Choose the stereo output bus used for cueing (1 = outputs 3-4, ...), or None to turn it off
+#[tauri::command]
+pub async fn set_cue_output(
+  bus: Option<usize>,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::info!("Setting cue output to {:?}", bus);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine
+    .set_cue_output(bus)
+    .map_err(|e| format!("Failed to set cue output: {}", e))
+}
+
+/// Assign a stem to a mute/solo group (None removes it from its group)
+#[tauri::command]
+pub async fn set_stem_group(
+  stem_id: String,
+  group: Option<String>,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting group for stem {} to {:?}", stem_id, group);
+
+  let mut stem = state.database
+    .get_stem(&stem_id)
+    .map_err(|e| format!("Failed to get stem from database: {}", e))?;
+
+  // Treat blank group names as ungrouped
+  stem.group = group
+    .map(|g| g.trim().to_string())
+    .filter(|g| !g.is_empty());
+
+  state.database
+    .update_stem(&stem)
+    .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+
+  Ok(())
+}
+
+/// Toggle mute for every stem in a group; returns the new mute state
+#[tauri::command]
+pub async fn toggle_group_mute(
+  song_id: String,
+  group: String,
+  state: State<'_, AppState>
+) -> Result<bool, String> {
+  log::debug!("Toggling mute for group '{}' in song {}", group, song_id);
+  apply_group_mute(&state, &song_id, &group)
+}
+
+/// Toggle solo for every stem in a group; returns the new solo state
+#[tauri::command]
+pub async fn toggle_group_solo(
+  song_id: String,
+  group: String,
+  state: State<'_, AppState>
+) -> Result<bool, String> {
+  log::debug!("Toggling solo for group '{}' in song {}", group, song_id);
+  apply_group_solo(&state, &song_id, &group)
+}
+
+// Get the stems of a song that belong to the given group
+fn group_stems(state: &AppState, song_id: &str, group: &str) -> Result<Vec<crate::database::Stem>, String> {
+  let stems: Vec<_> = state.database
+    .get_stems_for_song(song_id)
+    .map_err(|e| format!("Failed to get stems for song: {}", e))?
+    .into_iter()
+    .filter(|stem| stem.group.as_deref() == Some(group))
+    .collect();
+
+  if stems.is_empty() {
+    return Err(format!("No stems in group '{}'", group));
+  }
+
+  Ok(stems)
+}
+
+// Mute the whole group unless every stem in it is already muted, in which case unmute
+pub(crate) fn apply_group_mute(state: &AppState, song_id: &str, group: &str) -> Result<bool, String> {
+  let stems = group_stems(state, song_id, group)?;
+  let new_muted = !stems.iter().all(|stem| stem.is_muted);
+
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  for mut stem in stems {
+    // Stems that aren't loaded in the engine still get their persisted state updated
+    if let Some(stem_index) = stem_map.get(&stem.id) {
+      engine.set_stem_mute(*stem_index, new_muted);
+    }
+
+    stem.is_muted = new_muted;
+    state.database
+      .update_stem(&stem)
+      .map_err(|e| format!("Failed to update stem in database: {}", e))?;
+  }
+
+  Ok(new_muted)
+}
+
+// Snapshot the persisted stem settings (commands keep them in sync with the engine)
+pub(crate) fn capture_mixer_snapshot(state: &AppState, song_id: &str, name: &str) -> Result<MixerSnapshot, String> {
+  let name = name.trim();
+  if name.is_empty() {
+    return Err("Snapshot name is required".to_string());
+  }
+
+  let stems = state.database
+    .get_stems_for_song(song_id)
+    .map_err(|e| format!("Failed to get stems for song: {}", e))?;
+
+  if stems.is_empty() {
+    return Err("Song has no stems".to_string());
+  }
+
+  let snapshot = MixerSnapshot {
+    id: uuid::Uuid::new_v4().to_string(),
+    song_id: song_id.to_string(),
+    name: name.to_string(),
+    stems: stems
+      .into_iter()
+      .map(|stem| (stem.id, StemMix {
+        volume: stem.volume,
+        is_muted: stem.is_muted,
+        pan: stem.pan,
+        trim_db: stem.trim_db,
+      }))
+      .collect(),
+    created_at: chrono::Utc::now().timestamp(),
+  };
+
+  state.database
+    .create_mixer_snapshot(&snapshot)
+    .map_err(|e| format!("Failed to save mixer snapshot: {}", e))?;
+
+  Ok(snapshot)
+}
+
+// Write a snapshot's settings to the database in one transaction, then to any loaded stems
+// Stems added to the song after the snapshot was taken are left as they are
+pub(crate) fn apply_mixer_snapshot(state: &AppState, snapshot_id: &str) -> Result<(), String> {
+  let snapshot = state.database
+    .get_mixer_snapshot(snapshot_id)
+    .map_err(|e| format!("Failed to get mixer snapshot: {}", e))?;
+
+  let settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+  let stems: Vec<crate::database::Stem> = state.database
+    .get_stems_for_song(&snapshot.song_id)
+    .map_err(|e| format!("Failed to get stems for song: {}", e))?
+    .into_iter()
+    .filter_map(|mut stem| {
+      let mix = snapshot.stems.get(&stem.id)?;
+      stem.volume = mix.volume;
+      stem.is_muted = mix.is_muted;
+      stem.pan = mix.pan;
+      stem.trim_db = mix.trim_db;
+      Some(stem)
+    })
+    .collect();
+
+  state.database
+    .update_stems(&stems)
+    .map_err(|e| format!("Failed to update stems in database: {}", e))?;
+
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  for stem in &stems {
+    if let Some(&stem_index) = stem_map.get(&stem.id) {
+      engine.set_stem_volume(stem_index, stem.volume as f32);
+      engine.set_stem_mute(stem_index, stem.is_muted);
+      engine.set_stem_pan(stem_index, stem.effective_pan(&settings) as f32);
+      engine.set_stem_trim(stem_index, stem.trim_db as f32);
+    }
+  }
+
+  Ok(())
+}
+
+// Restore each stem's default volume and mute in the database, then in the engine for loaded stems
+pub(crate) fn apply_default_mix(state: &AppState, song_id: &str) -> Result<(), String> {
+  let stems: Vec<crate::database::Stem> = state.database
+    .get_stems_for_song(song_id)
+    .map_err(|e| format!("Failed to get stems for song: {}", e))?
+    .into_iter()
+    .map(|mut stem| {
+      stem.volume = stem.default_volume;
+      stem.is_muted = stem.default_mute;
+      stem
+    })
+    .collect();
+
+  state.database
+    .update_stems(&stems)
+    .map_err(|e| format!("Failed to update stems in database: {}", e))?;
+
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  for stem in &stems {
+    if let Some(&stem_index) = stem_map.get(&stem.id) {
+      engine.set_stem_volume(stem_index, stem.volume as f32);
+      engine.set_stem_mute(stem_index, stem.is_muted);
+    }
+  }
+
+  Ok(())
+}
+
+// Apply a batch of volumes under a single engine lock and persist them in one transaction
+pub(crate) fn apply_stem_volumes(state: &AppState, updates: &[(String, f64)]) -> Result<(), String> {
+  let clamped: Vec<(String, f64)> = updates
+    .iter()
+    .map(|(stem_id, volume)| (stem_id.clone(), volume.clamp(0.0, 1.0)))
+    .collect();
+
+  // Persist first so a bad stem id leaves both the database and the engine untouched
+  state.database
+    .update_stem_volumes(&clamped)
+    .map_err(|e| format!("Failed to update stem volumes in database: {}", e))?;
+
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
 
-  let current_solo = engine.is_stem_soloed(*stem_index);
-  let new_solo = !current_solo;
-  engine.set_stem_solo(*stem_index, new_solo);
+  // Stems that aren't loaded in the engine only get their persisted volume updated
+  let engine_updates: Vec<(usize, f32)> = clamped
+    .iter()
+    .filter_map(|(stem_id, volume)| stem_map.get(stem_id).map(|&index| (index, *volume as f32)))
+    .collect();
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine.set_stem_volumes(&engine_updates);
+
+  Ok(())
+}
+
+// Toggle a stem's solo; in exclusive mode soloing it releases every other solo
+pub(crate) fn apply_stem_solo(state: &AppState, stem_id: &str) -> Result<bool, String> {
+  let releases_others = solo_releases_others(state)?;
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+  let stem_index = *stem_map
+    .get(stem_id)
+    .ok_or_else(|| format!("Stem not found in audio engine: {}", stem_id))?;
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  let new_solo = !engine.is_stem_soloed(stem_index);
+  if new_solo && releases_others {
+    unsolo_all_except(&mut engine, &[stem_index]);
+  }
+  engine.set_stem_solo(stem_index, new_solo);
+
+  // Note: Solo state is not persisted in database (it's ephemeral)
+  Ok(new_solo)
+}
+
+// Whether a new solo releases the ones already held ("exclusive") rather than adding to them
+fn solo_releases_others(state: &AppState) -> Result<bool, String> {
+  let settings = state.database
+    .get_settings()
+    .map_err(|e| format!("Failed to get settings: {}", e))?;
+  Ok(settings.solo_mode == "exclusive")
+}
+
+// Unsolo every stem except the given engine indices
+fn unsolo_all_except(engine: &mut crate::audio::MultiTrackEngine, keep: &[usize]) {
+  for index in 0..engine.max_stems() {
+    if !keep.contains(&index) {
+      engine.set_stem_solo(index, false);
+    }
+  }
+}
+
+// Solo the whole group unless every loaded stem in it is already soloed, in which case unsolo
+pub(crate) fn apply_group_solo(state: &AppState, song_id: &str, group: &str) -> Result<bool, String> {
+  let stems = group_stems(state, song_id, group)?;
+
+  let stem_map = lock_or_recover(&state.stem_id_map, "stem ID map");
+
+  let indices: Vec<usize> = stems
+    .iter()
+    .filter_map(|stem| stem_map.get(&stem.id).copied())
+    .collect();
+
+  if indices.is_empty() {
+    return Err(format!("No stems in group '{}' are loaded in the audio engine", group));
+  }
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  let new_solo = !indices.iter().all(|&index| engine.is_stem_soloed(index));
+  if new_solo && solo_releases_others(state)? {
+    unsolo_all_except(&mut engine, &indices);
+  }
+  for index in indices {
+    engine.set_stem_solo(index, new_solo);
+  }
 
   // Note: Solo state is not persisted in database (it's ephemeral)
 
@@ -126,21 +927,235 @@ pub async fn set_master_volume(
   let clamped_volume = volume.clamp(0.0, 1.0);
 
   // Update the audio engine
-  let mut engine = state.audio_engine
-    .lock()
-    .map_err(|_| "Failed to lock audio engine")?;
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
 
   engine.set_master_volume(clamped_volume as f32);
 
   Ok(())
 }
 
-/// Get all stems for the currently loaded song
+/// Enable or disable the master limiter
+#[tauri::command]
+pub async fn set_limiter_enabled(
+  enabled: bool,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting master limiter enabled: {}", enabled);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine.set_limiter_enabled(enabled);
+
+  Ok(())
+}
+
+/// Set the master limiter threshold in dBFS (-20.0 to 0.0)
+#[tauri::command]
+pub async fn set_limiter_threshold_db(
+  threshold_db: f64,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  log::debug!("Setting master limiter threshold to {} dB", threshold_db);
+
+  let mut engine = lock_or_recover(&state.audio_engine, "audio engine");
+
+  engine.set_limiter_threshold_db(threshold_db as f32);
+
+  Ok(())
+}
+
+/// Choose what the level meters show: "peak", "rms" or "vu_slow"
+#[tauri::command]
+pub async fn set_meter_mode(
+  mode: String,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  let meter_mode = MeterMode::from_name(&mode.to_lowercase())
+    .ok_or_else(|| format!("Invalid meter mode '{}', expected 'peak', 'rms' or 'vu_slow'", mode))?;
+
+  log::debug!("Setting meter mode to {}", mode);
+
+  lock_or_recover(&state.audio_engine, "audio engine").set_meter_mode(meter_mode);
+
+  Ok(())
+}
+
+/// Set how fast the meters fall after a peak, in dB per second
+#[tauri::command]
+pub async fn set_meter_decay(
+  db_per_sec: f64,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  if !db_per_sec.is_finite() || db_per_sec <= 0.0 {
+    return Err(format!("Invalid meter decay: {} dB/s", db_per_sec));
+  }
+
+  log::debug!("Setting meter decay to {} dB/s", db_per_sec);
+
+  lock_or_recover(&state.audio_engine, "audio engine").set_meter_decay(db_per_sec as f32);
+
+  Ok(())
+}
+
+/// Hold each meter's peak for `seconds` before it falls (0 turns peak hold off)
+#[tauri::command]
+pub async fn set_meter_peak_hold(
+  seconds: f64,
+  state: State<'_, AppState>
+) -> Result<(), String> {
+  if !seconds.is_finite() || !(0.0..=10.0).contains(&seconds) {
+    return Err(format!("Invalid peak hold time: {} s", seconds));
+  }
+
+  log::debug!("Setting meter peak hold to {} s", seconds);
+
+  lock_or_recover(&state.audio_engine, "audio engine").set_meter_peak_hold(seconds as f32);
+
+  Ok(())
+}
+
+/// Get all stems for the currently loaded song (empty when no song is loaded)
 #[tauri::command]
 pub async fn get_current_stems(
   state: State<'_, AppState>
 ) -> Result<Vec<crate::database::Stem>, String> {
-  // This would need to track the current song ID in app state
-  // For now, return an empty list
-  Ok(Vec::new())
+  current_stems(&state)
+}
+
+pub(crate) fn current_stems(state: &AppState) -> Result<Vec<Stem>, String> {
+  let Some(song_id) = lock_or_recover(&state.current_song_id, "current song").clone() else {
+    return Ok(Vec::new());
+  };
+
+  state.database
+    .get_stems_for_song(&song_id)
+    .map_err(|e| format!("Failed to get stems: {}", e))
+}
+
+/// Which engine index each database stem is playing on, for diagnosing stem control issues
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StemMapping {
+  pub stems: HashMap<String, usize>, // Database stem id -> engine index
+  pub slots: Vec<bool>, // Whether each engine index holds a stem
+}
+
+pub(crate) fn stem_mapping(state: &AppState) -> StemMapping {
+  let stems = lock_or_recover(&state.stem_id_map, "stem ID map").clone();
+  let slots = lock_or_recover(&state.audio_engine, "audio engine").loaded_slots();
+  StemMapping { stems, slots }
+}
+
+/// Snapshot of the stem id to engine index map and which engine slots are loaded
+#[tauri::command]
+pub async fn get_stem_mapping(state: State<'_, AppState>) -> Result<StemMapping, String> {
+  Ok(stem_mapping(&state))
+}
+
+/// Min/max peaks of one stem for the per-stem editor, at `buckets` resolution. Computed once
+/// from the cached samples (or a fresh decode) and then served from the waveforms table
+#[tauri::command]
+pub async fn get_stem_waveform(
+  stem_id: String,
+  buckets: usize,
+  state: State<'_, AppState>,
+) -> Result<StemWaveform, String> {
+  let state = state.inner().clone();
+  tokio::task::spawn_blocking(move || stem_waveform(&state, &stem_id, buckets))
+    .await
+    .map_err(|e| format!("Waveform task failed: {}", e))?
+}
+
+pub(crate) fn stem_waveform(state: &AppState, stem_id: &str, buckets: usize) -> Result<StemWaveform, String> {
+  if !(1..=MAX_WAVEFORM_BUCKETS).contains(&buckets) {
+    return Err(format!("Invalid bucket count: {}, expected 1-{}", buckets, MAX_WAVEFORM_BUCKETS));
+  }
+
+  let stem = state.database
+    .get_stem(stem_id)
+    .map_err(|e| format!("Failed to get stem: {}", e))?;
+  let source = waveform_source(&stem.file_path);
+
+  if let Some(waveform) = state.database
+    .get_stem_waveform(stem_id, buckets as i64, source.as_deref())
+    .map_err(|e| format!("Failed to get waveform: {}", e))?
+  {
+    return Ok(waveform);
+  }
+
+  let samples = stem_samples(state, &stem)?;
+  let peaks = compute_peaks(&samples, 2, buckets);
+  let waveform = StemWaveform {
+    stem_id: stem.id,
+    buckets: buckets as i64,
+    min: peaks.min,
+    max: peaks.max,
+    created_at: chrono::Utc::now().timestamp(),
+    source: source.unwrap_or_default(),
+  };
+
+  state.database
+    .save_stem_waveform(&waveform)
+    .map_err(|e| format!("Failed to save waveform: {}", e))?;
+
+  log::info!("Computed {}-bucket waveform for stem {}", buckets, stem_id);
+  Ok(waveform)
+}
+
+// "path|size|mtime" of a stem's file, so stored peaks are only reused for the same audio.
+// None when the file can't be read
+fn waveform_source(path: &str) -> Option<String> {
+  let metadata = std::fs::metadata(path).ok()?;
+  let modified = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+  Some(format!("{}|{}|{}", path, metadata.len(), modified.as_nanos()))
+}
+
+// A stem's decoded samples: the song cache's copy if it's fresh, otherwise a decode at the project rate
+fn stem_samples(state: &AppState, stem: &Stem) -> Result<Arc<Vec<f32>>, String> {
+  let cached = lock_or_recover(&state.song_cache, "song cache")
+    .peek(&stem.song_id)
+    .and_then(|song| song.stems.iter().find(|s| s.stem_id == stem.id && !s.is_stale()).map(|s| s.samples.clone()));
+  if let Some(samples) = cached {
+    return Ok(samples);
+  }
+
+  let sample_rate = lock_or_recover(&state.audio_engine, "audio engine").project_sample_rate();
+  Ok(Arc::new(decode_at_rate(&stem.file_path, &stem.name, sample_rate, |_, _| {})?))
+}
+
+/// Set a stem's trim so its loudest sample lands at `target_dbfs` (-1 dBFS by default), for
+/// lifting quiet DI stems. Returns the trim applied, in dB
+#[tauri::command]
+pub async fn normalize_stem_peak(
+  stem_id: String,
+  target_dbfs: Option<f64>,
+  state: State<'_, AppState>,
+) -> Result<f64, String> {
+  let state = state.inner().clone();
+  let target_dbfs = target_dbfs.unwrap_or(DEFAULT_PEAK_TARGET_DBFS);
+  tokio::task::spawn_blocking(move || normalize_stem_peak_to(&state, &stem_id, target_dbfs))
+    .await
+    .map_err(|e| format!("Normalize task failed: {}", e))?
+}
+
+pub(crate) fn normalize_stem_peak_to(state: &AppState, stem_id: &str, target_dbfs: f64) -> Result<f64, String> {
+  if !target_dbfs.is_finite() || !(-48.0..=0.0).contains(&target_dbfs) {
+    return Err(format!("Invalid peak target: {} dBFS, expected -48 to 0", target_dbfs));
+  }
+
+  let stem = state.database
+    .get_stem(stem_id)
+    .map_err(|e| format!("Failed to get stem: {}", e))?;
+  let samples = stem_samples(state, &stem)?;
+
+  let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+  if peak <= 0.0 {
+    return Err(format!("Stem '{}' is silent, nothing to normalize", stem.name));
+  }
+
+  let peak_dbfs = 20.0 * (peak as f64).log10();
+  let trim_db = (target_dbfs - peak_dbfs).clamp(*STEM_TRIM_RANGE.start(), *STEM_TRIM_RANGE.end());
+  apply_stem_trim(state, stem_id, trim_db)?;
+
+  log::info!("Normalized stem '{}': peak {:.1} dBFS, trim {:+.1} dB", stem.name, peak_dbfs, trim_db);
+  Ok(trim_db)
 }
@@ -1,5 +1,5 @@
 use super::*;
-use crate::audio::{MultiTrackEngine, StemCapacity};
+use crate::audio::{MultiTrackEngine, PlaybackState, StemCapacity};
 use crate::database::{Database, Song, Stem, Setlist};
 
 // Helper function to create test database
@@ -20,6 +20,9 @@ fn create_test_song(db: &Database, name: &str) -> Song {
     mixdown_path: None,
     created_at: chrono::Utc::now().timestamp(),
     updated_at: chrono::Utc::now().timestamp(),
+    play_count: 0,
+    last_played_at: None,
+    loudness_lufs: None,
   };
 
   db.create_song(&song).expect("Failed to create test song");
@@ -39,12 +42,121 @@ fn create_test_stem(db: &Database, song_id: &str, name: &str) -> Stem {
     duration: 180.0,
     volume: 0.8,
     is_muted: false,
+    display_order: 0,
+    group: None,
+    pan: None,
+    is_cue: false,
+    solo_safe: false,
+    offset_samples: 0,
+    phase_inverted: false,
+    original_file_path: None,
+    default_volume: 0.8,
+    default_mute: false,
+    delay_samples: 0,
+    swap_channels: false,
+    mono_sum: false,
+    trim_db: 0.0,
+    gate_enabled: false,
+    gate_threshold_db: -50.0,
+    gate_attack_ms: 1.0,
+    gate_release_ms: 50.0,
+    file_hash: None,
+    file_hash_modified: None,
   };
 
   db.create_stem(&stem).expect("Failed to create test stem");
   stem
 }
 
+// Create a test stem whose file_path points at a real file
+fn create_stem_at(db: &Database, song_id: &str, name: &str, path: &std::path::Path) -> Stem {
+  let mut stem = create_test_stem(db, song_id, name);
+  stem.file_path = path.to_string_lossy().to_string();
+  db.update_stem(&stem).expect("Failed to update test stem");
+  stem
+}
+
+// App state whose disk cache lives in its own temp directory instead of the user's
+fn test_app_state(db: Database, engine: MultiTrackEngine) -> AppState {
+  let cache_dir = std::env::temp_dir().join(format!("trax_test_stem_cache_{}", uuid::Uuid::new_v4()));
+  AppState::new(db, engine, cache_dir)
+}
+
+// Build app state with every stem loaded into the engine in order
+fn create_loaded_state(db: Database, stems: &[&Stem]) -> AppState {
+  let engine = MultiTrackEngine::new(8).expect("Failed to create engine");
+  let state = test_app_state(db, engine);
+  {
+    let mut engine = state.audio_engine.lock().unwrap();
+    let mut map = state.stem_id_map.lock().unwrap();
+    for stem in stems {
+      let index = engine.load_stem_from_samples(Arc::new(vec![0.0; 16])).unwrap();
+      map.insert(stem.id.clone(), index);
+    }
+  }
+  state
+}
+
+// Stereo 16-bit 48 kHz, the format most test files are written in
+const TEST_WAV_SPEC: hound::WavSpec = hound::WavSpec {
+  channels: 2,
+  sample_rate: 48000,
+  bits_per_sample: 16,
+  sample_format: hound::SampleFormat::Int,
+};
+
+// Empty directory of its own under the system temp dir
+fn test_temp_dir(label: &str) -> std::path::PathBuf {
+  let dir = std::env::temp_dir().join(format!("trax_{}_test_{}", label, uuid::Uuid::new_v4()));
+  std::fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+// Write a WAV file named `name` into `dir` with the interleaved samples from `fill`
+fn write_test_wav<S: hound::Sample + Copy>(
+  dir: &std::path::Path,
+  name: &str,
+  spec: hound::WavSpec,
+  fill: impl IntoIterator<Item = S>,
+) -> std::path::PathBuf {
+  let path = dir.join(name);
+  let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+  for sample in fill {
+    writer.write_sample(sample).unwrap();
+  }
+  writer.finalize().unwrap();
+  path
+}
+
+// Put `song` in the song cache as decoded at the device rate, with the given samples per stem
+fn cache_song(state: &AppState, song: &Song, stems: &[(&Stem, Vec<f32>)]) {
+  let rate = state.audio_engine.lock().unwrap().device_sample_rate();
+  let stems = stems.iter().map(|(stem, samples)| CachedStem {
+    stem_id: stem.id.clone(),
+    samples: Arc::new(samples.clone()),
+    sample_rate: rate,
+    volume: stem.volume as f32,
+    is_muted: stem.is_muted,
+    source_path: String::new(),
+    source_modified: None,
+    source_hash: None,
+  }).collect();
+  state.song_cache.lock().unwrap().insert(song.id.clone(), CachedSong {
+    song_id: song.id.clone(),
+    stems,
+  });
+}
+
+// Run a command future to completion on a multi-threaded runtime, as Tauri would
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+  tokio::runtime::Builder::new_multi_thread()
+    .worker_threads(2)
+    .enable_all()
+    .build()
+    .unwrap()
+    .block_on(future)
+}
+
 #[cfg(test)]
 mod database_integration_tests {
   use super::*;
@@ -109,6 +221,9 @@ mod database_integration_tests {
       created_at: now,
       updated_at: now,
       song_ids: vec![song1.id.clone(), song2.id.clone()],
+      position: 0,
+      color: None,
+      songs: vec![],
     };
 
     db.create_setlist(&setlist).expect("Failed to create setlist");
@@ -195,7 +310,7 @@ mod app_state_tests {
     let engine = MultiTrackEngine::with_capacity(StemCapacity::Standard)
       .expect("Failed to create engine");
 
-    let state = AppState::new(db, engine);
+    let state = test_app_state(db, engine);
 
     // Verify state is accessible
     assert!(state.audio_engine.lock().is_ok());
@@ -208,7 +323,7 @@ mod app_state_tests {
     let engine = MultiTrackEngine::with_capacity(StemCapacity::Standard)
       .expect("Failed to create engine");
 
-    let state = AppState::new(db, engine);
+    let state = test_app_state(db, engine);
 
     // Test stem ID mapping
     let mut map = state.stem_id_map.lock().expect("Failed to lock stem map");
@@ -216,6 +331,93 @@ mod app_state_tests {
 
     assert_eq!(map.get("test-stem-id"), Some(&0));
   }
+
+  #[test]
+  fn test_lock_free_position_matches_engine() {
+    let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+    engine.load_stem_from_samples(Arc::new(vec![0.0; 48000 * 2 * 4])).unwrap();
+    let state = test_app_state(create_test_database(), engine);
+
+    state.audio_engine.lock().unwrap().seek(1.25).unwrap();
+    let expected = state.audio_engine.lock().unwrap().position();
+    assert_eq!(state.playback_position_seconds(), expected);
+
+    // Playback advances the shared atomic, and the rate follows the project rate
+    {
+      let mut engine = state.audio_engine.lock().unwrap();
+      engine.play().unwrap();
+      let mut output = vec![0.0f32; 480 * 2];
+      engine.process_block(&mut output, 2);
+    }
+    assert_eq!(state.playback_position_seconds(), state.audio_engine.lock().unwrap().position());
+
+    state.audio_engine.lock().unwrap().set_project_sample_rate(96000).unwrap();
+    state.audio_engine.lock().unwrap().seek(0.5).unwrap();
+    assert_eq!(state.playback_position_seconds(), 0.5);
+
+    // Readable while a command holds the engine
+    let _engine = state.audio_engine.lock().unwrap();
+    assert_eq!(state.playback_position_seconds(), 0.5);
+  }
+
+  #[test]
+  fn test_frame_position_matches_seconds_at_project_rate() {
+    let engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+    let state = test_app_state(create_test_database(), engine);
+
+    for (rate, seconds) in [(48000, 1.5), (96000, 2.25), (44100, 0.75)] {
+      let mut engine = state.audio_engine.lock().unwrap();
+      engine.set_project_sample_rate(rate).unwrap();
+      engine.load_stem_from_samples(Arc::new(vec![0.0; rate as usize * 2 * 4])).unwrap();
+      engine.seek(seconds).unwrap();
+      drop(engine);
+
+      let (frames, sample_rate) = state.playback_position_frames();
+      assert_eq!(sample_rate, rate);
+      assert_eq!(frames, (seconds * rate as f64) as u64);
+      assert_eq!(frames as f64 / sample_rate as f64, state.playback_position_seconds());
+    }
+
+    // An hour in, the frame count is still exact
+    let rate = state.audio_engine.lock().unwrap().project_sample_rate() as u64;
+    state.playback_position.store(3600 * rate * 2 + 2, std::sync::atomic::Ordering::Release);
+    assert_eq!(state.playback_position_frames().0, 3600 * rate + 1);
+  }
+
+  #[test]
+  fn test_panic_stop_silences_everything() {
+    let mut engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+    engine.set_limiter_enabled(false);
+    let stem = engine.load_stem_from_samples(Arc::new(vec![0.5; 4800])).unwrap();
+    engine.set_master_volume(0.7);
+    engine.set_cue_output(Some(1)).unwrap();
+    engine.set_stem_cue(stem, true);
+    engine.play().unwrap();
+    engine.seek(0.01).unwrap();
+
+    let state = test_app_state(create_test_database(), engine);
+    let events = Arc::new(Mutex::new(Vec::<(String, serde_json::Value)>::new()));
+    let recorder = |events: Arc<Mutex<Vec<(String, serde_json::Value)>>>| {
+      move |event: &str, payload: serde_json::Value| events.lock().unwrap().push((event.to_string(), payload))
+    };
+    stop_all_audio(&state, recorder(events.clone()));
+    // Calling it again from the stopped state is harmless
+    stop_all_audio(&state, recorder(events.clone()));
+
+    // The webview stops the drone pad on this event
+    let panics = events.lock().unwrap().iter().filter(|(event, _)| event == "playback:panic").count();
+    assert_eq!(panics, 2, "Every panic stop should tell the drone pad to stop");
+
+    let engine = state.audio_engine.lock().unwrap();
+    assert_eq!(engine.state(), crate::audio::PlaybackState::Stopped);
+    assert_eq!(engine.position(), 0.0);
+    assert!(!engine.is_stem_cued(stem));
+
+    let mut output = vec![1.0f32; 64];
+    engine.process_block(&mut output, 4);
+    assert!(output.iter().all(|&s| s == 0.0), "Output should be silent after a panic stop");
+    assert_eq!(engine.master_volume(), 0.7, "Master volume is restored for the next song");
+  }
 }
 
 #[cfg(test)]
@@ -258,6 +460,9 @@ mod command_logic_tests {
       created_at: now,
       updated_at: now,
       song_ids: vec![],
+      position: 0,
+      color: None,
+      songs: vec![],
     };
 
     db.create_setlist(&setlist).expect("Failed to create setlist");
@@ -280,4 +485,1989 @@ mod command_logic_tests {
     assert_eq!(retrieved.song_ids[1], song1.id);
     assert_eq!(retrieved.song_ids[2], song3.id);
   }
+
+  #[test]
+  fn test_stem_info_reports_resampling_to_project_rate() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Song");
+    let mut stem = create_test_stem(&db, &song.id, "Click");
+    stem.sample_rate = 44100;
+    stem.channels = 1;
+
+    let info = StemInfo::new(stem, 48000);
+    assert!(info.will_resample, "A 44.1k stem is resampled to the 48k project rate");
+    assert!(info.is_mono);
+    assert_eq!(info.stem.sample_rate, 44100);
+
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["project_sample_rate"], 48000);
+    assert_eq!(json["name"], "Click", "Stem fields are flattened alongside the extra ones");
+
+    let native = StemInfo::new(create_test_stem(&db, &song.id, "Pad"), 48000);
+    assert!(!native.will_resample);
+    assert!(!native.is_mono);
+  }
+}
+
+#[cfg(test)]
+mod setlist_insert_tests {
+  use super::*;
+
+  fn setlist_with(db: &Database, song_ids: &[&str]) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let setlist = Setlist {
+      id: uuid::Uuid::new_v4().to_string(),
+      name: "Insert Set".to_string(),
+      created_at: now,
+      updated_at: now,
+      song_ids: song_ids.iter().map(|id| id.to_string()).collect(),
+      position: 0,
+      color: None,
+      songs: vec![],
+    };
+    db.create_setlist(&setlist).unwrap();
+    setlist.id
+  }
+
+  #[test]
+  fn test_insert_song_at_front_middle_and_past_end() {
+    let db = create_test_database();
+    let a = create_test_song(&db, "A").id;
+    let b = create_test_song(&db, "B").id;
+    let c = create_test_song(&db, "C").id;
+    let d = create_test_song(&db, "D").id;
+    let e = create_test_song(&db, "E").id;
+    let setlist_id = setlist_with(&db, &[&a, &b]);
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    assert_eq!(insert_setlist_song(&state, &setlist_id, &c, 0).unwrap(), vec![c.clone(), a.clone(), b.clone()]);
+    assert_eq!(insert_setlist_song(&state, &setlist_id, &d, 2).unwrap(), vec![c.clone(), a.clone(), d.clone(), b.clone()]);
+    assert_eq!(
+      insert_setlist_song(&state, &setlist_id, &e, 99).unwrap(),
+      vec![c.clone(), a.clone(), d.clone(), b.clone(), e.clone()]
+    );
+    assert_eq!(state.database.get_setlist(&setlist_id).unwrap().song_ids.len(), 5);
+  }
+
+  #[test]
+  fn test_insert_existing_song_moves_it() {
+    let db = create_test_database();
+    let a = create_test_song(&db, "A").id;
+    let b = create_test_song(&db, "B").id;
+    let c = create_test_song(&db, "C").id;
+    let setlist_id = setlist_with(&db, &[&a, &b, &c]);
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    assert_eq!(insert_setlist_song(&state, &setlist_id, &c, 1).unwrap(), vec![a.clone(), c.clone(), b.clone()]);
+    assert_eq!(insert_setlist_song(&state, &setlist_id, &a, 10).unwrap(), vec![c.clone(), b.clone(), a.clone()]);
+
+    let stored = state.database.get_setlist(&setlist_id).unwrap().song_ids;
+    assert_eq!(stored, vec![c, b, a], "Moving a song must not duplicate it");
+  }
+}
+
+#[cfg(test)]
+mod setlist_duration_tests {
+  use super::*;
+
+  #[test]
+  fn test_setlist_duration_includes_gaps() {
+    let db = create_test_database();
+    let mut song_ids = Vec::new();
+    for (name, duration) in [("Opener", 180.0), ("Ballad", 240.5), ("Closer", 300.0)] {
+      let mut song = create_test_song(&db, name);
+      song.duration = duration;
+      db.update_song(&song).unwrap();
+      song_ids.push(song.id);
+    }
+    // A song with no readable duration keeps its slot but adds no time
+    let mut untimed = create_test_song(&db, "Untimed");
+    untimed.duration = 0.0;
+    db.update_song(&untimed).unwrap();
+
+    let now = chrono::Utc::now().timestamp();
+    let setlist = Setlist {
+      id: uuid::Uuid::new_v4().to_string(),
+      name: "Timed Set".to_string(),
+      created_at: now,
+      updated_at: now,
+      song_ids: song_ids.clone(),
+      position: 0,
+      color: None,
+      songs: vec![],
+    };
+    db.create_setlist(&setlist).unwrap();
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    let timing = setlist_duration(&state, &setlist.id, 30.0).unwrap();
+    assert_eq!(timing.songs.len(), 3);
+    assert!((timing.total_seconds - (180.0 + 240.5 + 300.0 + 2.0 * 30.0)).abs() < 1e-9);
+    assert_eq!(timing.songs[1].start_time, 210.0);
+    assert_eq!(timing.songs[2].start_time, 480.5);
+
+    let no_gaps = setlist_duration(&state, &setlist.id, 0.0).unwrap();
+    assert!((no_gaps.total_seconds - 720.5).abs() < 1e-9);
+
+    let mut with_untimed = state.database.get_setlist(&setlist.id).unwrap();
+    with_untimed.song_ids.insert(1, untimed.id.clone());
+    state.database.update_setlist(&with_untimed).unwrap();
+    let timing = setlist_duration(&state, &setlist.id, 30.0).unwrap();
+    assert!(!timing.songs[1].has_duration);
+    assert_eq!(timing.songs[1].duration, 0.0);
+    assert!((timing.total_seconds - (720.5 + 3.0 * 30.0)).abs() < 1e-9);
+
+    assert!(setlist_duration(&state, &setlist.id, -1.0).is_err());
+  }
+}
+
+#[cfg(test)]
+mod setlist_template_tests {
+  use super::*;
+
+  #[test]
+  fn test_setlist_from_template_fills_slots_in_order() {
+    let db = create_test_database();
+    let opener = create_test_song(&db, "Opener Song").id;
+    let worship = create_test_song(&db, "Worship Song").id;
+    let closer = create_test_song(&db, "Closer Song").id;
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    let slots = vec!["Opener".to_string(), "Worship".to_string(), "Closer".to_string()];
+    let template = new_setlist_template(&state, "Sunday Morning", slots.clone()).unwrap();
+    assert_eq!(state.database.get_setlist_template(&template.id).unwrap().slots, slots);
+
+    // Filled out of order; the template decides the order
+    let slot_song_map = HashMap::from([(2, closer.clone()), (0, opener.clone()), (1, worship.clone())]);
+    let setlist_id = setlist_from_template(&state, &template.id, &slot_song_map, None).unwrap();
+
+    let setlist = state.database.get_setlist(&setlist_id).unwrap();
+    assert_eq!(setlist.name, "Sunday Morning");
+    assert_eq!(setlist.song_ids, vec![opener.clone(), worship, closer]);
+
+    let missing_slot = HashMap::from([(3, opener)]);
+    assert!(setlist_from_template(&state, &template.id, &missing_slot, None).is_err());
+  }
+}
+
+#[cfg(test)]
+mod lock_recovery_tests {
+  use super::*;
+
+  #[test]
+  fn test_poisoned_engine_lock_is_recovered() {
+    let state = test_app_state(create_test_database(), MultiTrackEngine::new(2).expect("Failed to create engine"));
+
+    // Panic while holding the engine lock, as a bug in a command or callback would
+    let engine = state.audio_engine.clone();
+    let result = std::thread::spawn(move || {
+      let mut engine = engine.lock().unwrap();
+      engine.set_master_volume(0.5);
+      panic!("simulated panic while holding the engine lock");
+    }).join();
+    assert!(result.is_err());
+    assert!(state.audio_engine.is_poisoned());
+
+    let engine = lock_or_recover(&state.audio_engine, "audio engine");
+    assert!((engine.master_volume() - 0.5).abs() < 1e-6, "Recovered guard sees the last written state");
+    drop(engine);
+
+    assert!(!state.audio_engine.is_poisoned(), "Recovery clears the poison for later commands");
+    assert!(state.audio_engine.lock().is_ok());
+  }
+}
+
+#[cfg(test)]
+mod song_cache_tests {
+  use super::*;
+  use std::time::{Duration, SystemTime};
+
+  #[test]
+  fn test_cache_efficiency_ratios() {
+    let mut cache = SongCache::new(1024 * 1024);
+    let empty = cache.efficiency_at(10_000);
+    assert_eq!(empty.hit_rate, None, "No loads yet");
+
+    // An hour ago: 1 hit, 3 misses (cold cache)
+    cache.record_lookup_at(true, 6_400);
+    for _ in 0..3 {
+      cache.record_lookup_at(false, 6_400);
+    }
+    // Last few minutes: 3 hits, 1 miss
+    for _ in 0..3 {
+      cache.record_lookup_at(true, 9_800);
+    }
+    cache.record_lookup_at(false, 9_990);
+
+    let efficiency = cache.efficiency_at(10_000);
+    assert_eq!((efficiency.hits, efficiency.misses), (4, 4));
+    assert_eq!(efficiency.hit_rate, Some(0.5));
+    assert_eq!((efficiency.recent_hits, efficiency.recent_misses), (3, 1));
+    assert_eq!(efficiency.recent_hit_rate, Some(0.75));
+    assert_eq!(efficiency.recent_window_secs, 600);
+
+    // Once the window has moved past every lookup only the totals remain
+    let later = cache.efficiency_at(20_000);
+    assert_eq!(later.hit_rate, Some(0.5));
+    assert_eq!(later.recent_hit_rate, None);
+  }
+
+  fn cached_song_for(path: &std::path::Path) -> CachedSong {
+    let source_path = path.to_str().unwrap().to_string();
+    CachedSong {
+      song_id: "song-1".to_string(),
+      stems: vec![CachedStem {
+        stem_id: "stem-1".to_string(),
+        samples: Arc::new(vec![0.0; 16]),
+        sample_rate: 48000,
+        volume: 1.0,
+        is_muted: false,
+        source_modified: source_modified_time(&source_path),
+        source_hash: None,
+        source_path,
+      }],
+    }
+  }
+
+  #[test]
+  fn test_cache_entry_valid_while_source_unchanged() {
+    let path = std::env::temp_dir().join(format!("trax_cache_{}.wav", uuid::Uuid::new_v4()));
+    std::fs::write(&path, b"original").unwrap();
+
+    let mut cache = SongCache::new(1024 * 1024);
+    cache.insert("song-1".to_string(), cached_song_for(&path));
+
+    assert!(cache.get("song-1").is_some());
+    assert!(cache.get("song-1").is_some());
+
+    std::fs::remove_file(&path).ok();
+  }
+
+  #[test]
+  fn test_modified_source_invalidates_cache_entry() {
+    let path = std::env::temp_dir().join(format!("trax_cache_{}.wav", uuid::Uuid::new_v4()));
+    std::fs::write(&path, b"original").unwrap();
+
+    let mut cache = SongCache::new(1024 * 1024);
+    cache.insert("song-1".to_string(), cached_song_for(&path));
+    assert!(cache.get("song-1").is_some());
+
+    // Rewrite the source and bump its mtime so the change is visible regardless of fs resolution
+    std::fs::write(&path, b"edited").unwrap();
+    let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+
+    assert!(cache.get("song-1").is_none(), "Stale entry should be invalidated");
+    assert!(!cache.contains("song-1"));
+    assert_eq!(cache.stats().1, 0);
+
+    std::fs::remove_file(&path).ok();
+  }
+
+  #[test]
+  fn test_cached_song_info_reports_stem_durations() {
+    let stem = |id: &str, frames: usize, sample_rate: u32| CachedStem {
+      stem_id: id.to_string(),
+      samples: Arc::new(vec![0.0; frames * 2]),
+      sample_rate,
+      volume: 1.0,
+      is_muted: false,
+      source_path: "/path/to/test.wav".to_string(),
+      source_modified: None,
+      source_hash: None,
+    };
+
+    let state = test_app_state(create_test_database(), MultiTrackEngine::new(2).expect("Failed to create engine"));
+    state.song_cache.lock().unwrap().insert("song-1".to_string(), CachedSong {
+      song_id: "song-1".to_string(),
+      stems: vec![stem("drums", 96000, 48000), stem("bass", 22050, 44100)],
+    });
+
+    let info = cached_song_info(&state, "song-1").unwrap();
+    assert_eq!(info.len(), 2);
+    assert_eq!(info[0].stem_id, "drums");
+    assert_eq!(info[0].sample_count, 192000);
+    assert_eq!(info[0].frame_count, 96000);
+    assert!((info[0].duration - 2.0).abs() < 1e-9);
+    assert_eq!(info[1].sample_rate, 44100);
+    assert!((info[1].duration - 0.5).abs() < 1e-9);
+
+    assert!(cached_song_info(&state, "missing").is_err());
+  }
+
+  #[test]
+  fn test_cache_sized_from_settings() {
+    let gb = 1024 * 1024 * 1024;
+    assert_eq!(SongCache::from_gb(4.0).stats().2, 4 * gb);
+    assert_eq!(SongCache::from_gb(0.5).stats().2, gb / 2);
+    assert_eq!(SongCache::from_gb(0.1).stats().2, MIN_CACHE_SIZE_BYTES, "Tiny sizes are raised to the floor");
+
+    // AppState picks the saved size up at startup
+    let db = create_test_database();
+    let mut settings = db.get_settings().unwrap();
+    settings.in_memory_cache_gb = 8.0;
+    db.update_settings(&settings).unwrap();
+    let state = test_app_state(db, MultiTrackEngine::new(2).expect("Failed to create engine"));
+    assert_eq!(state.song_cache.lock().unwrap().stats().2, 8 * gb);
+  }
+
+  #[test]
+  fn test_identical_sources_share_cached_samples() {
+    let dir = test_temp_dir("cache");
+    let click_a = dir.join("click_a.wav");
+    let click_b = dir.join("click_b.wav");
+    std::fs::write(&click_a, b"same click track").unwrap();
+    std::fs::write(&click_b, b"same click track").unwrap();
+
+    let song_with_click = |song_id: &str, path: &std::path::Path| {
+      let mut song = cached_song_for(path);
+      song.song_id = song_id.to_string();
+      song.stems[0].source_hash = Some(crate::import::calculate_content_hash(path).unwrap());
+      song
+    };
+
+    let mut cache = SongCache::new(1024 * 1024);
+    cache.insert("song-1".to_string(), song_with_click("song-1", &click_a));
+    cache.insert("song-2".to_string(), song_with_click("song-2", &click_b));
+
+    let first = cache.peek("song-1").unwrap().stems[0].samples.clone();
+    let second = cache.peek("song-2").unwrap().stems[0].samples.clone();
+    assert!(Arc::ptr_eq(&first, &second), "Identical sources should share one decoded copy");
+    assert_eq!(cache.stats().1, 16 * 4, "Shared samples should be counted once");
+
+    // The shared copy outlives the first song that referenced it
+    cache.remove("song-1");
+    let hash = crate::import::calculate_content_hash(&click_a).unwrap();
+    assert!(cache.shared_samples(&hash, 48000).is_some());
+    assert_eq!(cache.stats().1, 16 * 4);
+
+    cache.remove("song-2");
+    assert!(cache.shared_samples(&hash, 48000).is_none());
+    assert_eq!(cache.stats().1, 0);
+
+    std::fs::remove_dir_all(&dir).ok();
+  }
+
+  #[test]
+  fn test_sources_differing_after_first_megabyte_are_not_shared() {
+    let dir = test_temp_dir("cache");
+    // Equal length with the same silent first megabyte, e.g. a count-in
+    let intro = vec![0u8; 1024 * 1024];
+    let (verse, chorus) = (dir.join("verse.wav"), dir.join("chorus.wav"));
+    std::fs::write(&verse, [intro.as_slice(), b"verse"].concat()).unwrap();
+    std::fs::write(&chorus, [intro.as_slice(), b"choir"].concat()).unwrap();
+
+    let mut cache = SongCache::new(1024 * 1024);
+    for (song_id, path) in [("song-1", &verse), ("song-2", &chorus)] {
+      let mut song = cached_song_for(path);
+      song.song_id = song_id.to_string();
+      song.stems[0].source_hash = Some(crate::import::calculate_content_hash(path).unwrap());
+      cache.insert(song_id.to_string(), song);
+    }
+
+    let first = cache.peek("song-1").unwrap().stems[0].samples.clone();
+    let second = cache.peek("song-2").unwrap().stems[0].samples.clone();
+    assert!(!Arc::ptr_eq(&first, &second), "Different sources must keep their own samples");
+    assert_eq!(cache.stats().1, 2 * 16 * 4);
+
+    std::fs::remove_dir_all(&dir).ok();
+  }
+
+  #[test]
+  fn test_stored_stem_hash_is_reused_until_the_file_changes() {
+    let dir = test_temp_dir("cache");
+    let path = dir.join("click.wav");
+    std::fs::write(&path, b"click track").unwrap();
+    let db = create_test_database();
+    let song = create_test_song(&db, "Song");
+    let stem = create_stem_at(&db, &song.id, "Click", &path);
+    let hash = crate::import::calculate_content_hash(&path).unwrap();
+
+    // First load hashes the file and stores the hash with its mtime
+    let modified = source_modified_time(&stem.file_path);
+    assert_eq!(stem_content_hash(&db, &stem, modified), Some(hash.clone()));
+    let stored = db.get_stem(&stem.id).unwrap();
+    assert_eq!(stored.file_hash, Some(hash.clone()));
+    assert!(stored.file_hash_modified.is_some());
+
+    // While the mtime matches, the stored hash is trusted without reading the file
+    let marked = Stem { file_hash: Some("stored".to_string()), ..stored };
+    assert_eq!(stem_content_hash(&db, &marked, modified), Some("stored".to_string()));
+
+    // An edit moves the mtime, so the file is hashed again
+    std::fs::write(&path, b"new click track").unwrap();
+    let later = modified.unwrap() + Duration::from_secs(5);
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+    let rehashed = crate::import::calculate_content_hash(&path).unwrap();
+    assert_eq!(stem_content_hash(&db, &marked, source_modified_time(&stem.file_path)), Some(rehashed.clone()));
+    assert_eq!(db.get_stem(&stem.id).unwrap().file_hash, Some(rehashed));
+
+    std::fs::remove_dir_all(&dir).ok();
+  }
+}
+
+#[cfg(test)]
+mod stem_group_tests {
+  use super::*;
+
+  fn create_grouped_stem(db: &Database, song_id: &str, name: &str, group: Option<&str>) -> Stem {
+    let mut stem = create_test_stem(db, song_id, name);
+    stem.group = group.map(|g| g.to_string());
+    db.update_stem(&stem).expect("Failed to set stem group");
+    stem
+  }
+
+  #[test]
+  fn test_toggle_group_mute_only_affects_group() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Grouped Song");
+    let kick = create_grouped_stem(&db, &song.id, "Kick", Some("Drums"));
+    let snare = create_grouped_stem(&db, &song.id, "Snare", Some("Drums"));
+    let bass = create_grouped_stem(&db, &song.id, "Bass", None);
+    let keys = create_grouped_stem(&db, &song.id, "Keys", Some("Keys"));
+
+    let state = create_loaded_state(db, &[&kick, &snare, &bass, &keys]);
+
+    let muted = stems::apply_group_mute(&state, &song.id, "Drums").unwrap();
+    assert!(muted);
+
+    {
+      let engine = state.audio_engine.lock().unwrap();
+      let map = state.stem_id_map.lock().unwrap();
+      assert!(engine.is_stem_muted(map[&kick.id]));
+      assert!(engine.is_stem_muted(map[&snare.id]));
+      assert!(!engine.is_stem_muted(map[&bass.id]));
+      assert!(!engine.is_stem_muted(map[&keys.id]));
+    }
+
+    // Mute state is persisted for group members only
+    assert!(state.database.get_stem(&kick.id).unwrap().is_muted);
+    assert!(state.database.get_stem(&snare.id).unwrap().is_muted);
+    assert!(!state.database.get_stem(&bass.id).unwrap().is_muted);
+    assert!(!state.database.get_stem(&keys.id).unwrap().is_muted);
+
+    // Toggling again unmutes the group
+    let muted = stems::apply_group_mute(&state, &song.id, "Drums").unwrap();
+    assert!(!muted);
+    let engine = state.audio_engine.lock().unwrap();
+    let map = state.stem_id_map.lock().unwrap();
+    assert!(!engine.is_stem_muted(map[&kick.id]));
+    assert!(!engine.is_stem_muted(map[&snare.id]));
+  }
+
+  #[test]
+  fn test_toggle_group_solo() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Grouped Song");
+    let kick = create_grouped_stem(&db, &song.id, "Kick", Some("Drums"));
+    let vocals = create_grouped_stem(&db, &song.id, "Vocals", Some("Vocals"));
+
+    let state = create_loaded_state(db, &[&kick, &vocals]);
+
+    assert!(stems::apply_group_solo(&state, &song.id, "Drums").unwrap());
+    {
+      let engine = state.audio_engine.lock().unwrap();
+      let map = state.stem_id_map.lock().unwrap();
+      assert!(engine.is_stem_soloed(map[&kick.id]));
+      assert!(!engine.is_stem_soloed(map[&vocals.id]));
+    }
+
+    assert!(!stems::apply_group_solo(&state, &song.id, "Drums").unwrap());
+    assert!(stems::apply_group_solo(&state, &song.id, "Missing").is_err());
+  }
+
+  #[test]
+  fn test_exclusive_solo_mode_releases_other_solos() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Solo Song");
+    let kick = create_grouped_stem(&db, &song.id, "Kick", Some("Drums"));
+    let vocals = create_grouped_stem(&db, &song.id, "Vocals", None);
+    let bass = create_grouped_stem(&db, &song.id, "Bass", None);
+
+    let state = create_loaded_state(db, &[&kick, &vocals, &bass]);
+
+    // Additive (default): solos stack
+    assert!(stems::apply_stem_solo(&state, &vocals.id).unwrap());
+    assert!(stems::apply_stem_solo(&state, &bass.id).unwrap());
+    {
+      let engine = state.audio_engine.lock().unwrap();
+      let map = state.stem_id_map.lock().unwrap();
+      assert!(engine.is_stem_soloed(map[&vocals.id]));
+      assert!(engine.is_stem_soloed(map[&bass.id]));
+    }
+
+    let mut settings = state.database.get_settings().unwrap();
+    settings.solo_mode = "exclusive".to_string();
+    state.database.update_settings(&settings).unwrap();
+
+    // Exclusive: a new solo (stem or group) releases the others
+    assert!(stems::apply_group_solo(&state, &song.id, "Drums").unwrap());
+    let engine = state.audio_engine.lock().unwrap();
+    let map = state.stem_id_map.lock().unwrap();
+    assert!(engine.is_stem_soloed(map[&kick.id]));
+    assert!(!engine.is_stem_soloed(map[&vocals.id]));
+    assert!(!engine.is_stem_soloed(map[&bass.id]));
+  }
+}
+
+#[cfg(test)]
+mod load_once_tests {
+  use super::*;
+  use std::collections::HashSet;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::time::Duration;
+
+  fn decoded_song(song_id: &str) -> CachedSong {
+    CachedSong {
+      song_id: song_id.to_string(),
+      stems: vec![CachedStem {
+        stem_id: "stem-1".to_string(),
+        samples: Arc::new(vec![0.0; 16]),
+        sample_rate: 48000,
+        volume: 1.0,
+        is_muted: false,
+        source_path: String::new(),
+        source_modified: None,
+        source_hash: None,
+      }],
+    }
+  }
+
+  #[test]
+  fn test_concurrent_loads_decode_once() {
+    let loading_songs = Arc::new(Mutex::new(HashSet::new()));
+    let song_cache = Arc::new(Mutex::new(SongCache::new(1024 * 1024)));
+    let decode_count = Arc::new(AtomicUsize::new(0));
+
+    let load = || {
+      let decode_count = decode_count.clone();
+      move || async move {
+        decode_count.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Ok(decoded_song("song-1"))
+      }
+    };
+
+    let (first, second) = block_on(async {
+      futures::join!(
+        load_once(&loading_songs, &song_cache, "song-1", load()),
+        load_once(&loading_songs, &song_cache, "song-1", load()),
+      )
+    });
+
+    assert!(first.is_ok());
+    assert!(second.is_ok());
+    assert_eq!(decode_count.load(Ordering::SeqCst), 1, "Song should be decoded exactly once");
+    assert!(song_cache.lock().unwrap().contains("song-1"));
+    assert!(loading_songs.lock().unwrap().is_empty(), "In-flight marker should be released");
+  }
+
+  #[test]
+  fn test_failed_load_releases_in_flight_marker() {
+    let loading_songs = Arc::new(Mutex::new(HashSet::new()));
+    let song_cache = Arc::new(Mutex::new(SongCache::new(1024 * 1024)));
+
+    let result = block_on(load_once(&loading_songs, &song_cache, "song-1", || async {
+      Err::<CachedSong, String>("decode failed".to_string())
+    }));
+
+    assert!(result.is_err());
+    assert!(loading_songs.lock().unwrap().is_empty());
+    assert!(!song_cache.lock().unwrap().contains("song-1"));
+  }
+}
+
+#[cfg(test)]
+mod transport_state_tests {
+  use super::*;
+
+  #[test]
+  fn test_transport_state_after_play() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Transport Song");
+    let stem = create_test_stem(&db, &song.id, "Vocals");
+
+    let engine = MultiTrackEngine::new(4).expect("Failed to create engine");
+    let state = test_app_state(db, engine);
+
+    let transport = transport_state(&state).unwrap();
+    assert_eq!(transport.state, "stopped");
+    assert_eq!(transport.song_id, None);
+
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate() as usize;
+    cache_song(&state, &song, &[(&stem, vec![0.0; rate * 2 * 3])]);
+
+    start_cached_song(&state, &song.id).unwrap();
+
+    let transport = transport_state(&state).unwrap();
+    assert_eq!(transport.state, "playing");
+    assert_eq!(transport.song_id.as_deref(), Some(song.id.as_str()));
+    assert!((transport.duration - 3.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_song_cached_at_another_rate_is_not_started() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Stale Rate Song");
+    let stem = create_test_stem(&db, &song.id, "Vocals");
+
+    let engine = MultiTrackEngine::new(4).expect("Failed to create engine");
+    let state = test_app_state(db, engine);
+
+    // Decoded before the device moved the project to another rate
+    let rate = state.audio_engine.lock().unwrap().project_sample_rate();
+    let other_rate = if rate == 44100 { 48000 } else { 44100 };
+    let cached = CachedSong {
+      song_id: song.id.clone(),
+      stems: vec![CachedStem {
+        stem_id: stem.id.clone(),
+        samples: Arc::new(vec![0.0; other_rate as usize * 2]),
+        sample_rate: other_rate,
+        volume: 1.0,
+        is_muted: false,
+        source_path: String::new(),
+        source_modified: None,
+        source_hash: None,
+      }],
+    };
+    state.song_cache.lock().unwrap().insert(song.id.clone(), cached.clone());
+
+    let err = start_cached_song(&state, &song.id).unwrap_err();
+    assert!(err.contains("play it again"), "{}", err);
+    assert!(!state.song_cache.lock().unwrap().contains(&song.id), "The stale song is dropped");
+    assert_eq!(state.audio_engine.lock().unwrap().stem_count(), 0);
+
+    // play_song drops it before deciding whether to decode
+    let mut cache = state.song_cache.lock().unwrap();
+    cache.insert(song.id.clone(), cached);
+    cache.remove_at_other_rate(&song.id, other_rate);
+    assert!(cache.contains(&song.id));
+    cache.remove_at_other_rate(&song.id, rate);
+    assert!(!cache.contains(&song.id));
+  }
+
+  #[test]
+  fn test_device_switch_that_moves_the_rate_leaves_nothing_loaded() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Switched Song");
+    let stem = create_test_stem(&db, &song.id, "Vocals");
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate();
+    cache_song(&state, &song, &[(&stem, vec![0.0; rate as usize * 2])]);
+    start_cached_song(&state, &song.id).unwrap();
+
+    // The project was set up at a rate this device can't run at
+    let device = {
+      let engine = state.audio_engine.lock().unwrap();
+      engine.project_sample_rate_arc().store(32000, std::sync::atomic::Ordering::Release);
+      engine.current_device_name().expect("Engine should have an output device")
+    };
+
+    assert_eq!(apply_audio_device(&state, &device).unwrap(), Some(rate));
+    assert_eq!(state.audio_engine.lock().unwrap().stem_count(), 0);
+    assert!(!state.song_cache.lock().unwrap().contains(&song.id), "The song decoded at the old rate is dropped");
+    assert!(state.stem_id_map.lock().unwrap().is_empty());
+    assert_eq!(transport_state(&state).unwrap().song_id, None);
+    assert_eq!(state.database.get_settings().unwrap().sample_rate, rate as i32, "The new rate is saved");
+  }
+
+  #[test]
+  fn test_seek_with_autoplay_resumes_paused_song() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Scrub Song");
+    let stem = create_test_stem(&db, &song.id, "Keys");
+    let state = create_loaded_state(db, &[&stem]);
+
+    let mut engine = state.audio_engine.lock().unwrap();
+    engine.play().unwrap();
+    engine.pause().unwrap();
+    drop(engine);
+
+    seek_to_position_with(&state, 0.0, Some(false)).unwrap();
+    assert_eq!(state.audio_engine.lock().unwrap().state(), PlaybackState::Paused, "Scrubbing stays paused");
+
+    seek_to_position_with(&state, 0.0, Some(true)).unwrap();
+    assert_eq!(state.audio_engine.lock().unwrap().state(), PlaybackState::Playing);
+
+    seek_to_position_with(&state, 0.0, None).unwrap();
+    assert_eq!(state.audio_engine.lock().unwrap().state(), PlaybackState::Playing, "No autoplay keeps the state");
+
+    seek_to_position_with(&state, 0.0, Some(false)).unwrap();
+    assert_eq!(state.audio_engine.lock().unwrap().state(), PlaybackState::Paused);
+  }
+
+  #[test]
+  fn test_unload_song_frees_engine_and_cache() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Rehearsal Song");
+    let stem = create_test_stem(&db, &song.id, "Pads");
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate() as usize;
+    cache_song(&state, &song, &[(&stem, vec![0.0; rate * 2])]);
+    start_cached_song(&state, &song.id).unwrap();
+    assert_eq!(state.audio_engine.lock().unwrap().stem_count(), 1);
+
+    unload_song_from(&state, &song.id).unwrap();
+
+    let engine = state.audio_engine.lock().unwrap();
+    assert_eq!(engine.stem_count(), 0, "The engine holds no stems");
+    assert_eq!(engine.state(), PlaybackState::Stopped);
+    drop(engine);
+    assert!(!state.song_cache.lock().unwrap().contains(&song.id), "The cache released the song");
+    assert!(state.stem_id_map.lock().unwrap().is_empty());
+    assert_eq!(*state.current_song_id.lock().unwrap(), None);
+  }
+
+  #[test]
+  fn test_stop_clears_the_engine_with_the_current_song() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Stopped Song");
+    let stem = create_test_stem(&db, &song.id, "Keys");
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate() as usize;
+    cache_song(&state, &song, &[(&stem, vec![0.0; rate * 2])]);
+    start_cached_song(&state, &song.id).unwrap();
+
+    stop_current_song(&state).unwrap();
+
+    assert_eq!(state.audio_engine.lock().unwrap().stem_count(), 0, "Resume has nothing left to play");
+    assert!(state.stem_id_map.lock().unwrap().is_empty());
+    assert_eq!(transport_state(&state).unwrap().song_id, None);
+    assert!(state.song_cache.lock().unwrap().contains(&song.id), "The song stays cached");
+  }
+
+  #[test]
+  fn test_stem_mapping_after_play() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Mapped Song");
+    let vocals = create_test_stem(&db, &song.id, "Vocals");
+    let drums = create_test_stem(&db, &song.id, "Drums");
+
+    let engine = MultiTrackEngine::new(4).expect("Failed to create engine");
+    let state = test_app_state(db, engine);
+
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate() as usize;
+    cache_song(&state, &song, &[(&vocals, vec![0.0; rate * 2]), (&drums, vec![0.0; rate * 2])]);
+
+    assert!(stem_mapping(&state).stems.is_empty());
+    start_cached_song(&state, &song.id).unwrap();
+
+    let mapping = stem_mapping(&state);
+    assert_eq!(mapping.stems.len(), 2, "One entry per cached stem");
+    assert_eq!(mapping.slots.len(), 4);
+    for stem_id in [&vocals.id, &drums.id] {
+      let index = mapping.stems[stem_id];
+      assert!(mapping.slots[index], "Stem {} maps to an empty slot", stem_id);
+    }
+    assert_eq!(mapping.slots.iter().filter(|&&loaded| loaded).count(), 2);
+  }
+
+  #[test]
+  fn test_current_stems_after_play() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Current Song");
+    let stem = create_test_stem(&db, &song.id, "Vocals");
+    let other = create_test_song(&db, "Other Song");
+    create_test_stem(&db, &other.id, "Keys");
+
+    let engine = MultiTrackEngine::new(4).expect("Failed to create engine");
+    let state = test_app_state(db, engine);
+    assert!(current_stems(&state).unwrap().is_empty());
+    assert!(current_song(&state).unwrap().is_none());
+
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate() as usize;
+    cache_song(&state, &song, &[(&stem, vec![0.0; rate * 2])]);
+    start_cached_song(&state, &song.id).unwrap();
+
+    let stems = current_stems(&state).unwrap();
+    assert_eq!(stems.len(), 1);
+    assert_eq!(stems[0].id, stem.id);
+    assert_eq!(current_song(&state).unwrap().unwrap().name, "Current Song");
+  }
+
+  #[test]
+  fn test_loudness_target_equalizes_songs() {
+    let db = create_test_database();
+    let mut settings = db.get_settings().unwrap();
+    settings.loudness_target_enabled = true;
+    settings.loudness_target_lufs = -18.0;
+    db.update_settings(&settings).unwrap();
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate();
+
+    // Two seconds of a 1 kHz tone, one song mastered much hotter than the other
+    let tone = |amplitude: f32| -> Vec<f32> {
+      (0..rate as usize * 2)
+        .flat_map(|i| {
+          let sample = amplitude * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate as f32).sin();
+          [sample, sample]
+        })
+        .collect()
+    };
+
+    let mut played_loudness = Vec::new();
+    for (name, amplitude) in [("Hot Master", 0.5f32), ("Quiet Master", 0.08f32)] {
+      let song = create_test_song(&state.database, name);
+      let stem = create_test_stem(&state.database, &song.id, "Mix");
+      let samples = tone(amplitude);
+      let measured = crate::audio::loudness::integrated_loudness(&samples, 2, rate).unwrap();
+      state.database.set_song_loudness(&song.id, Some(measured)).unwrap();
+
+      cache_song(&state, &song, &[(&stem, samples.clone())]);
+      start_cached_song(&state, &song.id).unwrap();
+
+      let trim = db_gain(state.audio_engine.lock().unwrap().master_trim_db());
+      let trimmed: Vec<f32> = samples.iter().map(|sample| sample * trim).collect();
+      played_loudness.push(crate::audio::loudness::integrated_loudness(&trimmed, 2, rate).unwrap());
+    }
+
+    assert!((played_loudness[0] - played_loudness[1]).abs() < 0.1, "Both songs should play equally loud: {:?}", played_loudness);
+    assert!((played_loudness[0] + 18.0).abs() < 0.1, "Songs should play at the target: {:?}", played_loudness);
+  }
+
+  fn db_gain(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+  }
+
+  #[test]
+  fn test_playing_song_twice_counts_two_plays() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Played Song");
+    let stem = create_test_stem(&db, &song.id, "Vocals");
+
+    let engine = MultiTrackEngine::new(4).expect("Failed to create engine");
+    let state = test_app_state(db, engine);
+
+    // Caching the song (what preloading does) must not count as a play
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate() as usize;
+    cache_song(&state, &song, &[(&stem, vec![0.0; rate * 2])]);
+    let cached = state.database.get_song(&song.id).unwrap();
+    assert_eq!(cached.play_count, 0);
+    assert_eq!(cached.last_played_at, None);
+
+    let before = chrono::Utc::now().timestamp();
+    start_cached_song(&state, &song.id).unwrap();
+    start_cached_song(&state, &song.id).unwrap();
+
+    let played = state.database.get_song(&song.id).unwrap();
+    assert_eq!(played.play_count, 2);
+    assert!(played.last_played_at.unwrap() >= before);
+
+    let recent = state.database.get_recently_played(10).unwrap();
+    assert_eq!(recent.len(), 1);
+    assert_eq!(recent[0].id, song.id);
+  }
+
+  #[test]
+  fn test_play_song_emits_song_changed() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Announced Song");
+    let stem = create_test_stem(&db, &song.id, "Vocals");
+
+    let engine = MultiTrackEngine::new(4).expect("Failed to create engine");
+    let state = test_app_state(db, engine);
+
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate() as usize;
+    cache_song(&state, &song, &[(&stem, vec![0.0; rate * 2])]);
+
+    let events = Arc::new(Mutex::new(Vec::<(String, serde_json::Value)>::new()));
+    let recorder = {
+      let events = events.clone();
+      move |event: &str, payload: serde_json::Value| {
+        events.lock().unwrap().push((event.to_string(), payload));
+      }
+    };
+
+    block_on(play_song_with(song.id.clone(), &state, recorder)).unwrap();
+
+    let events = events.lock().unwrap();
+    let changed: Vec<&serde_json::Value> = events.iter()
+      .filter(|(event, _)| event == "playback:song-changed")
+      .map(|(_, payload)| payload)
+      .collect();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0]["song_id"], song.id.as_str());
+    assert_eq!(changed[0]["name"], "Announced Song");
+  }
+}
+
+#[cfg(test)]
+mod stem_rename_tests {
+  use super::*;
+
+  #[test]
+  fn test_rename_stem_persists_and_dedupes() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Rename Song");
+    let vocals = create_test_stem(&db, &song.id, "Vocals");
+    let guitar = create_test_stem(&db, &song.id, "Gtr");
+    let keys = create_test_stem(&db, &song.id, "Keys");
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    let renamed = stems::apply_stem_rename(&state, &guitar.id, "  Electric Guitar ").unwrap();
+    assert_eq!(renamed, "Electric Guitar");
+    assert_eq!(state.database.get_stem(&guitar.id).unwrap().name, "Electric Guitar");
+
+    // Colliding names get numbered; renaming a stem to its own name is not a collision
+    assert_eq!(stems::apply_stem_rename(&state, &keys.id, "vocals").unwrap(), "vocals 2");
+    assert_eq!(stems::apply_stem_rename(&state, &vocals.id, "Vocals").unwrap(), "Vocals");
+    assert_eq!(state.database.get_stem(&keys.id).unwrap().name, "vocals 2");
+
+    assert!(stems::apply_stem_rename(&state, &keys.id, "   ").is_err());
+    assert_eq!(state.database.get_stem(&keys.id).unwrap().name, "vocals 2", "Rejected names change nothing");
+  }
+}
+
+#[cfg(test)]
+mod library_validation_tests {
+  use super::*;
+
+  #[test]
+  fn test_validate_songs_reports_corrupt_and_missing_stems() {
+    let dir = test_temp_dir("validate");
+    let good_path = write_test_wav(&dir, "good.wav", TEST_WAV_SPEC, (0..4800).map(|i| (i % 100) as i16));
+
+    let corrupt_path = dir.join("corrupt.wav");
+    std::fs::write(&corrupt_path, b"this is not audio data at all").unwrap();
+
+    let db = create_test_database();
+    let song = create_test_song(&db, "Health Check");
+    create_stem_at(&db, &song.id, "Good", &good_path);
+    let corrupt = create_stem_at(&db, &song.id, "Corrupt", &corrupt_path);
+    let missing = create_stem_at(&db, &song.id, "Missing", &dir.join("missing.wav"));
+
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+    let report = validate_songs(&state, &[song]).unwrap();
+
+    assert_eq!(report.songs_checked, 1);
+    assert_eq!(report.stems_checked, 3);
+    assert_eq!(report.problems.len(), 2, "Only the bad stems should be reported");
+
+    let corrupt_problem = report.problems.iter().find(|p| p.stem_id == corrupt.id).unwrap();
+    assert_eq!(corrupt_problem.kind, StemProblemKind::Undecodable);
+    let missing_problem = report.problems.iter().find(|p| p.stem_id == missing.id).unwrap();
+    assert_eq!(missing_problem.kind, StemProblemKind::Missing);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_half_length_stem_is_flagged() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Uneven Export");
+    create_test_stem(&db, &song.id, "Drums");
+    create_test_stem(&db, &song.id, "Bass");
+    create_test_stem(&db, &song.id, "Keys");
+    let mut short = create_test_stem(&db, &song.id, "Vocals");
+    short.duration = 90.0;
+    db.update_stem(&short).unwrap();
+
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+    let mismatches = stem_length_mismatches(&state, &song.id, 1.0).unwrap();
+
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].stem_id, short.id);
+    assert_eq!(mismatches[0].median_duration, 180.0);
+    assert_eq!(mismatches[0].difference, -90.0);
+
+    // A generous tolerance lets it through
+    assert!(stem_length_mismatches(&state, &song.id, 120.0).unwrap().is_empty());
+  }
+
+  #[test]
+  fn test_songs_with_identical_stems_are_duplicates() {
+    let dir = test_temp_dir("duplicate");
+    let write = |name: &str, content: &[u8]| {
+      let path = dir.join(name);
+      std::fs::write(&path, content).unwrap();
+      path
+    };
+    let drums = b"drums audio".as_slice();
+    let bass = b"bass audio".as_slice();
+
+    let db = create_test_database();
+    let original = create_test_song(&db, "Great Is Thy Faithfulness");
+    create_stem_at(&db, &original.id, "Drums", &write("drums.wav", drums));
+    create_stem_at(&db, &original.id, "Bass", &write("bass.wav", bass));
+    // Same files imported again from another folder, in another order and under other names
+    let again = create_test_song(&db, "Great Is Thy Faithfulness (1)");
+    create_stem_at(&db, &again.id, "Bass Guitar", &write("copy_bass.wav", bass));
+    let copied_drums = create_stem_at(&db, &again.id, "Kit", &write("copy_drums.wav", drums));
+    // Shares a stem, but isn't the same song
+    let other = create_test_song(&db, "Other Song");
+    create_stem_at(&db, &other.id, "Drums", &write("other_drums.wav", drums));
+
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+    let clusters = duplicate_song_clusters(&state).unwrap();
+
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(clusters[0].stem_count, 2);
+    let mut ids: Vec<&str> = clusters[0].songs.iter().map(|song| song.id.as_str()).collect();
+    ids.sort();
+    let mut expected = vec![original.id.as_str(), again.id.as_str()];
+    expected.sort();
+    assert_eq!(ids, expected);
+    assert!(state.database.get_stem(&copied_drums.id).unwrap().file_hash.is_some(), "Computed hashes are saved");
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_songs_differing_after_the_first_megabyte_are_not_duplicates() {
+    let dir = test_temp_dir("duplicate");
+    // Same length and the same silent first megabyte, e.g. two songs' click tracks with a count-in
+    let intro = vec![0u8; 1024 * 1024];
+    let write = |name: &str, tail: &[u8]| {
+      let path = dir.join(name);
+      std::fs::write(&path, [intro.as_slice(), tail].concat()).unwrap();
+      path
+    };
+
+    let db = create_test_database();
+    let first = create_test_song(&db, "First Song");
+    create_stem_at(&db, &first.id, "Click", &write("first_click.wav", b"first"));
+    let second = create_test_song(&db, "Second Song");
+    let mut converted = create_stem_at(&db, &second.id, "Click", &write("second_click.wav", b"other"));
+    // An original that matches the first song's file doesn't make the stems the same
+    converted.original_file_path = Some(dir.join("first_click.wav").to_string_lossy().to_string());
+    db.update_stem(&converted).unwrap();
+
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+    assert!(duplicate_song_clusters(&state).unwrap().is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
+
+#[cfg(test)]
+mod mixer_tests {
+  use super::*;
+
+  #[test]
+  fn test_batched_stem_volumes_applied_and_persisted() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Scene Song");
+    let vocals = create_test_stem(&db, &song.id, "Vocals");
+    let drums = create_test_stem(&db, &song.id, "Drums");
+    let bass = create_test_stem(&db, &song.id, "Bass");
+
+    let state = create_loaded_state(db, &[&vocals, &drums, &bass]);
+
+    let updates = vec![
+      (vocals.id.clone(), 0.25),
+      (drums.id.clone(), 0.5),
+      (bass.id.clone(), 1.5), // Clamped to 1.0
+    ];
+    stems::apply_stem_volumes(&state, &updates).unwrap();
+
+    {
+      let engine = state.audio_engine.lock().unwrap();
+      let map = state.stem_id_map.lock().unwrap();
+      assert_eq!(engine.stem_volume(map[&vocals.id]), 0.25);
+      assert_eq!(engine.stem_volume(map[&drums.id]), 0.5);
+      assert_eq!(engine.stem_volume(map[&bass.id]), 1.0);
+    }
+
+    assert_eq!(state.database.get_stem(&vocals.id).unwrap().volume, 0.25);
+    assert_eq!(state.database.get_stem(&drums.id).unwrap().volume, 0.5);
+    assert_eq!(state.database.get_stem(&bass.id).unwrap().volume, 1.0);
+  }
+
+  #[test]
+  fn test_stem_delay_is_held_to_ten_seconds() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Delayed Song");
+    let mut click = create_test_stem(&db, &song.id, "Click");
+    click.sample_rate = 44100;
+    db.update_stem(&click).unwrap();
+
+    let state = create_loaded_state(db, &[&click]);
+    let project_rate = state.audio_engine.lock().unwrap().project_sample_rate() as i64;
+    let index = state.stem_id_map.lock().unwrap()[&click.id];
+
+    assert_eq!(stems::apply_stem_delay(&state, &click.id, 441).unwrap(), 441);
+    assert_eq!(state.audio_engine.lock().unwrap().stem_delay(index), project_rate / 100);
+
+    // A minute either way is cut to ten seconds of the source file and of the loaded samples
+    assert_eq!(stems::apply_stem_delay(&state, &click.id, 44100 * 60).unwrap(), 441000);
+    assert_eq!(state.audio_engine.lock().unwrap().stem_delay(index), project_rate * 10);
+    assert_eq!(stems::apply_stem_delay(&state, &click.id, i64::MIN).unwrap(), -441000);
+    assert_eq!(state.audio_engine.lock().unwrap().stem_delay(index), -project_rate * 10);
+    assert_eq!(state.database.get_stem(&click.id).unwrap().delay_samples, -441000);
+  }
+
+  #[test]
+  fn test_mixer_snapshot_recall_restores_saved_balance() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Snapshot Song");
+    let vocals = create_test_stem(&db, &song.id, "Vocals");
+    let drums = create_test_stem(&db, &song.id, "Drums");
+
+    let state = create_loaded_state(db, &[&vocals, &drums]);
+    stems::apply_stem_volumes(&state, &[(vocals.id.clone(), 0.6), (drums.id.clone(), 0.4)]).unwrap();
+    stems::apply_stem_trim(&state, &vocals.id, -6.0).unwrap();
+
+    let snapshot = stems::capture_mixer_snapshot(&state, &song.id, "Sunday mix").unwrap();
+    assert_eq!(state.database.list_mixer_snapshots(&song.id).unwrap().len(), 1);
+
+    // Change the balance after saving
+    stems::apply_stem_volumes(&state, &[(vocals.id.clone(), 0.1), (drums.id.clone(), 0.9)]).unwrap();
+    let mut muted_drums = state.database.get_stem(&drums.id).unwrap();
+    muted_drums.is_muted = true;
+    state.database.update_stem(&muted_drums).unwrap();
+    stems::apply_stem_trim(&state, &vocals.id, 3.0).unwrap();
+
+    stems::apply_mixer_snapshot(&state, &snapshot.id).unwrap();
+
+    let restored_vocals = state.database.get_stem(&vocals.id).unwrap();
+    let restored_drums = state.database.get_stem(&drums.id).unwrap();
+    assert_eq!(restored_vocals.volume, 0.6);
+    assert_eq!(restored_vocals.trim_db, -6.0);
+    assert_eq!(restored_drums.volume, 0.4);
+    assert!(!restored_drums.is_muted);
+
+    let engine = state.audio_engine.lock().unwrap();
+    let map = state.stem_id_map.lock().unwrap();
+    assert_eq!(engine.stem_volume(map[&vocals.id]), 0.6);
+    assert_eq!(engine.stem_volume(map[&drums.id]), 0.4);
+    assert!((engine.stem_trim_db(map[&vocals.id]) + 6.0).abs() < 1e-4);
+    drop(engine);
+
+    // Snapshots saved before trims were captured recall at 0 dB
+    let old: crate::database::StemMix = serde_json::from_str(r#"{"volume": 0.5, "is_muted": false, "pan": null}"#).unwrap();
+    assert_eq!(old.trim_db, 0.0);
+  }
+
+  #[test]
+  fn test_reset_mix_restores_import_defaults() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Default Mix Song");
+    let vocals = create_test_stem(&db, &song.id, "Vocals");
+    let drums = create_test_stem(&db, &song.id, "Drums");
+
+    let state = create_loaded_state(db, &[&vocals, &drums]);
+    stems::apply_stem_volumes(&state, &[(vocals.id.clone(), 0.3), (drums.id.clone(), 1.0)]).unwrap();
+    let mut muted_drums = state.database.get_stem(&drums.id).unwrap();
+    muted_drums.is_muted = true;
+    state.database.update_stem(&muted_drums).unwrap();
+    state.audio_engine.lock().unwrap().set_stem_mute(state.stem_id_map.lock().unwrap()[&drums.id], true);
+
+    stems::apply_default_mix(&state, &song.id).unwrap();
+
+    let reset_vocals = state.database.get_stem(&vocals.id).unwrap();
+    let reset_drums = state.database.get_stem(&drums.id).unwrap();
+    assert_eq!(reset_vocals.volume, 0.8);
+    assert_eq!(reset_drums.volume, 0.8);
+    assert!(!reset_drums.is_muted);
+    assert_eq!(reset_drums.default_volume, 0.8, "Defaults themselves are never changed");
+
+    let engine = state.audio_engine.lock().unwrap();
+    let map = state.stem_id_map.lock().unwrap();
+    assert_eq!(engine.stem_volume(map[&vocals.id]), 0.8);
+    assert!(!engine.is_stem_muted(map[&drums.id]));
+  }
+
+  #[test]
+  fn test_batched_stem_volumes_roll_back_on_unknown_stem() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Scene Song");
+    let vocals = create_test_stem(&db, &song.id, "Vocals");
+
+    let state = create_loaded_state(db, &[&vocals]);
+
+    let updates = vec![(vocals.id.clone(), 0.1), ("missing-stem".to_string(), 0.5)];
+    assert!(stems::apply_stem_volumes(&state, &updates).is_err());
+
+    assert_eq!(state.database.get_stem(&vocals.id).unwrap().volume, 0.8, "Nothing should be persisted");
+    let engine = state.audio_engine.lock().unwrap();
+    assert_eq!(engine.stem_volume(0), 1.0, "Engine should be untouched");
+  }
+}
+
+#[cfg(test)]
+mod song_decode_tests {
+  use super::*;
+
+  #[test]
+  fn test_decode_song_skips_corrupt_stem() {
+    let dir = test_temp_dir("decode");
+    let write_wav = |name: &str| write_test_wav(&dir, name, TEST_WAV_SPEC, (0..4800).map(|i| (i % 100) as i16));
+    let corrupt_path = dir.join("corrupt.wav");
+    std::fs::write(&corrupt_path, b"this is not audio data at all").unwrap();
+
+    let db = create_test_database();
+    let song = create_test_song(&db, "Resilient Song");
+    let drums = create_stem_at(&db, &song.id, "Drums", &write_wav("drums.wav"));
+    let corrupt = create_stem_at(&db, &song.id, "Corrupt", &corrupt_path);
+    let bass = create_stem_at(&db, &song.id, "Bass", &write_wav("bass.wav"));
+
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+    let events = Arc::new(Mutex::new(Vec::<(String, serde_json::Value)>::new()));
+    let recorder = {
+      let events = events.clone();
+      move |event: &str, payload: serde_json::Value| {
+        events.lock().unwrap().push((event.to_string(), payload));
+      }
+    };
+
+    block_on(load_once(&state.loading_songs, &state.song_cache, &song.id, || {
+      decode_song(song.id.clone(), &state, recorder)
+    })).expect("Song should load without the corrupt stem");
+
+    let cached = state.song_cache.lock().unwrap().get(&song.id).expect("Song should be cached");
+    let cached_ids: Vec<&str> = cached.stems.iter().map(|s| s.stem_id.as_str()).collect();
+    assert_eq!(cached_ids, vec![drums.id.as_str(), bass.id.as_str()]);
+
+    let events = events.lock().unwrap();
+    let warnings: Vec<&serde_json::Value> = events.iter()
+      .filter(|(event, _)| event == "stem:warning")
+      .map(|(_, payload)| payload)
+      .collect();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["stem_id"], corrupt.id.as_str());
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_warming_setlist_fills_disk_cache() {
+    let dir = test_temp_dir("warm");
+    // Distinct periods keep every file's content, and so its cache entry, apart
+    let write_wav = |name: &str, sample_rate: u32, period: i16| {
+      let spec = hound::WavSpec { sample_rate, ..TEST_WAV_SPEC };
+      write_test_wav(&dir, name, spec, (0..4410).map(move |i| (i as i16) % period))
+    };
+
+    let db = create_test_database();
+    let opener = create_test_song(&db, "Opener");
+    let closer = create_test_song(&db, "Closer");
+    let stems = vec![
+      create_stem_at(&db, &opener.id, "Drums", &write_wav("drums.wav", 48000, 100)),
+      create_stem_at(&db, &opener.id, "Bass", &write_wav("bass.wav", 44100, 100)),
+      create_stem_at(&db, &closer.id, "Keys", &write_wav("keys.wav", 48000, 90)),
+    ];
+    let now = chrono::Utc::now().timestamp();
+    let setlist = Setlist {
+      id: uuid::Uuid::new_v4().to_string(),
+      name: "Sunday".to_string(),
+      created_at: now,
+      updated_at: now,
+      song_ids: vec![opener.id.clone(), closer.id.clone(), opener.id.clone()],
+      position: 0,
+      color: None,
+      songs: vec![],
+    };
+    db.create_setlist(&setlist).unwrap();
+
+    let state = AppState::new(db, MultiTrackEngine::new(4).expect("Failed to create engine"), dir.join("cache"));
+    let rate = state.audio_engine.lock().unwrap().device_sample_rate();
+
+    let ignore = |_: &str, _: serde_json::Value| {};
+    let warmup = block_on(warm_setlist_disk_cache(&state, &setlist.id, ignore)).unwrap();
+    assert_eq!(warmup, DiskCacheWarmup { stems: 3, already_cached: 0, written: 3, failed: 0 });
+
+    for stem in &stems {
+      let hash = state.database.get_stem(&stem.id).unwrap().file_hash.expect("Warming stores the stem's hash");
+      let samples = state.disk_cache.get(&stem.id, &hash, rate);
+      assert!(samples.is_some_and(|s| !s.is_empty()), "Stem '{}' should be a disk cache hit", stem.name);
+    }
+
+    let again = block_on(warm_setlist_disk_cache(&state, &setlist.id, ignore)).unwrap();
+    assert_eq!(again.already_cached, 3, "A second warm-up has nothing to write");
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_prepare_song_fills_cache_without_playing() {
+    let dir = test_temp_dir("prepare");
+    let write_wav = |name: &str| write_test_wav(&dir, name, TEST_WAV_SPEC, (0..4800).map(|i| (i % 100) as i16));
+    let corrupt_path = dir.join("corrupt.wav");
+    std::fs::write(&corrupt_path, b"this is not audio data at all").unwrap();
+
+    let db = create_test_database();
+    let song = create_test_song(&db, "Opener");
+    create_stem_at(&db, &song.id, "Drums", &write_wav("drums.wav"));
+    create_stem_at(&db, &song.id, "Bass", &write_wav("bass.wav"));
+    create_stem_at(&db, &song.id, "Keys", &corrupt_path);
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    let ignore = |_: &str, _: serde_json::Value| {};
+    let readiness = block_on(prepare_song_with(song.id.clone(), &state, ignore)).unwrap();
+
+    assert_eq!(readiness.loaded_stems, 2);
+    assert_eq!(readiness.total_stems, 3);
+    assert_eq!(readiness.warnings.len(), 1);
+    assert!(readiness.warnings[0].starts_with("Keys: "), "{:?}", readiness.warnings);
+    assert!(state.song_cache.lock().unwrap().contains(&song.id));
+
+    // Nothing reached the engine
+    let engine = state.audio_engine.lock().unwrap();
+    assert_eq!(engine.state(), PlaybackState::Stopped);
+    assert_eq!(engine.active_stems(), 0);
+    assert!(state.current_song_id.lock().unwrap().is_none());
+    drop(engine);
+
+    // Preparing again is answered from the cache; the failed stem is still reported
+    let again = block_on(prepare_song_with(song.id.clone(), &state, ignore)).unwrap();
+    assert_eq!(again.loaded_stems, 2);
+    assert_eq!(again.warnings, vec!["Keys: not loaded".to_string()]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_song_over_stem_capacity_errors_or_merges_per_setting() {
+    let dir = test_temp_dir("overflow");
+    let spec = hound::WavSpec { bits_per_sample: 32, sample_format: hound::SampleFormat::Float, ..TEST_WAV_SPEC };
+    let write_wav = |name: &str, level: f32| write_test_wav(&dir, name, spec, std::iter::repeat_n(level, 4800 * 2));
+
+    let db = create_test_database();
+    let song = create_test_song(&db, "Big Session");
+    let drums = create_stem_at(&db, &song.id, "Drums", &write_wav("drums.wav", 0.1));
+    let strings = create_stem_at(&db, &song.id, "Strings", &write_wav("strings.wav", 0.2));
+    let choir = create_stem_at(&db, &song.id, "Choir", &write_wav("choir.wav", 0.3));
+    let state = test_app_state(db, MultiTrackEngine::new(2).expect("Failed to create engine"));
+
+    let ignore = |_: &str, _: serde_json::Value| {};
+
+    // The default refuses the song up front
+    let Err(error) = block_on(decode_song(song.id.clone(), &state, ignore)) else {
+      panic!("A song over capacity should be refused");
+    };
+    assert!(error.contains("3 stems") && error.contains("holds 2"), "{}", error);
+    assert!(!state.song_cache.lock().unwrap().contains(&song.id));
+
+    // Merging folds the last stems into one slot
+    let mut settings = state.database.get_settings().unwrap();
+    settings.stem_overflow_policy = "merge".to_string();
+    state.database.update_settings(&settings).unwrap();
+
+    let readiness = block_on(prepare_song_with(song.id.clone(), &state, ignore)).unwrap();
+    assert_eq!((readiness.loaded_stems, readiness.total_stems), (3, 3), "{:?}", readiness.warnings);
+
+    let cache = state.song_cache.lock().unwrap();
+    let cached = cache.peek(&song.id).unwrap();
+    assert_eq!(cached.stems.len(), 2);
+    assert_eq!(cached.stems[0].stem_id, drums.id);
+    assert!(cached.stems.iter().all(|s| s.stem_id != strings.id && s.stem_id != choir.id));
+    assert!((cached.stems[1].samples[0] - 0.5 * 0.8).abs() < 1e-6, "Strings and choir are summed at their volume");
+    drop(cache);
+
+    start_cached_song(&state, &song.id).unwrap();
+    assert_eq!(state.audio_engine.lock().unwrap().active_stems(), 2);
+
+    // A stem with its own trim can't lose it in the mixdown, so the others are merged instead
+    let mut trimmed = state.database.get_stem(&choir.id).unwrap();
+    trimmed.trim_db = -3.0;
+    state.database.update_stem(&trimmed).unwrap();
+    state.song_cache.lock().unwrap().clear();
+    block_on(prepare_song_with(song.id.clone(), &state, ignore)).unwrap();
+    let cache = state.song_cache.lock().unwrap();
+    let cached = cache.peek(&song.id).unwrap();
+    assert_eq!(cached.stems[0].stem_id, choir.id);
+    assert!(cached.stems.iter().all(|s| s.stem_id != drums.id && s.stem_id != strings.id));
+    drop(cache);
+
+    // With two stems that can't be merged, the song doesn't fit
+    let mut gated = state.database.get_stem(&drums.id).unwrap();
+    gated.gate_enabled = true;
+    state.database.update_stem(&gated).unwrap();
+    state.song_cache.lock().unwrap().clear();
+    let Err(error) = block_on(decode_song(song.id.clone(), &state, ignore)) else {
+      panic!("Stems with their own settings shouldn't be merged");
+    };
+    assert!(error.contains("Can't fit 3 stems into 2 slots"), "{}", error);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_decode_song_fails_when_no_stem_decodes() {
+    let dir = test_temp_dir("decode");
+    let corrupt_path = dir.join("corrupt.wav");
+    std::fs::write(&corrupt_path, b"this is not audio data at all").unwrap();
+
+    let db = create_test_database();
+    let song = create_test_song(&db, "Broken Song");
+    create_stem_at(&db, &song.id, "Corrupt", &corrupt_path);
+
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+    let result = block_on(decode_song(song.id.clone(), &state, |_: &str, _: serde_json::Value| {}));
+
+    assert!(result.is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_decode_limit_caps_concurrent_jobs() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let active = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let jobs: Vec<_> = (0..8)
+      .map(|i| {
+        let active = active.clone();
+        let peak = peak.clone();
+        move || {
+          let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+          peak.fetch_max(now, Ordering::SeqCst);
+          std::thread::sleep(std::time::Duration::from_millis(20));
+          active.fetch_sub(1, Ordering::SeqCst);
+          i
+        }
+      })
+      .collect();
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(2));
+    let results = block_on(run_blocking_limited(&semaphore, jobs));
+
+    let values: Vec<i32> = results.into_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(values, (0..8).collect::<Vec<_>>(), "Results keep job order");
+    assert!(peak.load(Ordering::SeqCst) <= 2, "At most 2 jobs should run at once");
+    assert!(peak.load(Ordering::SeqCst) >= 1);
+  }
+
+  #[test]
+  fn test_decode_song_with_single_decode_thread() {
+    let dir = test_temp_dir("decode");
+
+    let db = create_test_database();
+    let song = create_test_song(&db, "Queued Song");
+    for name in ["Drums", "Bass", "Keys", "Vocals"] {
+      let path = write_test_wav(&dir, &format!("{}.wav", name), TEST_WAV_SPEC, (0..960).map(|i| (i % 50) as i16));
+      create_stem_at(&db, &song.id, name, &path);
+    }
+
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+    assert_eq!(state.set_max_decode_threads(1), 1);
+
+    let cached = block_on(decode_song(song.id.clone(), &state, |_: &str, _: serde_json::Value| {}))
+      .expect("All stems should decode one at a time");
+
+    assert_eq!(cached.stems.len(), 4);
+    assert!(cached.stems.iter().all(|stem| stem.frame_count() == 480));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  // In-memory WAV whose reads drop out once `fail_after` bytes have been read, like a share that
+  // goes away mid-decode
+  struct FlakyReader {
+    data: std::io::Cursor<Vec<u8>>,
+    fail_after: Option<u64>,
+  }
+
+  impl std::io::Read for FlakyReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+      let Some(limit) = self.fail_after else {
+        return self.data.read(buf);
+      };
+      let left = limit.saturating_sub(self.data.position()) as usize;
+      if left == 0 {
+        return Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "share went away"));
+      }
+      let len = buf.len().min(left);
+      self.data.read(&mut buf[..len])
+    }
+  }
+
+  impl std::io::Seek for FlakyReader {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+      self.data.seek(pos)
+    }
+  }
+
+  impl symphonia::core::io::MediaSource for FlakyReader {
+    fn is_seekable(&self) -> bool {
+      true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+      Some(self.data.get_ref().len() as u64)
+    }
+  }
+
+  #[test]
+  fn test_decode_at_rate_retries_a_reader_that_drops_out() {
+    let mut wav = std::io::Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut wav, TEST_WAV_SPEC).unwrap();
+    for i in 0..9600 {
+      writer.write_sample((i % 100) as i16).unwrap();
+    }
+    writer.finalize().unwrap();
+    let wav = wav.into_inner();
+
+    let open = |opens: &mut u32, drop_outs: u32| {
+      *opens += 1;
+      let reader = FlakyReader {
+        data: std::io::Cursor::new(wav.clone()),
+        fail_after: (*opens <= drop_outs).then_some(4096),
+      };
+      crate::audio::AudioDecoder::from_source(Box::new(reader), Some("wav"))
+    };
+
+    // The first read drops out partway through the samples; the retry decodes all of them
+    let mut opens = 0;
+    let samples = decode_with_at_rate(|| open(&mut opens, 1), "Drums", 48000, |_, _| {}).unwrap();
+    assert_eq!(opens, 2);
+    assert_eq!(samples.len(), 9600);
+
+    // A reader that never recovers fails once the retries run out
+    let mut opens = 0;
+    let error = decode_with_at_rate(|| open(&mut opens, u32::MAX), "Drums", 48000, |_, _| {}).unwrap_err();
+    assert_eq!(opens, crate::audio::decoder::TRANSIENT_IO_ATTEMPTS);
+    assert!(error.starts_with("Failed to decode 'Drums'"), "{}", error);
+  }
+}
+
+#[cfg(test)]
+mod tap_tempo_tests {
+  use super::*;
+  use std::time::{Duration, Instant};
+
+  #[test]
+  fn test_even_taps_give_bpm() {
+    let mut tap_tempo = TapTempo::new();
+    let start = Instant::now();
+
+    assert_eq!(tap_tempo.tap_at(start), None, "One tap has no interval yet");
+    let mut bpm = None;
+    for i in 1..6 {
+      bpm = tap_tempo.tap_at(start + Duration::from_millis(500 * i));
+    }
+    assert!((bpm.unwrap() - 120.0).abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_long_pause_restarts_tapping() {
+    let mut tap_tempo = TapTempo::new();
+    let start = Instant::now();
+    tap_tempo.tap_at(start);
+    tap_tempo.tap_at(start + Duration::from_millis(1000)); // 60 BPM
+
+    // After a 3s gap the old taps are dropped
+    let restart = start + Duration::from_millis(4000);
+    assert_eq!(tap_tempo.tap_at(restart), None);
+    let bpm = tap_tempo.tap_at(restart + Duration::from_millis(400)).unwrap();
+    assert!((bpm - 150.0).abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_commit_tap_tempo_sets_song_tempo() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Tapped Song");
+    let state = test_app_state(db, MultiTrackEngine::new(2).expect("Failed to create engine"));
+
+    assert!(commit_tapped_tempo(&state, &song.id).is_err(), "Nothing tapped yet");
+
+    {
+      let mut tap_tempo = state.tap_tempo.lock().unwrap();
+      let start = Instant::now();
+      for i in 0..4 {
+        tap_tempo.tap_at(start + Duration::from_millis(600 * i));
+      }
+    }
+
+    let bpm = commit_tapped_tempo(&state, &song.id).unwrap();
+    assert_eq!(bpm, 100.0);
+    assert_eq!(state.database.get_song(&song.id).unwrap().tempo, Some(100.0));
+    assert_eq!(state.tap_tempo.lock().unwrap().bpm(), None, "Committing starts a new sequence");
+  }
+}
+
+#[cfg(test)]
+mod sample_rate_tests {
+  use super::*;
+
+  #[test]
+  fn test_sample_rates_in_ranges() {
+    // A discrete device and one reporting a continuous range
+    assert_eq!(sample_rates_in_ranges(&[(48000, 48000), (44100, 44100)]), vec![44100, 48000]);
+    assert_eq!(
+      sample_rates_in_ranges(&[(44100, 96000)]),
+      vec![44100, 48000, 88200, 96000]
+    );
+    assert!(sample_rates_in_ranges(&[]).is_empty());
+  }
+
+  #[test]
+  fn test_supported_sample_rates_for_default_device() {
+    // Best-effort: machines without an output device have nothing to report
+    let Ok(rates) = get_supported_sample_rates(None) else {
+      return;
+    };
+
+    assert!(!rates.is_empty());
+    assert!(rates.windows(2).all(|pair| pair[0] < pair[1]), "Rates should be sorted and distinct");
+  }
+}
+
+#[cfg(test)]
+mod audio_host_tests {
+  use super::*;
+
+  #[test]
+  fn test_list_audio_hosts_includes_default_host() {
+    let hosts = list_audio_hosts().unwrap();
+
+    assert!(!hosts.is_empty(), "At least the platform's default host should be listed");
+    assert!(hosts[0].is_default);
+    assert_eq!(hosts.iter().filter(|h| h.is_default).count(), 1);
+
+    let engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+    assert!(hosts.iter().any(|h| h.id == engine.audio_host_name()));
+  }
+}
+
+#[cfg(test)]
+mod settings_file_tests {
+  use super::*;
+
+  #[test]
+  fn test_exported_settings_round_trip() {
+    let source = create_test_database();
+    let mut settings = source.get_settings().unwrap();
+    settings.audio_output_device = Some("Interface Out 1-2".to_string());
+    settings.audio_buffer_size = 256;
+    settings.cue_pan_side = "left".to_string();
+    settings.solo_mode = "exclusive".to_string();
+    settings.fade_curve = "equal_power".to_string();
+    settings.import_sample_rate = 44100;
+    settings.in_memory_cache_gb = 1.5;
+    settings.practice_mode = true;
+    settings.log_level = "debug".to_string();
+    source.update_settings(&settings).unwrap();
+
+    let path = std::env::temp_dir().join(format!("trax_settings_{}.json", uuid::Uuid::new_v4()));
+    let path_str = path.to_str().unwrap();
+    write_settings_file(&source, path_str).unwrap();
+
+    let fresh = create_test_database();
+    let imported = read_settings_file(&fresh, path_str).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(imported, settings);
+    assert_eq!(fresh.get_settings().unwrap(), settings);
+  }
+
+  #[test]
+  fn test_settings_file_ignores_unknown_and_rejects_invalid() {
+    let db = create_test_database();
+    let path = std::env::temp_dir().join(format!("trax_settings_{}.json", uuid::Uuid::new_v4()));
+    let path_str = path.to_str().unwrap();
+
+    // Fields from a newer version are ignored and missing ones take their defaults
+    std::fs::write(&path, r#"{"settings": {"theme": "light", "future_option": 7}, "extra_table": []}"#).unwrap();
+    let imported = read_settings_file(&db, path_str).unwrap();
+    assert_eq!(imported.theme, "light");
+    assert_eq!(imported.audio_buffer_size, crate::database::AppSettings::default().audio_buffer_size);
+
+    std::fs::write(&path, r#"{"settings": {"solo_mode": "sometimes"}}"#).unwrap();
+    assert!(read_settings_file(&db, path_str).is_err());
+    assert_eq!(db.get_settings().unwrap().theme, "light", "Invalid files shouldn't change anything");
+
+    std::fs::remove_file(&path).ok();
+  }
+
+  #[test]
+  fn test_imported_settings_apply_highpass_and_loudness_target() {
+    let db = create_test_database();
+    let song = create_test_song(&db, "Loud Song");
+    db.set_song_loudness(&song.id, Some(-8.0)).unwrap();
+
+    let engine = MultiTrackEngine::new(2).expect("Failed to create engine");
+    let state = test_app_state(db, engine);
+    *state.current_song_id.lock().unwrap() = Some(song.id.clone());
+
+    let mut settings = state.database.get_settings().unwrap();
+    settings.master_highpass_enabled = true;
+    settings.master_highpass_hz = 40.0;
+    settings.loudness_target_enabled = true;
+    settings.loudness_target_lufs = -14.0;
+    apply_imported_settings(&state, &settings);
+
+    let engine = state.audio_engine.lock().unwrap();
+    assert_eq!(engine.master_highpass(), (true, 40.0));
+    assert!((engine.master_trim_db() + 6.0).abs() < 0.01, "A -8 LUFS song is trimmed to -14 LUFS");
+  }
+}
+
+#[cfg(test)]
+mod preload_tests {
+  use super::*;
+
+  fn ids(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+  }
+
+  #[test]
+  fn test_advancing_changes_preload_queue() {
+    let songs = ids(&["a", "b", "c", "d", "e"]);
+
+    let first = preload_plan(&songs, 0).unwrap();
+    assert_eq!(first.pinned, "a");
+    assert_eq!(first.queue, ids(&["a", "b", "c"]));
+    assert!(first.evict.is_empty());
+
+    let second = preload_plan(&songs, 1).unwrap();
+    assert_eq!(second.queue, ids(&["b", "c", "d"]), "Advancing queues the next song");
+    assert!(second.evict.is_empty(), "The previous song is kept for a quick step back");
+
+    let fourth = preload_plan(&songs, 3).unwrap();
+    assert_eq!(fourth.queue, ids(&["d", "e"]));
+    assert_eq!(fourth.evict, ids(&["a", "b"]));
+
+    assert!(preload_plan(&songs, 5).is_none());
+  }
+
+  #[test]
+  fn test_repeated_song_is_not_evicted() {
+    let songs = ids(&["a", "b", "c", "a"]);
+    let plan = preload_plan(&songs, 2).unwrap();
+    assert_eq!(plan.queue, ids(&["c", "a"]));
+    assert!(plan.evict.is_empty(), "A song coming up again stays cached");
+  }
+
+  #[test]
+  fn test_moving_cursor_bumps_generation() {
+    let db = create_test_database();
+    let a = create_test_song(&db, "A").id;
+    let b = create_test_song(&db, "B").id;
+    let now = chrono::Utc::now().timestamp();
+    let setlist = Setlist {
+      id: uuid::Uuid::new_v4().to_string(),
+      name: "Preload Set".to_string(),
+      created_at: now,
+      updated_at: now,
+      song_ids: vec![a.clone(), b.clone()],
+      position: 0,
+      color: None,
+      songs: vec![],
+    };
+    db.create_setlist(&setlist).unwrap();
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    let (song, first) = move_setlist_cursor(&state, &setlist.id, 0).unwrap().unwrap();
+    assert_eq!(song.id, a);
+    let (song, second) = move_setlist_cursor(&state, &setlist.id, 1).unwrap().unwrap();
+    assert_eq!(song.id, b);
+    assert!(second > first, "A stale preload must see the cursor moved");
+
+    assert!(move_setlist_cursor(&state, &setlist.id, 2).unwrap().is_none());
+    assert_eq!(state.setlist_cursor.lock().unwrap().index, 1, "Moving past the end leaves the cursor alone");
+  }
+
+  #[test]
+  fn test_auto_advance_plays_next_song_at_end() {
+    let db = create_test_database();
+    let first = create_test_song(&db, "First");
+    let second = create_test_song(&db, "Second");
+    let now = chrono::Utc::now().timestamp();
+    let setlist = Setlist {
+      id: uuid::Uuid::new_v4().to_string(),
+      name: "Instrumental Bed".to_string(),
+      created_at: now,
+      updated_at: now,
+      song_ids: vec![first.id.clone(), second.id.clone()],
+      position: 0,
+      color: None,
+      songs: vec![],
+    };
+    db.create_setlist(&setlist).unwrap();
+    let state = test_app_state(db, MultiTrackEngine::new(4).expect("Failed to create engine"));
+
+    // Short songs so the first one ends within a few blocks
+    for song in [&first, &second] {
+      let stem = create_test_stem(&state.database, &song.id, "Pad");
+      cache_song(&state, song, &[(&stem, vec![0.1; 256 * 2])]);
+    }
+
+    move_setlist_cursor(&state, &setlist.id, 0).unwrap().unwrap();
+    state.setlist_cursor.lock().unwrap().auto_advance = true;
+    start_cached_song(&state, &first.id).unwrap();
+
+    let ignore = |_: &str, _: serde_json::Value| {};
+    assert!(block_on(advance_after_song_end(&state, ignore)).unwrap().is_none(), "Nothing ended yet");
+
+    let mut output = vec![0.0f32; 512 * 2];
+    state.audio_engine.lock().unwrap().process_block(&mut output, 2);
+    assert_eq!(state.audio_engine.lock().unwrap().state(), PlaybackState::Stopped);
+
+    let (song, _) = block_on(advance_after_song_end(&state, ignore)).unwrap().unwrap();
+    assert_eq!(song.id, second.id);
+    assert_eq!(state.current_song_id.lock().unwrap().as_deref(), Some(second.id.as_str()));
+    assert_eq!(state.setlist_cursor.lock().unwrap().index, 1);
+    assert_eq!(state.audio_engine.lock().unwrap().state(), PlaybackState::Playing);
+  }
+}
+
+#[cfg(test)]
+mod waveform_tests {
+  use super::*;
+
+  #[test]
+  fn test_stem_waveform_is_computed_once_and_cached() {
+    let dir = test_temp_dir("waveform");
+
+    // First half quiet, second half a full-scale square wave
+    let path = write_test_wav(&dir, "bass.wav", TEST_WAV_SPEC, (0..4800).flat_map(|frame| {
+      let value: i16 = if frame < 2400 { 0 } else if frame % 2 == 0 { i16::MAX } else { -i16::MAX };
+      [value, value]
+    }));
+
+    let db = create_test_database();
+    let song = create_test_song(&db, "Waveform Song");
+    let stem = create_stem_at(&db, &song.id, "Bass", &path);
+    let state = test_app_state(db, MultiTrackEngine::new(2).expect("Failed to create engine"));
+
+    let waveform = stems::stem_waveform(&state, &stem.id, 4).unwrap();
+    assert_eq!(waveform.min.len(), 4);
+    assert_eq!(waveform.max.len(), 4);
+    assert_eq!(waveform.max[0], 0.0, "The quiet half has no peaks");
+    assert!(waveform.max[3] > 0.99 && waveform.min[3] < -0.99, "The loud half peaks at full scale");
+    assert_eq!(stems::stem_waveform(&state, &stem.id, 4).unwrap(), waveform);
+
+    // Replacing the audio (as freezing or relocating the stem does) computes new peaks
+    let quiet_path = write_test_wav(&dir, "bass_quiet.wav", TEST_WAV_SPEC, std::iter::repeat_n(0i16, 4800 * 2));
+    let mut moved = state.database.get_stem(&stem.id).unwrap();
+    moved.file_path = quiet_path.to_string_lossy().to_string();
+    state.database.update_stem(&moved).unwrap();
+    assert_eq!(stems::stem_waveform(&state, &stem.id, 4).unwrap().max, vec![0.0; 4]);
+    std::fs::copy(&path, &quiet_path).unwrap();
+    let waveform = stems::stem_waveform(&state, &stem.id, 4).unwrap();
+    assert!(waveform.max[3] > 0.99, "An edited file is read again");
+
+    // With the source gone, only the cached peaks can answer
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(stems::stem_waveform(&state, &stem.id, 4).unwrap(), waveform);
+    assert!(stems::stem_waveform(&state, &stem.id, 8).is_err(), "Another resolution needs the audio");
+    assert!(stems::stem_waveform(&state, &stem.id, 0).is_err());
+  }
+
+  #[test]
+  fn test_preview_waveform_decodes_only_the_first_second() {
+    let dir = test_temp_dir("preview_waveform");
+
+    // Half scale for the first second, full scale for the four after it
+    let spec = hound::WavSpec { sample_rate: 44100, ..TEST_WAV_SPEC };
+    let path = write_test_wav(&dir, "long_pad.wav", spec, (0..44100 * 5).flat_map(|frame| {
+      let level = if frame < 44100 { i16::MAX / 2 } else { i16::MAX };
+      let value = if frame % 2 == 0 { level } else { -level };
+      [value, value]
+    }));
+
+    let peaks = preview_waveform(path.to_str().unwrap(), 32, 1.0).unwrap();
+    assert_eq!(peaks.min.len(), 32);
+    assert_eq!(peaks.max.len(), 32);
+    assert!(peaks.max.iter().all(|&p| (p - 0.5).abs() < 0.01), "Only the half-scale first second is read");
+    assert!(peaks.min[31] < -0.49, "The last bucket still has audio");
+
+    assert!(preview_waveform(path.to_str().unwrap(), 0, 1.0).is_err());
+    assert!(preview_waveform(path.to_str().unwrap(), 32, 0.0).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
+
+#[cfg(test)]
+mod normalize_tests {
+  use super::*;
+
+  #[test]
+  fn test_normalize_stem_peak_lifts_half_scale_stem_to_full_scale() {
+    let dir = test_temp_dir("normalize");
+    let spec = hound::WavSpec { bits_per_sample: 32, sample_format: hound::SampleFormat::Float, ..TEST_WAV_SPEC };
+    let path = write_test_wav(&dir, "di_guitar.wav", spec, (0..4800).flat_map(|frame| {
+      let value: f32 = if frame % 2 == 0 { 0.5 } else { -0.25 };
+      [value, value]
+    }));
+
+    let db = create_test_database();
+    let song = create_test_song(&db, "Normalize Song");
+    let stem = create_stem_at(&db, &song.id, "DI Guitar", &path);
+    let state = test_app_state(db, MultiTrackEngine::new(2).expect("Failed to create engine"));
+
+    let trim_db = stems::normalize_stem_peak_to(&state, &stem.id, 0.0).unwrap();
+    assert!((trim_db - 6.02).abs() < 0.05, "A 0.5 peak needs about +6 dB, got {}", trim_db);
+    let stored = state.database.get_stem(&stem.id).unwrap();
+    assert_eq!(stored.trim_db, trim_db);
+
+    assert!(stems::normalize_stem_peak_to(&state, &stem.id, 3.0).is_err(), "Targets above 0 dBFS are refused");
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
+
+#[cfg(test)]
+mod command_error_tests {
+  use super::*;
+  use crate::import::{import_song, ImportRequest};
+  use std::path::PathBuf;
+
+  fn request(file_paths: Vec<PathBuf>) -> ImportRequest {
+    ImportRequest {
+      file_paths,
+      title: "Error Song".to_string(),
+      artist: None,
+      key: None,
+      time_signature: None,
+      align_leading_silence: false,
+      target_sample_rate: None,
+      generate_mixdown: false,
+    }
+  }
+
+  fn error_code(error: CommandError) -> String {
+    serde_json::to_value(&error).unwrap()["code"].as_str().unwrap().to_string()
+  }
+
+  #[test]
+  fn test_missing_file_import_reports_file_not_found() {
+    let db = create_test_database();
+    let missing = std::env::temp_dir().join(format!("trax_missing_{}.wav", uuid::Uuid::new_v4()));
+
+    let error = CommandError::from(import_song(&db, request(vec![missing])).unwrap_err()).context("Import failed");
+    assert_eq!(error.code, ErrorCode::FileNotFound);
+    assert!(error.message.starts_with("Import failed: "));
+    assert_eq!(error_code(error), "FILE_NOT_FOUND");
+  }
+
+  #[test]
+  fn test_one_missing_file_among_several_reports_file_not_found() {
+    let dir = test_temp_dir("error");
+    let present = write_test_wav(&dir, "keys.wav", TEST_WAV_SPEC, (0..4800).map(|i| (i % 200) as i16));
+    let missing = dir.join("bass.wav");
+
+    let db = create_test_database();
+    let error = CommandError::from(import_song(&db, request(vec![present, missing.clone()])).unwrap_err());
+    assert_eq!(error.code, ErrorCode::FileNotFound);
+    assert!(error.message.contains(&*missing.to_string_lossy()), "The missing file is named: {}", error.message);
+    assert!(db.list_songs(None).unwrap().is_empty(), "Nothing is imported");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_cache_and_preview_errors_are_categorized() {
+    let state = test_app_state(create_test_database(), MultiTrackEngine::new(2).expect("Failed to create engine"));
+    assert_eq!(cached_song_info(&state, "not-cached").unwrap_err().code, ErrorCode::Cache);
+
+    let missing = std::env::temp_dir().join(format!("trax_missing_{}.wav", uuid::Uuid::new_v4()));
+    assert_eq!(preview_waveform(missing.to_str().unwrap(), 0, 1.0).unwrap_err().code, ErrorCode::Validation);
+    assert_eq!(preview_waveform(missing.to_str().unwrap(), 32, 1.0).unwrap_err().code, ErrorCode::FileNotFound);
+  }
+
+  #[test]
+  fn test_duplicate_import_reports_duplicate() {
+    let dir = test_temp_dir("error");
+    let first = write_test_wav(&dir, "guitar.wav", TEST_WAV_SPEC, (0..4800).map(|i| (i % 200) as i16));
+    let copy = dir.join("guitar_copy.wav");
+    std::fs::copy(&first, &copy).unwrap();
+
+    let db = create_test_database();
+    let error = CommandError::from(import_song(&db, request(vec![first, copy])).unwrap_err());
+    assert_eq!(error_code(error), "DUPLICATE");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
 }
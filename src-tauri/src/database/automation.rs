@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use rusqlite::{Connection, Result, params};
+use super::models::AutomationPoint;
+
+// Replace a stem's automation (points stored as JSON). An empty list removes it
+pub fn set_stem_automation(conn: &Connection, stem_id: &str, points: &[AutomationPoint]) -> Result<()> {
+  if points.is_empty() {
+    conn.execute("DELETE FROM stem_automation WHERE stem_id = ?1", [stem_id])?;
+    return Ok(());
+  }
+
+  let points_json = serde_json::to_string(points)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+  conn.execute(
+    "INSERT OR REPLACE INTO stem_automation (stem_id, points) VALUES (?1, ?2)",
+    params![stem_id, points_json],
+  )?;
+  Ok(())
+}
+
+// Get a stem's automation points (empty if it has none)
+pub fn get_stem_automation(conn: &Connection, stem_id: &str) -> Result<Vec<AutomationPoint>> {
+  let mut stmt = conn.prepare("SELECT points FROM stem_automation WHERE stem_id = ?1")?;
+  let mut rows = stmt.query_map([stem_id], |row| points_from_row(row, 0))?;
+
+  match rows.next() {
+    Some(points) => points,
+    None => Ok(Vec::new()),
+  }
+}
+
+// Automation for every stem of a song that has some, keyed by stem ID
+pub fn get_song_automation(conn: &Connection, song_id: &str) -> Result<HashMap<String, Vec<AutomationPoint>>> {
+  let mut stmt = conn.prepare(
+    "SELECT a.stem_id, a.points FROM stem_automation a
+     JOIN stems s ON s.id = a.stem_id WHERE s.song_id = ?1"
+  )?;
+
+  let rows = stmt.query_map([song_id], |row| Ok((row.get(0)?, points_from_row(row, 1)?)))?;
+  rows.collect()
+}
+
+fn points_from_row(row: &rusqlite::Row, index: usize) -> Result<Vec<AutomationPoint>> {
+  let points_json: String = row.get(index)?;
+  serde_json::from_str(&points_json)
+    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+}
@@ -0,0 +1,62 @@
+use rusqlite::{Connection, Result, params};
+use super::models::MixerSnapshot;
+
+// Save a mixer snapshot (stem settings stored as JSON)
+pub fn create_mixer_snapshot(conn: &Connection, snapshot: &MixerSnapshot) -> Result<()> {
+  let stems_json = serde_json::to_string(&snapshot.stems)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+  conn.execute(
+    "INSERT INTO mixer_snapshots (id, song_id, name, stems, created_at)
+     VALUES (?1, ?2, ?3, ?4, ?5)",
+    params![
+      snapshot.id,
+      snapshot.song_id,
+      snapshot.name,
+      stems_json,
+      snapshot.created_at,
+    ],
+  )?;
+  Ok(())
+}
+
+// Get a mixer snapshot by ID
+pub fn get_mixer_snapshot(conn: &Connection, id: &str) -> Result<MixerSnapshot> {
+  conn.query_row(
+    "SELECT id, song_id, name, stems, created_at
+     FROM mixer_snapshots WHERE id = ?1",
+    [id],
+    snapshot_from_row,
+  )
+}
+
+// List a song's mixer snapshots (oldest first)
+pub fn list_mixer_snapshots(conn: &Connection, song_id: &str) -> Result<Vec<MixerSnapshot>> {
+  let mut stmt = conn.prepare(
+    "SELECT id, song_id, name, stems, created_at
+     FROM mixer_snapshots WHERE song_id = ?1 ORDER BY created_at ASC, name ASC"
+  )?;
+
+  let snapshots = stmt.query_map([song_id], snapshot_from_row)?;
+  snapshots.collect()
+}
+
+// Delete a mixer snapshot
+pub fn delete_mixer_snapshot(conn: &Connection, id: &str) -> Result<()> {
+  conn.execute("DELETE FROM mixer_snapshots WHERE id = ?1", [id])?;
+  Ok(())
+}
+
+fn snapshot_from_row(row: &rusqlite::Row) -> Result<MixerSnapshot> {
+  let stems_json: String = row.get(3)?;
+  let stems = serde_json::from_str(&stems_json)
+    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?;
+
+  Ok(MixerSnapshot {
+    id: row.get(0)?,
+    song_id: row.get(1)?,
+    name: row.get(2)?,
+    stems,
+    created_at: row.get(4)?,
+  })
+}
@@ -5,6 +5,12 @@ mod songs;
 mod stems;
 mod setlists;
 mod settings;
+mod stem_keywords;
+mod mixer_snapshots;
+mod setlist_templates;
+mod waveforms;
+mod automation;
+mod maintenance;
 
 #[cfg(test)]
 mod tests;
@@ -22,8 +28,12 @@ pub struct Database {
 impl Database {
   // Create a new database instance with file-based storage
   pub fn new() -> Result<Self> {
-    let db_path = connection::get_database_path();
-    let conn = connection::create_connection(&db_path)?;
+    Self::open(&connection::get_database_path())
+  }
+
+  // Open (or create) a file-based database at the given path
+  pub fn open(db_path: &std::path::PathBuf) -> Result<Self> {
+    let conn = connection::create_connection(db_path)?;
 
     // Initialize schema and run migrations
     schema::initialize_schema(&conn)?;
@@ -91,6 +101,33 @@ impl Database {
     songs::list_songs(&conn, filter)
   }
 
+  // Insert a song and all of its stems atomically (rolls back everything if any insert fails)
+  pub fn import_song_transactional(&self, song: &Song, stems: &[Stem]) -> Result<()> {
+    let mut conn = self.get_connection()?;
+    let tx = conn.transaction()?;
+
+    songs::create_song(&tx, song)?;
+    for stem in stems {
+      stems::create_stem(&tx, stem)?;
+    }
+
+    // Dropping the transaction without committing rolls it back
+    tx.commit()
+  }
+
+  // Save a song's and its stems' new file locations together (all or nothing)
+  pub fn relocate_song_transactional(&self, song: &Song, stems: &[Stem]) -> Result<()> {
+    let mut conn = self.get_connection()?;
+    let tx = conn.transaction()?;
+
+    songs::update_song(&tx, song)?;
+    for stem in stems {
+      stems::update_stem(&tx, stem)?;
+    }
+
+    tx.commit()
+  }
+
   // ========================================
   // STEM OPERATIONS
   // ========================================
@@ -115,6 +152,21 @@ impl Database {
     stems::update_stem(&conn, stem)
   }
 
+  pub fn update_stems(&self, stems: &[Stem]) -> Result<()> {
+    let conn = self.get_connection()?;
+    stems::update_stems(&conn, stems)
+  }
+
+  pub fn update_stem_volumes(&self, updates: &[(String, f64)]) -> Result<()> {
+    let conn = self.get_connection()?;
+    stems::update_stem_volumes(&conn, updates)
+  }
+
+  pub fn set_stem_file_hash(&self, id: &str, file_hash: &str, modified: Option<i64>) -> Result<()> {
+    let conn = self.get_connection()?;
+    stems::set_stem_file_hash(&conn, id, file_hash, modified)
+  }
+
   pub fn delete_stem(&self, id: &str) -> Result<()> {
     let conn = self.get_connection()?;
     stems::delete_stem(&conn, id)
@@ -149,6 +201,43 @@ impl Database {
     setlists::list_setlists(&conn)
   }
 
+  pub fn reorder_setlists(&self, ids: &[String]) -> Result<()> {
+    let conn = self.get_connection()?;
+    setlists::reorder_setlists(&conn, ids)
+  }
+
+  pub fn set_setlist_song_override(
+    &self,
+    setlist_id: &str,
+    song_id: &str,
+    key_override: Option<&str>,
+    tempo_note: Option<&str>,
+    transition_note: Option<&str>,
+  ) -> Result<()> {
+    let conn = self.get_connection()?;
+    setlists::set_setlist_song_override(&conn, setlist_id, song_id, key_override, tempo_note, transition_note)
+  }
+
+  pub fn record_song_play(&self, id: &str) -> Result<()> {
+    let conn = self.get_connection()?;
+    songs::record_song_play(&conn, id, chrono::Utc::now().timestamp())
+  }
+
+  pub fn set_song_loudness(&self, id: &str, loudness_lufs: Option<f64>) -> Result<()> {
+    let conn = self.get_connection()?;
+    songs::set_song_loudness(&conn, id, loudness_lufs)
+  }
+
+  pub fn get_library_facets(&self) -> Result<LibraryFacets> {
+    let conn = self.get_connection()?;
+    songs::get_library_facets(&conn)
+  }
+
+  pub fn get_recently_played(&self, limit: usize) -> Result<Vec<Song>> {
+    let conn = self.get_connection()?;
+    songs::get_recently_played(&conn, limit)
+  }
+
   pub fn get_setlist_songs(&self, setlist_id: &str) -> Result<Vec<Song>> {
     let setlist = self.get_setlist(setlist_id)?;
     let mut songs = Vec::new();
@@ -163,6 +252,106 @@ impl Database {
     Ok(songs)
   }
 
+  // ========================================
+  // MIXER SNAPSHOT OPERATIONS
+  // ========================================
+
+  pub fn create_mixer_snapshot(&self, snapshot: &MixerSnapshot) -> Result<()> {
+    let conn = self.get_connection()?;
+    mixer_snapshots::create_mixer_snapshot(&conn, snapshot)
+  }
+
+  pub fn get_mixer_snapshot(&self, id: &str) -> Result<MixerSnapshot> {
+    let conn = self.get_connection()?;
+    mixer_snapshots::get_mixer_snapshot(&conn, id)
+  }
+
+  pub fn list_mixer_snapshots(&self, song_id: &str) -> Result<Vec<MixerSnapshot>> {
+    let conn = self.get_connection()?;
+    mixer_snapshots::list_mixer_snapshots(&conn, song_id)
+  }
+
+  pub fn delete_mixer_snapshot(&self, id: &str) -> Result<()> {
+    let conn = self.get_connection()?;
+    mixer_snapshots::delete_mixer_snapshot(&conn, id)
+  }
+
+  // ========================================
+  // SETLIST TEMPLATE OPERATIONS
+  // ========================================
+
+  pub fn create_setlist_template(&self, template: &SetlistTemplate) -> Result<()> {
+    let conn = self.get_connection()?;
+    setlist_templates::create_setlist_template(&conn, template)
+  }
+
+  pub fn get_setlist_template(&self, id: &str) -> Result<SetlistTemplate> {
+    let conn = self.get_connection()?;
+    setlist_templates::get_setlist_template(&conn, id)
+  }
+
+  pub fn list_setlist_templates(&self) -> Result<Vec<SetlistTemplate>> {
+    let conn = self.get_connection()?;
+    setlist_templates::list_setlist_templates(&conn)
+  }
+
+  pub fn delete_setlist_template(&self, id: &str) -> Result<()> {
+    let conn = self.get_connection()?;
+    setlist_templates::delete_setlist_template(&conn, id)
+  }
+
+  // ========================================
+  // WAVEFORM OPERATIONS
+  // ========================================
+
+  pub fn save_stem_waveform(&self, waveform: &StemWaveform) -> Result<()> {
+    let conn = self.get_connection()?;
+    waveforms::save_stem_waveform(&conn, waveform)
+  }
+
+  pub fn get_stem_waveform(&self, stem_id: &str, buckets: i64, source: Option<&str>) -> Result<Option<StemWaveform>> {
+    let conn = self.get_connection()?;
+    waveforms::get_stem_waveform(&conn, stem_id, buckets, source)
+  }
+
+  // ========================================
+  // AUTOMATION OPERATIONS
+  // ========================================
+
+  pub fn set_stem_automation(&self, stem_id: &str, points: &[AutomationPoint]) -> Result<()> {
+    let conn = self.get_connection()?;
+    automation::set_stem_automation(&conn, stem_id, points)
+  }
+
+  pub fn get_stem_automation(&self, stem_id: &str) -> Result<Vec<AutomationPoint>> {
+    let conn = self.get_connection()?;
+    automation::get_stem_automation(&conn, stem_id)
+  }
+
+  pub fn get_song_automation(&self, song_id: &str) -> Result<std::collections::HashMap<String, Vec<AutomationPoint>>> {
+    let conn = self.get_connection()?;
+    automation::get_song_automation(&conn, song_id)
+  }
+
+  // ========================================
+  // STEM KEYWORD OPERATIONS
+  // ========================================
+
+  pub fn add_stem_keyword(&self, keyword: &StemKeyword) -> Result<()> {
+    let conn = self.get_connection()?;
+    stem_keywords::add_stem_keyword(&conn, keyword)
+  }
+
+  pub fn list_stem_keywords(&self) -> Result<Vec<StemKeyword>> {
+    let conn = self.get_connection()?;
+    stem_keywords::list_stem_keywords(&conn)
+  }
+
+  pub fn delete_stem_keyword(&self, keyword: &str) -> Result<()> {
+    let conn = self.get_connection()?;
+    stem_keywords::delete_stem_keyword(&conn, keyword)
+  }
+
   // ========================================
   // SETTINGS OPERATIONS
   // ========================================
@@ -176,6 +365,15 @@ impl Database {
     let conn = self.get_connection()?;
     settings::update_settings(&conn, settings)
   }
+
+  // ========================================
+  // MAINTENANCE
+  // ========================================
+
+  pub fn maintain(&self) -> Result<MaintenanceReport> {
+    let conn = self.get_connection()?;
+    maintenance::maintain_database(&conn)
+  }
 }
 
 // Error type for database operations
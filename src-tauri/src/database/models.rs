@@ -13,6 +13,11 @@ pub struct Song {
   pub mixdown_path: Option<String>,
   pub created_at: i64,
   pub updated_at: i64,
+  // Fields added after the first archive format default when missing (see import/archive.rs)
+  #[serde(default)]
+  pub play_count: i64,
+  pub last_played_at: Option<i64>, // Unix timestamp of the last play_song, None if never played
+  pub loudness_lufs: Option<f64>, // Integrated loudness measured at first load, None until measured
 }
 
 // Stem model matching TypeScript interface
@@ -29,6 +34,99 @@ pub struct Stem {
   pub volume: f64,
   pub is_muted: bool,
   pub display_order: i32,
+  pub group: Option<String>, // Mute/solo group (e.g. "Drums"), None if ungrouped
+  pub pan: Option<f64>, // -1.0 (left) to 1.0 (right), None = use the default pan
+  pub is_cue: bool, // Click/guide track meant for the band's monitors only
+  pub solo_safe: bool, // Stays audible when other stems are soloed
+  pub offset_samples: i64, // Leading frames skipped on playback to align the stem
+  // Fields added after the first archive format default when missing (see import/archive.rs)
+  #[serde(default)]
+  pub phase_inverted: bool, // Polarity flipped on playback (fixes out-of-phase mics)
+  pub original_file_path: Option<String>, // Source file when `file_path` is a copy (converted at import or frozen)
+  #[serde(default)]
+  pub default_volume: f64, // Import-time volume restored by reset_mix_to_default
+  #[serde(default)]
+  pub default_mute: bool,
+  #[serde(default)]
+  pub delay_samples: i64, // Manual latency compensation in frames, applied on top of the alignment offset
+  #[serde(default)]
+  pub swap_channels: bool, // Left and right exchanged on playback
+  #[serde(default)]
+  pub mono_sum: bool, // Left and right averaged into both sides on playback
+  #[serde(default)]
+  pub trim_db: f64, // Input gain applied before the fader, baked in by freeze_stem
+  #[serde(default)]
+  pub gate_enabled: bool, // Noise gate silences the stem between phrases
+  #[serde(default = "default_gate_threshold_db")]
+  pub gate_threshold_db: f64, // Level the gate opens at
+  #[serde(default = "default_gate_attack_ms")]
+  pub gate_attack_ms: f64, // Time the gate takes to open
+  #[serde(default = "default_gate_release_ms")]
+  pub gate_release_ms: f64, // Time the gate takes to close
+  pub file_hash: Option<String>, // SHA-256 of the whole file at file_path, None until computed
+  #[serde(default)]
+  pub file_hash_modified: Option<i64>, // Mtime of file_path (ns since the epoch) file_hash was computed at
+}
+
+// Gate threshold of stems stored before the gate existed (matches the V25 column default)
+fn default_gate_threshold_db() -> f64 {
+  -50.0
+}
+
+// Gate timing of stems stored before it was adjustable (matches the V39 column defaults)
+fn default_gate_attack_ms() -> f64 {
+  1.0
+}
+
+fn default_gate_release_ms() -> f64 {
+  50.0
+}
+
+impl Stem {
+  // Pan to apply on load: the user's override if set, otherwise hard to the
+  // configured cue side for cue stems and center for everything else
+  pub fn effective_pan(&self, settings: &AppSettings) -> f64 {
+    match self.pan {
+      Some(pan) => pan,
+      None if self.is_cue => settings.cue_pan(),
+      None => 0.0,
+    }
+  }
+}
+
+// Custom stem detection keyword (e.g. "bgv" -> "BGV")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StemKeyword {
+  pub keyword: String,
+  pub display_name: String,
+  pub created_at: i64,
+}
+
+// Saved mixer balance for a song, keyed by stem id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MixerSnapshot {
+  pub id: String,
+  pub song_id: String,
+  pub name: String,
+  pub stems: std::collections::HashMap<String, StemMix>,
+  pub created_at: i64,
+}
+
+// Per-stem mixer settings captured in a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StemMix {
+  pub volume: f64,
+  pub is_muted: bool,
+  pub pan: Option<f64>, // None = default pan
+  #[serde(default)] // Snapshots from before trims were all taken at 0 dB
+  pub trim_db: f64,
+}
+
+// One point of a stem's volume automation; the engine interpolates linearly between points
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AutomationPoint {
+  pub position_seconds: f64,
+  pub volume: f64, // 0.0 to 1.0, replaces the fader while automation is active
 }
 
 // Setlist model matching TypeScript interface
@@ -39,15 +137,95 @@ pub struct Setlist {
   pub created_at: i64,
   pub updated_at: i64,
   pub song_ids: Vec<String>,
+  // Place in the setlist list; set by the database on create and by reorder_setlists
+  #[serde(default)]
+  pub position: i32,
+  #[serde(default)]
+  pub color: Option<String>, // UI color tag, e.g. "#3b82f6"
+  // Per-setlist details for each song, in setlist order
+  #[serde(default)]
+  pub songs: Vec<SetlistSong>,
 }
 
-// AppSettings model matching TypeScript interface
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Reusable setlist structure: labeled slots (e.g. "Opener", "Worship") filled with songs each week
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetlistTemplate {
+  pub id: String,
+  pub name: String,
+  pub slots: Vec<String>, // Slot labels in setlist order; labels may repeat
+  pub created_at: i64,
+}
+
+// Cached min/max peaks of a stem at one resolution, for drawing its waveform
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StemWaveform {
+  pub stem_id: String,
+  pub buckets: i64,
+  pub min: Vec<f32>, // Lowest sample in each bucket
+  pub max: Vec<f32>, // Highest sample in each bucket
+  pub created_at: i64,
+  #[serde(skip)]
+  pub source: String, // Path, size and modification time of the file the peaks came from
+}
+
+// Values present in the library, for populating the filter dropdowns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryFacets {
+  pub keys: Vec<KeyFacet>, // Distinct keys, alphabetical; songs without a key are left out
+  pub tempo_min: Option<f64>, // None when no song has a tempo
+  pub tempo_max: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyFacet {
+  pub key: String,
+  pub song_count: i64,
+}
+
+// A song's entry in a specific setlist, with performance overrides
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SetlistSong {
+  pub song_id: String,
+  pub position: i32,
+  pub key_override: Option<String>,
+  pub tempo_note: Option<String>,
+  pub transition_note: Option<String>,
+}
+
+// AppSettings model matching TypeScript interface.
+// Missing fields take their defaults so settings files from older versions still load
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
   pub audio_output_device: Option<String>,
   pub audio_buffer_size: i32,
   pub sample_rate: i32,
   pub theme: String,
+  pub cue_pan_side: String, // "left" or "right" - where click/guide stems are panned by default
+  pub solo_mode: String, // "additive" (solos stack) or "exclusive" (a new solo releases the others)
+  pub fade_curve: String, // "linear" or "equal_power"
+  pub max_decode_threads: i32, // Stems decoded at once, 0 = one per CPU core
+  pub import_sample_rate: i32, // Convert imported stems to this rate, 0 = keep each file's rate
+  pub audio_host: Option<String>, // Host API for output devices (e.g. "ASIO"), None = platform default
+  pub in_memory_cache_gb: f64, // Size limit of the decoded song cache
+  pub practice_mode: bool, // Throwaway imports: no mixdown or converted copies written to disk
+  pub log_level: String, // "error", "warn", "info", "debug" or "trace"
+  pub stem_capacity: i32, // Stems the engine can hold at once (1-256), applied at startup
+  pub auto_advance_gap_seconds: f64, // Silence between songs when a setlist auto-advances
+  pub loudness_target_enabled: bool, // Trim the master so every song plays at the target loudness
+  pub loudness_target_lufs: f64,
+  pub group_drum_kit_stems: bool, // Import kick/snare/hat/... files as "Drums" stems
+  pub master_highpass_enabled: bool, // Low-cut on the master to protect the subs
+  pub master_highpass_hz: f64,
+  pub stem_overflow_policy: String, // "error" or "merge": what loading a song with more stems than the engine holds does
+  pub disk_cache_gb: f64, // Size limit of the decoded stems kept on disk
+}
+
+impl AppSettings {
+  // Pan position for cue stems based on the configured side
+  pub fn cue_pan(&self) -> f64 {
+    if self.cue_pan_side == "left" { -1.0 } else { 1.0 }
+  }
 }
 
 // Default implementation for AppSettings
@@ -58,6 +236,24 @@ impl Default for AppSettings {
       audio_buffer_size: 512,
       sample_rate: 48000,
       theme: "dark".to_string(),
+      cue_pan_side: "right".to_string(),
+      solo_mode: "additive".to_string(),
+      fade_curve: "linear".to_string(),
+      max_decode_threads: 0,
+      import_sample_rate: 0,
+      audio_host: None,
+      in_memory_cache_gb: 3.0,
+      practice_mode: false,
+      log_level: "info".to_string(),
+      stem_capacity: 32,
+      auto_advance_gap_seconds: 0.0,
+      loudness_target_enabled: false,
+      loudness_target_lufs: -16.0,
+      group_drum_kit_stems: false,
+      master_highpass_enabled: false,
+      master_highpass_hz: 30.0,
+      stem_overflow_policy: "error".to_string(),
+      disk_cache_gb: 10.0,
     }
   }
 }
@@ -70,6 +266,8 @@ pub struct SongFilter {
   pub tempo_max: Option<f64>,
   pub key: Option<String>,
   pub sort_by: Option<SortBy>,
+  pub sort_direction: Option<SortDirection>, // None = the sort's natural direction
+  pub secondary_sort: Option<SortBy>, // Tie-breaker, always in its natural direction
 }
 
 #[derive(Debug, Clone)]
@@ -79,4 +277,29 @@ pub enum SortBy {
   Tempo,
   Duration,
   DateAdded,
+  MostPlayed,
+  RecentlyPlayed,
+}
+
+impl SortBy {
+  // Newest / most played first for date and play sorts, ascending for everything else
+  pub fn default_direction(&self) -> SortDirection {
+    match self {
+      SortBy::DateAdded | SortBy::MostPlayed | SortBy::RecentlyPlayed => SortDirection::Descending,
+      _ => SortDirection::Ascending,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortDirection {
+  Ascending,
+  Descending,
+}
+
+// Result of a WAL checkpoint + integrity check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+  pub integrity: String, // "ok" when the database is healthy
+  pub wal_bytes_reclaimed: u64,
 }
@@ -1,10 +1,16 @@
 use rusqlite::{Connection, Result};
 
 // Current schema version
-pub const SCHEMA_VERSION: i32 = 3;
+pub const SCHEMA_VERSION: i32 = 40;
 
 // Initialize the database schema
 pub fn initialize_schema(conn: &Connection) -> Result<()> {
+  initialize_schema_to(conn, SCHEMA_VERSION)
+}
+
+// Initialize the schema, running migrations only up to `target_version`
+// (tests use this to build a database at an older version)
+pub(crate) fn initialize_schema_to(conn: &Connection, target_version: i32) -> Result<()> {
   // Enable foreign keys
   conn.execute_batch("PRAGMA foreign_keys = ON;")?;
 
@@ -21,18 +27,166 @@ pub fn initialize_schema(conn: &Connection) -> Result<()> {
   let current_version = get_current_version(conn)?;
 
   // Run migrations
-  if current_version < 1 {
+  if current_version < 1 && target_version >= 1 {
     run_migration_v1(conn)?;
   }
 
-  if current_version < 2 {
+  if current_version < 2 && target_version >= 2 {
     run_migration_v2(conn)?;
   }
 
-  if current_version < 3 {
+  if current_version < 3 && target_version >= 3 {
     run_migration_v3(conn)?;
   }
 
+  if current_version < 4 && target_version >= 4 {
+    run_migration_v4(conn)?;
+  }
+
+  if current_version < 5 && target_version >= 5 {
+    run_migration_v5(conn)?;
+  }
+
+  if current_version < 6 && target_version >= 6 {
+    run_migration_v6(conn)?;
+  }
+
+  if current_version < 7 && target_version >= 7 {
+    run_migration_v7(conn)?;
+  }
+
+  if current_version < 8 && target_version >= 8 {
+    run_migration_v8(conn)?;
+  }
+
+  if current_version < 9 && target_version >= 9 {
+    run_migration_v9(conn)?;
+  }
+
+  if current_version < 10 && target_version >= 10 {
+    run_migration_v10(conn)?;
+  }
+
+  if current_version < 11 && target_version >= 11 {
+    run_migration_v11(conn)?;
+  }
+
+  if current_version < 12 && target_version >= 12 {
+    run_migration_v12(conn)?;
+  }
+
+  if current_version < 13 && target_version >= 13 {
+    run_migration_v13(conn)?;
+  }
+
+  if current_version < 14 && target_version >= 14 {
+    run_migration_v14(conn)?;
+  }
+
+  if current_version < 15 && target_version >= 15 {
+    run_migration_v15(conn)?;
+  }
+
+  if current_version < 16 && target_version >= 16 {
+    run_migration_v16(conn)?;
+  }
+
+  if current_version < 17 && target_version >= 17 {
+    run_migration_v17(conn)?;
+  }
+
+  if current_version < 18 && target_version >= 18 {
+    run_migration_v18(conn)?;
+  }
+
+  if current_version < 19 && target_version >= 19 {
+    run_migration_v19(conn)?;
+  }
+
+  if current_version < 20 && target_version >= 20 {
+    run_migration_v20(conn)?;
+  }
+
+  if current_version < 21 && target_version >= 21 {
+    run_migration_v21(conn)?;
+  }
+
+  if current_version < 22 && target_version >= 22 {
+    run_migration_v22(conn)?;
+  }
+
+  if current_version < 23 && target_version >= 23 {
+    run_migration_v23(conn)?;
+  }
+
+  if current_version < 24 && target_version >= 24 {
+    run_migration_v24(conn)?;
+  }
+
+  if current_version < 25 && target_version >= 25 {
+    run_migration_v25(conn)?;
+  }
+
+  if current_version < 26 && target_version >= 26 {
+    run_migration_v26(conn)?;
+  }
+
+  if current_version < 27 && target_version >= 27 {
+    run_migration_v27(conn)?;
+  }
+
+  if current_version < 28 && target_version >= 28 {
+    run_migration_v28(conn)?;
+  }
+
+  if current_version < 29 && target_version >= 29 {
+    run_migration_v29(conn)?;
+  }
+
+  if current_version < 30 && target_version >= 30 {
+    run_migration_v30(conn)?;
+  }
+
+  if current_version < 31 && target_version >= 31 {
+    run_migration_v31(conn)?;
+  }
+
+  if current_version < 32 && target_version >= 32 {
+    run_migration_v32(conn)?;
+  }
+
+  if current_version < 33 && target_version >= 33 {
+    run_migration_v33(conn)?;
+  }
+
+  if current_version < 34 && target_version >= 34 {
+    run_migration_v34(conn)?;
+  }
+
+  if current_version < 35 && target_version >= 35 {
+    run_migration_v35(conn)?;
+  }
+
+  if current_version < 36 && target_version >= 36 {
+    run_migration_v36(conn)?;
+  }
+
+  if current_version < 37 && target_version >= 37 {
+    run_migration_v37(conn)?;
+  }
+
+  if current_version < 38 && target_version >= 38 {
+    run_migration_v38(conn)?;
+  }
+
+  if current_version < 39 && target_version >= 39 {
+    run_migration_v39(conn)?;
+  }
+
+  if current_version < 40 && target_version >= 40 {
+    run_migration_v40(conn)?;
+  }
+
   Ok(())
 }
 
@@ -190,3 +344,625 @@ fn run_migration_v3(conn: &Connection) -> Result<()> {
 
   Ok(())
 }
+
+// Migration V4: Add stem_group to stems table (grouped mute/solo)
+fn run_migration_v4(conn: &Connection) -> Result<()> {
+  // "group" is a reserved word, so the column is named stem_group
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN stem_group TEXT",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 4)?;
+
+  Ok(())
+}
+
+// Migration V5: Add pan and cue flag to stems, default cue side to settings
+fn run_migration_v5(conn: &Connection) -> Result<()> {
+  // NULL pan means "use the default" (center, or the cue side for cue stems)
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN pan REAL",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN is_cue INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN cue_pan_side TEXT NOT NULL DEFAULT 'right'",
+    [],
+  )?;
+
+  // Flag existing click/guide stems as cue stems
+  conn.execute(
+    "UPDATE stems SET is_cue = 1 WHERE name LIKE 'Click%' OR name LIKE 'Guide%'",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 5)?;
+
+  Ok(())
+}
+
+// Migration V6: Add stem_keywords table for custom stem detection
+fn run_migration_v6(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS stem_keywords (
+      keyword TEXT PRIMARY KEY NOT NULL,
+      display_name TEXT NOT NULL,
+      created_at INTEGER NOT NULL
+    )",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 6)?;
+
+  Ok(())
+}
+
+// Migration V7: Replace setlists.song_ids JSON with a setlist_songs detail table
+fn run_migration_v7(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS setlist_songs (
+      setlist_id TEXT NOT NULL,
+      song_id TEXT NOT NULL,
+      position INTEGER NOT NULL,
+      key_override TEXT,
+      tempo_note TEXT,
+      transition_note TEXT,
+      PRIMARY KEY (setlist_id, song_id),
+      FOREIGN KEY (setlist_id) REFERENCES setlists(id) ON DELETE CASCADE
+    )",
+    [],
+  )?;
+
+  // Convert existing setlists (song order is the JSON array order)
+  let existing: Vec<(String, String)> = conn
+    .prepare("SELECT id, song_ids FROM setlists")?
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+    .collect::<Result<Vec<_>>>()?;
+
+  for (setlist_id, song_ids_json) in existing {
+    let song_ids: Vec<String> = serde_json::from_str(&song_ids_json).unwrap_or_else(|e| {
+      log::warn!("Setlist {} has invalid song_ids, migrating it empty: {}", setlist_id, e);
+      Vec::new()
+    });
+
+    for (position, song_id) in song_ids.iter().enumerate() {
+      conn.execute(
+        "INSERT OR IGNORE INTO setlist_songs (setlist_id, song_id, position) VALUES (?1, ?2, ?3)",
+        rusqlite::params![setlist_id, song_id, position as i32],
+      )?;
+    }
+  }
+
+  conn.execute("ALTER TABLE setlists DROP COLUMN song_ids", [])?;
+
+  // Record migration
+  record_migration(conn, 7)?;
+
+  Ok(())
+}
+
+// Migration V8: Add solo_safe to stems and solo_mode to settings
+fn run_migration_v8(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN solo_safe INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN solo_mode TEXT NOT NULL DEFAULT 'additive'",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 8)?;
+
+  Ok(())
+}
+
+// Migration V9: Add mixer_snapshots table (saved per-song mixer balances)
+fn run_migration_v9(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS mixer_snapshots (
+      id TEXT PRIMARY KEY NOT NULL,
+      song_id TEXT NOT NULL,
+      name TEXT NOT NULL,
+      stems TEXT NOT NULL,
+      created_at INTEGER NOT NULL,
+      FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+    )",
+    [],
+  )?;
+
+  conn.execute(
+    "CREATE INDEX IF NOT EXISTS idx_mixer_snapshots_song_id ON mixer_snapshots(song_id)",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 9)?;
+
+  Ok(())
+}
+
+// Migration V10: Add offset_samples to stems (leading-silence alignment)
+fn run_migration_v10(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN offset_samples INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 10)?;
+
+  Ok(())
+}
+
+// Migration V11: Add play statistics to songs
+fn run_migration_v11(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE songs ADD COLUMN play_count INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE songs ADD COLUMN last_played_at INTEGER",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 11)?;
+
+  Ok(())
+}
+
+// Migration V12: Add fade_curve to settings
+fn run_migration_v12(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN fade_curve TEXT NOT NULL DEFAULT 'linear'",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 12)?;
+
+  Ok(())
+}
+
+// Migration V13: Add phase_inverted to stems
+fn run_migration_v13(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN phase_inverted INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 13)?;
+
+  Ok(())
+}
+
+// Migration V14: Add max_decode_threads to settings
+fn run_migration_v14(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN max_decode_threads INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 14)?;
+
+  Ok(())
+}
+
+// Migration V15: Add import sample rate conversion (settings.import_sample_rate, stems.original_file_path)
+fn run_migration_v15(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN import_sample_rate INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN original_file_path TEXT",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 15)?;
+
+  Ok(())
+}
+
+// Migration V16: Add default_volume / default_mute to stems
+fn run_migration_v16(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN default_volume REAL NOT NULL DEFAULT 0.8",
+    [],
+  )?;
+
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN default_mute INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Existing stems have no import-time record; their current mix becomes the default
+  conn.execute(
+    "UPDATE stems SET default_volume = volume, default_mute = is_muted",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 16)?;
+
+  Ok(())
+}
+
+// Migration V17: Add delay_samples to stems
+fn run_migration_v17(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN delay_samples INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 17)?;
+
+  Ok(())
+}
+
+// Migration V18: Add audio_host to settings
+fn run_migration_v18(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN audio_host TEXT",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 18)?;
+
+  Ok(())
+}
+
+// Migration V19: Add swap_channels / mono_sum to stems
+fn run_migration_v19(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN swap_channels INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN mono_sum INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 19)?;
+
+  Ok(())
+}
+
+// Migration V20: Add in_memory_cache_gb to settings
+fn run_migration_v20(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN in_memory_cache_gb REAL NOT NULL DEFAULT 3.0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 20)?;
+
+  Ok(())
+}
+
+// Migration V21: Add practice_mode to settings
+fn run_migration_v21(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN practice_mode INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 21)?;
+
+  Ok(())
+}
+
+// Migration V22: Add trim_db to stems
+fn run_migration_v22(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN trim_db REAL NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 22)?;
+
+  Ok(())
+}
+
+// Migration V23: Add log_level to settings
+fn run_migration_v23(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN log_level TEXT NOT NULL DEFAULT 'info'",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 23)?;
+
+  Ok(())
+}
+
+// Migration V24: Add stem_automation table (volume envelopes along the song timeline)
+fn run_migration_v24(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS stem_automation (
+      stem_id TEXT PRIMARY KEY NOT NULL,
+      points TEXT NOT NULL,
+      FOREIGN KEY (stem_id) REFERENCES stems(id) ON DELETE CASCADE
+    )",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 24)?;
+
+  Ok(())
+}
+
+// Migration V25: Add a per-stem noise gate
+fn run_migration_v25(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN gate_enabled INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN gate_threshold_db REAL NOT NULL DEFAULT -50",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 25)?;
+
+  Ok(())
+}
+
+// Migration V26: Add stem_capacity to settings
+fn run_migration_v26(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN stem_capacity INTEGER NOT NULL DEFAULT 32",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 26)?;
+
+  Ok(())
+}
+
+// Migration V27: Add auto_advance_gap_seconds to settings
+fn run_migration_v27(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN auto_advance_gap_seconds REAL NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 27)?;
+
+  Ok(())
+}
+
+// Migration V28: Add measured loudness to songs and the loudness target to settings
+fn run_migration_v28(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE songs ADD COLUMN loudness_lufs REAL",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN loudness_target_enabled INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN loudness_target_lufs REAL NOT NULL DEFAULT -16",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 28)?;
+
+  Ok(())
+}
+
+// Migration V29: Add the source file hash to stems
+fn run_migration_v29(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN file_hash TEXT",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 29)?;
+
+  Ok(())
+}
+
+// Migration V30: Add setlist_templates table (reusable setlist structures)
+fn run_migration_v30(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS setlist_templates (
+      id TEXT PRIMARY KEY NOT NULL,
+      name TEXT NOT NULL,
+      slots TEXT NOT NULL,
+      created_at INTEGER NOT NULL
+    )",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 30)?;
+
+  Ok(())
+}
+
+// Migration V31: Add group_drum_kit_stems to settings
+fn run_migration_v31(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN group_drum_kit_stems INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 31)?;
+
+  Ok(())
+}
+
+// Migration V32: Add waveforms table (cached per-stem peak overviews)
+fn run_migration_v32(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS waveforms (
+      stem_id TEXT NOT NULL,
+      buckets INTEGER NOT NULL,
+      min_peaks TEXT NOT NULL,
+      max_peaks TEXT NOT NULL,
+      created_at INTEGER NOT NULL,
+      PRIMARY KEY (stem_id, buckets),
+      FOREIGN KEY (stem_id) REFERENCES stems(id) ON DELETE CASCADE
+    )",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 32)?;
+
+  Ok(())
+}
+
+// Migration V33: Add master high-pass filter settings
+fn run_migration_v33(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN master_highpass_enabled INTEGER NOT NULL DEFAULT 0",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN master_highpass_hz REAL NOT NULL DEFAULT 30",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 33)?;
+
+  Ok(())
+}
+
+// Migration V34: Add stem_overflow_policy to settings
+fn run_migration_v34(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN stem_overflow_policy TEXT NOT NULL DEFAULT 'error'",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 34)?;
+
+  Ok(())
+}
+
+// Migration V35: Add position and color to setlists
+fn run_migration_v35(conn: &Connection) -> Result<()> {
+  conn.execute("ALTER TABLE setlists ADD COLUMN position INTEGER NOT NULL DEFAULT 0", [])?;
+  conn.execute("ALTER TABLE setlists ADD COLUMN color TEXT", [])?;
+
+  // Keep the order setlists were listed in before (newest first)
+  let existing: Vec<String> = conn
+    .prepare("SELECT id FROM setlists ORDER BY created_at DESC")?
+    .query_map([], |row| row.get(0))?
+    .collect::<Result<Vec<_>>>()?;
+
+  for (position, setlist_id) in existing.iter().enumerate() {
+    conn.execute(
+      "UPDATE setlists SET position = ?1 WHERE id = ?2",
+      rusqlite::params![position as i32, setlist_id],
+    )?;
+  }
+
+  // Record migration
+  record_migration(conn, 35)?;
+
+  Ok(())
+}
+
+// Migration V36: Add disk_cache_gb to settings
+fn run_migration_v36(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE settings ADD COLUMN disk_cache_gb REAL NOT NULL DEFAULT 10.0",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 36)?;
+
+  Ok(())
+}
+
+// Migration V37: Key waveforms on the file they were computed from. Existing rows have no
+// source, so they're recomputed on first use
+fn run_migration_v37(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE waveforms ADD COLUMN source TEXT NOT NULL DEFAULT ''",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 37)?;
+
+  Ok(())
+}
+
+// Migration V38: Stored stem hashes covered only part of the imported source file. Clear them
+// so find_duplicate_songs hashes each stem's own file in full
+fn run_migration_v38(conn: &Connection) -> Result<()> {
+  conn.execute("UPDATE stems SET file_hash = NULL", [])?;
+
+  // Record migration
+  record_migration(conn, 38)?;
+
+  Ok(())
+}
+
+// Migration V39: Per-stem noise gate attack and release times, defaulting to the timing the
+// gate used before they were adjustable
+fn run_migration_v39(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN gate_attack_ms REAL NOT NULL DEFAULT 1",
+    [],
+  )?;
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN gate_release_ms REAL NOT NULL DEFAULT 50",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 39)?;
+
+  Ok(())
+}
+
+// Migration V40: Source mtime each stem hash was computed at, so loads reuse the stored hash
+// until the file changes. Existing hashes have none and are recomputed once
+fn run_migration_v40(conn: &Connection) -> Result<()> {
+  conn.execute(
+    "ALTER TABLE stems ADD COLUMN file_hash_modified INTEGER",
+    [],
+  )?;
+
+  // Record migration
+  record_migration(conn, 40)?;
+
+  Ok(())
+}
@@ -0,0 +1,60 @@
+use rusqlite::{Connection, Result, params};
+use super::models::SetlistTemplate;
+
+// Save a setlist template (slot labels stored as JSON)
+pub fn create_setlist_template(conn: &Connection, template: &SetlistTemplate) -> Result<()> {
+  let slots_json = serde_json::to_string(&template.slots)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+  conn.execute(
+    "INSERT INTO setlist_templates (id, name, slots, created_at)
+     VALUES (?1, ?2, ?3, ?4)",
+    params![
+      template.id,
+      template.name,
+      slots_json,
+      template.created_at,
+    ],
+  )?;
+  Ok(())
+}
+
+// Get a setlist template by ID
+pub fn get_setlist_template(conn: &Connection, id: &str) -> Result<SetlistTemplate> {
+  conn.query_row(
+    "SELECT id, name, slots, created_at
+     FROM setlist_templates WHERE id = ?1",
+    [id],
+    template_from_row,
+  )
+}
+
+// List all setlist templates by name
+pub fn list_setlist_templates(conn: &Connection) -> Result<Vec<SetlistTemplate>> {
+  let mut stmt = conn.prepare(
+    "SELECT id, name, slots, created_at
+     FROM setlist_templates ORDER BY name COLLATE NOCASE ASC, created_at ASC"
+  )?;
+
+  let templates = stmt.query_map([], template_from_row)?;
+  templates.collect()
+}
+
+// Delete a setlist template (setlists made from it are unaffected)
+pub fn delete_setlist_template(conn: &Connection, id: &str) -> Result<()> {
+  conn.execute("DELETE FROM setlist_templates WHERE id = ?1", [id])?;
+  Ok(())
+}
+
+fn template_from_row(row: &rusqlite::Row) -> Result<SetlistTemplate> {
+  let slots_json: String = row.get(2)?;
+  let slots = serde_json::from_str(&slots_json)
+    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+
+  Ok(SetlistTemplate {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    slots,
+    created_at: row.get(3)?,
+  })
+}
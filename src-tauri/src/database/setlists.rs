@@ -1,92 +1,197 @@
 use rusqlite::{Connection, Result, params};
-use super::models::Setlist;
+use super::models::{Setlist, SetlistSong};
 
 // Create a new setlist
 pub fn create_setlist(conn: &Connection, setlist: &Setlist) -> Result<()> {
-  let song_ids_json = serde_json::to_string(&setlist.song_ids)
-    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+  let tx = conn.unchecked_transaction()?;
 
-  conn.execute(
-    "INSERT INTO setlists (id, name, created_at, updated_at, song_ids)
-     VALUES (?1, ?2, ?3, ?4, ?5)",
+  // New setlists go to the top of the list, as they did before setlists could be reordered
+  tx.execute("UPDATE setlists SET position = position + 1", [])?;
+  tx.execute(
+    "INSERT INTO setlists (id, name, created_at, updated_at, position, color)
+     VALUES (?1, ?2, ?3, ?4, 0, ?5)",
     params![
       setlist.id,
       setlist.name,
       setlist.created_at,
       setlist.updated_at,
-      song_ids_json,
+      setlist.color,
     ],
   )?;
-  Ok(())
+  sync_setlist_songs(&tx, &setlist.id, &setlist.song_ids)?;
+
+  tx.commit()
 }
 
 // Get a setlist by ID
 pub fn get_setlist(conn: &Connection, id: &str) -> Result<Setlist> {
-  conn.query_row(
-    "SELECT id, name, created_at, updated_at, song_ids
+  let mut setlist = conn.query_row(
+    "SELECT id, name, created_at, updated_at, position, color
      FROM setlists WHERE id = ?1",
     [id],
-    |row| {
-      let song_ids_json: String = row.get(4)?;
-      let song_ids: Vec<String> = serde_json::from_str(&song_ids_json)
-        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
-
-      Ok(Setlist {
-        id: row.get(0)?,
-        name: row.get(1)?,
-        created_at: row.get(2)?,
-        updated_at: row.get(3)?,
-        song_ids,
-      })
-    },
-  )
+    setlist_from_row,
+  )?;
+
+  attach_setlist_songs(conn, &mut setlist)?;
+  Ok(setlist)
 }
 
 // Update a setlist
+// Song order comes from song_ids; overrides of songs that stay in the setlist are kept
 pub fn update_setlist(conn: &Connection, setlist: &Setlist) -> Result<()> {
   let updated_at = chrono::Utc::now().timestamp();
-  let song_ids_json = serde_json::to_string(&setlist.song_ids)
-    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+  let tx = conn.unchecked_transaction()?;
 
-  conn.execute(
-    "UPDATE setlists SET name = ?1, updated_at = ?2, song_ids = ?3
+  tx.execute(
+    "UPDATE setlists SET name = ?1, color = ?2, updated_at = ?3
      WHERE id = ?4",
     params![
       setlist.name,
+      setlist.color,
       updated_at,
-      song_ids_json,
       setlist.id,
     ],
   )?;
-  Ok(())
+  sync_setlist_songs(&tx, &setlist.id, &setlist.song_ids)?;
+
+  tx.commit()
 }
 
-// Delete a setlist
+// Delete a setlist (setlist_songs rows cascade)
 pub fn delete_setlist(conn: &Connection, id: &str) -> Result<()> {
   conn.execute("DELETE FROM setlists WHERE id = ?1", [id])?;
   Ok(())
 }
 
-// List all setlists
+// List all setlists in their user-set order
 pub fn list_setlists(conn: &Connection) -> Result<Vec<Setlist>> {
   let mut stmt = conn.prepare(
-    "SELECT id, name, created_at, updated_at, song_ids
-     FROM setlists ORDER BY created_at DESC"
+    "SELECT id, name, created_at, updated_at, position, color
+     FROM setlists ORDER BY position ASC, created_at DESC"
+  )?;
+
+  let mut setlists = stmt
+    .query_map([], setlist_from_row)?
+    .collect::<Result<Vec<_>>>()?;
+
+  for setlist in &mut setlists {
+    attach_setlist_songs(conn, setlist)?;
+  }
+
+  Ok(setlists)
+}
+
+// Put setlists in the given order; setlists not listed keep their order after them
+pub fn reorder_setlists(conn: &Connection, ids: &[String]) -> Result<()> {
+  let tx = conn.unchecked_transaction()?;
+
+  let current: Vec<String> = tx
+    .prepare("SELECT id FROM setlists ORDER BY position ASC, created_at DESC")?
+    .query_map([], |row| row.get(0))?
+    .collect::<Result<Vec<_>>>()?;
+
+  if ids.iter().any(|id| !current.contains(id)) {
+    return Err(rusqlite::Error::QueryReturnedNoRows);
+  }
+
+  let rest = current.iter().filter(|id| !ids.contains(id));
+  for (position, id) in ids.iter().chain(rest).enumerate() {
+    tx.execute(
+      "UPDATE setlists SET position = ?1 WHERE id = ?2",
+      params![position as i32, id],
+    )?;
+  }
+
+  tx.commit()
+}
+
+// Set the per-setlist overrides for a song (None clears a field)
+pub fn set_setlist_song_override(
+  conn: &Connection,
+  setlist_id: &str,
+  song_id: &str,
+  key_override: Option<&str>,
+  tempo_note: Option<&str>,
+  transition_note: Option<&str>,
+) -> Result<()> {
+  let updated = conn.execute(
+    "UPDATE setlist_songs SET key_override = ?1, tempo_note = ?2, transition_note = ?3
+     WHERE setlist_id = ?4 AND song_id = ?5",
+    params![key_override, tempo_note, transition_note, setlist_id, song_id],
+  )?;
+
+  if updated == 0 {
+    return Err(rusqlite::Error::QueryReturnedNoRows);
+  }
+
+  conn.execute(
+    "UPDATE setlists SET updated_at = ?1 WHERE id = ?2",
+    params![chrono::Utc::now().timestamp(), setlist_id],
+  )?;
+  Ok(())
+}
+
+// Get the songs of a setlist with their overrides, in setlist order
+pub fn get_setlist_song_entries(conn: &Connection, setlist_id: &str) -> Result<Vec<SetlistSong>> {
+  let mut stmt = conn.prepare(
+    "SELECT song_id, position, key_override, tempo_note, transition_note
+     FROM setlist_songs WHERE setlist_id = ?1 ORDER BY position ASC"
   )?;
 
-  let setlists = stmt.query_map([], |row| {
-    let song_ids_json: String = row.get(4)?;
-    let song_ids: Vec<String> = serde_json::from_str(&song_ids_json)
-      .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
-
-    Ok(Setlist {
-      id: row.get(0)?,
-      name: row.get(1)?,
-      created_at: row.get(2)?,
-      updated_at: row.get(3)?,
-      song_ids,
+  let entries = stmt.query_map([setlist_id], |row| {
+    Ok(SetlistSong {
+      song_id: row.get(0)?,
+      position: row.get(1)?,
+      key_override: row.get(2)?,
+      tempo_note: row.get(3)?,
+      transition_note: row.get(4)?,
     })
   })?;
 
-  setlists.collect()
+  entries.collect()
+}
+
+fn setlist_from_row(row: &rusqlite::Row) -> Result<Setlist> {
+  Ok(Setlist {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    created_at: row.get(2)?,
+    updated_at: row.get(3)?,
+    position: row.get(4)?,
+    color: row.get(5)?,
+    song_ids: Vec::new(),
+    songs: Vec::new(),
+  })
+}
+
+fn attach_setlist_songs(conn: &Connection, setlist: &mut Setlist) -> Result<()> {
+  setlist.songs = get_setlist_song_entries(conn, &setlist.id)?;
+  setlist.song_ids = setlist.songs.iter().map(|s| s.song_id.clone()).collect();
+  Ok(())
+}
+
+// Make setlist_songs match the given order: drop removed songs, upsert positions
+fn sync_setlist_songs(conn: &Connection, setlist_id: &str, song_ids: &[String]) -> Result<()> {
+  let existing: Vec<String> = get_setlist_song_entries(conn, setlist_id)?
+    .into_iter()
+    .map(|s| s.song_id)
+    .collect();
+
+  for song_id in existing.iter().filter(|id| !song_ids.contains(id)) {
+    conn.execute(
+      "DELETE FROM setlist_songs WHERE setlist_id = ?1 AND song_id = ?2",
+      params![setlist_id, song_id],
+    )?;
+  }
+
+  for (position, song_id) in song_ids.iter().enumerate() {
+    conn.execute(
+      "INSERT INTO setlist_songs (setlist_id, song_id, position)
+       VALUES (?1, ?2, ?3)
+       ON CONFLICT(setlist_id, song_id) DO UPDATE SET position = excluded.position",
+      params![setlist_id, song_id, position as i32],
+    )?;
+  }
+
+  Ok(())
 }
@@ -4,7 +4,11 @@ use super::models::AppSettings;
 // Get app settings (always returns the single row)
 pub fn get_settings(conn: &Connection) -> Result<AppSettings> {
   conn.query_row(
-    "SELECT audio_output_device, audio_buffer_size, sample_rate, theme
+    "SELECT audio_output_device, audio_buffer_size, sample_rate, theme, cue_pan_side, solo_mode, fade_curve,
+     max_decode_threads, import_sample_rate, audio_host, in_memory_cache_gb,
+     practice_mode, log_level, stem_capacity, auto_advance_gap_seconds,
+     loudness_target_enabled, loudness_target_lufs, group_drum_kit_stems,
+     master_highpass_enabled, master_highpass_hz, stem_overflow_policy, disk_cache_gb
      FROM settings WHERE id = 1",
     [],
     |row| {
@@ -13,6 +17,24 @@ pub fn get_settings(conn: &Connection) -> Result<AppSettings> {
         audio_buffer_size: row.get(1)?,
         sample_rate: row.get(2)?,
         theme: row.get(3)?,
+        cue_pan_side: row.get(4)?,
+        solo_mode: row.get(5)?,
+        fade_curve: row.get(6)?,
+        max_decode_threads: row.get(7)?,
+        import_sample_rate: row.get(8)?,
+        audio_host: row.get(9)?,
+        in_memory_cache_gb: row.get(10)?,
+        practice_mode: row.get::<_, i32>(11)? != 0,
+        log_level: row.get(12)?,
+        stem_capacity: row.get(13)?,
+        auto_advance_gap_seconds: row.get(14)?,
+        loudness_target_enabled: row.get::<_, i32>(15)? != 0,
+        loudness_target_lufs: row.get(16)?,
+        group_drum_kit_stems: row.get::<_, i32>(17)? != 0,
+        master_highpass_enabled: row.get::<_, i32>(18)? != 0,
+        master_highpass_hz: row.get(19)?,
+        stem_overflow_policy: row.get(20)?,
+        disk_cache_gb: row.get(21)?,
       })
     },
   )
@@ -22,12 +44,36 @@ pub fn get_settings(conn: &Connection) -> Result<AppSettings> {
 pub fn update_settings(conn: &Connection, settings: &AppSettings) -> Result<()> {
   conn.execute(
     "UPDATE settings SET audio_output_device = ?1, audio_buffer_size = ?2,
-     sample_rate = ?3, theme = ?4 WHERE id = 1",
+     sample_rate = ?3, theme = ?4, cue_pan_side = ?5, solo_mode = ?6, fade_curve = ?7,
+     max_decode_threads = ?8, import_sample_rate = ?9,
+     audio_host = ?10, in_memory_cache_gb = ?11, practice_mode = ?12,
+     log_level = ?13, stem_capacity = ?14, auto_advance_gap_seconds = ?15,
+     loudness_target_enabled = ?16, loudness_target_lufs = ?17, group_drum_kit_stems = ?18,
+     master_highpass_enabled = ?19, master_highpass_hz = ?20, stem_overflow_policy = ?21,
+     disk_cache_gb = ?22 WHERE id = 1",
     params![
       settings.audio_output_device,
       settings.audio_buffer_size,
       settings.sample_rate,
       settings.theme,
+      settings.cue_pan_side,
+      settings.solo_mode,
+      settings.fade_curve,
+      settings.max_decode_threads,
+      settings.import_sample_rate,
+      settings.audio_host,
+      settings.in_memory_cache_gb,
+      settings.practice_mode as i32,
+      settings.log_level,
+      settings.stem_capacity,
+      settings.auto_advance_gap_seconds,
+      settings.loudness_target_enabled as i32,
+      settings.loudness_target_lufs,
+      settings.group_drum_kit_stems as i32,
+      settings.master_highpass_enabled as i32,
+      settings.master_highpass_hz,
+      settings.stem_overflow_policy,
+      settings.disk_cache_gb,
     ],
   )?;
   Ok(())
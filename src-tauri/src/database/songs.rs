@@ -1,11 +1,11 @@
 use rusqlite::{Connection, Result, params};
-use super::models::{Song, SongFilter, SortBy};
+use super::models::{KeyFacet, LibraryFacets, Song, SongFilter, SortBy, SortDirection};
 
 // Create a new song
 pub fn create_song(conn: &Connection, song: &Song) -> Result<()> {
   conn.execute(
-    "INSERT INTO songs (id, name, artist, duration, tempo, key, time_signature, mixdown_path, created_at, updated_at)
-     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    "INSERT INTO songs (id, name, artist, duration, tempo, key, time_signature, mixdown_path, created_at, updated_at, play_count, last_played_at, loudness_lufs)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
     params![
       song.id,
       song.name,
@@ -17,6 +17,9 @@ pub fn create_song(conn: &Connection, song: &Song) -> Result<()> {
       song.mixdown_path,
       song.created_at,
       song.updated_at,
+      song.play_count,
+      song.last_played_at,
+      song.loudness_lufs,
     ],
   )?;
   Ok(())
@@ -25,27 +28,14 @@ pub fn create_song(conn: &Connection, song: &Song) -> Result<()> {
 // Get a song by ID
 pub fn get_song(conn: &Connection, id: &str) -> Result<Song> {
   conn.query_row(
-    "SELECT id, name, artist, duration, tempo, key, time_signature, mixdown_path, created_at, updated_at
+    "SELECT id, name, artist, duration, tempo, key, time_signature, mixdown_path, created_at, updated_at, play_count, last_played_at, loudness_lufs
      FROM songs WHERE id = ?1",
     [id],
-    |row| {
-      Ok(Song {
-        id: row.get(0)?,
-        name: row.get(1)?,
-        artist: row.get(2)?,
-        duration: row.get(3)?,
-        tempo: row.get(4)?,
-        key: row.get(5)?,
-        time_signature: row.get(6)?,
-        mixdown_path: row.get(7)?,
-        created_at: row.get(8)?,
-        updated_at: row.get(9)?,
-      })
-    },
+    song_from_row,
   )
 }
 
-// Update a song
+// Update a song (play statistics are only changed by record_song_play)
 pub fn update_song(conn: &Connection, song: &Song) -> Result<()> {
   let updated_at = chrono::Utc::now().timestamp();
   conn.execute(
@@ -75,7 +65,7 @@ pub fn delete_song(conn: &Connection, id: &str) -> Result<()> {
 // List songs with optional filtering and sorting
 pub fn list_songs(conn: &Connection, filter: Option<SongFilter>) -> Result<Vec<Song>> {
   let mut query = String::from(
-    "SELECT id, name, artist, duration, tempo, key, time_signature, mixdown_path, created_at, updated_at FROM songs WHERE 1=1"
+    "SELECT id, name, artist, duration, tempo, key, time_signature, mixdown_path, created_at, updated_at, play_count, last_played_at, loudness_lufs FROM songs WHERE 1=1"
   );
   let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
@@ -104,15 +94,17 @@ pub fn list_songs(conn: &Connection, filter: Option<SongFilter>) -> Result<Vec<S
       params.push(Box::new(key.clone()));
     }
 
-    // Apply sorting
+    // Apply sorting: ORDER BY <primary> <direction>[, <secondary> <its direction>]
     if let Some(ref sort) = f.sort_by {
-      query.push_str(" ORDER BY ");
-      match sort {
-        SortBy::Name => query.push_str("name COLLATE NOCASE"),
-        SortBy::Artist => query.push_str("artist COLLATE NOCASE"),
-        SortBy::Tempo => query.push_str("tempo"),
-        SortBy::Duration => query.push_str("duration"),
-        SortBy::DateAdded => query.push_str("created_at DESC"),
+      let direction = f.sort_direction.unwrap_or_else(|| sort.default_direction());
+      query.push_str(&format!(" ORDER BY {} {}", sort_column(sort), direction_sql(direction)));
+
+      if let Some(ref secondary) = f.secondary_sort {
+        query.push_str(&format!(
+          ", {} {}",
+          sort_column(secondary),
+          direction_sql(secondary.default_direction())
+        ));
       }
     }
   }
@@ -120,20 +112,103 @@ pub fn list_songs(conn: &Connection, filter: Option<SongFilter>) -> Result<Vec<S
   let mut stmt = conn.prepare(&query)?;
   let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
 
-  let songs = stmt.query_map(param_refs.as_slice(), |row| {
-    Ok(Song {
-      id: row.get(0)?,
-      name: row.get(1)?,
-      artist: row.get(2)?,
-      duration: row.get(3)?,
-      tempo: row.get(4)?,
-      key: row.get(5)?,
-      time_signature: row.get(6)?,
-      mixdown_path: row.get(7)?,
-      created_at: row.get(8)?,
-      updated_at: row.get(9)?,
-    })
-  })?;
+  let songs = stmt.query_map(param_refs.as_slice(), song_from_row)?;
+
+  songs.collect()
+}
+
+// Count a play of a song and stamp when it happened
+pub fn record_song_play(conn: &Connection, id: &str, played_at: i64) -> Result<()> {
+  let updated = conn.execute(
+    "UPDATE songs SET play_count = play_count + 1, last_played_at = ?1 WHERE id = ?2",
+    params![played_at, id],
+  )?;
+
+  if updated == 0 {
+    return Err(rusqlite::Error::QueryReturnedNoRows);
+  }
+  Ok(())
+}
+
+// Store a song's measured integrated loudness (None clears it so it's measured again)
+pub fn set_song_loudness(conn: &Connection, id: &str, loudness_lufs: Option<f64>) -> Result<()> {
+  let updated = conn.execute(
+    "UPDATE songs SET loudness_lufs = ?1 WHERE id = ?2",
+    params![loudness_lufs, id],
+  )?;
+
+  if updated == 0 {
+    return Err(rusqlite::Error::QueryReturnedNoRows);
+  }
+  Ok(())
+}
+
+// Songs that have been played, most recent first
+pub fn get_recently_played(conn: &Connection, limit: usize) -> Result<Vec<Song>> {
+  let mut stmt = conn.prepare(
+    "SELECT id, name, artist, duration, tempo, key, time_signature, mixdown_path, created_at, updated_at, play_count, last_played_at, loudness_lufs
+     FROM songs WHERE last_played_at IS NOT NULL
+     ORDER BY last_played_at DESC LIMIT ?1"
+  )?;
 
+  let songs = stmt.query_map([limit as i64], song_from_row)?;
   songs.collect()
 }
+
+// Distinct keys with their song counts, plus the tempo range, across the whole library
+pub fn get_library_facets(conn: &Connection) -> Result<LibraryFacets> {
+  let mut stmt = conn.prepare(
+    "SELECT key, COUNT(*) FROM songs
+     WHERE key IS NOT NULL AND TRIM(key) != ''
+     GROUP BY key ORDER BY key"
+  )?;
+  let keys = stmt
+    .query_map([], |row| Ok(KeyFacet { key: row.get(0)?, song_count: row.get(1)? }))?
+    .collect::<Result<Vec<_>>>()?;
+
+  // MIN/MAX skip NULL tempos and return NULL when there are none
+  let (tempo_min, tempo_max) = conn.query_row(
+    "SELECT MIN(tempo), MAX(tempo) FROM songs",
+    [],
+    |row| Ok((row.get(0)?, row.get(1)?)),
+  )?;
+
+  Ok(LibraryFacets { keys, tempo_min, tempo_max })
+}
+
+fn song_from_row(row: &rusqlite::Row) -> Result<Song> {
+  Ok(Song {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    artist: row.get(2)?,
+    duration: row.get(3)?,
+    tempo: row.get(4)?,
+    key: row.get(5)?,
+    time_signature: row.get(6)?,
+    mixdown_path: row.get(7)?,
+    created_at: row.get(8)?,
+    updated_at: row.get(9)?,
+    play_count: row.get(10)?,
+    last_played_at: row.get(11)?,
+    loudness_lufs: row.get(12)?,
+  })
+}
+
+fn sort_column(sort: &SortBy) -> &'static str {
+  match sort {
+    SortBy::Name => "name COLLATE NOCASE",
+    SortBy::Artist => "artist COLLATE NOCASE",
+    SortBy::Tempo => "tempo",
+    SortBy::Duration => "duration",
+    SortBy::DateAdded => "created_at",
+    SortBy::MostPlayed => "play_count",
+    SortBy::RecentlyPlayed => "last_played_at",
+  }
+}
+
+fn direction_sql(direction: SortDirection) -> &'static str {
+  match direction {
+    SortDirection::Ascending => "ASC",
+    SortDirection::Descending => "DESC",
+  }
+}
@@ -0,0 +1,41 @@
+use rusqlite::{Connection, Result, params};
+use super::models::StemKeyword;
+
+// Add a custom stem keyword (replaces the display name if the keyword already exists)
+pub fn add_stem_keyword(conn: &Connection, keyword: &StemKeyword) -> Result<()> {
+  conn.execute(
+    "INSERT INTO stem_keywords (keyword, display_name, created_at)
+     VALUES (?1, ?2, ?3)
+     ON CONFLICT(keyword) DO UPDATE SET display_name = excluded.display_name",
+    params![
+      keyword.keyword,
+      keyword.display_name,
+      keyword.created_at,
+    ],
+  )?;
+  Ok(())
+}
+
+// List all custom stem keywords (oldest first, which is also their match priority)
+pub fn list_stem_keywords(conn: &Connection) -> Result<Vec<StemKeyword>> {
+  let mut stmt = conn.prepare(
+    "SELECT keyword, display_name, created_at
+     FROM stem_keywords ORDER BY created_at ASC, keyword ASC"
+  )?;
+
+  let keywords = stmt.query_map([], |row| {
+    Ok(StemKeyword {
+      keyword: row.get(0)?,
+      display_name: row.get(1)?,
+      created_at: row.get(2)?,
+    })
+  })?;
+
+  keywords.collect()
+}
+
+// Delete a custom stem keyword
+pub fn delete_stem_keyword(conn: &Connection, keyword: &str) -> Result<()> {
+  conn.execute("DELETE FROM stem_keywords WHERE keyword = ?1", [keyword])?;
+  Ok(())
+}
@@ -4,8 +4,9 @@ use super::models::Stem;
 // Create a new stem
 pub fn create_stem(conn: &Connection, stem: &Stem) -> Result<()> {
   conn.execute(
-    "INSERT INTO stems (id, song_id, name, file_path, file_size, sample_rate, channels, duration, volume, is_muted, display_order)
-     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    "INSERT INTO stems (id, song_id, name, file_path, file_size, sample_rate, channels, duration, volume, is_muted, display_order, stem_group, pan, is_cue, solo_safe, offset_samples, phase_inverted, original_file_path,
+     default_volume, default_mute, delay_samples, swap_channels, mono_sum, trim_db, gate_enabled, gate_threshold_db, gate_attack_ms, gate_release_ms, file_hash, file_hash_modified)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30)",
     params![
       stem.id,
       stem.song_id,
@@ -18,6 +19,25 @@ pub fn create_stem(conn: &Connection, stem: &Stem) -> Result<()> {
       stem.volume,
       stem.is_muted as i32,
       stem.display_order,
+      stem.group,
+      stem.pan,
+      stem.is_cue as i32,
+      stem.solo_safe as i32,
+      stem.offset_samples,
+      stem.phase_inverted as i32,
+      stem.original_file_path,
+      stem.default_volume,
+      stem.default_mute as i32,
+      stem.delay_samples,
+      stem.swap_channels as i32,
+      stem.mono_sum as i32,
+      stem.trim_db,
+      stem.gate_enabled as i32,
+      stem.gate_threshold_db,
+      stem.gate_attack_ms,
+      stem.gate_release_ms,
+      stem.file_hash,
+      stem.file_hash_modified,
     ],
   )?;
   Ok(())
@@ -26,7 +46,8 @@ pub fn create_stem(conn: &Connection, stem: &Stem) -> Result<()> {
 // Get a stem by ID
 pub fn get_stem(conn: &Connection, id: &str) -> Result<Stem> {
   conn.query_row(
-    "SELECT id, song_id, name, file_path, file_size, sample_rate, channels, duration, volume, is_muted, display_order
+    "SELECT id, song_id, name, file_path, file_size, sample_rate, channels, duration, volume, is_muted, display_order, stem_group, pan, is_cue, solo_safe, offset_samples, phase_inverted, original_file_path,
+     default_volume, default_mute, delay_samples, swap_channels, mono_sum, trim_db, gate_enabled, gate_threshold_db, gate_attack_ms, gate_release_ms, file_hash, file_hash_modified
      FROM stems WHERE id = ?1",
     [id],
     |row| {
@@ -42,6 +63,25 @@ pub fn get_stem(conn: &Connection, id: &str) -> Result<Stem> {
         volume: row.get(8)?,
         is_muted: row.get::<_, i32>(9)? != 0,
         display_order: row.get(10)?,
+        group: row.get(11)?,
+        pan: row.get(12)?,
+        is_cue: row.get::<_, i32>(13)? != 0,
+        solo_safe: row.get::<_, i32>(14)? != 0,
+        offset_samples: row.get(15)?,
+        phase_inverted: row.get::<_, i32>(16)? != 0,
+        original_file_path: row.get(17)?,
+        default_volume: row.get(18)?,
+        default_mute: row.get::<_, i32>(19)? != 0,
+        delay_samples: row.get(20)?,
+        swap_channels: row.get::<_, i32>(21)? != 0,
+        mono_sum: row.get::<_, i32>(22)? != 0,
+        trim_db: row.get(23)?,
+        gate_enabled: row.get::<_, i32>(24)? != 0,
+        gate_threshold_db: row.get(25)?,
+        gate_attack_ms: row.get(26)?,
+        gate_release_ms: row.get(27)?,
+        file_hash: row.get(28)?,
+        file_hash_modified: row.get(29)?,
       })
     },
   )
@@ -50,7 +90,8 @@ pub fn get_stem(conn: &Connection, id: &str) -> Result<Stem> {
 // Get all stems for a song
 pub fn get_stems_for_song(conn: &Connection, song_id: &str) -> Result<Vec<Stem>> {
   let mut stmt = conn.prepare(
-    "SELECT id, song_id, name, file_path, file_size, sample_rate, channels, duration, volume, is_muted, display_order
+    "SELECT id, song_id, name, file_path, file_size, sample_rate, channels, duration, volume, is_muted, display_order, stem_group, pan, is_cue, solo_safe, offset_samples, phase_inverted, original_file_path,
+     default_volume, default_mute, delay_samples, swap_channels, mono_sum, trim_db, gate_enabled, gate_threshold_db, gate_attack_ms, gate_release_ms, file_hash, file_hash_modified
      FROM stems WHERE song_id = ?1 ORDER BY display_order ASC"
   )?;
 
@@ -67,6 +108,25 @@ pub fn get_stems_for_song(conn: &Connection, song_id: &str) -> Result<Vec<Stem>>
       volume: row.get(8)?,
       is_muted: row.get::<_, i32>(9)? != 0,
       display_order: row.get(10)?,
+      group: row.get(11)?,
+      pan: row.get(12)?,
+      is_cue: row.get::<_, i32>(13)? != 0,
+      solo_safe: row.get::<_, i32>(14)? != 0,
+      offset_samples: row.get(15)?,
+      phase_inverted: row.get::<_, i32>(16)? != 0,
+      original_file_path: row.get(17)?,
+      default_volume: row.get(18)?,
+      default_mute: row.get::<_, i32>(19)? != 0,
+      delay_samples: row.get(20)?,
+      swap_channels: row.get::<_, i32>(21)? != 0,
+      mono_sum: row.get::<_, i32>(22)? != 0,
+      trim_db: row.get(23)?,
+      gate_enabled: row.get::<_, i32>(24)? != 0,
+      gate_threshold_db: row.get(25)?,
+      gate_attack_ms: row.get(26)?,
+      gate_release_ms: row.get(27)?,
+      file_hash: row.get(28)?,
+      file_hash_modified: row.get(29)?,
     })
   })?;
 
@@ -77,8 +137,13 @@ pub fn get_stems_for_song(conn: &Connection, song_id: &str) -> Result<Vec<Stem>>
 pub fn update_stem(conn: &Connection, stem: &Stem) -> Result<()> {
   conn.execute(
     "UPDATE stems SET name = ?1, file_path = ?2, file_size = ?3, sample_rate = ?4,
-     channels = ?5, duration = ?6, volume = ?7, is_muted = ?8, display_order = ?9
-     WHERE id = ?10",
+     channels = ?5, duration = ?6, volume = ?7, is_muted = ?8, display_order = ?9,
+     stem_group = ?10, pan = ?11, is_cue = ?12, solo_safe = ?13, offset_samples = ?14,
+     phase_inverted = ?15, original_file_path = ?16, default_volume = ?17, default_mute = ?18,
+     delay_samples = ?19, swap_channels = ?20, mono_sum = ?21, trim_db = ?22,
+     gate_enabled = ?23, gate_threshold_db = ?24, gate_attack_ms = ?25, gate_release_ms = ?26,
+     file_hash = ?27, file_hash_modified = ?28
+     WHERE id = ?29",
     params![
       stem.name,
       stem.file_path,
@@ -89,12 +154,72 @@ pub fn update_stem(conn: &Connection, stem: &Stem) -> Result<()> {
       stem.volume,
       stem.is_muted as i32,
       stem.display_order,
+      stem.group,
+      stem.pan,
+      stem.is_cue as i32,
+      stem.solo_safe as i32,
+      stem.offset_samples,
+      stem.phase_inverted as i32,
+      stem.original_file_path,
+      stem.default_volume,
+      stem.default_mute as i32,
+      stem.delay_samples,
+      stem.swap_channels as i32,
+      stem.mono_sum as i32,
+      stem.trim_db,
+      stem.gate_enabled as i32,
+      stem.gate_threshold_db,
+      stem.gate_attack_ms,
+      stem.gate_release_ms,
+      stem.file_hash,
+      stem.file_hash_modified,
       stem.id,
     ],
   )?;
   Ok(())
 }
 
+// Update several stems in one transaction (all or nothing)
+pub fn update_stems(conn: &Connection, stems: &[Stem]) -> Result<()> {
+  let tx = conn.unchecked_transaction()?;
+
+  for stem in stems {
+    update_stem(&tx, stem)?;
+  }
+
+  tx.commit()
+}
+
+// Update the volume of several stems in one transaction (all or nothing)
+pub fn update_stem_volumes(conn: &Connection, updates: &[(String, f64)]) -> Result<()> {
+  let tx = conn.unchecked_transaction()?;
+
+  for (stem_id, volume) in updates {
+    let updated = tx.execute(
+      "UPDATE stems SET volume = ?1 WHERE id = ?2",
+      params![volume, stem_id],
+    )?;
+    if updated == 0 {
+      return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+  }
+
+  tx.commit()
+}
+
+// Save a stem's content hash with the source mtime it was computed at
+pub fn set_stem_file_hash(conn: &Connection, id: &str, file_hash: &str, modified: Option<i64>) -> Result<()> {
+  let updated = conn.execute(
+    "UPDATE stems SET file_hash = ?1, file_hash_modified = ?2 WHERE id = ?3",
+    params![file_hash, modified, id],
+  )?;
+
+  if updated == 0 {
+    return Err(rusqlite::Error::QueryReturnedNoRows);
+  }
+  Ok(())
+}
+
 // Delete a stem
 pub fn delete_stem(conn: &Connection, id: &str) -> Result<()> {
   conn.execute("DELETE FROM stems WHERE id = ?1", [id])?;
@@ -21,6 +21,9 @@ mod database_tests {
       mixdown_path: None,
       created_at: chrono::Utc::now().timestamp(),
       updated_at: chrono::Utc::now().timestamp(),
+      play_count: 0,
+      last_played_at: None,
+      loudness_lufs: None,
     }
   }
 
@@ -37,6 +40,26 @@ mod database_tests {
       duration: 180.0,
       volume: 0.8,
       is_muted: false,
+      display_order: 0,
+      group: None,
+      pan: None,
+      is_cue: false,
+      solo_safe: false,
+      offset_samples: 0,
+      phase_inverted: false,
+      original_file_path: None,
+      default_volume: 0.8,
+      default_mute: false,
+      delay_samples: 0,
+      swap_channels: false,
+      mono_sum: false,
+      trim_db: 0.0,
+      gate_enabled: false,
+      gate_threshold_db: -50.0,
+      gate_attack_ms: 1.0,
+      gate_release_ms: 50.0,
+      file_hash: None,
+      file_hash_modified: None,
     }
   }
 
@@ -48,6 +71,9 @@ mod database_tests {
       created_at: chrono::Utc::now().timestamp(),
       updated_at: chrono::Utc::now().timestamp(),
       song_ids: vec![],
+      position: 0,
+      color: None,
+      songs: vec![],
     }
   }
 
@@ -216,6 +242,27 @@ mod database_tests {
     assert_eq!(songs.len(), 2, "Should retrieve all songs");
   }
 
+  #[test]
+  fn test_song_round_trips_time_signature_and_mixdown_path() {
+    let db = create_test_db().unwrap();
+    let mut song = create_test_song();
+    song.time_signature = Some("6/8".to_string());
+    song.mixdown_path = Some("/mixdowns/song.wav".to_string());
+    db.create_song(&song).unwrap();
+
+    let retrieved = db.get_song(&song.id).unwrap();
+    assert_eq!(retrieved.time_signature.as_deref(), Some("6/8"));
+    assert_eq!(retrieved.mixdown_path.as_deref(), Some("/mixdowns/song.wav"));
+
+    song.time_signature = Some("3/4".to_string());
+    song.mixdown_path = None;
+    db.update_song(&song).unwrap();
+
+    let listed = db.list_songs(None).unwrap();
+    assert_eq!(listed[0].time_signature.as_deref(), Some("3/4"));
+    assert_eq!(listed[0].mixdown_path, None, "Clearing the mixdown path should persist");
+  }
+
   // ===========================================
   // STEM CRUD OPERATIONS
   // ===========================================
@@ -333,6 +380,29 @@ mod database_tests {
     );
   }
 
+  #[test]
+  fn test_stem_automation_round_trip() {
+    let db = create_test_db().unwrap();
+    let song = create_test_song();
+    db.create_song(&song).unwrap();
+    let stem = create_test_stem(&song.id);
+    db.create_stem(&stem).unwrap();
+
+    assert!(db.get_stem_automation(&stem.id).unwrap().is_empty());
+
+    let points = vec![
+      AutomationPoint { position_seconds: 60.0, volume: 0.0 },
+      AutomationPoint { position_seconds: 64.0, volume: 0.8 },
+    ];
+    db.set_stem_automation(&stem.id, &points).unwrap();
+    assert_eq!(db.get_stem_automation(&stem.id).unwrap(), points);
+    assert_eq!(db.get_song_automation(&song.id).unwrap().get(&stem.id), Some(&points));
+
+    // An empty list removes the automation
+    db.set_stem_automation(&stem.id, &[]).unwrap();
+    assert!(db.get_song_automation(&song.id).unwrap().is_empty());
+  }
+
   // ===========================================
   // SETLIST CRUD OPERATIONS
   // ===========================================
@@ -385,6 +455,41 @@ mod database_tests {
     assert!(retrieved.is_err(), "Deleted setlist should not be found");
   }
 
+  #[test]
+  fn test_reorder_setlists_and_color() {
+    let db = create_test_db().unwrap();
+    let mut setlists = Vec::new();
+    for name in ["First", "Second", "Third"] {
+      let mut setlist = create_test_setlist();
+      setlist.name = name.to_string();
+      db.create_setlist(&setlist).unwrap();
+      setlists.push(setlist);
+    }
+
+    // New setlists go on top, newest first
+    let listed = db.list_setlists().unwrap();
+    let names: Vec<&str> = listed.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Third", "Second", "First"]);
+    assert_eq!(listed.iter().map(|s| s.position).collect::<Vec<_>>(), vec![0, 1, 2]);
+
+    setlists[1].color = Some("#ef4444".to_string());
+    db.update_setlist(&setlists[1]).unwrap();
+
+    let ids = vec![setlists[2].id.clone(), setlists[0].id.clone(), setlists[1].id.clone()];
+    db.reorder_setlists(&ids).unwrap();
+
+    let listed = db.list_setlists().unwrap();
+    let names: Vec<&str> = listed.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Third", "First", "Second"]);
+    assert_eq!(listed.iter().map(|s| s.position).collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(db.get_setlist(&setlists[1].id).unwrap().color.as_deref(), Some("#ef4444"));
+    assert_eq!(listed[0].color, None);
+
+    // Unknown ids are rejected without changing the order
+    assert!(db.reorder_setlists(&["missing".to_string()]).is_err());
+    assert_eq!(db.list_setlists().unwrap()[0].name, "Third");
+  }
+
   #[test]
   fn test_setlist_with_ordered_songs() {
     let db = create_test_db().unwrap();
@@ -415,6 +520,87 @@ mod database_tests {
     assert_eq!(setlists.len(), 2, "Should retrieve all setlists");
   }
 
+  #[test]
+  fn test_setlist_song_override_is_scoped_to_one_setlist() {
+    let db = create_test_db().unwrap();
+    let song = create_test_song();
+    db.create_song(&song).unwrap();
+
+    let mut setlist1 = create_test_setlist();
+    setlist1.song_ids = vec![song.id.clone()];
+    db.create_setlist(&setlist1).unwrap();
+
+    let mut setlist2 = create_test_setlist();
+    setlist2.song_ids = vec![song.id.clone()];
+    db.create_setlist(&setlist2).unwrap();
+
+    db.set_setlist_song_override(&setlist1.id, &song.id, Some("D"), Some("Slower"), None).unwrap();
+
+    let first = db.get_setlist(&setlist1.id).unwrap();
+    assert_eq!(first.songs[0].key_override.as_deref(), Some("D"));
+    assert_eq!(first.songs[0].tempo_note.as_deref(), Some("Slower"));
+
+    let second = db.get_setlist(&setlist2.id).unwrap();
+    assert_eq!(second.songs[0].key_override, None, "Other setlists keep the song's default");
+    assert_eq!(second.songs[0].tempo_note, None);
+
+    assert_eq!(db.get_song(&song.id).unwrap().key.as_deref(), Some("C"), "Song itself is unchanged");
+  }
+
+  #[test]
+  fn test_setlist_song_override_survives_reorder() {
+    let db = create_test_db().unwrap();
+    let song1 = create_test_song();
+    let song2 = create_test_song();
+    db.create_song(&song1).unwrap();
+    db.create_song(&song2).unwrap();
+
+    let mut setlist = create_test_setlist();
+    setlist.song_ids = vec![song1.id.clone(), song2.id.clone()];
+    db.create_setlist(&setlist).unwrap();
+    db.set_setlist_song_override(&setlist.id, &song1.id, Some("E"), None, Some("Segue")).unwrap();
+
+    setlist.song_ids = vec![song2.id.clone(), song1.id.clone()];
+    db.update_setlist(&setlist).unwrap();
+
+    let retrieved = db.get_setlist(&setlist.id).unwrap();
+    assert_eq!(retrieved.song_ids, vec![song2.id.clone(), song1.id.clone()]);
+    assert_eq!(retrieved.songs[1].song_id, song1.id);
+    assert_eq!(retrieved.songs[1].key_override.as_deref(), Some("E"));
+    assert_eq!(retrieved.songs[1].transition_note.as_deref(), Some("Segue"));
+  }
+
+  #[test]
+  fn test_setlist_song_override_requires_song_in_setlist() {
+    let db = create_test_db().unwrap();
+    let setlist = create_test_setlist();
+    db.create_setlist(&setlist).unwrap();
+
+    let result = db.set_setlist_song_override(&setlist.id, "missing-song", Some("D"), None, None);
+    assert!(result.is_err(), "Override for a song not in the setlist should fail");
+  }
+
+  #[test]
+  fn test_migration_converts_setlist_song_ids_json() {
+    // Build a V6 database, where setlists still store their songs as JSON
+    let conn = connection::create_in_memory_connection().unwrap();
+    schema::initialize_schema_to(&conn, 6).unwrap();
+    conn.execute(
+      "INSERT INTO setlists (id, name, created_at, updated_at, song_ids) VALUES ('legacy', 'Legacy', 0, 0, ?1)",
+      [serde_json::to_string(&vec!["song-b", "song-a"]).unwrap()],
+    ).unwrap();
+
+    schema::initialize_schema(&conn).unwrap();
+    let db = Database { conn: std::sync::Arc::new(std::sync::Mutex::new(conn)) };
+
+    let setlist = db.get_setlist("legacy").unwrap();
+    assert_eq!(setlist.song_ids, vec!["song-b".to_string(), "song-a".to_string()]);
+    assert_eq!(setlist.songs[0].position, 0);
+    assert_eq!(setlist.songs[1].position, 1);
+    assert_eq!(db.get_schema_version().unwrap(), schema::SCHEMA_VERSION);
+  }
+
+
   // ===========================================
   // APP SETTINGS PERSISTENCE
   // ===========================================
@@ -482,6 +668,8 @@ mod database_tests {
       tempo_max: None,
       key: None,
       sort_by: None,
+      sort_direction: None,
+      secondary_sort: None,
     };
     let results = db.list_songs(Some(filter)).unwrap();
     assert_eq!(results.len(), 1);
@@ -505,6 +693,8 @@ mod database_tests {
       tempo_max: None,
       key: None,
       sort_by: None,
+      sort_direction: None,
+      secondary_sort: None,
     };
     let results = db.list_songs(Some(filter)).unwrap();
     assert_eq!(results.len(), 1);
@@ -531,6 +721,8 @@ mod database_tests {
       tempo_max: Some(140.0),
       key: None,
       sort_by: None,
+      sort_direction: None,
+      secondary_sort: None,
     };
     let results = db.list_songs(Some(filter)).unwrap();
     assert_eq!(results.len(), 1);
@@ -557,12 +749,35 @@ mod database_tests {
       tempo_max: None,
       key: Some("C".to_string()),
       sort_by: None,
+      sort_direction: None,
+      secondary_sort: None,
     };
     let results = db.list_songs(Some(filter)).unwrap();
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].key, Some("C".to_string()));
   }
 
+  #[test]
+  fn test_library_facets_list_distinct_keys_and_tempo_range() {
+    let db = create_test_db().unwrap();
+    for (key, tempo) in [(Some("C"), Some(72.0)), (Some("G"), None), (Some("Am"), Some(140.0)), (Some("C"), Some(96.0)), (None, Some(180.0))] {
+      let mut song = create_test_song();
+      song.key = key.map(str::to_string);
+      song.tempo = tempo;
+      db.create_song(&song).unwrap();
+    }
+
+    let facets = db.get_library_facets().unwrap();
+    let keys: Vec<(&str, i64)> = facets.keys.iter().map(|f| (f.key.as_str(), f.song_count)).collect();
+    assert_eq!(keys, vec![("Am", 1), ("C", 2), ("G", 1)], "The key-less song is left out");
+    assert_eq!(facets.tempo_min, Some(72.0));
+    assert_eq!(facets.tempo_max, Some(180.0));
+
+    let empty = create_test_db().unwrap().get_library_facets().unwrap();
+    assert!(empty.keys.is_empty());
+    assert_eq!(empty.tempo_min, None);
+  }
+
   #[test]
   fn test_combined_filters() {
     let db = create_test_db().unwrap();
@@ -587,6 +802,8 @@ mod database_tests {
       tempo_max: Some(130.0),
       key: Some("C".to_string()),
       sort_by: None,
+      sort_direction: None,
+      secondary_sort: None,
     };
     let results = db.list_songs(Some(filter)).unwrap();
     assert_eq!(results.len(), 1);
@@ -613,6 +830,8 @@ mod database_tests {
       tempo_max: None,
       key: None,
       sort_by: Some(SortBy::Name),
+      sort_direction: None,
+      secondary_sort: None,
     };
     let results = db.list_songs(Some(filter)).unwrap();
     assert_eq!(results[0].name, "Apple Song");
@@ -620,6 +839,51 @@ mod database_tests {
     assert_eq!(results[2].name, "Zebra Song");
   }
 
+  #[test]
+  fn test_sort_songs_by_name_descending() {
+    let db = create_test_db().unwrap();
+    for name in ["Zebra Song", "apple Song", "Mango Song"] {
+      let mut song = create_test_song();
+      song.name = name.to_string();
+      db.create_song(&song).unwrap();
+    }
+
+    let filter = SongFilter {
+      sort_by: Some(SortBy::Name),
+      sort_direction: Some(SortDirection::Descending),
+      ..Default::default()
+    };
+    let names: Vec<String> = db.list_songs(Some(filter)).unwrap().into_iter().map(|s| s.name).collect();
+    assert_eq!(names, vec!["Zebra Song", "Mango Song", "apple Song"]);
+  }
+
+  #[test]
+  fn test_sort_songs_by_artist_then_name() {
+    let db = create_test_db().unwrap();
+    for (artist, name) in [("Hillsong", "So Will I"), ("Elevation", "Graves"), ("Hillsong", "Oceans"), ("Elevation", "Do It Again")] {
+      let mut song = create_test_song();
+      song.artist = Some(artist.to_string());
+      song.name = name.to_string();
+      db.create_song(&song).unwrap();
+    }
+
+    let filter = SongFilter {
+      sort_by: Some(SortBy::Artist),
+      secondary_sort: Some(SortBy::Name),
+      ..Default::default()
+    };
+    let order: Vec<(String, String)> = db.list_songs(Some(filter)).unwrap()
+      .into_iter()
+      .map(|s| (s.artist.unwrap(), s.name))
+      .collect();
+    assert_eq!(order, vec![
+      ("Elevation".to_string(), "Do It Again".to_string()),
+      ("Elevation".to_string(), "Graves".to_string()),
+      ("Hillsong".to_string(), "Oceans".to_string()),
+      ("Hillsong".to_string(), "So Will I".to_string()),
+    ]);
+  }
+
   // ===========================================
   // DATA INTEGRITY TESTS
   // ===========================================
@@ -659,4 +923,51 @@ mod database_tests {
       "Should not allow duplicate UUIDs"
     );
   }
+
+  #[test]
+  fn test_import_song_transactional_commits_song_and_stems() {
+    let db = create_test_db().unwrap();
+    let song = create_test_song();
+    let stems = vec![create_test_stem(&song.id), create_test_stem(&song.id)];
+
+    db.import_song_transactional(&song, &stems).unwrap();
+
+    assert_eq!(db.list_songs(None).unwrap().len(), 1);
+    assert_eq!(db.get_stems_for_song(&song.id).unwrap().len(), 2);
+  }
+
+  #[test]
+  fn test_import_song_transactional_rolls_back_on_stem_failure() {
+    let db = create_test_db().unwrap();
+    let song = create_test_song();
+    let stem1 = create_test_stem(&song.id);
+    let mut stem2 = create_test_stem(&song.id);
+    stem2.id = stem1.id.clone(); // Duplicate primary key forces the second insert to fail
+
+    let result = db.import_song_transactional(&song, &[stem1, stem2]);
+    assert!(result.is_err(), "Duplicate stem ID should fail the import");
+
+    assert_eq!(db.list_songs(None).unwrap().len(), 0, "No partial song should remain");
+    assert_eq!(db.get_stems_for_song(&song.id).unwrap().len(), 0, "No orphaned stems should remain");
+  }
+
+  #[test]
+  fn test_maintain_database_reports_ok_integrity() {
+    let dir = std::env::temp_dir().join(format!("trax_maintain_test_{}", uuid::Uuid::new_v4()));
+    let db = Database::open(&dir.join("trax.db")).unwrap();
+    let song = create_test_song();
+    db.import_song_transactional(&song, &[create_test_stem(&song.id), create_test_stem(&song.id)]).unwrap();
+
+    let report = db.maintain().unwrap();
+    assert_eq!(report.integrity, "ok");
+    assert!(report.wal_bytes_reclaimed > 0, "Writes should have left WAL frames to reclaim");
+    assert!(!dir.join("trax.db-wal").exists() || std::fs::metadata(dir.join("trax.db-wal")).unwrap().len() == 0);
+
+    // In-memory databases have no WAL but still get checked
+    let report = create_test_db().unwrap().maintain().unwrap();
+    assert_eq!(report.integrity, "ok");
+    assert_eq!(report.wal_bytes_reclaimed, 0);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
 }
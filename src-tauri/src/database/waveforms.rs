@@ -0,0 +1,52 @@
+use rusqlite::{Connection, Result, params};
+use super::models::StemWaveform;
+
+// Save a stem's waveform peaks (stored as JSON), replacing any at the same resolution
+pub fn save_stem_waveform(conn: &Connection, waveform: &StemWaveform) -> Result<()> {
+  let to_json = |peaks: &Vec<f32>| serde_json::to_string(peaks)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)));
+
+  conn.execute(
+    "INSERT OR REPLACE INTO waveforms (stem_id, buckets, min_peaks, max_peaks, created_at, source)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    params![
+      waveform.stem_id,
+      waveform.buckets,
+      to_json(&waveform.min)?,
+      to_json(&waveform.max)?,
+      waveform.created_at,
+      waveform.source,
+    ],
+  )?;
+  Ok(())
+}
+
+// Get a stem's waveform at `buckets` resolution, None if it hasn't been computed from `source`
+// (the stem's file as it is now; a frozen, relocated or edited file needs new peaks). Without a
+// source (the file can't be read) whatever was computed last is returned
+pub fn get_stem_waveform(conn: &Connection, stem_id: &str, buckets: i64, source: Option<&str>) -> Result<Option<StemWaveform>> {
+  let mut stmt = conn.prepare(
+    "SELECT stem_id, buckets, min_peaks, max_peaks, created_at, source
+     FROM waveforms WHERE stem_id = ?1 AND buckets = ?2 AND (?3 IS NULL OR source = ?3)"
+  )?;
+
+  let mut waveforms = stmt.query_map(params![stem_id, buckets, source], waveform_from_row)?;
+  waveforms.next().transpose()
+}
+
+fn waveform_from_row(row: &rusqlite::Row) -> Result<StemWaveform> {
+  let peaks = |index: usize| -> Result<Vec<f32>> {
+    let json: String = row.get(index)?;
+    serde_json::from_str(&json)
+      .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+  };
+
+  Ok(StemWaveform {
+    stem_id: row.get(0)?,
+    buckets: row.get(1)?,
+    min: peaks(2)?,
+    max: peaks(3)?,
+    created_at: row.get(4)?,
+    source: row.get(5)?,
+  })
+}
@@ -3,23 +3,29 @@ use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::time::Duration;
 use tauri::{AppHandle, Emitter};
 
-use crate::audio::PlaybackState;
+use crate::audio::{MeterHandles, PlaybackState};
+use crate::commands::{self, AppState};
 
 /// Start a background task that emits playback position updates
 pub fn start_position_emitter(
   app_handle: AppHandle,
   position: Arc<AtomicU64>,
+  duration: Arc<AtomicU64>,
   playback_state: Arc<Mutex<PlaybackState>>,
-  stem_levels: Vec<Arc<AtomicU32>>,
-  master_level: Arc<AtomicU32>,
+  meters: MeterHandles,
+  sample_rate: Arc<AtomicU32>,
 ) {
   tauri::async_runtime::spawn(async move {
     loop {
       tokio::time::sleep(Duration::from_millis(50)).await; // 20 FPS for smooth meters
 
-      // Get current position (sample position)
-      let sample_position = position.load(Ordering::Acquire);
-      let position_seconds = sample_position as f64 / (48000.0 * 2.0); // TARGET_SAMPLE_RATE * channels
+      // Get current position: whole frames at the project rate (the atomic counts stereo samples)
+      let project_rate = sample_rate.load(Ordering::Acquire);
+      let position_frames = position.load(Ordering::Acquire) / 2;
+      let position_seconds = position_frames as f64 / project_rate as f64;
+
+      // Get loaded song duration (0.0 when nothing is loaded)
+      let duration_seconds = f64::from_bits(duration.load(Ordering::Acquire));
 
       // Get playback state
       let is_playing = {
@@ -30,18 +36,23 @@ pub fn start_position_emitter(
         matches!(state, PlaybackState::Playing)
       };
 
-      // Get stem levels (convert from atomic bits to f32)
-      let levels: Vec<f32> = stem_levels
-        .iter()
-        .map(|level| f32::from_bits(level.load(Ordering::Acquire)))
-        .collect();
+      // Get stem levels and held peaks (convert from atomic bits to f32)
+      let read = |values: &[Arc<AtomicU32>]| -> Vec<f32> {
+        values.iter().map(|value| f32::from_bits(value.load(Ordering::Acquire))).collect()
+      };
+      let levels = read(&meters.stem_levels);
+      let held = read(&meters.stem_peak_holds);
 
       // Get master level
-      let master = f32::from_bits(master_level.load(Ordering::Acquire));
+      let master = f32::from_bits(meters.master_level.load(Ordering::Acquire));
+      let master_held = f32::from_bits(meters.master_peak_hold.load(Ordering::Acquire));
 
       // Emit position event
       if let Err(e) = app_handle.emit("playback:position", serde_json::json!({
-        "position": position_seconds
+        "position": position_seconds,
+        "frame_position": position_frames,
+        "sample_rate": project_rate,
+        "duration": duration_seconds
       })) {
         log::error!("Failed to emit position event: {}", e);
       }
@@ -56,10 +67,81 @@ pub fn start_position_emitter(
       // Emit stem levels event with master level
       if let Err(e) = app_handle.emit("playback:levels", serde_json::json!({
         "levels": levels,
-        "master": master
+        "held": held,
+        "master": master,
+        "master_held": master_held
       })) {
         log::error!("Failed to emit levels event: {}", e);
       }
     }
   });
 }
+
+/// Start a background task that moves playback to the default device when the
+/// output device disappears, emitting "audio:device-changed" so the UI can warn
+pub fn start_device_watcher(app_handle: AppHandle, state: AppState) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(Duration::from_millis(500)).await;
+
+      let mut engine = match state.audio_engine.lock() {
+        Ok(engine) => engine,
+        Err(_) => continue,
+      };
+      let previous_device = engine.current_device_name();
+      let previous_rate = engine.project_sample_rate();
+      let already_reported = engine.device_fallback_failed();
+
+      match engine.check_device_fallback() {
+        Ok(Some(device_name)) => {
+          let sample_rate = engine.project_sample_rate();
+          drop(engine);
+          log::warn!("Audio output moved from {:?} to {}", previous_device, device_name);
+          // The default device may not run at the project rate; the project follows it and
+          // the songs decoded at the old rate are dropped
+          let sample_rate_changed = (sample_rate != previous_rate).then_some(sample_rate);
+          if sample_rate_changed.is_some() {
+            if let Err(e) = commands::follow_device_sample_rate(&state, sample_rate) {
+              log::error!("Failed to follow the default device's sample rate: {}", e);
+            }
+          }
+          if let Err(e) = app_handle.emit("audio:device-changed", serde_json::json!({
+            "device_name": device_name,
+            "previous_device": previous_device,
+            "sample_rate_changed": sample_rate_changed,
+            "reason": "disconnected"
+          })) {
+            log::error!("Failed to emit device change event: {}", e);
+          }
+        }
+        Ok(None) => {}
+        // Retried every tick until a device appears; the first failure says it all
+        Err(e) if !already_reported => log::error!("Failed to fall back to the default device: {}", e),
+        Err(e) => log::debug!("Still no default device to fall back to: {}", e),
+      }
+    }
+  });
+}
+
+/// Start a background task that plays the next setlist song when the current one ends,
+/// if the running setlist was started with auto-advance
+pub fn start_auto_advance_watcher(app_handle: AppHandle, state: AppState) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(Duration::from_millis(50)).await;
+
+      let emit_handle = app_handle.clone();
+      let emit = move |event: &str, payload: serde_json::Value| {
+        let _ = emit_handle.emit(event, payload);
+      };
+      match commands::advance_after_song_end(&state, emit).await {
+        Ok(Some((song, generation))) => {
+          log::info!("Auto-advanced to '{}'", song.name);
+          commands::spawn_preload(&state, generation, app_handle.clone());
+        }
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to auto-advance the setlist: {}", e),
+      }
+    }
+  });
+}
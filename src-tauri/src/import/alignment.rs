@@ -0,0 +1,33 @@
+/// Level below which leading audio counts as silence (about -60 dBFS)
+pub const LEADING_SILENCE_THRESHOLD: f32 = 0.001;
+
+/// Count the frames of leading silence in interleaved stereo samples: everything
+/// before the first sample above `threshold` on either channel.
+/// A stem that never rises above the threshold is left unaligned (0)
+pub fn detect_leading_silence(samples: &[f32], threshold: f32) -> u64 {
+  samples
+    .iter()
+    .position(|s| s.abs() > threshold)
+    .map(|index| (index / 2) as u64)
+    .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_detect_leading_silence() {
+    let mut samples = vec![0.0f32; 1000 * 2];
+    samples.extend_from_slice(&[0.0, 0.5, 0.5, 0.5]);
+    assert_eq!(detect_leading_silence(&samples, LEADING_SILENCE_THRESHOLD), 1000);
+
+    // Noise under the threshold still counts as silence
+    let mut noisy = vec![0.0005f32; 10 * 2];
+    noisy.push(0.2);
+    assert_eq!(detect_leading_silence(&noisy, LEADING_SILENCE_THRESHOLD), 10);
+
+    assert_eq!(detect_leading_silence(&[0.3, 0.3], LEADING_SILENCE_THRESHOLD), 0);
+    assert_eq!(detect_leading_silence(&[0.0; 64], LEADING_SILENCE_THRESHOLD), 0);
+  }
+}
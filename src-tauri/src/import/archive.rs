@@ -0,0 +1,227 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::database::{Database, Song, Stem};
+use super::ImportError;
+use super::mixdown::get_app_data_directory;
+
+const MANIFEST_NAME: &str = "manifest.json";
+// 2: stems carry the mix defaults, phase, delay, channel, trim and gate settings.
+// Missing fields from version 1 archives take their serde defaults
+const ARCHIVE_FORMAT_VERSION: u32 = 2;
+
+/// Song metadata and stem list stored alongside the audio in a song archive
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveManifest {
+  format_version: u32,
+  song: Song,
+  stems: Vec<ArchiveStem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveStem {
+  entry: String,     // Path of the audio inside the archive
+  file_name: String, // Original file name, used when unpacking
+  stem: Stem,
+}
+
+/// Get the directory songs unpacked from archives are stored in
+pub fn get_songs_directory() -> Result<PathBuf, ImportError> {
+  let songs_dir = get_app_data_directory()?.join("songs");
+
+  if !songs_dir.exists() {
+    fs::create_dir_all(&songs_dir)?;
+  }
+
+  Ok(songs_dir)
+}
+
+/// Get the directory the disk cache of decoded stems lives in (created on first write)
+pub fn get_stem_cache_directory() -> Result<PathBuf, ImportError> {
+  Ok(get_app_data_directory()?.join("stem_cache"))
+}
+
+/// Write a song's stem files and a JSON manifest of its metadata into a ZIP archive.
+/// A partly written archive is removed if the export fails
+pub fn export_song_archive(db: &Database, song_id: &str, dest_path: &Path) -> Result<(), ImportError> {
+  let result = write_song_archive(db, song_id, dest_path);
+  if result.is_err() && dest_path.exists() {
+    let _ = fs::remove_file(dest_path);
+  }
+  result
+}
+
+fn write_song_archive(db: &Database, song_id: &str, dest_path: &Path) -> Result<(), ImportError> {
+  let song = db.get_song(song_id)
+    .map_err(|e| ImportError::Database(format!("Failed to get song: {}", e)))?;
+  let stems = db.get_stems_for_song(song_id)
+    .map_err(|e| ImportError::Database(format!("Failed to get stems: {}", e)))?;
+
+  let mut zip = ZipWriter::new(File::create(dest_path)?);
+  // Audio barely compresses, so store it as-is and only deflate the manifest
+  let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+  let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+  let mut archive_stems = Vec::with_capacity(stems.len());
+  for (index, stem) in stems.into_iter().enumerate() {
+    let file_name = Path::new(&stem.file_path)
+      .file_name()
+      .and_then(|n| n.to_str())
+      .unwrap_or("stem.wav")
+      .to_string();
+    // Prefix with the index so stems with the same file name don't collide in the archive
+    let entry = format!("stems/{:02}-{}", index + 1, file_name);
+
+    let mut source = File::open(&stem.file_path)
+      .map_err(|e| ImportError::FileNotFound(format!("{}: {}", stem.file_path, e)))?;
+    zip.start_file(entry.as_str(), stored)
+      .map_err(|e| ImportError::Archive(e.to_string()))?;
+    io::copy(&mut source, &mut zip)?;
+
+    archive_stems.push(ArchiveStem { entry, file_name, stem });
+  }
+
+  let manifest = ArchiveManifest {
+    format_version: ARCHIVE_FORMAT_VERSION,
+    song,
+    stems: archive_stems,
+  };
+  let manifest_json = serde_json::to_vec_pretty(&manifest)
+    .map_err(|e| ImportError::Archive(format!("Failed to write manifest: {}", e)))?;
+
+  zip.start_file(MANIFEST_NAME, deflated)
+    .map_err(|e| ImportError::Archive(e.to_string()))?;
+  zip.write_all(&manifest_json)?;
+  zip.finish()
+    .map_err(|e| ImportError::Archive(e.to_string()))?;
+
+  log::info!("Exported song '{}' with {} stems to {}", manifest.song.name, manifest.stems.len(), dest_path.display());
+  Ok(())
+}
+
+/// Unpack a song archive into `songs_dir` and recreate its song and stem records.
+/// The song and stems get new IDs, so an archive can be imported alongside the original
+pub fn import_song_archive(db: &Database, archive_path: &Path, songs_dir: &Path) -> Result<String, ImportError> {
+  let mut zip = ZipArchive::new(File::open(archive_path)?)
+    .map_err(|e| ImportError::Archive(format!("Failed to open archive: {}", e)))?;
+
+  let manifest: ArchiveManifest = {
+    let mut entry = zip.by_name(MANIFEST_NAME)
+      .map_err(|_| ImportError::Archive("Archive has no manifest".to_string()))?;
+    let mut json = Vec::new();
+    entry.read_to_end(&mut json)?;
+    serde_json::from_slice(&json)
+      .map_err(|e| ImportError::Archive(format!("Invalid manifest: {}", e)))?
+  };
+
+  if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+    return Err(ImportError::Archive(format!(
+      "Archive format version {} is newer than supported ({})",
+      manifest.format_version, ARCHIVE_FORMAT_VERSION
+    )));
+  }
+  if manifest.stems.is_empty() {
+    return Err(ImportError::Validation("Archive contains no stems".to_string()));
+  }
+
+  let song_dir = songs_dir.join(safe_file_name(&manifest.song.name, "Song"));
+  fs::create_dir_all(&song_dir)?;
+
+  let song_id = uuid::Uuid::new_v4().to_string();
+  let now = chrono::Utc::now().timestamp();
+
+  // Unpack every stem first; on any failure remove what was written so far
+  let mut unpacked: Vec<PathBuf> = Vec::with_capacity(manifest.stems.len());
+  let mut stems = Vec::with_capacity(manifest.stems.len());
+  for archive_stem in &manifest.stems {
+    let dest = unique_path(&song_dir, &safe_file_name(&archive_stem.file_name, "stem.wav"));
+    if let Err(e) = unpack_entry(&mut zip, &archive_stem.entry, &dest) {
+      remove_files(&unpacked);
+      return Err(e);
+    }
+    unpacked.push(dest.clone());
+
+    let mut stem = Stem {
+      id: uuid::Uuid::new_v4().to_string(),
+      song_id: song_id.clone(),
+      file_path: dest.to_string_lossy().to_string(),
+      original_file_path: None, // The unpacked file is the only copy in this library
+      file_hash: None, // Archives from older versions carry partial hashes; computed when needed
+      file_hash_modified: None,
+      ..archive_stem.stem.clone()
+    };
+    // Version 1 had no mix defaults; the archived mix is the best default there is
+    if manifest.format_version < 2 {
+      stem.default_volume = stem.volume;
+      stem.default_mute = stem.is_muted;
+    }
+    stems.push(stem);
+  }
+
+  let song = Song {
+    id: song_id.clone(),
+    mixdown_path: None, // Mixdowns aren't archived
+    created_at: now,
+    updated_at: now,
+    play_count: 0, // Play history belongs to the library it came from
+    last_played_at: None,
+    loudness_lufs: None,
+    ..manifest.song
+  };
+
+  if let Err(e) = db.import_song_transactional(&song, &stems) {
+    remove_files(&unpacked);
+    return Err(ImportError::Database(format!("Failed to create song: {}", e)));
+  }
+
+  log::info!("Imported song '{}' with {} stems from {}", song.name, stems.len(), archive_path.display());
+  Ok(song_id)
+}
+
+fn unpack_entry<R: Read + io::Seek>(zip: &mut ZipArchive<R>, entry: &str, dest: &Path) -> Result<(), ImportError> {
+  let mut source = zip.by_name(entry)
+    .map_err(|_| ImportError::Archive(format!("Archive is missing '{}'", entry)))?;
+  let mut file = File::create(dest)?;
+  io::copy(&mut source, &mut file)?;
+  Ok(())
+}
+
+pub(super) fn remove_files<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) {
+  for path in paths {
+    let _ = fs::remove_file(path);
+  }
+}
+
+// Keep only the final path component so manifest names can't escape the target directory
+pub(super) fn safe_file_name(name: &str, fallback: &str) -> String {
+  Path::new(name)
+    .file_name()
+    .and_then(|n| n.to_str())
+    .map(|n| n.trim().to_string())
+    .filter(|n| !n.is_empty() && n != "." && n != "..")
+    .unwrap_or_else(|| fallback.to_string())
+}
+
+// "Vocals.wav" -> "Vocals (2).wav" etc. until the name is free in `dir`
+pub(super) fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+  let candidate = dir.join(file_name);
+  if !candidate.exists() {
+    return candidate;
+  }
+
+  let path = Path::new(file_name);
+  let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(file_name);
+  let extension = path.extension().and_then(|e| e.to_str());
+
+  (2..)
+    .map(|n| match extension {
+      Some(ext) => dir.join(format!("{} ({}).{}", stem, n, ext)),
+      None => dir.join(format!("{} ({})", stem, n)),
+    })
+    .find(|p| !p.exists())
+    .expect("unbounded search always finds a free name")
+}
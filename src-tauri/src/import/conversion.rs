@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use crate::audio::decoder::AudioDecoder;
+use crate::audio::resampler::resample_sinc;
+use super::ImportError;
+
+/// A stem file rewritten at the project sample rate
+#[derive(Debug, Clone)]
+pub struct ConvertedFile {
+  pub path: PathBuf,
+  pub file_size: i64,
+  pub duration: f64,
+}
+
+/// File name of the converted copy of `source`: "Vocals.wav" -> "Vocals.48000Hz.wav"
+pub fn converted_file_name(source: &Path, sample_rate: u32) -> String {
+  let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("stem");
+  format!("{}.{}Hz.wav", stem, sample_rate)
+}
+
+/// Decode `source`, resample it to `sample_rate` and write it to `dest` as a 32-bit float WAV.
+/// The original file is left untouched
+pub fn convert_sample_rate(source: &Path, sample_rate: u32, dest: &Path) -> Result<ConvertedFile, ImportError> {
+  let source_str = source.to_str()
+    .ok_or_else(|| ImportError::Validation(format!("Invalid file path: {}", source.display())))?;
+
+  let mut decoder = AudioDecoder::new(source_str)
+    .map_err(|e| ImportError::InvalidFormat(format!("Failed to open {}: {}", source.display(), e)))?;
+  let metadata = decoder.get_metadata()
+    .map_err(|e| ImportError::MetadataExtraction(format!("{}: {}", source.display(), e)))?;
+  let samples = decoder.decode_all()
+    .map_err(|e| ImportError::InvalidFormat(format!("Failed to decode {}: {}", source.display(), e)))?;
+
+  let channels = metadata.channels.max(1);
+  let resampled = resample_sinc(&samples, metadata.sample_rate, sample_rate, channels);
+
+  let spec = WavSpec {
+    channels,
+    sample_rate,
+    bits_per_sample: 32,
+    sample_format: SampleFormat::Float,
+  };
+  let mut writer = WavWriter::create(dest, spec)
+    .map_err(|e| ImportError::Io(std::io::Error::other(e)))?;
+  for &sample in &resampled {
+    writer.write_sample(sample)
+      .map_err(|e| ImportError::Io(std::io::Error::other(e)))?;
+  }
+  writer.finalize()
+    .map_err(|e| ImportError::Io(std::io::Error::other(e)))?;
+
+  log::info!("Converted {} from {}Hz to {}Hz: {}", source.display(), metadata.sample_rate, sample_rate, dest.display());
+
+  Ok(ConvertedFile {
+    file_size: std::fs::metadata(dest)?.len() as i64,
+    duration: (resampled.len() / channels as usize) as f64 / sample_rate as f64,
+    path: dest.to_path_buf(),
+  })
+}
@@ -2,6 +2,7 @@ use sha2::{Sha256, Digest};
 use std::fs::File;
 use std::io::{Read, BufReader};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use super::ImportError;
 
 const HASH_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
@@ -42,6 +43,36 @@ pub fn calculate_file_hash(file_path: &Path) -> Result<String, ImportError> {
   Ok(format!("{:x}", hash_result))
 }
 
+/// Calculate SHA-256 hash of the whole file
+/// Slower than calculate_file_hash, but equal hashes mean equal content (used to share decoded audio)
+pub fn calculate_content_hash(file_path: &Path) -> Result<String, ImportError> {
+  let file = File::open(file_path).map_err(|e| match e.kind() {
+    std::io::ErrorKind::NotFound => ImportError::FileNotFound(file_path.to_string_lossy().to_string()),
+    _ => ImportError::MetadataExtraction(format!("Failed to open file: {}", e)),
+  })?;
+
+  let mut reader = BufReader::new(file);
+  let mut hasher = Sha256::new();
+  let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+  loop {
+    let bytes_read = reader.read(&mut buffer)
+      .map_err(|e| ImportError::MetadataExtraction(format!("Failed to read file: {}", e)))?;
+    if bytes_read == 0 {
+      break;
+    }
+    hasher.update(&buffer[..bytes_read]);
+  }
+
+  Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// A file mtime as stored next to its content hash (ns since the epoch), so the hash is only
+/// recomputed after the file changes
+pub fn modified_nanos(modified: SystemTime) -> Option<i64> {
+  let since_epoch = modified.duration_since(UNIX_EPOCH).ok()?;
+  i64::try_from(since_epoch.as_nanos()).ok()
+}
+
 /// Check if a file with this hash already exists in the database
 pub fn is_duplicate(hash: &str, existing_hashes: &[String]) -> bool {
   existing_hashes.contains(&hash.to_string())
@@ -108,6 +139,23 @@ mod tests {
     std::fs::remove_file(file_path).ok();
   }
 
+  #[test]
+  fn test_content_hash_covers_the_whole_file() {
+    // Same length and the same first megabyte, different after it
+    let mut content_a = vec![0u8; 2 * 1024 * 1024];
+    let mut content_b = content_a.clone();
+    content_a[HASH_BUFFER_SIZE + 10] = 1;
+    content_b[HASH_BUFFER_SIZE + 10] = 2;
+    let file1 = create_temp_file(&content_a);
+    let file2 = create_temp_file(&content_b);
+
+    assert_eq!(calculate_file_hash(&file1).unwrap(), calculate_file_hash(&file2).unwrap());
+    assert_ne!(calculate_content_hash(&file1).unwrap(), calculate_content_hash(&file2).unwrap());
+
+    std::fs::remove_file(file1).ok();
+    std::fs::remove_file(file2).ok();
+  }
+
   #[test]
   fn test_is_duplicate_found() {
     let hash = "abc123".to_string();
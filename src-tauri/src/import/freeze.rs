@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use crate::audio::decoder::AudioDecoder;
+use crate::database::{Database, Stem};
+use super::ImportError;
+use super::archive::unique_path;
+
+/// Render a stem with its trim, polarity, channel mode and pan baked in, point the stem at the
+/// rendered file and reset that processing to neutral. The fader volume and mute stay live.
+/// The pre-freeze file is kept and recorded as the stem's `original_file_path`
+pub fn freeze_stem(db: &Database, stem_id: &str) -> Result<Stem, ImportError> {
+  let mut stem = db.get_stem(stem_id)
+    .map_err(|e| ImportError::Database(format!("Failed to get stem: {}", e)))?;
+  let settings = db.get_settings()
+    .map_err(|e| ImportError::Database(format!("Failed to get settings: {}", e)))?;
+
+  let source = PathBuf::from(&stem.file_path);
+  let mut decoder = AudioDecoder::new(&stem.file_path)
+    .map_err(|e| ImportError::InvalidFormat(format!("Failed to open {}: {}", stem.file_path, e)))?;
+  let metadata = decoder.get_metadata()
+    .map_err(|e| ImportError::MetadataExtraction(format!("{}: {}", stem.file_path, e)))?;
+  let samples = decoder.decode_all()
+    .map_err(|e| ImportError::InvalidFormat(format!("Failed to decode {}: {}", stem.file_path, e)))?;
+
+  let rendered = render_processed(&samples, metadata.channels.max(1) as usize, &stem, stem.effective_pan(&settings) as f32);
+
+  let dest = frozen_path(&source);
+  write_stereo_wav(&dest, &rendered, metadata.sample_rate)?;
+
+  let frozen = Stem {
+    file_path: dest.to_string_lossy().to_string(),
+    file_size: std::fs::metadata(&dest)?.len() as i64,
+    sample_rate: metadata.sample_rate as i32,
+    channels: 2,
+    // Keep the very first source if the stem was already a converted or frozen copy
+    original_file_path: stem.original_file_path.take().or(Some(stem.file_path.clone())),
+    trim_db: 0.0,
+    phase_inverted: false,
+    swap_channels: false,
+    mono_sum: false,
+    // Explicitly centred: the default pan would hard-pan a cue stem a second time
+    pan: Some(0.0),
+    // The rendered audio no longer matches the imported file
+    file_hash: None,
+    file_hash_modified: None,
+    ..stem
+  };
+
+  if let Err(e) = db.update_stem(&frozen) {
+    let _ = std::fs::remove_file(&dest);
+    return Err(ImportError::Database(format!("Failed to update stem: {}", e)));
+  }
+
+  log::info!("Froze stem '{}' to {}", frozen.name, dest.display());
+  Ok(frozen)
+}
+
+// "Vocals.wav" -> "Vocals.frozen.wav" alongside it, numbered if that already exists
+fn frozen_path(source: &Path) -> PathBuf {
+  let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("stem");
+  let dir = source.parent().unwrap_or_else(|| Path::new("."));
+  unique_path(dir, &format!("{}.frozen.wav", stem))
+}
+
+// Apply a stem's playback processing the same way the engine does, producing interleaved stereo.
+// Mono sources are duplicated to both sides; extra channels beyond the first two are dropped
+fn render_processed(samples: &[f32], channels: usize, stem: &Stem, pan: f32) -> Vec<f32> {
+  let trim = 10.0f32.powf(stem.trim_db as f32 / 20.0);
+  let gain = if stem.phase_inverted { -trim } else { trim };
+  let left_gain = gain * (1.0 - pan).min(1.0);
+  let right_gain = gain * (1.0 + pan).min(1.0);
+
+  samples
+    .chunks_exact(channels)
+    .flat_map(|frame| {
+      let (mut left, mut right) = if channels == 1 { (frame[0], frame[0]) } else { (frame[0], frame[1]) };
+      if stem.swap_channels {
+        std::mem::swap(&mut left, &mut right);
+      }
+      if stem.mono_sum {
+        let sum = (left + right) * 0.5;
+        left = sum;
+        right = sum;
+      }
+      [left * left_gain, right * right_gain]
+    })
+    .collect()
+}
+
+fn write_stereo_wav(dest: &Path, samples: &[f32], sample_rate: u32) -> Result<(), ImportError> {
+  let spec = WavSpec {
+    channels: 2,
+    sample_rate,
+    bits_per_sample: 32,
+    sample_format: SampleFormat::Float,
+  };
+  let mut writer = WavWriter::create(dest, spec)
+    .map_err(|e| ImportError::Io(std::io::Error::other(e)))?;
+  for &sample in samples {
+    writer.write_sample(sample)
+      .map_err(|e| ImportError::Io(std::io::Error::other(e)))?;
+  }
+  writer.finalize()
+    .map_err(|e| ImportError::Io(std::io::Error::other(e)))
+}
@@ -3,8 +3,8 @@ use std::path::Path;
 use symphonia::core::codecs::DecoderOptions;
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::{Hint, ProbeResult};
 use super::ImportError;
 
 #[derive(Debug, Clone)]
@@ -13,6 +13,7 @@ pub struct AudioMetadata {
   pub channels: i32,
   pub duration: f64,
   pub file_size: i64,
+  pub artist: Option<String>, // From the file's tags, if present
 }
 
 /// Extract metadata from an audio file using symphonia
@@ -44,10 +45,11 @@ pub fn extract_metadata(file_path: &Path) -> Result<AudioMetadata, ImportError>
   let format_opts = FormatOptions::default();
   let metadata_opts = MetadataOptions::default();
 
-  let probed = symphonia::default::get_probe()
+  let mut probed = symphonia::default::get_probe()
     .format(&hint, mss, &format_opts, &metadata_opts)
     .map_err(|e| ImportError::InvalidFormat(format!("Failed to probe format: {}", e)))?;
 
+  let artist = read_artist_tag(&mut probed);
   let mut format = probed.format;
 
   // Get the default track (usually the first audio track)
@@ -86,9 +88,30 @@ pub fn extract_metadata(file_path: &Path) -> Result<AudioMetadata, ImportError>
     channels,
     duration,
     file_size,
+    artist,
   })
 }
 
+// Artist from the container's tags (RIFF INFO, Vorbis comments...), falling back to
+// metadata found ahead of the container such as a leading ID3 block
+fn read_artist_tag(probed: &mut ProbeResult) -> Option<String> {
+  probed.format.metadata().current().and_then(find_artist)
+    .or_else(|| probed.metadata.get().and_then(|metadata| metadata.current().and_then(find_artist)))
+}
+
+fn find_artist(revision: &MetadataRevision) -> Option<String> {
+  let tag_value = |key: StandardTagKey| {
+    revision.tags()
+      .iter()
+      .find(|tag| tag.std_key == Some(key))
+      // RIFF INFO strings keep their NUL terminator
+      .map(|tag| tag.value.to_string().trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string())
+      .filter(|value| !value.is_empty())
+  };
+
+  tag_value(StandardTagKey::Artist).or_else(|| tag_value(StandardTagKey::AlbumArtist))
+}
+
 /// Calculate duration by decoding the entire audio stream (fallback method)
 fn calculate_duration_by_decoding(
   format: &mut Box<dyn symphonia::core::formats::FormatReader>,
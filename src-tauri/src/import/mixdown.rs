@@ -11,10 +11,10 @@ use rayon::prelude::*;
 
 use super::ImportError;
 
-/// Get the app data directory for storing mixdowns
+/// Get the app data directory that managed files (mixdowns, unpacked songs) live under
 /// Works on both Windows, macOS, and Linux
 /// Uses the same base directory as the database for consistency
-pub fn get_mixdowns_directory() -> Result<PathBuf, ImportError> {
+pub fn get_app_data_directory() -> Result<PathBuf, ImportError> {
   // Get the app data directory based on platform
   // Must match the database location from database/connection.rs
   let app_data = if cfg!(target_os = "windows") {
@@ -51,7 +51,12 @@ pub fn get_mixdowns_directory() -> Result<PathBuf, ImportError> {
       .join("trax")
   };
 
-  let mixdowns_dir = app_data.join("mixdowns");
+  Ok(app_data)
+}
+
+/// Get the app data directory for storing mixdowns
+pub fn get_mixdowns_directory() -> Result<PathBuf, ImportError> {
+  let mixdowns_dir = get_app_data_directory()?.join("mixdowns");
 
   // Create directory if it doesn't exist
   if !mixdowns_dir.exists() {
@@ -159,6 +164,7 @@ fn decode_audio_file(file_path: &Path) -> Result<(Vec<f32>, Vec<f32>, u32), Impo
 }
 
 /// Decoded stem data for caching
+#[derive(Debug)]
 pub struct DecodedStem {
   pub samples: Vec<f32>,
   pub sample_rate: u32,
@@ -323,3 +329,16 @@ pub fn generate_mixdown(
   log::info!("All stems ready for cache");
   Ok((mixdown_path.to_string_lossy().to_string(), cached_stems))
 }
+
+/// Decode stems in parallel for the cache without writing a mixdown. Each stem keeps its own
+/// sample rate
+pub fn decode_stems(stem_file_paths: &[PathBuf]) -> Result<Vec<DecodedStem>, ImportError> {
+  stem_file_paths
+    .par_iter()
+    .map(|file_path| {
+      let (left, right, sample_rate) = decode_audio_file(file_path)?;
+      let samples = left.iter().zip(&right).flat_map(|(&l, &r)| [l, r]).collect();
+      Ok(DecodedStem { samples, sample_rate })
+    })
+    .collect()
+}
@@ -2,18 +2,29 @@ mod metadata;
 mod stem_detection;
 mod duplicate;
 mod mixdown;
+mod alignment;
+mod archive;
+mod conversion;
+mod relocate;
+mod freeze;
 
 #[cfg(test)]
 mod tests;
 
 use std::path::{Path, PathBuf};
 use rayon::prelude::*;
+use serde::Serialize;
+use crate::audio::SampleFormatInfo;
 use crate::database::{Database, Song, Stem};
 
 pub use metadata::{extract_metadata, AudioMetadata};
-pub use stem_detection::detect_stem_name;
-pub use duplicate::calculate_file_hash;
+pub use stem_detection::{detect_stem_name_with_config, is_cue_stem_name, StemDetectionConfig};
+pub use duplicate::{calculate_content_hash, calculate_file_hash, modified_nanos};
 pub use mixdown::DecodedStem;
+pub use archive::{export_song_archive, get_songs_directory, get_stem_cache_directory, import_song_archive};
+pub use conversion::{convert_sample_rate, converted_file_name, ConvertedFile};
+pub use relocate::relocate_song;
+pub use freeze::freeze_stem;
 
 // Re-export ImportResult from the main import function section
 // (defined later in this file)
@@ -42,6 +53,9 @@ pub enum ImportError {
   #[error("Duplicate file detected: {0}")]
   Duplicate(String),
 
+  #[error("Archive error: {0}")]
+  Archive(String),
+
   #[error("IO error: {0}")]
   Io(#[from] std::io::Error),
 }
@@ -58,6 +72,14 @@ pub struct ImportRequest {
   pub artist: Option<String>,
   pub key: Option<String>,
   pub time_signature: Option<String>,
+  /// Skip each stem's leading silence on playback so stems exported with pre-roll line up
+  pub align_leading_silence: bool,
+  /// Convert stems to this sample rate at import, writing the converted files to the song's
+  /// folder in the songs directory so loads skip resampling. None keeps each file at its own rate
+  pub target_sample_rate: Option<u32>,
+  /// Write a mixdown of all stems to the mixdowns directory (true for normal imports).
+  /// Throwaway imports can skip it; stems are still decoded in memory for the cache
+  pub generate_mixdown: bool,
 }
 
 impl ImportRequest {
@@ -122,6 +144,43 @@ struct ProcessedFile {
   metadata: AudioMetadata,
   stem_name: String,
   hash: String,
+  is_cue: bool,
+}
+
+/// What importing a file would create, reported by `preview_import`
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewFile {
+  pub file_path: String,
+  pub stem_name: String,
+  pub duration: f64,
+  pub sample_rate: i32,
+  pub channels: i32,
+  pub artist: Option<String>,
+  pub is_cue: bool,
+  pub sample_format: Option<SampleFormatInfo>, // None when the format couldn't be probed
+}
+
+impl From<&ProcessedFile> for PreviewFile {
+  fn from(file: &ProcessedFile) -> Self {
+    PreviewFile {
+      file_path: file.file_path.to_string_lossy().to_string(),
+      stem_name: file.stem_name.clone(),
+      duration: file.metadata.duration,
+      sample_rate: file.metadata.sample_rate,
+      channels: file.metadata.channels,
+      artist: file.metadata.artist.clone(),
+      is_cue: file.is_cue,
+      sample_format: None,
+    }
+  }
+}
+
+/// Dry-run result of an import: detected stems plus files that couldn't be read
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+  pub files: Vec<PreviewFile>,
+  pub artist: Option<String>, // First artist tag found across the files
+  pub errors: Vec<String>,
 }
 
 // ========================================
@@ -135,12 +194,12 @@ pub fn validate_file_path(file_path: &Path) -> Result<(), ImportError> {
     .and_then(|e| e.to_str())
     .ok_or_else(|| ImportError::Validation("File has no extension".to_string()))?;
 
-  let supported_extensions = ["wav", "mp3", "flac"];
+  let supported_extensions = ["wav", "mp3", "flac", "aiff", "aif"];
   let ext_lower = extension.to_lowercase();
 
   if !supported_extensions.contains(&ext_lower.as_str()) {
     return Err(ImportError::InvalidFormat(
-      format!("Unsupported file format: {}. Supported formats: WAV, MP3, FLAC", extension)
+      format!("Unsupported file format: {}. Supported formats: WAV, MP3, FLAC, AIFF", extension)
     ));
   }
 
@@ -152,7 +211,10 @@ pub fn validate_file_path(file_path: &Path) -> Result<(), ImportError> {
 // ========================================
 
 /// Process multiple files concurrently using rayon
-pub fn process_files_concurrently(file_paths: &[PathBuf]) -> Vec<Result<ProcessedFile, ImportError>> {
+pub fn process_files_concurrently(
+  file_paths: &[PathBuf],
+  detection_config: &StemDetectionConfig,
+) -> Vec<Result<ProcessedFile, ImportError>> {
   file_paths
     .par_iter()
     .map(|file_path| {
@@ -167,7 +229,8 @@ pub fn process_files_concurrently(file_paths: &[PathBuf]) -> Vec<Result<Processe
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown");
-      let stem_name = detect_stem_name(filename);
+      let stem_name = detect_stem_name_with_config(filename, detection_config);
+      let is_cue = is_cue_stem_name(&stem_name);
 
       // Calculate hash
       let hash = calculate_file_hash(file_path)?;
@@ -177,11 +240,28 @@ pub fn process_files_concurrently(file_paths: &[PathBuf]) -> Vec<Result<Processe
         metadata,
         stem_name,
         hash,
+        is_cue,
       })
     })
     .collect()
 }
 
+/// Build the stem detection config from the custom keywords and drum grouping setting
+pub fn load_stem_detection_config(db: &Database) -> Result<StemDetectionConfig, ImportError> {
+  let keywords = db.list_stem_keywords()
+    .map_err(|e| ImportError::Database(format!("Failed to load stem keywords: {}", e)))?;
+  let group_drum_kit = db.get_settings()
+    .map_err(|e| ImportError::Database(format!("Failed to load settings: {}", e)))?
+    .group_drum_kit_stems;
+
+  Ok(StemDetectionConfig::with_custom_keywords(
+    keywords
+      .into_iter()
+      .map(|k| (k.keyword, k.display_name))
+      .collect(),
+  ).with_drum_kit_grouping(group_drum_kit))
+}
+
 // ========================================
 // STEM NAME DEDUPLICATION
 // ========================================
@@ -213,45 +293,212 @@ fn deduplicate_stem_names(processed_files: &mut [ProcessedFile]) {
   }
 }
 
+/// Name for a stem joining a song whose other stems are named `taken`: `name` itself if it's
+/// free, otherwise numbered the way deduplicate_stem_names does ("Vocals 2", "Vocals 3", ...)
+pub fn unique_stem_name(name: &str, taken: &[String]) -> String {
+  let is_taken = |candidate: &str| taken.iter().any(|t| t.eq_ignore_ascii_case(candidate));
+  if !is_taken(name) {
+    return name.to_string();
+  }
+
+  (2..)
+    .map(|n| format!("{} {}", name, n))
+    .find(|candidate| !is_taken(candidate))
+    .expect("unbounded search always finds a free name")
+}
+
+// ========================================
+// SAMPLE RATE CONVERSION
+// ========================================
+
+// Convert every file not already at `target_rate`, in parallel, into the song's folder under
+// the songs directory. Entries are None for files kept as-is. If any conversion fails, the
+// copies written so far are removed
+fn convert_processed_files(
+  processed_files: &[ProcessedFile],
+  target_rate: Option<u32>,
+  title: &str,
+) -> Result<Vec<Option<ConvertedFile>>, ImportError> {
+  let Some(rate) = target_rate.filter(|&r| r > 0) else {
+    return Ok(vec![None; processed_files.len()]);
+  };
+  if processed_files.iter().all(|file| file.metadata.sample_rate == rate as i32) {
+    return Ok(vec![None; processed_files.len()]);
+  }
+
+  let song_dir = get_songs_directory()?.join(archive::safe_file_name(title, "Song"));
+  std::fs::create_dir_all(&song_dir)?;
+
+  // Pick every destination up front, creating each file so parallel conversions (or two
+  // sources with the same name) never land on the same path
+  let mut destinations = Vec::with_capacity(processed_files.len());
+  for file in processed_files {
+    if file.metadata.sample_rate == rate as i32 {
+      destinations.push(None);
+      continue;
+    }
+    let dest = archive::unique_path(&song_dir, &converted_file_name(&file.file_path, rate));
+    if let Err(e) = std::fs::File::create(&dest) {
+      archive::remove_files(destinations.iter().flatten());
+      return Err(e.into());
+    }
+    destinations.push(Some(dest));
+  }
+
+  processed_files
+    .par_iter()
+    .zip(destinations.par_iter())
+    .map(|(file, dest)| match dest {
+      Some(dest) => convert_sample_rate(&file.file_path, rate, dest).map(Some),
+      None => Ok(None),
+    })
+    .collect::<Result<Vec<_>, _>>()
+    .inspect_err(|_| archive::remove_files(destinations.iter().flatten()))
+}
+
+fn remove_converted_files(converted: &[Option<ConvertedFile>]) {
+  for file in converted.iter().flatten() {
+    let _ = std::fs::remove_file(&file.path);
+  }
+}
+
+// ========================================
+// IMPORT PREVIEW
+// ========================================
+
+/// Detect stem names and metadata the way `import_song` would, without writing to the
+/// database or generating a mixdown
+pub fn preview_import(db: &Database, file_paths: &[PathBuf]) -> Result<ImportPreview, ImportError> {
+  if file_paths.is_empty() {
+    return Err(ImportError::Validation("At least one audio file is required".to_string()));
+  }
+
+  let detection_config = load_stem_detection_config(db)?;
+
+  let mut processed_files = Vec::new();
+  let mut errors = Vec::new();
+  for result in process_files_concurrently(file_paths, &detection_config) {
+    match result {
+      Ok(file) => processed_files.push(file),
+      Err(e) => errors.push(e.to_string()),
+    }
+  }
+
+  deduplicate_stem_names(&mut processed_files);
+
+  // Probe each file's sample format so unsupported bit depths show up before importing
+  let files: Vec<PreviewFile> = processed_files
+    .par_iter()
+    .map(|file| PreviewFile {
+      sample_format: crate::audio::decoder::probe_format(&file.file_path.to_string_lossy()).ok(),
+      ..PreviewFile::from(file)
+    })
+    .collect();
+  errors.extend(files.iter().filter_map(|file| {
+    file.sample_format.as_ref().filter(|format| !format.supported).map(|format| format!(
+      "{}: unsupported sample format {} ({}-bit)",
+      file.file_path, format.sample_format, format.bits_per_sample
+    ))
+  }));
+  let artist = files.iter().find_map(|file| file.artist.clone());
+
+  Ok(ImportPreview { files, artist, errors })
+}
+
 // ========================================
 // MAIN IMPORT FUNCTION
 // ========================================
 
 /// Result of importing a song - contains song_id and decoded stems for caching
+#[derive(Debug)]
 pub struct ImportResult {
   pub song_id: String,
   pub decoded_stems: Vec<DecodedStem>,
 }
 
+/// Stem name given to a song imported from a single pre-rendered track
+pub const FULL_MIX_STEM_NAME: &str = "Full Mix";
+
 /// Import a multi-track song into the database
 pub fn import_song(db: &Database, request: ImportRequest) -> Result<ImportResult, ImportError> {
+  import_song_named(db, request, None)
+}
+
+/// Import one pre-rendered track (e.g. a stereo mixdown) as a song with a single "Full Mix" stem
+pub fn import_single_track(db: &Database, request: ImportRequest) -> Result<ImportResult, ImportError> {
+  if request.file_paths.len() != 1 {
+    return Err(ImportError::Validation("A single track import takes exactly one file".to_string()));
+  }
+
+  let names = [FULL_MIX_STEM_NAME.to_string()];
+  import_song_named(db, request, Some(&names))
+}
+
+/// Import a song naming each stem explicitly: `stem_names[i]` names `request.file_paths[i]`
+/// instead of detecting it from the file name. Repeated names are still numbered apart
+pub fn import_song_with_names(db: &Database, request: ImportRequest, stem_names: &[String]) -> Result<ImportResult, ImportError> {
+  if stem_names.len() != request.file_paths.len() {
+    return Err(ImportError::Validation(format!(
+      "Expected one stem name per file, got {} names for {} files",
+      stem_names.len(), request.file_paths.len()
+    )));
+  }
+  let names: Vec<String> = stem_names.iter().map(|name| name.trim().to_string()).collect();
+  if let Some(index) = names.iter().position(|name| name.is_empty()) {
+    return Err(ImportError::Validation(format!(
+      "Stem name for {} is empty", request.file_paths[index].display()
+    )));
+  }
+
+  import_song_named(db, request, Some(&names))
+}
+
+// `stem_names`, one per request file in order, replaces the names detected from the file names
+fn import_song_named(db: &Database, request: ImportRequest, stem_names: Option<&[String]>) -> Result<ImportResult, ImportError> {
   // Validate request
   request.validate()?;
 
   // Process files concurrently
-  let results = process_files_concurrently(&request.file_paths);
+  // Load user-defined stem keywords (built-ins remain as defaults)
+  let detection_config = load_stem_detection_config(db)?;
+
+  let results = process_files_concurrently(&request.file_paths, &detection_config);
 
   // Separate successful and failed results
   let mut processed_files = Vec::new();
-  let mut errors = Vec::new();
+  let mut missing_files = Vec::new();
 
   for result in results {
     match result {
       Ok(file) => processed_files.push(file),
-      Err(e) => {
-        log::warn!("Failed to process file: {}", e);
-        errors.push(e.to_string());
+      Err(ImportError::FileNotFound(path)) => {
+        log::warn!("Failed to process file: not found: {}", path);
+        missing_files.push(path);
       }
+      Err(e) => log::warn!("Failed to process file: {}", e),
     }
   }
 
-  // Check if we have at least one valid file
+  // A chosen file that's gone fails the import, naming every missing file
+  if !missing_files.is_empty() {
+    return Err(ImportError::FileNotFound(missing_files.join(", ")));
+  }
   if processed_files.is_empty() {
     return Err(ImportError::Validation(
       "No valid audio files could be processed".to_string()
     ));
   }
 
+  if let Some(names) = stem_names {
+    for file in &mut processed_files {
+      let index = request.file_paths.iter().position(|path| *path == file.file_path);
+      if let Some(name) = index.and_then(|i| names.get(i)) {
+        file.stem_name = name.clone();
+        file.is_cue = is_cue_stem_name(name);
+      }
+    }
+  }
+
   // Deduplicate stem names
   deduplicate_stem_names(&mut processed_files);
 
@@ -272,6 +519,9 @@ pub fn import_song(db: &Database, request: ImportRequest) -> Result<ImportResult
     }
   }
 
+  // Convert stems to the requested rate before anything is recorded
+  let converted = convert_processed_files(&processed_files, request.target_sample_rate, &request.title)?;
+
   // Calculate song duration (use longest stem)
   let song_duration = processed_files
     .iter()
@@ -293,59 +543,123 @@ pub fn import_song(db: &Database, request: ImportRequest) -> Result<ImportResult
     mixdown_path: None, // Will be set after mixdown generation
     created_at: now,
     updated_at: now,
+    play_count: 0,
+    last_played_at: None,
+    loudness_lufs: None,
   };
 
-  // Start transaction by creating song first
-  db.create_song(&song)
-    .map_err(|e| ImportError::Database(format!("Failed to create song: {}", e)))?;
-
-  // Store the count and file paths before consuming the vector
+  // Store the count and file paths before consuming the vector (converted copies replace their originals)
   let stems_count = processed_files.len();
   let stem_file_paths: Vec<PathBuf> = processed_files.iter()
-    .map(|f| f.file_path.clone())
+    .zip(converted.iter())
+    .map(|(f, c)| c.as_ref().map(|c| c.path.clone()).unwrap_or_else(|| f.file_path.clone()))
     .collect();
 
-  // Create stem records
-  for (index, processed_file) in processed_files.iter().enumerate() {
-    let stem_id = uuid::Uuid::new_v4().to_string();
+  // Full content hash of each file the stem plays (for find_duplicate_songs and sharing decoded
+  // audio), None if unreadable. The mtime is read first so an edit made mid-hash still invalidates
+  let stem_file_hashes: Vec<(Option<String>, Option<i64>)> = stem_file_paths
+    .par_iter()
+    .map(|path| {
+      let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok().and_then(modified_nanos);
+      (calculate_content_hash(path).ok(), modified)
+    })
+    .collect();
 
-    let stem = Stem {
-      id: stem_id,
+  // Build all stem records up front so the song and its stems can be inserted atomically
+  let stems: Vec<Stem> = processed_files
+    .iter()
+    .zip(converted.iter())
+    .enumerate()
+    .map(|(index, (processed_file, converted_file))| Stem {
+      id: uuid::Uuid::new_v4().to_string(),
       song_id: song_id.clone(),
       name: processed_file.stem_name.clone(),
-      file_path: processed_file.file_path.to_string_lossy().to_string(),
-      file_size: processed_file.metadata.file_size,
-      sample_rate: processed_file.metadata.sample_rate,
+      file_path: stem_file_paths[index].to_string_lossy().to_string(),
+      file_size: converted_file.as_ref().map(|c| c.file_size).unwrap_or(processed_file.metadata.file_size),
+      sample_rate: match converted_file {
+        Some(_) => request.target_sample_rate.unwrap_or_default() as i32,
+        None => processed_file.metadata.sample_rate,
+      },
       channels: processed_file.metadata.channels,
-      duration: processed_file.metadata.duration,
+      duration: converted_file.as_ref().map(|c| c.duration).unwrap_or(processed_file.metadata.duration),
       volume: 0.8, // Default volume
       is_muted: false,
       display_order: index as i32,
-    };
+      group: None,
+      pan: None, // Cue stems are panned to the configured side on load
+      is_cue: processed_file.is_cue,
+      solo_safe: false,
+      offset_samples: 0, // Set after decoding when aligning leading silence
+      phase_inverted: false,
+      original_file_path: converted_file.as_ref().map(|_| processed_file.file_path.to_string_lossy().to_string()),
+      default_volume: 0.8, // The import-time mix, restored by reset_mix_to_default
+      default_mute: false,
+      delay_samples: 0,
+      swap_channels: false,
+      mono_sum: false,
+      trim_db: 0.0,
+      gate_enabled: false,
+      gate_threshold_db: -50.0,
+      gate_attack_ms: 1.0,
+      gate_release_ms: 50.0,
+      file_hash: stem_file_hashes[index].0.clone(),
+      file_hash_modified: stem_file_hashes[index].1,
+    })
+    .collect();
 
-    db.create_stem(&stem)
-      .map_err(|e| {
-        // If stem creation fails, we should ideally rollback the song creation
-        // For now, log the error
-        log::error!("Failed to create stem, song may be incomplete: {}", e);
-        ImportError::Database(format!("Failed to create stem: {}", e))
-      })?;
-  }
+  // Insert song + stems in a single transaction - a failure leaves nothing behind
+  db.import_song_transactional(&song, &stems)
+    .map_err(|e| {
+      log::error!("Failed to import song '{}', rolled back: {}", request.title, e);
+      remove_converted_files(&converted);
+      ImportError::Database(format!("Failed to create song: {}", e))
+    })?;
 
   // Generate mixdown from all stems
-  log::info!("Generating mixdown for song '{}'...", request.title);
-  let (mixdown_path, decoded_stems) = match mixdown::generate_mixdown(&song_id, &stem_file_paths) {
-    Ok((path, stems)) => {
-      log::info!("Mixdown generated successfully: {}", path);
-      (Some(path), stems)
-    }
-    Err(e) => {
-      log::error!("Failed to generate mixdown: {}. Song will be imported without mixdown.", e);
-      // Don't fail the entire import if mixdown generation fails
-      (None, Vec::new())
+  let (mixdown_path, decoded_stems) = if request.generate_mixdown {
+    log::info!("Generating mixdown for song '{}'...", request.title);
+    match mixdown::generate_mixdown(&song_id, &stem_file_paths) {
+      Ok((path, stems)) => {
+        log::info!("Mixdown generated successfully: {}", path);
+        (Some(path), stems)
+      }
+      Err(e) => {
+        log::error!("Failed to generate mixdown: {}. Song will be imported without mixdown.", e);
+        // Don't fail the entire import if mixdown generation fails
+        (None, Vec::new())
+      }
     }
+  } else {
+    log::info!("Skipping mixdown for song '{}'", request.title);
+    let decoded_stems = mixdown::decode_stems(&stem_file_paths).unwrap_or_else(|e| {
+      log::error!("Failed to decode stems: {}. Song will be imported without cached audio.", e);
+      Vec::new()
+    });
+    (None, decoded_stems)
   };
 
+  // Record each stem's leading silence so playback starts it at its aligned point
+  if request.align_leading_silence {
+    if decoded_stems.is_empty() {
+      log::warn!("Stems for '{}' could not be decoded, skipping alignment", request.title);
+    } else {
+      let aligned: Vec<Stem> = stems
+        .iter()
+        .zip(decoded_stems.iter())
+        .filter_map(|(stem, decoded)| {
+          let offset = alignment::detect_leading_silence(&decoded.samples, alignment::LEADING_SILENCE_THRESHOLD);
+          (offset > 0).then(|| Stem { offset_samples: offset as i64, ..stem.clone() })
+        })
+        .collect();
+
+      if !aligned.is_empty() {
+        log::info!("Aligning {} stems past their leading silence", aligned.len());
+        db.update_stems(&aligned)
+          .map_err(|e| ImportError::Database(format!("Failed to save stem alignment: {}", e)))?;
+      }
+    }
+  }
+
   // Update song with mixdown path
   if mixdown_path.is_some() {
     let mut updated_song = song.clone();
@@ -0,0 +1,103 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::database::{Database, Song, Stem};
+use super::ImportError;
+use super::archive::unique_path;
+
+/// Move a song's stem files and mixdown into `new_directory` and point the database at the
+/// new locations. If updating the database fails, every file is moved back
+pub fn relocate_song(db: &Database, song_id: &str, new_directory: &Path) -> Result<Song, ImportError> {
+  let mut song = db.get_song(song_id)
+    .map_err(|e| ImportError::Database(format!("Failed to get song: {}", e)))?;
+  let mut stems = db.get_stems_for_song(song_id)
+    .map_err(|e| ImportError::Database(format!("Failed to get stems: {}", e)))?;
+
+  ensure_writable(new_directory)?;
+
+  // (from, to) for every file moved so far, so a failure can put them back
+  let mut moved: Vec<(PathBuf, PathBuf)> = Vec::new();
+  let result = move_song_files(&mut song, &mut stems, new_directory, &mut moved)
+    .and_then(|_| {
+      db.relocate_song_transactional(&song, &stems)
+        .map_err(|e| ImportError::Database(format!("Failed to update file locations: {}", e)))
+    });
+  if let Err(e) = result {
+    restore_moves(&moved);
+    return Err(e);
+  }
+
+  log::info!("Relocated song '{}' ({} files) to {}", song.name, moved.len(), new_directory.display());
+  Ok(song)
+}
+
+// Move every stem file (and the mixdown, if it exists) into `dir`, updating the records' paths
+fn move_song_files(
+  song: &mut Song,
+  stems: &mut [Stem],
+  dir: &Path,
+  moved: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), ImportError> {
+  for stem in stems.iter_mut() {
+    stem.file_path = move_into(&stem.file_path, dir, moved)?;
+  }
+  if let Some(mixdown_path) = song.mixdown_path.clone().filter(|p| Path::new(p).exists()) {
+    song.mixdown_path = Some(move_into(&mixdown_path, dir, moved)?);
+  }
+  Ok(())
+}
+
+fn move_into(path: &str, dir: &Path, moved: &mut Vec<(PathBuf, PathBuf)>) -> Result<String, ImportError> {
+  let from = PathBuf::from(path);
+  let file_name = from.file_name()
+    .ok_or_else(|| ImportError::Validation(format!("Invalid file path: {}", path)))?;
+  // A file already in the destination stays put rather than being renamed next to itself
+  if from.parent().is_some_and(|parent| same_directory(parent, dir)) {
+    return Ok(path.to_string());
+  }
+  let to = unique_path(dir, &file_name.to_string_lossy());
+
+  move_file(&from, &to)
+    .map_err(|e| ImportError::Io(io::Error::new(e.kind(), format!("{}: {}", path, e))))?;
+  moved.push((from, to.clone()));
+  Ok(to.to_string_lossy().to_string())
+}
+
+fn same_directory(a: &Path, b: &Path) -> bool {
+  match (fs::canonicalize(a), fs::canonicalize(b)) {
+    (Ok(a), Ok(b)) => a == b,
+    _ => a == b,
+  }
+}
+
+// Create the directory if needed and prove a file can be written there
+fn ensure_writable(dir: &Path) -> Result<(), ImportError> {
+  fs::create_dir_all(dir)
+    .map_err(|e| ImportError::Validation(format!("Cannot create {}: {}", dir.display(), e)))?;
+
+  let probe = dir.join(format!(".trax-write-test-{}", uuid::Uuid::new_v4()));
+  fs::write(&probe, b"")
+    .map_err(|e| ImportError::Validation(format!("{} is not writable: {}", dir.display(), e)))?;
+  let _ = fs::remove_file(&probe);
+  Ok(())
+}
+
+// Rename, falling back to copy + delete when the destination is on another volume
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+  if fs::rename(from, to).is_ok() {
+    return Ok(());
+  }
+  fs::copy(from, to)?;
+  fs::remove_file(from).inspect_err(|_| {
+    let _ = fs::remove_file(to);
+  })
+}
+
+fn restore_moves(moved: &[(PathBuf, PathBuf)]) {
+  for (from, to) in moved.iter().rev() {
+    if let Err(e) = move_file(to, from) {
+      log::error!("Failed to move {} back to {}: {}", to.display(), from.display(), e);
+    }
+  }
+}
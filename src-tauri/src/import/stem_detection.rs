@@ -1,35 +1,83 @@
 use std::path::Path;
 
-/// Detect stem name from filename using common keywords
+// Built-in stem keywords (in order of priority)
+const BUILTIN_KEYWORDS: &[(&str, &str)] = &[
+  ("vocals", "Vocals"),
+  ("vox", "Vox"),
+  ("drums", "Drums"),
+  ("bass", "Bass"),
+  ("keys", "Keys"),
+  ("keyboard", "Keyboard"),
+  ("piano", "Piano"),
+  ("guitar", "Guitar"),
+  ("synth", "Synth"),
+  ("pad", "Pad"),
+  ("strings", "Strings"),
+  ("orchestra", "Orchestra"),
+  ("click", "Click"),
+  ("guide", "Guide"),
+  ("metronome", "Click"),
+  ("other", "Other"),
+];
+
+// Individual drum kit pieces (Logic/Pro Tools multitrack exports), matched as whole words
+const DRUM_KIT_PIECES: &[&str] = &[
+  "kick", "kik", "snare", "hat", "hats", "hihat", "hh", "tom", "toms", "floortom",
+  "overhead", "overheads", "oh", "ride", "crash", "cymbal", "cymbals", "room",
+];
+
+/// Keyword configuration for stem detection: user keywords are checked before the built-ins
+#[derive(Debug, Clone, Default)]
+pub struct StemDetectionConfig {
+  custom_keywords: Vec<(String, String)>,
+  group_drum_kit: bool, // Name kick/snare/hat/... stems "Drums" instead of after the piece
+}
+
+impl StemDetectionConfig {
+  /// Create a config from user keyword -> display name mappings
+  pub fn with_custom_keywords(custom_keywords: Vec<(String, String)>) -> Self {
+    StemDetectionConfig {
+      custom_keywords: custom_keywords
+        .into_iter()
+        .map(|(keyword, display)| (keyword.to_lowercase(), display))
+        .collect(),
+      group_drum_kit: false,
+    }
+  }
+
+  /// Group individual drum kit pieces ("01 Kick", "Song.Snare") under "Drums"
+  pub fn with_drum_kit_grouping(mut self, enabled: bool) -> Self {
+    self.group_drum_kit = enabled;
+    self
+  }
+
+  fn keywords(&self) -> Vec<(&str, &str)> {
+    self.custom_keywords
+      .iter()
+      .map(|(keyword, display)| (keyword.as_str(), display.as_str()))
+      .chain(BUILTIN_KEYWORDS.iter().copied())
+      .collect()
+  }
+}
+
+/// Detect stem name from filename using the built-in keywords
 pub fn detect_stem_name(filename: &str) -> String {
-  // Remove file extension
+  detect_stem_name_with_config(filename, &StemDetectionConfig::default())
+}
+
+/// Detect stem name from filename using custom keywords plus the built-ins
+pub fn detect_stem_name_with_config(filename: &str, config: &StemDetectionConfig) -> String {
+  // Remove file extension, and the ".stem" of NI Stems containers ("Song.stem.mp4")
   let name_without_ext = Path::new(filename)
     .file_stem()
     .and_then(|s| s.to_str())
     .unwrap_or(filename);
+  let name_without_ext = strip_suffix_ignore_case(name_without_ext, ".stem").unwrap_or(name_without_ext);
 
   // Convert to lowercase for case-insensitive matching
   let lowercase = name_without_ext.to_lowercase();
 
-  // Common stem keywords (in order of priority)
-  let keywords = vec![
-    ("vocals", "Vocals"),
-    ("vox", "Vox"),
-    ("drums", "Drums"),
-    ("bass", "Bass"),
-    ("keys", "Keys"),
-    ("keyboard", "Keyboard"),
-    ("piano", "Piano"),
-    ("guitar", "Guitar"),
-    ("synth", "Synth"),
-    ("pad", "Pad"),
-    ("strings", "Strings"),
-    ("orchestra", "Orchestra"),
-    ("click", "Click"),
-    ("guide", "Guide"),
-    ("metronome", "Click"),
-    ("other", "Other")
-  ];
+  let keywords = config.keywords();
 
   // Try to extract stem name from various patterns
 
@@ -72,8 +120,65 @@ pub fn detect_stem_name(filename: &str) -> String {
     }
   }
 
+  // Pattern 5: a drum kit piece when grouping ("01 Kick.wav"), once no keyword matched
+  if config.group_drum_kit && is_drum_kit_piece(&lowercase) {
+    return "Drums".to_string();
+  }
+
   // Fallback: Use filename without extension, cleaned up
-  clean_filename(name_without_ext)
+  clean_filename(strip_track_number(pro_tools_track(name_without_ext)))
+}
+
+// Pro Tools names stems "Session.Track", so only the part after the last dot names the stem
+fn pro_tools_track(name: &str) -> &str {
+  match name.rsplit_once('.') {
+    Some((_, suffix)) if suffix.chars().any(char::is_alphabetic) => suffix,
+    _ => name,
+  }
+}
+
+// Whether the track part of a lowercase name is a drum kit piece. Pieces are only looked for
+// after " - ", a Pro Tools dot or a track number, so a song title like "Oh Happy Day" or
+// "Room at the Cross" never counts; a name with none of those must be only kit pieces ("Toms")
+fn is_drum_kit_piece(name: &str) -> bool {
+  let after_dash = name.split_once(" - ").map(|(_, track)| track);
+  let track = after_dash.unwrap_or_else(|| pro_tools_track(name));
+  let without_number = strip_track_number(track);
+  let separated = track.len() != name.len() || without_number.len() != track.len();
+
+  let mut words = without_number
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .peekable();
+  if separated {
+    words.any(|word| DRUM_KIT_PIECES.contains(&word))
+  } else {
+    words.peek().is_some() && words.all(|word| DRUM_KIT_PIECES.contains(&word))
+  }
+}
+
+// "01 Kick" / "01_Kick" / "01-Kick" -> "Kick" (Logic numbers exported tracks). Names that
+// are only a number are left alone
+fn strip_track_number(name: &str) -> &str {
+  let after_digits = name.trim_start_matches(|c: char| c.is_ascii_digit());
+  if after_digits.len() == name.len() {
+    return name;
+  }
+  let rest = after_digits.trim_start_matches([' ', '_', '-', '.']);
+  if rest.len() == after_digits.len() || rest.is_empty() {
+    return name;
+  }
+  rest
+}
+
+fn strip_suffix_ignore_case<'a>(name: &'a str, suffix: &str) -> Option<&'a str> {
+  let split = name.len().checked_sub(suffix.len())?;
+  (name.is_char_boundary(split) && name[split..].eq_ignore_ascii_case(suffix)).then(|| &name[..split])
+}
+
+/// Whether a detected stem name is a click or guide track (meant for monitors only)
+pub fn is_cue_stem_name(stem_name: &str) -> bool {
+  matches!(stem_name, "Click" | "Guide")
 }
 
 /// Clean up filename by removing common patterns
@@ -161,4 +266,19 @@ mod tests {
     assert_eq!(clean_filename("drums_02_"), "Drums");
     assert_eq!(clean_filename("custom_name"), "Custom_name");
   }
+
+  #[test]
+  fn test_detect_stem_name_with_custom_keywords() {
+    let config = StemDetectionConfig::with_custom_keywords(vec![
+      ("BGV".to_string(), "BGV".to_string()),
+      ("leslie".to_string(), "Leslie".to_string()),
+    ]);
+
+    assert_eq!(detect_stem_name_with_config("Song - bgv.wav", &config), "BGV");
+    assert_eq!(detect_stem_name_with_config("Song_Leslie.wav", &config), "Leslie");
+    // Built-ins still apply
+    assert_eq!(detect_stem_name_with_config("Song - Vocals.wav", &config), "Vocals");
+    // Without the custom keyword, the filename fallback is used
+    assert_eq!(detect_stem_name("Song - bgv.wav"), "Song - bgv");
+  }
 }
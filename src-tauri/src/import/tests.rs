@@ -1,4 +1,5 @@
 use super::*;
+use super::stem_detection::detect_stem_name;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::PathBuf;
@@ -55,6 +56,44 @@ fn create_minimal_wav_file(dir: &PathBuf, filename: &str) -> PathBuf {
   create_test_audio_file(dir, filename, &wav_data)
 }
 
+// Create a 16-bit big-endian PCM AIFF file with a short ramp
+fn create_aiff_file(dir: &PathBuf, filename: &str, sample_rate: u32, channels: u16, frames: u32) -> PathBuf {
+  // COMM stores the sample rate as an 80-bit extended float
+  let exponent = 31 - sample_rate.leading_zeros();
+  let mut rate_bytes = Vec::with_capacity(10);
+  rate_bytes.extend_from_slice(&((16383 + exponent) as u16).to_be_bytes());
+  rate_bytes.extend_from_slice(&((sample_rate as u64) << (63 - exponent)).to_be_bytes());
+
+  let mut sound_data = Vec::new();
+  for frame in 0..frames {
+    for _ in 0..channels {
+      sound_data.extend_from_slice(&((frame as i16).wrapping_mul(64)).to_be_bytes());
+    }
+  }
+
+  let mut comm = Vec::new();
+  comm.extend_from_slice(&channels.to_be_bytes());
+  comm.extend_from_slice(&frames.to_be_bytes());
+  comm.extend_from_slice(&16u16.to_be_bytes());
+  comm.extend_from_slice(&rate_bytes);
+
+  let mut ssnd = vec![0u8; 8]; // offset + block size
+  ssnd.extend_from_slice(&sound_data);
+
+  let mut body = b"AIFF".to_vec();
+  body.extend_from_slice(b"COMM");
+  body.extend_from_slice(&(comm.len() as u32).to_be_bytes());
+  body.extend_from_slice(&comm);
+  body.extend_from_slice(b"SSND");
+  body.extend_from_slice(&(ssnd.len() as u32).to_be_bytes());
+  body.extend_from_slice(&ssnd);
+
+  let mut aiff_data = b"FORM".to_vec();
+  aiff_data.extend_from_slice(&(body.len() as u32).to_be_bytes());
+  aiff_data.extend_from_slice(&body);
+  create_test_audio_file(dir, filename, &aiff_data)
+}
+
 // ========================================
 // METADATA EXTRACTION TESTS
 // ========================================
@@ -182,6 +221,52 @@ fn test_detect_stem_name_with_numbers() {
   assert_eq!(detect_stem_name("Song - Vocals 1.wav"), "Vocals");
 }
 
+#[test]
+fn test_detect_stem_name_logic_track_numbers() {
+  assert_eq!(detect_stem_name("01 Kick.wav"), "Kick");
+  assert_eq!(detect_stem_name("12_Tambourine.wav"), "Tambourine");
+  assert_eq!(detect_stem_name("03 Bass.wav"), "Bass");
+  // A number that is the whole name, or part of a word, is kept
+  assert_eq!(detect_stem_name("3rd Verse Fx.wav"), "3rd Verse Fx");
+}
+
+#[test]
+fn test_detect_stem_name_pro_tools_dot_suffix() {
+  assert_eq!(detect_stem_name("SongName.Vocals.wav"), "Vocals");
+  assert_eq!(detect_stem_name("SongName.Tambourine.wav"), "Tambourine");
+  assert_eq!(detect_stem_name("Session 2.04 Shaker.aif"), "Shaker");
+}
+
+#[test]
+fn test_detect_stem_name_stems_container() {
+  assert_eq!(detect_stem_name("Great Song.stem.mp4"), "Great Song");
+  assert_eq!(detect_stem_name("Great Song - Drums.stem.mp4"), "Drums");
+}
+
+#[test]
+fn test_detect_stem_name_groups_drum_kit_pieces() {
+  let grouped = StemDetectionConfig::default().with_drum_kit_grouping(true);
+  for filename in ["01 Kick.wav", "SongName.Snare.wav", "Song - Hi Hat.wav", "05_Overheads.wav", "Toms.wav"] {
+    assert_eq!(detect_stem_name_with_config(filename, &grouped), "Drums", "{}", filename);
+  }
+  assert_eq!(detect_stem_name_with_config("02 Vocals.wav", &grouped), "Vocals");
+  assert_eq!(detect_stem_name_with_config("What A Day - Guitar.wav", &grouped), "Guitar", "Only whole words are kit pieces");
+
+  // Kit pieces in the song title don't count, and keywords are detected first
+  assert_eq!(detect_stem_name_with_config("Oh Happy Day - Vocals.wav", &grouped), "Vocals");
+  assert_eq!(detect_stem_name_with_config("Room at the Cross - Bass.wav", &grouped), "Bass");
+  assert_eq!(detect_stem_name_with_config("Oh Happy Day - Tambourine.wav", &grouped), "Oh Happy Day - Tambourine");
+  assert_eq!(detect_stem_name_with_config("Room at the Cross.wav", &grouped), "Room at the Cross");
+
+  // User keywords still win over grouping
+  let custom = StemDetectionConfig::with_custom_keywords(vec![("kick".to_string(), "Kick In".to_string())])
+    .with_drum_kit_grouping(true);
+  assert_eq!(detect_stem_name_with_config("01 Kick.wav", &custom), "Kick In");
+
+  // Without grouping each piece keeps its own name
+  assert_eq!(detect_stem_name("SongName.Snare.wav"), "Snare");
+}
+
 // ========================================
 // DUPLICATE DETECTION TESTS
 // ========================================
@@ -247,6 +332,9 @@ fn test_import_request_validation_valid() {
     artist: Some("Test Artist".to_string()),
     key: Some("C".to_string()),
     time_signature: Some("4/4".to_string()),
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = request.validate();
@@ -261,6 +349,9 @@ fn test_import_request_validation_missing_title() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = request.validate();
@@ -276,6 +367,9 @@ fn test_import_request_validation_no_files() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = request.validate();
@@ -291,6 +385,9 @@ fn test_import_request_optional_fields() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = request.validate();
@@ -379,6 +476,8 @@ fn test_validate_file_path_valid_extensions() {
     "VOCALS.WAV",
     "drums.MP3",
     "bass.FlAc",
+    "keys.aiff",
+    "pad.AIF",
   ];
 
   for filename in valid_files {
@@ -420,7 +519,7 @@ fn test_process_files_concurrently() {
     .map(|i| create_minimal_wav_file(&test_dir, &format!("song_{}.wav", i)))
     .collect();
 
-  let results = process_files_concurrently(&files);
+  let results = process_files_concurrently(&files, &StemDetectionConfig::default());
 
   assert_eq!(results.len(), 5);
   for result in results {
@@ -440,7 +539,7 @@ fn test_process_files_with_errors() {
   ];
   files.push(PathBuf::from("/nonexistent/file.wav"));
 
-  let results = process_files_concurrently(&files);
+  let results = process_files_concurrently(&files, &StemDetectionConfig::default());
 
   assert_eq!(results.len(), 4);
   let successes = results.iter().filter(|r| r.is_ok()).count();
@@ -474,12 +573,15 @@ fn test_deduplicate_stem_names() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = import_song(&db, request);
   assert!(result.is_ok(), "Should successfully import song with duplicate stem names");
 
-  let song_id = result.unwrap();
+  let song_id = result.unwrap().song_id;
   let stems = db.get_stems_for_song(&song_id).unwrap();
 
   assert_eq!(stems.len(), 3);
@@ -519,12 +621,15 @@ fn test_deduplicate_multiple_stem_types() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = import_song(&db, request);
   assert!(result.is_ok(), "Should successfully import song with multiple duplicate stem types");
 
-  let song_id = result.unwrap();
+  let song_id = result.unwrap().song_id;
   let stems = db.get_stems_for_song(&song_id).unwrap();
 
   assert_eq!(stems.len(), 6);
@@ -564,12 +669,15 @@ fn test_no_deduplication_needed() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = import_song(&db, request);
   assert!(result.is_ok(), "Should successfully import song with unique stem names");
 
-  let song_id = result.unwrap();
+  let song_id = result.unwrap().song_id;
   let stems = db.get_stems_for_song(&song_id).unwrap();
 
   assert_eq!(stems.len(), 3);
@@ -605,6 +713,9 @@ fn test_import_song_end_to_end() {
     artist: Some("Test Artist".to_string()),
     key: Some("C".to_string()),
     time_signature: Some("4/4".to_string()),
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = import_song(&db, request);
@@ -613,7 +724,7 @@ fn test_import_song_end_to_end() {
   }
   assert!(result.is_ok(), "Should successfully import song: {:?}", result.as_ref().err());
 
-  let song_id = result.unwrap();
+  let song_id = result.unwrap().song_id;
   let song = db.get_song(&song_id).unwrap();
 
   assert_eq!(song.name, "Test Song");
@@ -650,6 +761,9 @@ fn test_import_duplicate_detection() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
   let result = import_song(&db, request);
   assert!(result.is_err(), "Should detect duplicate file in same batch");
@@ -676,13 +790,16 @@ fn test_import_with_mixed_valid_invalid_files() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = import_song(&db, request);
   // Should succeed but skip corrupted file
   assert!(result.is_ok(), "Should import valid files and skip corrupted ones");
 
-  let song_id = result.unwrap();
+  let song_id = result.unwrap().song_id;
   let stems = db.get_stems_for_song(&song_id).unwrap();
 
   // Only 2 valid files should be imported
@@ -705,6 +822,9 @@ fn test_import_transaction_rollback_on_error() {
     artist: None,
     key: None,
     time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
   };
 
   let result = import_song(&db, request);
@@ -716,3 +836,692 @@ fn test_import_transaction_rollback_on_error() {
 
   cleanup_test_directory(&test_dir);
 }
+
+#[test]
+fn test_import_click_stem_flagged_as_cue() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  let request = ImportRequest {
+    file_paths: vec![
+      create_minimal_wav_file(&test_dir, "Song - Click.wav"),
+      create_minimal_wav_file(&test_dir, "Song - Vocals.wav"),
+    ],
+    title: "Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
+  };
+
+  let song_id = import_song(&db, request).unwrap().song_id;
+  let stems = db.get_stems_for_song(&song_id).unwrap();
+  let click = stems.iter().find(|s| s.name == "Click").unwrap();
+  let vocals = stems.iter().find(|s| s.name == "Vocals").unwrap();
+
+  assert!(click.is_cue, "Click stem should be flagged as cue");
+  assert!(!vocals.is_cue);
+
+  // Cue stems follow the configured side; other stems stay centered
+  let mut settings = db.get_settings().unwrap();
+  assert_eq!(click.effective_pan(&settings), 1.0);
+  settings.cue_pan_side = "left".to_string();
+  db.update_settings(&settings).unwrap();
+  let settings = db.get_settings().unwrap();
+  assert_eq!(click.effective_pan(&settings), -1.0);
+  assert_eq!(vocals.effective_pan(&settings), 0.0);
+
+  // A user override wins over the cue default
+  let mut overridden = click.clone();
+  overridden.pan = Some(0.25);
+  assert_eq!(overridden.effective_pan(&settings), 0.25);
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_detects_custom_stem_keyword() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  db.add_stem_keyword(&crate::database::StemKeyword {
+    keyword: "bgv".to_string(),
+    display_name: "BGV".to_string(),
+    created_at: chrono::Utc::now().timestamp(),
+  }).unwrap();
+
+  let request = ImportRequest {
+    file_paths: vec![create_minimal_wav_file(&test_dir, "Song - bgv.wav")],
+    title: "Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
+  };
+
+  let song_id = import_song(&db, request).unwrap().song_id;
+  let stems = db.get_stems_for_song(&song_id).unwrap();
+  assert_eq!(stems[0].name, "BGV");
+
+  // Removing the keyword falls back to built-in detection
+  db.delete_stem_keyword("bgv").unwrap();
+  assert!(db.list_stem_keywords().unwrap().is_empty());
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_aiff_stem() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  let aiff_path = create_aiff_file(&test_dir, "Test Song - Keys.aiff", 44100, 2, 4410);
+  let metadata = extract_metadata(&aiff_path).unwrap();
+  assert_eq!(metadata.sample_rate, 44100);
+  assert_eq!(metadata.channels, 2);
+  assert!((metadata.duration - 0.1).abs() < 1e-3);
+
+  let request = ImportRequest {
+    file_paths: vec![aiff_path.clone()],
+    title: "Test Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
+  };
+  let song_id = import_song(&db, request).unwrap().song_id;
+
+  let stems = db.get_stems_for_song(&song_id).unwrap();
+  assert_eq!(stems.len(), 1);
+  assert_eq!(stems[0].name, "Keys", "Extension should be stripped from the stem name");
+  assert_eq!(stems[0].sample_rate, 44100);
+
+  // The playback engine decodes AIFF too
+  let mut engine = crate::audio::MultiTrackEngine::new(2).unwrap();
+  assert!(engine.load_stem(aiff_path.to_str().unwrap()).is_ok(), "AIFF stem should load for playback");
+  assert!(engine.duration() > 0.09);
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_persists_mixdown_path() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  let request = ImportRequest {
+    file_paths: vec![
+      create_minimal_wav_file(&test_dir, "Mixdown Song - Vocals.wav"),
+      create_minimal_wav_file(&test_dir, "Mixdown Song - Bass.wav"),
+    ],
+    title: "Mixdown Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
+  };
+  let song_id = import_song(&db, request).unwrap().song_id;
+
+  let song = db.get_song(&song_id).unwrap();
+  let mixdown_path = song.mixdown_path.expect("Mixdown path should be stored on the song");
+  assert!(mixdown_path.ends_with(&mixdown::get_mixdown_filename(&song_id)));
+  assert!(PathBuf::from(&mixdown_path).exists(), "Mixdown file should exist");
+
+  let _ = fs::remove_file(&mixdown_path);
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_aligns_leading_silence() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  // Same tone in both stems, but the pad was bounced with 1000 frames of pre-roll
+  let write_stem = |filename: &str, silent_frames: usize| {
+    let path = test_dir.join(filename);
+    let spec = hound::WavSpec {
+      channels: 2,
+      sample_rate: 44100,
+      bits_per_sample: 16,
+      sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+    for frame in 0..(silent_frames + 2000) {
+      let sample = if frame < silent_frames { 0 } else { 8000 + (frame % 100) as i16 };
+      writer.write_sample(sample).unwrap();
+      writer.write_sample(sample).unwrap();
+    }
+    writer.finalize().unwrap();
+    path
+  };
+
+  let request = ImportRequest {
+    file_paths: vec![
+      write_stem("Aligned Song - Keys.wav", 0),
+      write_stem("Aligned Song - Pad.wav", 1000),
+    ],
+    title: "Aligned Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: true,
+    target_sample_rate: None,
+    generate_mixdown: true,
+  };
+  let song_id = import_song(&db, request).unwrap().song_id;
+
+  let stems = db.get_stems_for_song(&song_id).unwrap();
+  assert_eq!(stems[0].offset_samples, 0);
+  assert_eq!(stems[1].offset_samples, 1000, "Pre-roll should be recorded as the alignment offset");
+
+  if let Some(mixdown_path) = db.get_song(&song_id).unwrap().mixdown_path {
+    let _ = fs::remove_file(mixdown_path);
+  }
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_converts_stems_to_target_sample_rate() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  // One second of 44.1kHz stereo
+  let source = test_dir.join("Converted Song - Keys.wav");
+  let spec = hound::WavSpec {
+    channels: 2,
+    sample_rate: 44100,
+    bits_per_sample: 16,
+    sample_format: hound::SampleFormat::Int,
+  };
+  let mut writer = hound::WavWriter::create(&source, spec).unwrap();
+  for frame in 0..44100 {
+    let sample = ((frame % 200) as i16 - 100) * 100;
+    writer.write_sample(sample).unwrap();
+    writer.write_sample(sample).unwrap();
+  }
+  writer.finalize().unwrap();
+
+  let request = ImportRequest {
+    file_paths: vec![source.clone()],
+    title: "Converted Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: Some(48000),
+    generate_mixdown: true,
+  };
+  let result = import_song(&db, request).unwrap();
+
+  let stems = db.get_stems_for_song(&result.song_id).unwrap();
+  assert_eq!(stems[0].sample_rate, 48000);
+  assert_eq!(stems[0].original_file_path.as_deref(), Some(source.to_string_lossy().as_ref()));
+  let converted = PathBuf::from(&stems[0].file_path);
+  assert_eq!(converted.parent(), Some(get_songs_directory().unwrap().join("Converted Song").as_path()));
+  assert!(
+    converted.file_name().unwrap().to_string_lossy().starts_with("Converted Song - Keys.48000Hz"),
+    "Converted into the songs directory, not next to the source: {}", converted.display()
+  );
+
+  // The stored file is already at 48kHz, so loading it doesn't resample
+  let metadata = extract_metadata(&PathBuf::from(&stems[0].file_path)).unwrap();
+  assert_eq!(metadata.sample_rate, 48000);
+  assert!((metadata.duration - 1.0).abs() < 0.01);
+  assert_eq!(result.decoded_stems[0].sample_rate, 48000);
+  assert_eq!(result.decoded_stems[0].samples.len() / 2, 48000);
+
+  // The original is kept as it was
+  assert_eq!(extract_metadata(&source).unwrap().sample_rate, 44100);
+
+  if let Some(mixdown_path) = db.get_song(&result.song_id).unwrap().mixdown_path {
+    let _ = fs::remove_file(mixdown_path);
+  }
+  let _ = fs::remove_file(&converted);
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_relocate_song_moves_stems_and_mixdown() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  let download_dir = test_dir.join("downloads");
+  fs::create_dir_all(&download_dir).unwrap();
+  let request = ImportRequest {
+    file_paths: vec![
+      create_minimal_wav_file(&download_dir, "Moved Song - Vocals.wav"),
+      create_minimal_wav_file(&download_dir, "Moved Song - Bass.wav"),
+    ],
+    title: "Moved Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: true,
+  };
+  let song_id = import_song(&db, request).unwrap().song_id;
+  let old_paths: Vec<String> = db.get_stems_for_song(&song_id).unwrap().into_iter().map(|s| s.file_path).collect();
+
+  let library_dir = test_dir.join("library").join("Moved Song");
+  let song = relocate_song(&db, &song_id, &library_dir).unwrap();
+
+  let stems = db.get_stems_for_song(&song_id).unwrap();
+  assert_eq!(stems.len(), 2);
+  for (stem, old_path) in stems.iter().zip(&old_paths) {
+    let new_path = PathBuf::from(&stem.file_path);
+    assert_eq!(new_path.parent(), Some(library_dir.as_path()));
+    assert!(new_path.exists(), "Stem should exist at its new location");
+    assert!(!PathBuf::from(old_path).exists(), "Stem should no longer be in the download folder");
+  }
+
+  let stored = db.get_song(&song_id).unwrap();
+  assert_eq!(stored.mixdown_path, song.mixdown_path);
+  if let Some(mixdown_path) = stored.mixdown_path {
+    assert!(mixdown_path.starts_with(library_dir.to_string_lossy().as_ref()));
+    assert!(PathBuf::from(mixdown_path).exists());
+  }
+
+  // Relocating again to where the files already are leaves them alone
+  let again = relocate_song(&db, &song_id, &library_dir.join(".")).unwrap();
+  assert_eq!(again.mixdown_path, song.mixdown_path);
+  let unchanged: Vec<String> = db.get_stems_for_song(&song_id).unwrap().into_iter().map(|s| s.file_path).collect();
+  assert_eq!(unchanged, stems.iter().map(|s| s.file_path.clone()).collect::<Vec<_>>());
+  assert_eq!(fs::read_dir(&library_dir).unwrap().count(), stems.len() + usize::from(song.mixdown_path.is_some()));
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_song_archive_round_trip() {
+  let test_dir = create_test_directory();
+  let source_db = crate::database::Database::new_in_memory().unwrap();
+
+  let now = chrono::Utc::now().timestamp();
+  let song = Song {
+    id: uuid::Uuid::new_v4().to_string(),
+    name: "Archived Song".to_string(),
+    artist: Some("The Band".to_string()),
+    duration: 180.0,
+    tempo: Some(72.0),
+    key: Some("D".to_string()),
+    time_signature: Some("6/8".to_string()),
+    mixdown_path: None,
+    created_at: now,
+    updated_at: now,
+    play_count: 0,
+    last_played_at: None,
+    loudness_lufs: None,
+  };
+  // Both stems share a file name (from different folders) to exercise collision handling
+  fs::create_dir_all(test_dir.join("a")).unwrap();
+  fs::create_dir_all(test_dir.join("b")).unwrap();
+  let stems: Vec<Stem> = [("Vocals", "a", 0.6), ("Keys", "b", 0.9)]
+    .iter()
+    .enumerate()
+    .map(|(index, (name, folder, volume))| {
+      let path = create_minimal_wav_file(&test_dir.join(folder), "stem.wav");
+      Stem {
+        id: uuid::Uuid::new_v4().to_string(),
+        song_id: song.id.clone(),
+        name: name.to_string(),
+        file_path: path.to_string_lossy().to_string(),
+        file_size: fs::metadata(&path).unwrap().len() as i64,
+        sample_rate: 44100,
+        channels: 2,
+        duration: 180.0,
+        volume: *volume,
+        is_muted: index == 1,
+        display_order: index as i32,
+        group: None,
+        pan: None,
+        is_cue: false,
+        solo_safe: false,
+        offset_samples: 0,
+        phase_inverted: false,
+        original_file_path: None,
+        default_volume: *volume,
+        default_mute: index == 1,
+        delay_samples: 0,
+        swap_channels: false,
+        mono_sum: false,
+        trim_db: 0.0,
+        gate_enabled: false,
+        gate_threshold_db: -50.0,
+        gate_attack_ms: 1.0,
+        gate_release_ms: 50.0,
+        file_hash: None,
+        file_hash_modified: None,
+      }
+    })
+    .collect();
+  source_db.import_song_transactional(&song, &stems).unwrap();
+
+  let archive_path = test_dir.join("song.zip");
+  export_song_archive(&source_db, &song.id, &archive_path).unwrap();
+
+  let target_db = crate::database::Database::new_in_memory().unwrap();
+  let songs_dir = test_dir.join("library");
+  let song_id = import_song_archive(&target_db, &archive_path, &songs_dir).unwrap();
+
+  let imported = target_db.get_song(&song_id).unwrap();
+  assert_eq!(imported.name, "Archived Song");
+  assert_eq!(imported.artist.as_deref(), Some("The Band"));
+  assert_eq!(imported.tempo, Some(72.0));
+  assert_eq!(imported.time_signature.as_deref(), Some("6/8"));
+
+  let imported_stems = target_db.get_stems_for_song(&song_id).unwrap();
+  assert_eq!(imported_stems.len(), 2);
+  assert_eq!(imported_stems[0].name, "Vocals");
+  assert_eq!(imported_stems[0].volume, 0.6);
+  assert_eq!(imported_stems[1].name, "Keys");
+  assert!(imported_stems[1].is_muted);
+  assert_ne!(imported_stems[0].file_path, imported_stems[1].file_path, "Same-named stems must not overwrite each other");
+  for (original, copy) in stems.iter().zip(&imported_stems) {
+    assert!(copy.file_path.starts_with(songs_dir.to_str().unwrap()));
+    assert_eq!(fs::read(&original.file_path).unwrap(), fs::read(&copy.file_path).unwrap());
+  }
+
+  // Importing again gets new IDs and new file names rather than clobbering the first copy
+  let second_id = import_song_archive(&target_db, &archive_path, &songs_dir).unwrap();
+  assert_ne!(second_id, song_id);
+  assert_eq!(target_db.list_songs(None).unwrap().len(), 2);
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_version_1_song_archive() {
+  let test_dir = create_test_directory();
+  let wav = create_minimal_wav_file(&test_dir, "Vocals.wav");
+
+  // A manifest as the first archive format wrote it, before the later stem and song columns
+  let manifest = serde_json::json!({
+    "format_version": 1,
+    "song": {
+      "id": "old-song", "name": "Old Song", "artist": null, "duration": 180.0, "tempo": 90.0,
+      "key": "G", "time_signature": "4/4", "mixdown_path": null, "created_at": 0, "updated_at": 0
+    },
+    "stems": [{
+      "entry": "stems/01-Vocals.wav",
+      "file_name": "Vocals.wav",
+      "stem": {
+        "id": "old-stem", "song_id": "old-song", "name": "Vocals", "file_path": "/old/Vocals.wav",
+        "file_size": 44, "sample_rate": 44100, "channels": 2, "duration": 180.0, "volume": 0.6,
+        "is_muted": false, "display_order": 0, "group": null, "pan": null, "is_cue": false,
+        "solo_safe": false, "offset_samples": 0
+      }
+    }]
+  });
+  let archive_path = test_dir.join("old.zip");
+  let mut zip = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+  let options = zip::write::SimpleFileOptions::default();
+  zip.start_file("stems/01-Vocals.wav", options).unwrap();
+  zip.write_all(&fs::read(&wav).unwrap()).unwrap();
+  zip.start_file("manifest.json", options).unwrap();
+  zip.write_all(manifest.to_string().as_bytes()).unwrap();
+  zip.finish().unwrap();
+
+  let db = crate::database::Database::new_in_memory().unwrap();
+  let song_id = import_song_archive(&db, &archive_path, &test_dir.join("library")).unwrap();
+
+  assert_eq!(db.get_song(&song_id).unwrap().name, "Old Song");
+  let stems = db.get_stems_for_song(&song_id).unwrap();
+  assert_eq!(stems.len(), 1);
+  assert_eq!(stems[0].volume, 0.6);
+  assert_eq!(stems[0].default_volume, 0.6, "Mix defaults come from the archived mix");
+  assert_eq!(stems[0].trim_db, 0.0);
+  assert!(!stems[0].gate_enabled);
+  assert_eq!(stems[0].gate_threshold_db, -50.0);
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_failed_archive_export_removes_partial_file() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+  let wav = create_minimal_wav_file(&test_dir, "Vocals.wav");
+  let song: Song = serde_json::from_value(serde_json::json!({
+    "id": "song-1", "name": "Gone Song", "artist": null, "duration": 1.0, "tempo": null, "key": null,
+    "time_signature": null, "mixdown_path": null, "created_at": 0, "updated_at": 0
+  })).unwrap();
+  let stem: Stem = serde_json::from_value(serde_json::json!({
+    "id": "stem-1", "song_id": "song-1", "name": "Vocals", "file_path": wav.to_string_lossy(),
+    "file_size": 44, "sample_rate": 44100, "channels": 2, "duration": 1.0, "volume": 0.8,
+    "is_muted": false, "display_order": 0, "is_cue": false, "solo_safe": false, "offset_samples": 0
+  })).unwrap();
+  db.import_song_transactional(&song, &[stem]).unwrap();
+  fs::remove_file(&wav).unwrap();
+
+  let archive_path = test_dir.join("gone.zip");
+  assert!(export_song_archive(&db, "song-1", &archive_path).is_err());
+  assert!(!archive_path.exists(), "A failed export should not leave a partial archive");
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_preview_import_detects_stems_without_writing() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  let files = vec![
+    create_minimal_wav_file(&test_dir, "Preview Song - Vocals.wav"),
+    create_minimal_wav_file(&test_dir, "Preview Song - Drums.wav"),
+    create_test_audio_file(&test_dir, "Preview Song - Broken.wav", b"not audio"),
+  ];
+
+  let preview = preview_import(&db, &files).unwrap();
+
+  let names: Vec<&str> = preview.files.iter().map(|f| f.stem_name.as_str()).collect();
+  assert_eq!(names.len(), 2);
+  assert!(names.contains(&"Vocals"));
+  assert!(names.contains(&"Drums"));
+  assert_eq!(preview.errors.len(), 1, "The unreadable file is reported, not fatal");
+  assert!(preview.files.iter().all(|f| f.sample_rate == 44100 && f.channels == 2));
+
+  assert!(db.list_songs(None).unwrap().is_empty(), "Preview must not create songs");
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_extract_metadata_reads_artist_tag() {
+  let test_dir = create_test_directory();
+  let plain = create_minimal_wav_file(&test_dir, "plain.wav");
+
+  // Same WAV with a RIFF INFO chunk carrying the artist, placed between "fmt " and "data"
+  let original = fs::read(&plain).unwrap();
+  let mut tagged = original[..36].to_vec();
+  let artist = b"Test Artist\0";
+  let mut info = b"INFO".to_vec();
+  info.extend_from_slice(b"IART");
+  info.extend_from_slice(&(artist.len() as u32).to_le_bytes());
+  info.extend_from_slice(artist);
+  tagged.extend_from_slice(b"LIST");
+  tagged.extend_from_slice(&(info.len() as u32).to_le_bytes());
+  tagged.extend_from_slice(&info);
+  tagged.extend_from_slice(&original[36..]);
+  let riff_size = (tagged.len() - 8) as u32;
+  tagged[4..8].copy_from_slice(&riff_size.to_le_bytes());
+  let tagged_path = create_test_audio_file(&test_dir, "tagged.wav", &tagged);
+
+  assert_eq!(extract_metadata(&plain).unwrap().artist, None);
+  assert_eq!(extract_metadata(&tagged_path).unwrap().artist.as_deref(), Some("Test Artist"));
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_without_mixdown_writes_no_mixdown() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  let request = ImportRequest {
+    file_paths: vec![
+      create_minimal_wav_file(&test_dir, "Scratch - Vocals.wav"),
+      create_minimal_wav_file(&test_dir, "Scratch - Keys.wav"),
+    ],
+    title: "Scratch".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: false,
+  };
+  let result = import_song(&db, request).unwrap();
+
+  let song = db.get_song(&result.song_id).unwrap();
+  assert_eq!(song.mixdown_path, None);
+  let mixdown_file = mixdown::get_mixdowns_directory().unwrap()
+    .join(mixdown::get_mixdown_filename(&result.song_id));
+  assert!(!mixdown_file.exists(), "No mixdown file should be written");
+
+  // Stems are still decoded for the in-memory cache
+  assert_eq!(result.decoded_stems.len(), 2);
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_freeze_stem_bakes_in_trim() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  // Half a second of constant 0.5 on both channels
+  let source = test_dir.join("Frozen Song - Pad.wav");
+  let spec = hound::WavSpec {
+    channels: 2,
+    sample_rate: 48000,
+    bits_per_sample: 16,
+    sample_format: hound::SampleFormat::Int,
+  };
+  let mut writer = hound::WavWriter::create(&source, spec).unwrap();
+  for _ in 0..24000 * 2 {
+    writer.write_sample(16384i16).unwrap();
+  }
+  writer.finalize().unwrap();
+
+  let request = ImportRequest {
+    file_paths: vec![source.clone()],
+    title: "Frozen Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: false,
+  };
+  let song_id = import_song(&db, request).unwrap().song_id;
+  let mut stem = db.get_stems_for_song(&song_id).unwrap().remove(0);
+  stem.trim_db = -6.0;
+  db.update_stem(&stem).unwrap();
+
+  let frozen = freeze_stem(&db, &stem.id).unwrap();
+
+  assert_eq!(frozen.trim_db, 0.0, "Trim should be neutral once baked in");
+  assert_eq!(db.get_stem(&stem.id).unwrap().trim_db, 0.0);
+  assert_eq!(frozen.original_file_path.as_deref(), Some(source.to_string_lossy().as_ref()));
+  assert!(source.exists(), "The original file is kept");
+
+  let mut reader = hound::WavReader::open(&frozen.file_path).unwrap();
+  let samples: Vec<f32> = reader.samples::<f32>().map(|s| s.unwrap()).collect();
+  assert_eq!(samples.len(), 24000 * 2);
+  let expected = 0.5 * 10.0f32.powf(-6.0 / 20.0);
+  assert!(samples.iter().all(|s| (s - expected).abs() < 1e-3), "-6 dB should roughly halve the amplitude");
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_song_with_names_uses_the_given_names() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  // File names that detection would read very differently
+  let files = vec![
+    create_minimal_wav_file(&test_dir, "Audio 1.wav"),
+    create_minimal_wav_file(&test_dir, "Song - Drums.wav"),
+    create_minimal_wav_file(&test_dir, "metronome.wav"),
+  ];
+  let names = vec!["Lead Vox".to_string(), "Kick In".to_string(), " Click ".to_string()];
+  let request = ImportRequest {
+    file_paths: files.clone(),
+    title: "Named Song".to_string(),
+    artist: None,
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: false,
+  };
+
+  let song_id = import_song_with_names(&db, request.clone(), &names).unwrap().song_id;
+  let stems = db.get_stems_for_song(&song_id).unwrap();
+  let mut stem_names: Vec<(String, bool)> = stems.iter().map(|s| (s.name.clone(), s.is_cue)).collect();
+  stem_names.sort();
+  assert_eq!(stem_names, vec![
+    ("Click".to_string(), true),
+    ("Kick In".to_string(), false),
+    ("Lead Vox".to_string(), false),
+  ]);
+
+  // Collisions are still numbered apart
+  let other_files = vec![
+    create_minimal_wav_file(&test_dir, "a.wav"),
+    create_minimal_wav_file(&test_dir, "b.wav"),
+  ];
+  let twins = ImportRequest { file_paths: other_files, ..request.clone() };
+  let song_id = import_song_with_names(&db, twins, &["BGV".to_string(), "BGV".to_string()]).unwrap().song_id;
+  let mut twin_names: Vec<String> = db.get_stems_for_song(&song_id).unwrap().into_iter().map(|s| s.name).collect();
+  twin_names.sort();
+  assert_eq!(twin_names, vec!["BGV 1", "BGV 2"]);
+
+  assert!(import_song_with_names(&db, request.clone(), &names[..2]).is_err(), "One name per file");
+  let blank = vec!["Lead Vox".to_string(), "".to_string(), "Click".to_string()];
+  assert!(import_song_with_names(&db, request, &blank).is_err());
+
+  cleanup_test_directory(&test_dir);
+}
+
+#[test]
+fn test_import_single_track_creates_full_mix_stem() {
+  let test_dir = create_test_directory();
+  let db = crate::database::Database::new_in_memory().unwrap();
+
+  // A file name the detector would otherwise read as a click track
+  let file = create_minimal_wav_file(&test_dir, "Amazing Grace - Click.wav");
+  let request = ImportRequest {
+    file_paths: vec![file.clone()],
+    title: "Amazing Grace".to_string(),
+    artist: Some("Choir".to_string()),
+    key: None,
+    time_signature: None,
+    align_leading_silence: false,
+    target_sample_rate: None,
+    generate_mixdown: false,
+  };
+
+  let song_id = import_single_track(&db, request.clone()).unwrap().song_id;
+  assert_eq!(db.get_song(&song_id).unwrap().name, "Amazing Grace");
+
+  let stems = db.get_stems_for_song(&song_id).unwrap();
+  assert_eq!(stems.len(), 1);
+  assert_eq!(stems[0].name, FULL_MIX_STEM_NAME);
+  assert!(!stems[0].is_cue, "A full mix is never a cue stem");
+
+  let two_files = ImportRequest { file_paths: vec![file.clone(), file], ..request };
+  assert!(import_single_track(&db, two_files).is_err());
+
+  cleanup_test_directory(&test_dir);
+}
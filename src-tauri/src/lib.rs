@@ -3,9 +3,10 @@ mod database;
 mod import;
 mod commands;
 mod events;
+mod logging;
 
 use std::sync::Arc;
-use audio::MultiTrackEngine;
+use audio::{FadeCurve, MultiTrackEngine, StemCapacity};
 use database::Database;
 use commands::AppState;
 use tauri::{Manager, Emitter, menu::{MenuBuilder, SubmenuBuilder, MenuItemBuilder}};
@@ -18,10 +19,8 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize logger with default level INFO
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Info)
-        .init();
+    // Initialize logger with default level INFO (the saved level is applied once the database is open)
+    logging::init(log::LevelFilter::Info);
 
     log::info!("Initializing TraX application...");
 
@@ -31,24 +30,61 @@ pub fn run() {
 
     log::info!("Database initialized successfully");
 
-    // Initialize multi-track audio engine with extended capacity (32 stems)
+    if let Some(level) = database.get_settings().ok().and_then(|s| logging::parse_level(&s.log_level)) {
+        logging::set_level(level);
+    }
+
+    // Initialize multi-track audio engine with the saved capacity (extended, 32 stems, by default)
     // Uses parallel decoding for fast load times and full pre-decode for zero dropouts
-    let audio_engine = MultiTrackEngine::new_extended()
+    let capacity = database.get_settings()
+        .map(|s| StemCapacity::from_setting(s.stem_capacity))
+        .unwrap_or(StemCapacity::Extended);
+    let mut audio_engine = MultiTrackEngine::with_capacity(capacity)
         .expect("Failed to initialize audio engine");
 
+    // Apply the saved buffer size (the engine starts with the default)
+    if let Ok(settings) = database.get_settings() {
+        if settings.audio_buffer_size > 0 {
+            if let Err(e) = audio_engine.set_buffer_size(settings.audio_buffer_size as usize) {
+                log::warn!("Failed to apply saved buffer size: {}", e);
+            }
+        }
+        if let Some(curve) = FadeCurve::from_name(&settings.fade_curve) {
+            audio_engine.set_fade_curve(curve);
+        }
+        audio_engine.set_master_highpass(settings.master_highpass_enabled, settings.master_highpass_hz as f32);
+        if let Some(host) = &settings.audio_host {
+            if let Err(e) = audio_engine.set_audio_host(host) {
+                log::warn!("Failed to apply saved audio host: {}", e);
+            }
+        }
+        if settings.sample_rate > 0 {
+            if let Err(e) = audio_engine.set_project_sample_rate(settings.sample_rate as u32) {
+                log::warn!("Failed to apply saved project sample rate: {}", e);
+            }
+        }
+    }
+
     log::info!("Audio engine initialized successfully");
 
     // Create shared application state
-    let app_state = AppState::new(database, audio_engine);
+    let app_state = AppState::new(database, audio_engine, commands::default_stem_cache_dir());
+    if let Ok(settings) = app_state.database.get_settings() {
+        app_state.set_max_decode_threads(settings.max_decode_threads.max(0) as usize);
+    }
+
+    let device_state = app_state.clone();
+    let advance_state = app_state.clone();
 
     // Clone the Arc references needed for position emitter (before moving app_state)
-    let (position_arc, playback_state_arc, stem_levels_arc, master_level_arc) = {
+    let (position_arc, duration_arc, playback_state_arc, meters, sample_rate_arc) = {
         let engine = app_state.audio_engine.lock().unwrap();
         let pos = engine.position_arc();
+        let duration = engine.current_duration_arc();
         let state = engine.playback_state_arc();
-        let levels = engine.stem_levels_arc();
-        let master = engine.master_level_arc();
-        (pos, state, levels, master)
+        let meters = engine.meter_handles();
+        let sample_rate = engine.project_sample_rate_arc();
+        (pos, duration, state, meters, sample_rate)
     };
 
     tauri::Builder::default()
@@ -82,35 +118,111 @@ pub fn run() {
             });
 
             // Start the position emitter background task
-            events::start_position_emitter(app_handle, position_arc, playback_state_arc, stem_levels_arc, master_level_arc);
+            events::start_position_emitter(app_handle.clone(), position_arc, duration_arc, playback_state_arc, meters, sample_rate_arc);
+
+            // Watch for the output device disappearing mid-set
+            events::start_device_watcher(app_handle.clone(), device_state);
+
+            // Play the next setlist song when one ends, if the setlist auto-advances
+            events::start_auto_advance_watcher(app_handle, advance_state);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             greet,
             // Playback commands
             commands::load_song,
+            commands::prepare_song,
             commands::play_song,
             commands::resume_playback,
             commands::pause_playback,
             commands::stop_playback,
+            commands::unload_song,
+            commands::panic_stop,
+            commands::fade_out,
+            commands::start_recording,
+            commands::stop_recording,
+            commands::get_recording_status,
+            commands::set_auto_stop_at_end,
+            commands::set_device_fallback_enabled,
             commands::seek_to_position,
+            commands::skip_forward,
+            commands::skip_backward,
+            commands::practice_loop,
+            commands::stop_practice_loop,
+            commands::play_reverse,
             commands::get_playback_position,
+            commands::get_playback_position_frames,
+            commands::get_transport_state,
             commands::preload_setlist,
             commands::preload_setlist_smart,
+            commands::start_setlist,
+            commands::next_song,
             // Stem control commands
             commands::set_stem_volume,
+            commands::set_stem_volumes,
+            commands::save_mixer_snapshot,
+            commands::list_mixer_snapshots,
+            commands::recall_mixer_snapshot,
+            commands::reset_mix_to_default,
+            commands::delete_mixer_snapshot,
             commands::toggle_stem_mute,
             commands::toggle_stem_solo,
+            commands::set_stem_solo_safe,
+            commands::set_stem_phase_invert,
+            commands::set_stem_delay,
+            commands::set_stem_channel_mode,
+            commands::set_stem_trim,
+            commands::normalize_stem_peak,
+            commands::set_stem_gate,
+            commands::get_stem_mapping,
+            commands::get_stem_waveform,
+            commands::freeze_stem,
+            commands::rename_stem,
+            commands::set_stem_pan,
+            commands::set_stem_output,
+            commands::cue_stem,
+            commands::set_stem_automation,
+            commands::get_stem_automation,
+            commands::set_cue_output,
+            commands::set_stem_group,
+            commands::toggle_group_mute,
+            commands::toggle_group_solo,
             commands::set_master_volume,
+            commands::set_limiter_enabled,
+            commands::set_limiter_threshold_db,
+            commands::set_meter_mode,
+            commands::set_meter_decay,
+            commands::set_meter_peak_hold,
             commands::get_current_stems,
+            commands::get_current_song,
             // Library commands
             commands::import_files,
+            commands::import_files_with_names,
+            commands::import_single_track,
+            commands::preview_import,
+            commands::preview_audio_waveform,
             commands::get_all_songs,
             commands::search_songs,
             commands::filter_songs,
             commands::get_song,
+            commands::get_recently_played,
+            commands::get_library_facets,
+            commands::tap_tempo,
+            commands::commit_tap_tempo,
             commands::delete_song,
             commands::get_song_stems,
+            commands::get_song_mixdown,
+            commands::validate_library,
+            commands::validate_setlist,
+            commands::check_stem_alignment,
+            commands::maintain_database,
+            commands::find_duplicate_songs,
+            commands::export_song_archive,
+            commands::import_song_archive,
+            commands::relocate_song,
+            commands::add_stem_keyword,
+            commands::list_stem_keywords,
+            commands::delete_stem_keyword,
             // Setlist commands
             commands::create_setlist,
             commands::get_setlist,
@@ -118,12 +230,27 @@ pub fn run() {
             commands::delete_setlist,
             commands::get_all_setlists,
             commands::add_song_to_setlist,
+            commands::insert_song_into_setlist,
             commands::remove_song_from_setlist,
             commands::reorder_setlist_songs,
+            commands::reorder_setlists,
+            commands::set_setlist_song_override,
+            commands::get_setlist_duration,
+            commands::create_setlist_template,
+            commands::get_setlist_templates,
+            commands::delete_setlist_template,
+            commands::save_setlist_as_template,
+            commands::create_setlist_from_template,
             // Cache commands
             commands::get_cache_stats,
+            commands::get_cache_efficiency,
+            commands::get_cached_song_info,
             commands::set_cache_size,
             commands::clear_cache,
+            commands::warm_disk_cache,
+            commands::get_disk_cache_stats,
+            commands::set_disk_cache_size,
+            commands::clear_disk_cache,
             // Settings commands
             commands::get_audio_devices,
             commands::get_current_audio_device,
@@ -131,7 +258,32 @@ pub fn run() {
             commands::set_audio_device,
             commands::set_buffer_size,
             commands::set_sample_rate,
+            commands::set_project_sample_rate,
+            commands::set_group_drum_kit_stems,
+            commands::set_master_highpass,
+            commands::set_stem_overflow_policy,
+            commands::set_cue_pan_side,
+            commands::set_solo_mode,
+            commands::set_fade_curve,
+            commands::set_max_decode_threads,
+            commands::set_import_sample_rate,
+            commands::set_practice_mode,
+            commands::set_log_level,
+            commands::set_stem_capacity,
+            commands::set_auto_advance_gap,
+            commands::set_loudness_target,
+            commands::get_max_stems,
+            commands::get_active_stream_config,
+            commands::get_recent_logs,
+            commands::export_settings,
+            commands::import_settings,
+            commands::get_supported_sample_rates,
             commands::switch_audio_device,
+            commands::test_audio_device,
+            commands::list_audio_hosts,
+            commands::set_audio_host,
+            commands::get_output_channel_count,
+            commands::set_output_channels,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use log::{LevelFilter, Log, Metadata, Record};
+
+// Lines kept for get_recent_logs; older lines are dropped
+const RECENT_LOG_CAPACITY: usize = 2000;
+
+static RECENT_LOGS: Mutex<LogBuffer> = Mutex::new(LogBuffer::new(RECENT_LOG_CAPACITY));
+
+// Bounded buffer of formatted log lines, oldest first
+pub struct LogBuffer {
+  lines: VecDeque<String>,
+  capacity: usize,
+}
+
+impl LogBuffer {
+  pub const fn new(capacity: usize) -> Self {
+    LogBuffer { lines: VecDeque::new(), capacity }
+  }
+
+  pub fn push(&mut self, line: String) {
+    if self.capacity == 0 {
+      return;
+    }
+    if self.lines.len() == self.capacity {
+      self.lines.pop_front();
+    }
+    self.lines.push_back(line);
+  }
+
+  // The last `count` lines, oldest first
+  pub fn recent(&self, count: usize) -> Vec<String> {
+    let skip = self.lines.len().saturating_sub(count);
+    self.lines.iter().skip(skip).cloned().collect()
+  }
+}
+
+// Forwards to env_logger and keeps a copy of each line for the in-app log view.
+// The level is filtered globally through log::max_level so it can change at runtime
+struct AppLogger {
+  inner: env_logger::Logger,
+}
+
+impl Log for AppLogger {
+  fn enabled(&self, metadata: &Metadata) -> bool {
+    metadata.level() <= log::max_level()
+  }
+
+  fn log(&self, record: &Record) {
+    if !self.enabled(record.metadata()) {
+      return;
+    }
+
+    let line = format!(
+      "{} {:<5} [{}] {}",
+      chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+      record.level(),
+      record.target(),
+      record.args()
+    );
+    RECENT_LOGS.lock().unwrap_or_else(|e| e.into_inner()).push(line);
+
+    self.inner.log(record);
+  }
+
+  fn flush(&self) {
+    self.inner.flush();
+  }
+}
+
+/// Install the app logger at `level`. RUST_LOG is still honoured for per-module filters
+pub fn init(level: LevelFilter) {
+  // env_logger lets everything through; the global max level does the filtering
+  let inner = env_logger::Builder::from_default_env()
+    .filter_level(LevelFilter::Trace)
+    .build();
+
+  if log::set_boxed_logger(Box::new(AppLogger { inner })).is_ok() {
+    log::set_max_level(level);
+  }
+}
+
+/// Change the log level while running
+pub fn set_level(level: LevelFilter) {
+  log::set_max_level(level);
+}
+
+pub fn level() -> LevelFilter {
+  log::max_level()
+}
+
+/// Parse a level name ("error", "warn", "info", "debug", "trace" or "off"), ignoring case
+pub fn parse_level(name: &str) -> Option<LevelFilter> {
+  name.trim().parse().ok()
+}
+
+/// The most recent `count` log lines, oldest first
+pub fn recent_logs(count: usize) -> Vec<String> {
+  RECENT_LOGS.lock().unwrap_or_else(|e| e.into_inner()).recent(count)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_set_level_is_reflected() {
+    let original = level();
+
+    set_level(LevelFilter::Debug);
+    assert_eq!(level(), LevelFilter::Debug);
+    set_level(LevelFilter::Info);
+    assert_eq!(level(), LevelFilter::Info);
+
+    assert_eq!(parse_level("DEBUG"), Some(LevelFilter::Debug));
+    assert_eq!(parse_level("verbose"), None);
+
+    set_level(original);
+  }
+
+  #[test]
+  fn test_log_buffer_returns_most_recent_lines() {
+    let mut buffer = LogBuffer::new(3);
+    for i in 0..5 {
+      buffer.push(format!("line {}", i));
+    }
+
+    assert_eq!(buffer.recent(2), vec!["line 3", "line 4"]);
+    assert_eq!(buffer.recent(10), vec!["line 2", "line 3", "line 4"], "Oldest lines are dropped at capacity");
+    assert!(buffer.recent(0).is_empty());
+  }
+}
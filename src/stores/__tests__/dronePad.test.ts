@@ -0,0 +1,93 @@
+import { describe, it, expect, beforeEach, afterEach, vi } from 'vitest'
+import { setActivePinia, createPinia } from 'pinia'
+import { useDronePadStore } from '../dronePad'
+
+// Mock Tauri APIs
+vi.mock('@tauri-apps/api/event', () => ({
+  listen: vi.fn(),
+}))
+
+const { listen } = await import('@tauri-apps/api/event')
+
+// Just enough of the Web Audio API for the pad to start playing
+function stubAudio() {
+  const source = {
+    buffer: null,
+    loop: false,
+    playbackRate: { value: 1 },
+    connect: vi.fn(),
+    start: vi.fn(),
+    stop: vi.fn(),
+    disconnect: vi.fn(),
+  }
+  const gain = {
+    gain: { value: 0, cancelScheduledValues: vi.fn(), setValueAtTime: vi.fn(), linearRampToValueAtTime: vi.fn() },
+    connect: vi.fn(),
+    disconnect: vi.fn(),
+  }
+  class FakeAudioContext {
+    currentTime = 0
+    destination = {}
+    createGain = () => gain
+    createBufferSource = () => source
+    decodeAudioData = async () => ({})
+  }
+  vi.stubGlobal('AudioContext', FakeAudioContext)
+  vi.stubGlobal('fetch', async () => ({ arrayBuffer: async () => new ArrayBuffer(0) }))
+  return { source, gain }
+}
+
+describe('Drone Pad Store', () => {
+  beforeEach(() => {
+    setActivePinia(createPinia())
+    vi.clearAllMocks()
+    vi.useFakeTimers()
+  })
+
+  afterEach(() => {
+    vi.useRealTimers()
+    vi.unstubAllGlobals()
+  })
+
+  it('stops the pad at once on a panic stop', async () => {
+    const { source, gain } = stubAudio()
+    const store = useDronePadStore()
+    const onPanic = vi.mocked(listen).mock.calls.find(([event]) => event === 'playback:panic')?.[1]
+    expect(onPanic).toBeDefined()
+
+    store.selectedKey = 'C'
+    void store.togglePlayback()
+    await vi.advanceTimersByTimeAsync(0)
+    expect(source.start).toHaveBeenCalled()
+    expect(store.isPlaying).toBe(true)
+
+    onPanic!({ event: 'playback:panic', id: 0, payload: {} })
+
+    expect(source.stop).toHaveBeenCalled()
+    expect(gain.disconnect).toHaveBeenCalled()
+    expect(store.isPlaying).toBe(false)
+    expect(store.fadingDirection).toBe(null)
+  })
+
+  it('transposes the playing pad by resampling the loop', async () => {
+    const { source } = stubAudio()
+    const store = useDronePadStore()
+
+    store.selectedKey = 'C'
+    void store.togglePlayback()
+    await vi.advanceTimersByTimeAsync(0)
+    expect(source.playbackRate.value).toBe(1)
+
+    // An octave up plays the loop through twice as fast
+    store.setTranspose(12)
+    expect(store.transpose).toBe(12)
+    expect(source.playbackRate.value).toBeCloseTo(2)
+
+    store.setTranspose(-2)
+    expect(source.playbackRate.value).toBeCloseTo(2 ** (-2 / 12))
+
+    // Held to an octave either way
+    store.setTranspose(20)
+    expect(store.transpose).toBe(12)
+  })
+})
@@ -67,6 +67,20 @@ describe('Playback Store', () => {
     expect(store.currentPosition).toBe(0)
   })
 
+  it('panic stop silences playback through the backend', async () => {
+    vi.mocked(invoke).mockResolvedValueOnce(undefined)
+
+    const store = usePlaybackStore()
+    store.isPlaying = true
+    store.currentPosition = 30
+
+    await store.panicStop()
+
+    expect(invoke).toHaveBeenCalledWith('panic_stop')
+    expect(store.isPlaying).toBe(false)
+    expect(store.currentPosition).toBe(0)
+  })
+
   it('seeks to position', async () => {
     vi.mocked(invoke).mockResolvedValueOnce(undefined)
 
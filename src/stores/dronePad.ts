@@ -1,5 +1,6 @@
 import { defineStore } from 'pinia'
 import { ref, computed } from 'vue'
+import { listen } from '@tauri-apps/api/event'
 
 export type Key = 'C' | 'Db' | 'D' | 'Eb' | 'E' | 'F' | 'Gb' | 'G' | 'Ab' | 'A' | 'Bb' | 'B'
 
@@ -18,6 +19,7 @@ export const useDronePadStore = defineStore('dronePad', () => {
   const volume = ref(1.0)
   const isFading = ref(false)
   const fadingDirection = ref<'in' | 'out' | null>(null)
+  const transpose = ref(0) // Semitones, so the pad follows a song transposed mid-set
 
   // Audio context and nodes
   const audioContext = ref<AudioContext | null>(null)
@@ -54,6 +56,9 @@ export const useDronePadStore = defineStore('dronePad', () => {
     return `/drone-pads/${selectedPreset.value.folder}/${selectedKey.value}.mp3`
   })
 
+  // Playing the loop faster raises the pitch; it still loops cleanly, just shorter
+  const pitchRatio = computed(() => 2 ** (transpose.value / 12))
+
   // Initialize audio context
   function initAudioContext() {
     if (!audioContext.value) {
@@ -154,6 +159,7 @@ export const useDronePadStore = defineStore('dronePad', () => {
       const newSource = audioContext.value!.createBufferSource()
       newSource.buffer = audioBuffer.value
       newSource.loop = true
+      newSource.playbackRate.value = pitchRatio.value
       newSource.connect(newGainNode)
 
       // Start playback
@@ -176,6 +182,27 @@ export const useDronePadStore = defineStore('dronePad', () => {
     }
   }
 
+  // Silence the pad at once, crossfade included, without fading out
+  function stopNow() {
+    for (const source of [currentSource.value, previousSource.value]) {
+      source?.stop()
+      source?.disconnect()
+    }
+    currentGainNode.value?.disconnect()
+    previousGainNode.value?.disconnect()
+
+    currentSource.value = null
+    currentGainNode.value = null
+    previousSource.value = null
+    previousGainNode.value = null
+    isPlaying.value = false
+    isFading.value = false
+    fadingDirection.value = null
+  }
+
+  // A panic stop silences the pad along with everything the backend plays
+  listen('playback:panic', () => stopNow())
+
   // Toggle playback
   async function togglePlayback() {
     if (isPlaying.value) {
@@ -246,6 +273,7 @@ export const useDronePadStore = defineStore('dronePad', () => {
         const newSource = audioContext.value!.createBufferSource()
         newSource.buffer = newBuffer
         newSource.loop = true
+        newSource.playbackRate.value = pitchRatio.value
         newSource.connect(newGainNode)
 
         // Start new playback
@@ -314,6 +342,14 @@ export const useDronePadStore = defineStore('dronePad', () => {
     }
   }
 
+  // Transpose the pad, including one fading out, by up to an octave either way
+  function setTranspose(semitones: number) {
+    transpose.value = Math.max(-12, Math.min(12, Math.round(semitones)))
+    for (const source of [currentSource.value, previousSource.value]) {
+      if (source) source.playbackRate.value = pitchRatio.value
+    }
+  }
+
   // Cleanup
   function cleanup() {
     if (currentSource.value) {
@@ -348,6 +384,7 @@ export const useDronePadStore = defineStore('dronePad', () => {
     volume,
     isFading,
     fadingDirection,
+    transpose,
     dronePresets,
     availableKeys,
     currentAudioPath,
@@ -357,6 +394,8 @@ export const useDronePadStore = defineStore('dronePad', () => {
     changeKey,
     changePreset,
     setVolume,
+    setTranspose,
+    stopNow,
     cleanup
   }
 })
@@ -1,7 +1,8 @@
 import { defineStore } from 'pinia'
 import { ref, computed } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
-import type { Song, Stem, SongFilter } from '@/types/library'
+import type { Song, StemInfo, SongFilter } from '@/types/library'
+import { errorMessage } from '@/lib/utils'
 
 export const useLibraryStore = defineStore('library', () => {
   // State
@@ -57,6 +58,10 @@ export const useLibraryStore = defineStore('library', () => {
             return a.duration - b.duration
           case 'date_added':
             return b.created_at - a.created_at
+          case 'most_played':
+            return (b.play_count || 0) - (a.play_count || 0)
+          case 'recently_played':
+            return (b.last_played_at || 0) - (a.last_played_at || 0)
           default:
             return 0
         }
@@ -154,7 +159,7 @@ export const useLibraryStore = defineStore('library', () => {
 
       return songId
     } catch (e) {
-      error.value = e instanceof Error ? e.message : String(e)
+      error.value = errorMessage(e)
       console.error('Failed to import files:', e)
       throw e
     } finally {
@@ -162,9 +167,9 @@ export const useLibraryStore = defineStore('library', () => {
     }
   }
 
-  async function getSongStems(songId: string): Promise<Stem[]> {
+  async function getSongStems(songId: string): Promise<StemInfo[]> {
     try {
-      return await invoke<Stem[]>('get_song_stems', { songId })
+      return await invoke<StemInfo[]>('get_song_stems', { songId })
     } catch (e) {
       error.value = e instanceof Error ? e.message : String(e)
       console.error('Failed to get song stems:', e)
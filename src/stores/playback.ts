@@ -2,7 +2,7 @@ import { defineStore } from 'pinia'
 import { ref, computed } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
-import type { Song, Stem } from '@/types/library'
+import type { RecordingStatus, RecordingSummary, Song, Stem } from '@/types/library'
 
 export const usePlaybackStore = defineStore('playback', () => {
   // State
@@ -10,11 +10,17 @@ export const usePlaybackStore = defineStore('playback', () => {
   const currentSong = ref<Song | null>(null)
   const isPlaying = ref(false)
   const currentPosition = ref(0)
+  // Exact engine position in frames at the project sample rate, as of the last position event
+  const framePosition = ref(0)
+  const sampleRate = ref(48000)
   const duration = ref(0)
   const stems = ref<Stem[]>([])
   const volume = ref(0.8)
   const isLoadingStems = ref(false)
   const masterLevel = ref(0)
+  const masterPeakHold = ref(0)
+  // Folder of the running recording, null when not recording
+  const recordingDir = ref<string | null>(null)
 
   // Client-side position interpolation
   let animationFrameId: number | null = null
@@ -146,10 +152,43 @@ export const usePlaybackStore = defineStore('playback', () => {
     }
   }
 
-  async function seek(position: number) {
+  // Emergency stop: the backend silences everything and tells the drone pad to stop too
+  async function panicStop() {
     try {
-      await invoke('seek_to_position', { position })
+      await invoke('panic_stop')
+      isPlaying.value = false
+      stopPositionAnimation()
+      currentPosition.value = 0
+    } catch (e) {
+      console.error('Failed to panic stop:', e)
+      throw e
+    }
+  }
+
+  async function startRecording(outputDir: string, perStem = false) {
+    recordingDir.value = await invoke<string>('start_recording', { outputDir, perStem })
+    return recordingDir.value
+  }
+
+  async function stopRecording() {
+    const summary = await invoke<RecordingSummary>('stop_recording')
+    recordingDir.value = null
+    return summary
+  }
+
+  async function getRecordingStatus() {
+    return await invoke<RecordingStatus>('get_recording_status')
+  }
+
+  // autoplay: true jumps and plays, false holds playback paused (scrubber preview),
+  // undefined keeps the current transport state
+  async function seek(position: number, autoplay?: boolean) {
+    try {
+      await invoke('seek_to_position', autoplay === undefined ? { position } : { position, autoplay })
       currentPosition.value = position
+      if (autoplay !== undefined) {
+        updatePlaybackState(autoplay)
+      }
     } catch (e) {
       console.error('Failed to seek:', e)
       throw e
@@ -286,6 +325,14 @@ export const usePlaybackStore = defineStore('playback', () => {
   function initializeEventListeners() {
     // Listen for playback position updates
     listen('playback:position', (event: any) => {
+      // Prefer the engine's duration once a song is loaded
+      if (event.payload.duration > 0) {
+        duration.value = event.payload.duration
+      }
+      if (event.payload.sample_rate > 0) {
+        framePosition.value = event.payload.frame_position
+        sampleRate.value = event.payload.sample_rate
+      }
       updatePosition(event.payload.position)
     })
 
@@ -294,21 +341,41 @@ export const usePlaybackStore = defineStore('playback', () => {
       updatePlaybackState(event.payload.is_playing)
     })
 
+    // Listen for the backend announcing which song just started
+    listen('playback:song-changed', (event: any) => {
+      if (currentSong.value?.id === event.payload.song_id) {
+        duration.value = event.payload.duration
+      }
+    })
+
+    // Listen for the output device dropping out and playback moving to the default device
+    listen('audio:device-changed', (event: any) => {
+      console.warn(`Audio device ${event.payload.previous_device ?? 'unknown'} disconnected, now playing on ${event.payload.device_name}`)
+      if (event.payload.sample_rate_changed !== null) {
+        console.warn(`The project sample rate moved to ${event.payload.sample_rate_changed} Hz; songs reload at the new rate`)
+        // The backend dropped the song decoded at the old rate
+        updatePlaybackState(false)
+        setCurrentSong(null)
+      }
+    })
+
     // Listen for stem level updates
     listen('playback:levels', (event: any) => {
       updateStemLevels(event.payload)
     })
   }
 
-  function updateStemLevels(payload: { levels: number[], master: number }) {
-    // Update level for each stem
+  function updateStemLevels(payload: { levels: number[], held: number[], master: number, master_held: number }) {
+    // Update level and held peak for each stem
     stems.value.forEach((stem, index) => {
       if (index < payload.levels.length) {
         stem.level = payload.levels[index]
+        stem.peak_hold = payload.held[index]
       }
     })
     // Update master level
     masterLevel.value = payload.master
+    masterPeakHold.value = payload.master_held
   }
 
   return {
@@ -317,11 +384,15 @@ export const usePlaybackStore = defineStore('playback', () => {
     currentSong,
     isPlaying,
     currentPosition,
+    framePosition,
+    sampleRate,
     duration,
     stems,
     volume,
     isLoadingStems,
     masterLevel,
+    masterPeakHold,
+    recordingDir,
 
     // Getters
     formattedPosition,
@@ -334,7 +405,11 @@ export const usePlaybackStore = defineStore('playback', () => {
     resume,
     pause,
     stop,
+    panicStop,
     seek,
+    startRecording,
+    stopRecording,
+    getRecordingStatus,
     setVolume,
     setStemVolume,
     toggleStemMute,
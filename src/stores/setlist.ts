@@ -3,6 +3,7 @@ import { ref, computed } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
 import { useDebounceFn } from '@vueuse/core'
 import type { Setlist } from '@/types/library'
+import { errorMessage } from '@/lib/utils'
 import { useLibraryStore } from './library'
 
 export const useSetlistStore = defineStore('setlist', () => {
@@ -52,8 +53,10 @@ export const useSetlistStore = defineStore('setlist', () => {
         created_at: Date.now(),
         updated_at: Date.now(),
         song_ids: [],
+        position: 0,
       }
-      allSetlists.value.push(newSetlist)
+      // The backend puts new setlists on top
+      allSetlists.value = [newSetlist, ...allSetlists.value.map(s => ({ ...s, position: (s.position ?? 0) + 1 }))]
       currentSetlist.value = newSetlist
       return id
     } catch (e) {
@@ -101,7 +104,8 @@ export const useSetlistStore = defineStore('setlist', () => {
       await invoke('update_setlist', {
         setlistId: setlist.id,
         name: setlist.name,
-        songIds: setlist.song_ids
+        songIds: setlist.song_ids,
+        color: setlist.color ?? ''
       })
       // Update local state
       const index = allSetlists.value.findIndex(s => s.id === setlist.id)
@@ -224,6 +228,21 @@ export const useSetlistStore = defineStore('setlist', () => {
     }
   }
 
+  async function reorderSetlists(oldIndex: number, newIndex: number) {
+    const setlists = [...allSetlists.value]
+    const [moved] = setlists.splice(oldIndex, 1)
+    setlists.splice(newIndex, 0, moved)
+
+    error.value = null
+    try {
+      await invoke('reorder_setlists', { ids: setlists.map(s => s.id) })
+      allSetlists.value = setlists.map((s, position) => ({ ...s, position }))
+    } catch (e) {
+      error.value = errorMessage(e)
+      throw e
+    }
+  }
+
   // Auto-save with 500ms debounce
   const debouncedSave = useDebounceFn(async (setlist: Setlist) => {
     try {
@@ -248,6 +267,7 @@ export const useSetlistStore = defineStore('setlist', () => {
     addSongToSetlist,
     removeSongFromSetlist,
     reorderSongs,
+    reorderSetlists,
     debouncedSave,
   }
 })
@@ -10,6 +10,9 @@ export interface Song {
   mixdown_path: string | null
   created_at: number
   updated_at: number
+  play_count?: number
+  last_played_at?: number | null // Unix timestamp, null if never played
+  loudness_lufs?: number | null // Integrated loudness measured at first load
 }
 
 // Stem model matching Rust backend
@@ -25,10 +28,37 @@ export interface Stem {
   volume: number
   is_muted: boolean
   display_order: number
+  group?: string | null // Mute/solo group name
+  pan?: number | null // -1.0 (left) to 1.0 (right), null = default pan
+  is_cue?: boolean // Click/guide stem for monitors only
+  solo_safe?: boolean // Stays audible when other stems are soloed
+  offset_samples?: number // Leading frames skipped on playback to align the stem
+  phase_inverted?: boolean // Polarity flipped on playback
+  original_file_path?: string | null // Source file when file_path is a copy converted at import
+  default_volume?: number // Import-time volume restored by reset_mix_to_default
+  default_mute?: boolean
+  delay_samples?: number // Manual latency compensation in frames (negative plays early)
+  swap_channels?: boolean // Left and right exchanged on playback
+  mono_sum?: boolean // Left and right averaged into both sides on playback
+  trim_db?: number // Input gain before the fader
+  gate_enabled?: boolean // Noise gate silences the stem between phrases
+  gate_threshold_db?: number // Level the gate opens at
+  gate_attack_ms?: number // Time the gate takes to open
+  gate_release_ms?: number // Time the gate takes to close
+  file_hash?: string | null // Content hash of the imported source file
+  file_hash_modified?: number | null // Source mtime (ns since the epoch) the hash was computed at
   level?: number // Peak audio level (0.0 to 1.0+), updated in real-time
+  peak_hold?: number // Held peak level, updated with level
   is_solo?: boolean // Solo state (frontend only, not persisted)
 }
 
+// Stem as returned by get_song_stems, with how it will be converted at load
+export interface StemInfo extends Stem {
+  project_sample_rate: number // Rate every stem is decoded and played at
+  will_resample: boolean
+  is_mono: boolean
+}
+
 // Filter options for library queries
 export interface SongFilter {
   search_query?: string
@@ -45,6 +75,8 @@ export enum SortBy {
   Tempo = 'tempo',
   Duration = 'duration',
   DateAdded = 'date_added',
+  MostPlayed = 'most_played',
+  RecentlyPlayed = 'recently_played',
 }
 
 // Import request payload
@@ -72,6 +104,76 @@ export interface Setlist {
   created_at: number
   updated_at: number
   song_ids: string[]
+  position?: number
+  color?: string | null
+  songs?: SetlistSong[]
+}
+
+// Per-setlist song entry with performance overrides
+export interface SetlistSong {
+  song_id: string
+  position: number
+  key_override: string | null
+  tempo_note: string | null
+  transition_note: string | null
+}
+
+// Reusable setlist structure of labeled slots
+export interface SetlistTemplate {
+  id: string
+  name: string
+  slots: string[] // Slot labels in setlist order
+  created_at: number
+}
+
+// Keys and tempo range in the library, as returned by get_library_facets
+export interface KeyFacet {
+  key: string
+  song_count: number
+}
+
+export interface LibraryFacets {
+  keys: KeyFacet[] // Songs without a key are left out
+  tempo_min: number | null
+  tempo_max: number | null
+}
+
+// Result of prepare_song: how many stems are decoded and ready to play
+export interface SongReadiness {
+  loaded_stems: number
+  total_stems: number
+  warnings: string[] // One per stem that won't play, with the reason
+}
+
+// Min/max peaks of a stem, as returned by get_stem_waveform
+export interface StemWaveform {
+  stem_id: string
+  buckets: number
+  min: number[] // Lowest sample in each bucket
+  max: number[] // Highest sample in each bucket
+  created_at: number
+}
+
+// Peaks of the start of a file, from preview_audio_waveform
+export interface WaveformPeaks {
+  min: number[]
+  max: number[]
+}
+
+// Files written by a recording, from stop_recording
+export interface RecordingSummary {
+  files: string[]
+  frames: number
+  sample_rate: number
+  dropped_callbacks: number // Left out of every file because the writer fell behind
+}
+
+// Running recording, from get_recording_status
+export interface RecordingStatus {
+  recording: boolean
+  dropped_callbacks: number
+  writer_failed: boolean // Writing failed; nothing after that point is being recorded
+  interrupted: boolean // A device loss ended it; stopRecording still returns its files
 }
 
 // Audio device model matching Rust backend
@@ -79,3 +181,124 @@ export interface AudioDevice {
   name: string
   is_default: boolean
 }
+
+// Custom stem detection keyword matching Rust backend
+export interface StemKeyword {
+  keyword: string
+  display_name: string
+  created_at: number
+}
+
+// Transport snapshot from get_transport_state
+export interface TransportState {
+  state: 'playing' | 'paused' | 'stopped'
+  position: number
+  duration: number
+  song_id: string | null
+}
+
+// Library health check report from validate_library / validate_setlist
+export interface StemProblem {
+  song_id: string
+  song_name: string
+  stem_id: string
+  stem_name: string
+  file_path: string
+  kind: 'missing' | 'undecodable' | 'empty' | 'unsupported_format'
+  message: string
+}
+
+export interface LibraryReport {
+  songs_checked: number
+  stems_checked: number
+  problems: StemProblem[]
+}
+
+// Saved per-song mixer balance, keyed by stem id
+export interface StemMix {
+  volume: number
+  is_muted: boolean
+  pan: number | null
+  trim_db: number
+}
+
+export interface MixerSnapshot {
+  id: string
+  song_id: string
+  name: string
+  stems: Record<string, StemMix>
+  created_at: number
+}
+
+// Point of a stem's volume automation (get_stem_automation / set_stem_automation)
+export interface AutomationPoint {
+  position_seconds: number
+  volume: number // 0.0 to 1.0
+}
+
+// Result of the maintain_database command
+export interface MaintenanceReport {
+  integrity: string // "ok" when the database is healthy
+  wal_bytes_reclaimed: number
+}
+
+// One file as preview_import would import it
+export interface PreviewFile {
+  file_path: string
+  stem_name: string
+  duration: number
+  sample_rate: number
+  channels: number
+  artist: string | null
+  is_cue: boolean
+  sample_format: SampleFormatInfo | null
+}
+
+// Sample format a file decodes to (probe_format)
+export interface SampleFormatInfo {
+  sample_format: string
+  bits_per_sample: number
+  supported: boolean
+}
+
+// Dry-run result of an import
+export interface ImportPreview {
+  files: PreviewFile[]
+  artist: string | null // First artist tag found across the files
+  errors: string[]
+}
+
+// Running order timing from get_setlist_duration
+export interface SetlistSongTiming {
+  song_id: string
+  name: string
+  duration: number // 0 when the song has no usable duration
+  start_time: number // Seconds from the start of the set
+  has_duration: boolean
+}
+
+export interface SetlistDuration {
+  total_seconds: number
+  gap_seconds: number
+  songs: SetlistSongTiming[]
+}
+
+// Rejection value of the library and cache commands, e.g. { code: 'FILE_NOT_FOUND', message }.
+// Other commands reject with a plain string; errorMessage() reads either
+export interface CommandError {
+  code:
+    | 'FILE_NOT_FOUND'
+    | 'INVALID_FORMAT'
+    | 'DUPLICATE'
+    | 'VALIDATION'
+    | 'NOT_FOUND'
+    | 'DATABASE'
+    | 'DECODE'
+    | 'DEVICE'
+    | 'PLAYBACK'
+    | 'IO'
+    | 'ARCHIVE'
+    | 'CACHE'
+    | 'INTERNAL'
+  message: string
+}